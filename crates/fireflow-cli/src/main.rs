@@ -1,20 +1,37 @@
 use fireflow_core::api::*;
 use fireflow_core::config;
+use fireflow_core::core::{ChangeTracker, SplitError, SubsampleMethod, SubsampleSize};
 use fireflow_core::error::*;
+use fireflow_core::fcs_error::FcsError;
+use fireflow_core::header::Version;
+use fireflow_core::report::{ParseReport, ReportWarning, Severity};
+use fireflow_core::stats::{
+    BinScale, MeasurementHistogram, MeasurementSummary, RangeAudit, DEFAULT_PERCENTILES,
+};
+use fireflow_core::text::byteord::Endian;
+use fireflow_core::text::hardware::InstrumentHardware;
+use fireflow_core::text::keywords::{AlphaNumType, Cyt, NumType};
 use fireflow_core::validated::datepattern::DatePattern;
 use fireflow_core::validated::nonstandard::NonStdMeasPattern;
 use fireflow_core::validated::pattern::*;
+use fireflow_core::validated::repair::Anonymizer;
+use fireflow_core::validated::shortname::Shortname;
+use fireflow_core::validated::standard::KeywordSetError;
+use fireflow_core::validated::textdelim::TEXTDelim;
 
-use clap::{arg, value_parser, Command};
+use clap::parser::ValueSource;
+use clap::{arg, value_parser, ArgAction, ArgMatches, Command};
 use serde::ser::Serialize;
 use std::fmt::Display;
-use std::path::PathBuf;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
 fn print_json<T: Serialize>(j: &T) {
     println!("{}", serde_json::to_string(j).unwrap());
 }
 
-pub fn print_parsed_data(s: &StdDatasetOutput, _delim: &str) {
+pub fn print_parsed_data(s: &StdDatasetOutput, delim: &str) {
     let df = s.dataset.standardized.core.as_data();
     let nrows = df.nrows();
     let cols: Vec<_> = df.iter_columns().collect();
@@ -25,17 +42,104 @@ pub fn print_parsed_data(s: &StdDatasetOutput, _delim: &str) {
     let mut ns = s.dataset.standardized.core.shortnames().into_iter();
     print!("{}", ns.next().unwrap());
     for n in ns {
-        print!("\t{n}");
+        print!("{delim}{n}");
     }
     for r in 0..nrows {
         println!();
         print!("{}", cols[0].pos_to_string(r));
         (1..ncols)
-            .map(|c| print!("\t{}", cols[c].pos_to_string(r)))
+            .map(|c| print!("{delim}{}", cols[c].pos_to_string(r)))
             .collect()
     }
 }
 
+pub fn print_parsed_data_json(s: &StdDatasetOutput) {
+    let df: serde_json::Map<_, _> = s
+        .dataset
+        .standardized
+        .core
+        .named_columns()
+        .into_iter()
+        .map(|(n, c)| (n.to_string(), serde_json::to_value(c).unwrap()))
+        .collect();
+    print_json(&df);
+}
+
+pub fn print_metrics(m: &ParseMetrics) {
+    eprintln!("bytes read: {}", m.bytes_read);
+    eprintln!("HEADER: {:.3} ms", m.header_time.as_secs_f64() * 1e3);
+    eprintln!("TEXT: {:.3} ms", m.text_time.as_secs_f64() * 1e3);
+    if let Some(t) = m.data_time {
+        eprintln!("DATA: {:.3} ms", t.as_secs_f64() * 1e3);
+    }
+    eprintln!("total: {:.3} ms", m.total_time().as_secs_f64() * 1e3);
+    if let Some(r) = m.events_per_second() {
+        eprintln!("events/sec: {r:.0}");
+    }
+}
+
+pub fn print_summary(ss: &[MeasurementSummary], percentiles: &[f64], delim: &str) {
+    let ps = percentiles.iter().map(|p| format!("{delim}p{p}"));
+    println!(
+        "index{delim}$PnN{delim}$PnS{delim}count{delim}min{delim}max{delim}mean\
+         {delim}median{delim}stdev{}",
+        ps.collect::<String>()
+    );
+    for s in ss {
+        let longname = s
+            .longname
+            .as_ref()
+            .map(|x| x.to_string())
+            .unwrap_or_default();
+        print!("{}{delim}{}{delim}{longname}", s.index, s.shortname);
+        if let Some(x) = &s.stats {
+            print!(
+                "{delim}{}{delim}{}{delim}{}{delim}{}{delim}{}{delim}{}",
+                x.count, x.min, x.max, x.mean, x.median, x.stdev
+            );
+            for (_, v) in x.percentiles.iter() {
+                print!("{delim}{v}");
+            }
+        } else {
+            print!("{delim}0");
+            for _ in 0..(5 + percentiles.len()) {
+                print!("{delim}NA");
+            }
+        }
+        println!();
+    }
+}
+
+pub fn print_histograms(hs: &[MeasurementHistogram], delim: &str) {
+    println!("index{delim}$PnN{delim}lower{delim}upper{delim}count");
+    for h in hs {
+        let es = &h.histogram.edges;
+        for (i, c) in h.histogram.counts.iter().enumerate() {
+            println!(
+                "{}{delim}{}{delim}{}{delim}{}{delim}{c}",
+                h.index,
+                h.shortname,
+                es[i],
+                es[i + 1]
+            );
+        }
+    }
+}
+
+pub fn print_range_audit(xs: &[RangeAudit], delim: &str) {
+    println!(
+        "index{delim}$PnN{delim}$PnB{delim}$PnR{delim}bitmask{delim}max\
+         {delim}truncated{delim}saturated"
+    );
+    for x in xs {
+        let max = x.max.map_or("NA".into(), |m| m.to_string());
+        println!(
+            "{}{delim}{}{delim}{}{delim}{}{delim}{}{delim}{max}{delim}{}{delim}{}",
+            x.index, x.shortname, x.width, x.range, x.bitmask, x.truncated, x.saturated
+        );
+    }
+}
+
 // TODO use warnings_are_errors flag
 fn handle_warnings<X, W>(t: Terminal<X, W>) -> X
 where
@@ -91,6 +195,105 @@ where
     );
 }
 
+/// Collect warnings and errors into a report and write it to 'path' as JSON.
+///
+/// Issues are also printed as in [`handle_warnings`] and [`handle_failure`].
+fn handle_with_report<X, W, E, T>(
+    res: TerminalResult<X, W, E, T>,
+    path: &PathBuf,
+) -> Result<(X, ParseReport), ()>
+where
+    W: ReportWarning,
+    FcsError: From<E>,
+    T: Display,
+{
+    let (value, report) = ParseReport::from_terminal(res);
+    for e in report.entries.iter() {
+        if e.severity == Severity::Error {
+            eprintln!("ERROR: {}", e.message);
+        } else {
+            eprintln!("WARNING: {}", e.message);
+        }
+    }
+    if let Some(f) = report.failure.as_ref() {
+        eprintln!("TOPLEVEL ERROR: {f}");
+    }
+    let json = serde_json::to_string(&report).unwrap();
+    if let Err(e) = fs::write(path, json) {
+        eprintln!("ERROR: could not write report: {e}");
+        return Err(());
+    }
+    value.map(|x| (x, report)).ok_or(())
+}
+
+/// Set a flag only if given on the command line.
+///
+/// Otherwise leave the value from the profile as-is.
+fn set_flag(x: &mut bool, sargs: &ArgMatches, id: &str) {
+    if sargs.value_source(id) == Some(ValueSource::CommandLine) {
+        *x = sargs.get_flag(id);
+    }
+}
+
+/// Set HEADER and TEXT options for subcommands which read standardized TEXT.
+fn set_raw_text_args(conf: &mut config::RawTextReadConfig, sargs: &ArgMatches) {
+    conf.header.max_other = sargs.get_one::<usize>("max-other").copied();
+    conf.header.other_width = sargs
+        .get_one::<u8>("other-width")
+        .copied()
+        .map(|x| x.try_into().unwrap())
+        .unwrap_or_default();
+    conf.header.allow_negative = sargs.get_flag("allow-negative");
+    conf.header.squish_offsets = sargs.get_flag("squish-offsets");
+    set_flag(&mut conf.trim_value_whitespace, sargs, "trim-whitespace");
+    set_flag(&mut conf.allow_duplicated_stext, sargs, "allow-dup-stext");
+    set_flag(&mut conf.ignore_stext, sargs, "ignore-stext");
+}
+
+fn edit_keywords(
+    filepath: &Path,
+    outpath: &Path,
+    raw: RawTEXTOutput,
+    read_conf: &config::DataReadConfig,
+    dry_run: bool,
+    force: bool,
+) -> Result<(), ()> {
+    // Standardize the edited keywords to show what (if anything) the
+    // changes will break, and refuse to write a broken file unless forced
+    let res = fcs_std_text_from_raw(raw.clone(), &read_conf.standard);
+    let is_valid = match res {
+        Ok(t) => {
+            handle_warnings(t);
+            true
+        }
+        Err(f) => {
+            handle_failure(f);
+            false
+        }
+    };
+
+    if dry_run {
+        return Ok(());
+    }
+    if !is_valid && !force {
+        eprintln!(
+            "ERROR: not writing keywords which fail standardization (use --force to override)"
+        );
+        return Err(());
+    }
+    let write_conf = config::WriteConfig {
+        delim: TEXTDelim::new(raw.parse.delimiter).unwrap_or_default(),
+        ..config::WriteConfig::default()
+    };
+    let patched = fcs_edit_text(filepath, outpath, raw, read_conf, &write_conf)
+        .map(handle_warnings)
+        .map_err(handle_failure)?;
+    if !patched {
+        eprintln!("could not patch TEXT in place; rewrote entire file");
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), ()> {
     let begintext_arg = arg!(--"begintext-delta" [OFFSET] "adjustment for begin TEXT offset")
         .value_parser(value_parser!(i32));
@@ -116,6 +319,8 @@ fn main() -> Result<(), ()> {
     let allow_negative = arg!(--"allow-negative" "substitute 0 for negative offsets");
     let allow_dup_stext = arg!(--"allow-dup-stext" "only throw warning if STEXT is same as TEXT");
     let ignore_stext = arg!(--"ignore-stext" "ignore STEXT entirely");
+    let report_arg = arg!(--report [PATH] "write all warnings and errors to this file as JSON")
+        .value_parser(value_parser!(PathBuf));
 
     let cmd = Command::new("fireflow")
         .about("read and write FCS files")
@@ -124,6 +329,11 @@ fn main() -> Result<(), ()> {
                 .value_parser(value_parser!(PathBuf))
                 .required(true)
         )
+        .arg(
+            arg!(--strictness [PROFILE] "how strictly to follow the standard when reading")
+                .value_parser(["pedantic", "strict", "default", "lenient", "permissive"])
+                .global(true)
+        )
 
         .subcommand(
             Command::new("header")
@@ -147,6 +357,13 @@ fn main() -> Result<(), ()> {
                 .arg(&allow_negative)
                 .arg(&allow_dup_stext)
                 .arg(&ignore_stext)
+                .arg(arg!(--"allow-nonunique" "only warn if a key appears more than once"))
+                .arg(arg!(--"last-wins" "keep the last value of duplicated keys"))
+                .arg(
+                    arg!(-e --encoding [ENCODING] "encoding used to decode TEXT")
+                        .value_parser(["utf8", "latin1", "utf8-latin1"])
+                        .default_value("utf8"),
+                )
         )
 
         .subcommand(
@@ -154,6 +371,7 @@ fn main() -> Result<(), ()> {
                 .about("dump standardized keywords as JSON")
                 .arg(arg!(-H --header "also show header"))
                 .arg(arg!(-r --raw "also show raw"))
+                .arg(arg!(-f --full "dump complete TEXT including nonstandard keywords and warnings"))
                 .arg(&begintext_arg)
                 .arg(&endtext_arg)
                 .arg(arg!(-t --"time-name" [NAME] "name of time measurement"))
@@ -163,7 +381,10 @@ fn main() -> Result<(), ()> {
                 .arg(arg!(-d --"allow-pseudostandard" "allow pseudostandard keywords"))
                 .arg(arg!(-D --"disallow-deprecated" "disallow deprecated keywords"))
                 .arg(arg!(-p --"date-pattern" [PATTERN] "pattern to use when matching $DATE"))
-                .arg(arg!(-P --"ns-meas-pattern" [PATTERN] "pattern used to for nonstandard measurement keywords"))
+                .arg(arg!(-P --"ns-meas-pattern" [PATTERN]... "regexp pattern(s) used for nonstandard measurement keywords"))
+                .arg(arg!(--"ns-meas-prefix" [PREFIX]... "literal prefix(es) used for nonstandard measurement keywords"))
+                .arg(arg!(--"ns-meas-ignore-case" "match nonstandard measurement patterns case-insensitively"))
+                .arg(arg!(-V --vendor [VENDOR] "parse vendor keywords (off, auto, bd, cytek, beckman)"))
                 .arg(&repair_offset_spaces_arg)
                 .arg(&max_other)
                 .arg(&other_width)
@@ -171,11 +392,14 @@ fn main() -> Result<(), ()> {
                 .arg(&allow_negative)
                 .arg(&allow_dup_stext)
                 .arg(&ignore_stext)
+                .arg(&report_arg)
         )
 
         .subcommand(
             Command::new("measurements")
+                .visible_alias("meas-table")
                 .about("show a table of standardized measurement values")
+                .arg(arg!(-j --json "print measurements as JSON"))
                 .arg(&begintext_arg)
                 .arg(&endtext_arg)
                 .arg(&delim_arg)
@@ -189,6 +413,63 @@ fn main() -> Result<(), ()> {
                 .arg(&ignore_stext)
         )
 
+        .subcommand(
+            Command::new("optics")
+                .about("show lasers and detectors from $PnL/$PnO/$PnV/$PnF as JSON")
+                .arg(&begintext_arg)
+                .arg(&endtext_arg)
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("hardware")
+                .about("check $PnL and $PnF against an instrument description")
+                .arg(
+                    arg!(<HARDWARE_PATH> "path to JSON file describing lasers and filters")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(&begintext_arg)
+                .arg(&endtext_arg)
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("keywords")
+                .about("set or delete keywords in TEXT")
+                .arg(
+                    arg!(-s --set [KEYVAL] "keyword to set, as KEY=VALUE")
+                        .action(ArgAction::Append)
+                )
+                .arg(
+                    arg!(-x --delete [KEY] "keyword to delete")
+                        .action(ArgAction::Append)
+                )
+                .arg(arg!(-n --"dry-run" "only show changes and resulting problems"))
+                .arg(arg!(-f --force "write changes even if they break standardization"))
+                .arg(
+                    arg!(-O --output [OUTPUT_PATH] "output path (default is to edit in place)")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+        )
+
         .subcommand(
             Command::new("spillover")
                 .about("dump the spillover matrix if present")
@@ -205,9 +486,275 @@ fn main() -> Result<(), ()> {
                 .arg(&ignore_stext)
         )
 
+        .subcommand(
+            Command::new("subsample")
+                .about("write a new file with a subset of events")
+                .arg(
+                    arg!(<OUTPUT_PATH> "path to write subsampled file")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(-n --events [N] "number of events to keep")
+                        .value_parser(value_parser!(usize))
+                        .conflicts_with("fraction")
+                )
+                .arg(
+                    arg!(-f --fraction [FRAC] "fraction of events to keep")
+                        .value_parser(value_parser!(f64))
+                )
+                .arg(
+                    arg!(-s --seed [SEED] "seed for random subsampling")
+                        .value_parser(value_parser!(u64))
+                        .default_value("0")
+                )
+                .arg(arg!(-S --systematic "take every k-th event rather than random events"))
+                .arg(
+                    arg!(-H --head "take the first events rather than random events")
+                        .conflicts_with("systematic")
+                )
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("convert")
+                .about("write a new file converted to another FCS version")
+                .arg(
+                    arg!(<OUTPUT_PATH> "path to write converted file")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(-t --to <VERSION> "FCS version to convert to")
+                        .value_parser(["2.0", "3.0", "3.1", "3.2"])
+                )
+                .arg(arg!(-F --force "drop keywords which cannot be converted"))
+                .arg(arg!(-c --cyt [CYT] "set $CYT before converting (required for 3.2)"))
+                .arg(
+                    arg!(-d --datatype [TYPE] "convert DATA to this $DATATYPE")
+                        .value_parser(["A", "I", "F", "D"])
+                )
+                .arg(
+                    arg!(-m --"meas-datatype" [SPEC] "convert one measurement like NAME=TYPE where TYPE is I, F, or D (3.2 only, may be repeated)")
+                        .action(ArgAction::Append)
+                )
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("normalize")
+                .about("read any version permissively and write a repaired FCS 3.2 file")
+                .arg(
+                    arg!(<OUTPUT_PATH> "path to write normalized file")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(arg!(-B --"big-endian" "write DATA as big endian rather than little endian"))
+                .arg(
+                    arg!(-d --datatype [TYPE] "convert DATA to this $DATATYPE")
+                        .value_parser(["A", "I", "F", "D"])
+                )
+                .arg(arg!(-S --strict "fail on keywords which cannot be converted rather than dropping them"))
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("merge")
+                .about("write a new file with events from other files appended")
+                .arg(
+                    arg!(<OUTPUT_PATH> "path to write merged file")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(-a --append <PATH> "file with events to append (may be repeated)")
+                        .value_parser(value_parser!(PathBuf))
+                        .action(ArgAction::Append)
+                )
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("split")
+                .about("write new files with subsets of events")
+                .arg(
+                    arg!(<OUTPUT_PREFIX> "prefix of files to write, which will be numbered from 1")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(-r --range [RANGE] "events to write to one file as START:END indexed from 0 and excluding END (may be repeated)")
+                        .action(ArgAction::Append)
+                        .conflicts_with_all(["chunk", "trigger"])
+                )
+                .arg(
+                    arg!(-c --chunk [N] "write consecutive files with N events each")
+                        .value_parser(value_parser!(NonZeroUsize))
+                        .conflicts_with("trigger")
+                )
+                .arg(arg!(-t --trigger "write events above $TR to the first file and others to the second"))
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("anonymize")
+                .about("write a new file without keywords which may identify a patient or sample")
+                .arg(
+                    arg!(<OUTPUT_PATH> "path to write anonymized file")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(-k --key [KEY] "also remove this keyword (may be repeated)")
+                        .action(ArgAction::Append)
+                )
+                .arg(
+                    arg!(-H --hash [KEY] "replace value of this keyword with a hash (may be repeated)")
+                        .action(ArgAction::Append)
+                )
+                .arg(arg!(-s --salt [SALT] "string to prepend to values before hashing"))
+                .arg(arg!(-N --"remove-nonstandard" "also remove all non-standard keywords"))
+                .arg(arg!(-c --"copy-data" "copy DATA byte-for-byte from the original file"))
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
         .subcommand(
             Command::new("data")
                 .about("show a table of the DATA segment")
+                .arg(
+                    arg!(-f --format [FORMAT] "output format")
+                        .value_parser(["table", "csv", "json"])
+                        .default_value("table"),
+                )
+                .arg(
+                    arg!(-m --measurement [NAME] "only show this measurement (may be repeated)")
+                        .action(ArgAction::Append)
+                )
+                .arg(
+                    arg!(--skip [N] "number of events to skip")
+                        .value_parser(value_parser!(usize))
+                )
+                .arg(
+                    arg!(-n --events [N] "maximum number of events to show")
+                        .value_parser(value_parser!(usize))
+                )
+                .arg(
+                    arg!(--sample [N] "randomly sample this many events while reading")
+                        .value_parser(value_parser!(usize))
+                )
+                .arg(
+                    arg!(--"sample-seed" [SEED] "seed for random sampling")
+                        .value_parser(value_parser!(u64))
+                        .default_value("0")
+                )
+                .arg(arg!(--"sample-stride" "sample every k-th event rather than random events"))
+                .arg(arg!(--metrics "print timings and bytes read to stderr"))
+                .arg(&begintext_arg)
+                .arg(&endtext_arg)
+                .arg(&begindata_arg)
+                .arg(&enddata_arg)
+                .arg(&repair_offset_spaces_arg)
+                .arg(&delim_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+                .arg(&report_arg)
+        )
+
+        .subcommand(
+            Command::new("stats")
+                .about("show summary statistics for each measurement")
+                .arg(arg!(-j --json "print statistics as JSON"))
+                .arg(arg!(-r --raw "use values as stored in DATA rather than scaled values"))
+                .arg(
+                    arg!(-p --percentile [P] "percentile to show, from 0 to 100 (may be repeated)")
+                        .value_parser(value_parser!(f64))
+                        .action(ArgAction::Append)
+                )
+                .arg(&begintext_arg)
+                .arg(&endtext_arg)
+                .arg(&begindata_arg)
+                .arg(&enddata_arg)
+                .arg(&repair_offset_spaces_arg)
+                .arg(&delim_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("ranges")
+                .about("compare $PnR to $PnB and DATA for each integer measurement")
+                .arg(arg!(-j --json "print audit as JSON"))
+                .arg(arg!(-t --truncated "only show measurements whose $PnR cannot be stored"))
+                .arg(&begintext_arg)
+                .arg(&endtext_arg)
+                .arg(&begindata_arg)
+                .arg(&enddata_arg)
+                .arg(&repair_offset_spaces_arg)
+                .arg(&delim_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("histogram")
+                .about("show histogram counts for each measurement")
+                .arg(arg!(-j --json "print histograms as JSON"))
+                .arg(arg!(-r --raw "use values as stored in DATA rather than scaled values"))
+                .arg(
+                    arg!(-m --measurement [NAME] "only show this measurement (may be repeated)")
+                        .action(ArgAction::Append)
+                )
+                .arg(
+                    arg!(-b --bins [N] "number of bins")
+                        .value_parser(value_parser!(NonZeroUsize))
+                        .default_value("64")
+                )
+                .arg(
+                    arg!(-s --scale [SCALE] "bin spacing: linear, log, logicle, or logicle:T,W,M,A")
+                        .default_value("linear")
+                )
                 .arg(&begintext_arg)
                 .arg(&endtext_arg)
                 .arg(&begindata_arg)
@@ -220,11 +767,43 @@ fn main() -> Result<(), ()> {
                 .arg(&allow_negative)
                 .arg(&allow_dup_stext)
                 .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("validate")
+                .about("check HEADER and TEXT and exit with non-zero status if file is invalid")
+                .arg(arg!(-W --"warnings-are-errors" "also fail if there are any warnings"))
+                .arg(arg!(-l --lenient "only fail on issues which cannot be ignored"))
+                .arg(arg!(-j --json "print conformance report as JSON"))
+                .arg(
+                    arg!(--"datetime-tolerance" [SECONDS] "cross-check time keywords with this tolerance")
+                        .value_parser(value_parser!(u32))
+                )
+                .arg(&repair_offset_spaces_arg)
+                .arg(&max_other)
+                .arg(&other_width)
+                .arg(&squish_offsets)
+                .arg(&allow_negative)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
+        )
+
+        .subcommand(
+            Command::new("scan")
+                .about("summarize HEADER and TEXT of all FCS files in a directory as a table")
+                .arg(arg!(-j --json "print summaries as JSON"))
+                .arg(&delim_arg)
+                .arg(&repair_offset_spaces_arg)
+                .arg(&allow_dup_stext)
+                .arg(&ignore_stext)
         );
 
     let args = cmd.get_matches();
 
     let filepath = args.get_one::<PathBuf>("INPUT_PATH").unwrap();
+    let profile = args
+        .get_one::<String>("strictness")
+        .and_then(|p| p.parse::<config::Profile>().ok());
 
     // let get_text_delta = |args: &ArgMatches| {
     //     let mut begin = 0;
@@ -258,7 +837,7 @@ fn main() -> Result<(), ()> {
         }
 
         Some(("raw", sargs)) => {
-            let mut conf = config::RawTextReadConfig::default();
+            let mut conf = config::RawTextReadConfig::from_profile(profile.unwrap_or_default());
             conf.header = config::HeaderConfig {
                 max_other: sargs.get_one::<usize>("max-other").copied(),
                 other_width: sargs
@@ -270,10 +849,17 @@ fn main() -> Result<(), ()> {
                 squish_offsets: sargs.get_flag("squish-offsets"),
                 ..conf.header
             };
+            set_flag(&mut conf.trim_value_whitespace, sargs, "trim-whitespace");
+            set_flag(&mut conf.allow_duplicated_stext, sargs, "allow-dup-stext");
+            set_flag(&mut conf.ignore_stext, sargs, "ignore-stext");
             conf = config::RawTextReadConfig {
-                trim_value_whitespace: sargs.get_flag("trim-whitespace"),
-                allow_duplicated_stext: sargs.get_flag("allow-dup-stext"),
-                ignore_stext: sargs.get_flag("ignore-stext"),
+                allow_nonunique: conf.allow_nonunique || sargs.get_flag("allow-nonunique"),
+                nonunique_last_wins: sargs.get_flag("last-wins"),
+                text_encoding: sargs
+                    .get_one::<String>("encoding")
+                    .unwrap()
+                    .parse()
+                    .unwrap_or_default(),
                 ..conf
             };
             fcs_read_raw_text(filepath, &conf)
@@ -283,7 +869,7 @@ fn main() -> Result<(), ()> {
         }
 
         Some(("spillover", sargs)) => {
-            let mut conf = config::StdTextReadConfig::default();
+            let mut conf = config::StdTextReadConfig::from_profile(profile.unwrap_or_default());
             conf.raw.header = config::HeaderConfig {
                 max_other: sargs.get_one::<usize>("max-other").copied(),
                 other_width: sargs
@@ -296,8 +882,16 @@ fn main() -> Result<(), ()> {
                 ..conf.raw.header
             };
             // get_text_delta(sargs);
-            conf.raw.trim_value_whitespace = sargs.get_flag("trim-whitespace");
-            conf.raw.allow_duplicated_stext = sargs.get_flag("allow-dup-stext");
+            set_flag(
+                &mut conf.raw.trim_value_whitespace,
+                sargs,
+                "trim-whitespace",
+            );
+            set_flag(
+                &mut conf.raw.allow_duplicated_stext,
+                sargs,
+                "allow-dup-stext",
+            );
             let delim = sargs.get_one::<String>("delimiter").unwrap();
 
             fcs_read_std_text(filepath, &conf)
@@ -307,7 +901,7 @@ fn main() -> Result<(), ()> {
         }
 
         Some(("measurements", sargs)) => {
-            let mut conf = config::StdTextReadConfig::default();
+            let mut conf = config::StdTextReadConfig::from_profile(profile.unwrap_or_default());
             conf.raw.header = config::HeaderConfig {
                 max_other: sargs.get_one::<usize>("max-other").copied(),
                 other_width: sargs
@@ -320,19 +914,130 @@ fn main() -> Result<(), ()> {
                 ..conf.raw.header
             };
             // get_text_delta(sargs);
-            conf.raw.trim_value_whitespace = sargs.get_flag("trim-whitespace");
-            conf.raw.allow_duplicated_stext = sargs.get_flag("allow-dup-stext");
-            conf.raw.ignore_stext = sargs.get_flag("ignore-stext");
+            set_flag(
+                &mut conf.raw.trim_value_whitespace,
+                sargs,
+                "trim-whitespace",
+            );
+            set_flag(
+                &mut conf.raw.allow_duplicated_stext,
+                sargs,
+                "allow-dup-stext",
+            );
+            set_flag(&mut conf.raw.ignore_stext, sargs, "ignore-stext");
             let delim = sargs.get_one::<String>("delimiter").unwrap();
+            let json = sargs.get_flag("json");
 
             fcs_read_std_text(filepath, &conf)
                 .map(handle_warnings)
-                .map(|std| std.standardized.print_meas_table(delim))
+                .map(|std| {
+                    if json {
+                        print_json(&std.standardized.meas_table())
+                    } else {
+                        std.standardized.print_meas_table(delim)
+                    }
+                })
+                .map_err(handle_failure)
+        }
+
+        Some(("optics", sargs)) => {
+            let mut conf = config::StdTextReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.raw, sargs);
+
+            fcs_read_std_text(filepath, &conf)
+                .map(handle_warnings)
+                .map(|std| print_json(&std.standardized.optical_config()))
                 .map_err(handle_failure)
         }
 
+        Some(("hardware", sargs)) => {
+            let mut conf = config::StdTextReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.raw, sargs);
+            let hwpath = sargs.get_one::<PathBuf>("HARDWARE_PATH").unwrap();
+            let hw: InstrumentHardware = fs::read_to_string(hwpath)
+                .map_err(|e| e.to_string())
+                .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+                .map_err(|e| eprintln!("ERROR: could not load hardware: {e}"))?;
+
+            fcs_read_std_text(filepath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)
+                .and_then(|std| {
+                    std.standardized.check_hardware(&hw).map_err(|es| {
+                        for e in es {
+                            eprintln!("ERROR: {e}");
+                        }
+                    })
+                })
+        }
+
+        Some(("keywords", sargs)) => {
+            let mut conf = config::StdTextReadConfig::from_profile(profile.unwrap_or_default());
+            conf.raw.header = config::HeaderConfig {
+                max_other: sargs.get_one::<usize>("max-other").copied(),
+                other_width: sargs
+                    .get_one::<u8>("other-width")
+                    .copied()
+                    .map(|x| x.try_into().unwrap())
+                    .unwrap_or_default(),
+                allow_negative: sargs.get_flag("allow-negative"),
+                squish_offsets: sargs.get_flag("squish-offsets"),
+                ..conf.raw.header
+            };
+            set_flag(
+                &mut conf.raw.trim_value_whitespace,
+                sargs,
+                "trim-whitespace",
+            );
+            set_flag(
+                &mut conf.raw.allow_duplicated_stext,
+                sargs,
+                "allow-dup-stext",
+            );
+            let dry_run = sargs.get_flag("dry-run");
+            let outpath = sargs.get_one::<PathBuf>("output").unwrap_or(filepath);
+            let sets: Vec<_> = sargs
+                .get_many::<String>("set")
+                .into_iter()
+                .flatten()
+                .map(|x| {
+                    x.split_once("=")
+                        .map(|(k, v)| (k.to_string(), Some(v.to_string())))
+                        .ok_or_else(|| eprintln!("ERROR: --set must be like KEY=VALUE, got '{x}'"))
+                })
+                .collect::<Result<_, _>>()?;
+            let deletes = sargs
+                .get_many::<String>("delete")
+                .into_iter()
+                .flatten()
+                .map(|k| (k.clone(), None));
+
+            let mut raw = fcs_read_raw_text(filepath, &conf.raw)
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+
+            for (k, v) in sets.into_iter().chain(deletes) {
+                let res = match v {
+                    Some(x) => raw.keywords.set(&k, x.clone()).map(|old| (old, Some(x))),
+                    None => raw
+                        .keywords
+                        .delete(&k)
+                        .map(|old| (old, None))
+                        .map_err(KeywordSetError::from),
+                };
+                let (old, new) = res.map_err(|e| eprintln!("ERROR: {e}"))?;
+                let show = |x: Option<String>| x.map_or("<none>".into(), |y| format!("'{y}'"));
+                println!("{k}: {} -> {}", show(old), show(new));
+            }
+
+            let mut read_conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            read_conf.standard = conf;
+            let force = sargs.get_flag("force");
+            edit_keywords(filepath, outpath, raw, &read_conf, dry_run, force)
+        }
+
         Some(("std", sargs)) => {
-            let mut conf = config::StdTextReadConfig::default();
+            let mut conf = config::StdTextReadConfig::from_profile(profile.unwrap_or_default());
 
             conf.raw.header = config::HeaderConfig {
                 max_other: sargs.get_one::<usize>("max-other").copied(),
@@ -352,34 +1057,332 @@ fn main() -> Result<(), ()> {
                 conf.raw.date_pattern = Some(d.parse::<DatePattern>().unwrap());
             }
 
-            if let Some(m) = sargs.get_one::<String>("ns-meas-pattern").cloned() {
-                conf.nonstandard_measurement_pattern =
-                    Some(m.parse::<NonStdMeasPattern>().unwrap());
-            }
+            let ns_ignore_case = sargs.get_flag("ns-meas-ignore-case");
+            let ns_pats = sargs
+                .get_many::<String>("ns-meas-pattern")
+                .into_iter()
+                .flatten()
+                .map(|m| NonStdMeasPattern::new(m, false, ns_ignore_case));
+            let ns_prefixes = sargs
+                .get_many::<String>("ns-meas-prefix")
+                .into_iter()
+                .flatten()
+                .map(|m| NonStdMeasPattern::new(m, true, ns_ignore_case));
+            conf.nonstandard_measurement_patterns = ns_pats
+                .chain(ns_prefixes)
+                .collect::<Result<_, _>>()
+                .unwrap();
 
             if let Some(m) = sargs.get_one::<String>("time-name").cloned() {
                 conf.time.pattern = Some(m.parse::<TimePattern>().unwrap());
             }
 
+            if let Some(v) = sargs.get_one::<String>("vendor") {
+                conf.vendor = v.parse().unwrap_or_default();
+            }
+
             conf.time.allow_missing = sargs.get_flag("ensure-time");
-            conf.raw.allow_duplicated_stext = sargs.get_flag("allow-dup-stext");
-            conf.raw.ignore_stext = sargs.get_flag("ignore-stext");
+            set_flag(
+                &mut conf.raw.allow_duplicated_stext,
+                sargs,
+                "allow-dup-stext",
+            );
+            set_flag(&mut conf.raw.ignore_stext, sargs, "ignore-stext");
             // conf.time.allow_nonlinear_scale = sargs.get_flag("ensure-time-linear");
             // conf.time.allow_nontime_keywords = sargs.get_flag("ensure-time-nogain");
-            conf.allow_pseudostandard = sargs.get_flag("allow-pseudostandard");
-            conf.disallow_deprecated = sargs.get_flag("disallow-deprecated");
-            conf.raw.trim_value_whitespace = sargs.get_flag("trim-whitespace");
+            set_flag(
+                &mut conf.allow_pseudostandard,
+                sargs,
+                "allow-pseudostandard",
+            );
+            set_flag(&mut conf.disallow_deprecated, sargs, "disallow-deprecated");
+            set_flag(
+                &mut conf.raw.trim_value_whitespace,
+                sargs,
+                "trim-whitespace",
+            );
 
-            fcs_read_std_text(filepath, &conf)
-                .map(handle_warnings)
-                .map(|std| {
+            if let Some(p) = sargs.get_one::<PathBuf>("report") {
+                let (std, report) = handle_with_report(fcs_read_std_text(filepath, &conf), p)?;
+                if sargs.get_flag("full") {
+                    let ws: Vec<_> = report
+                        .entries
+                        .iter()
+                        .filter(|e| e.severity != Severity::Error)
+                        .map(|e| &e.message)
+                        .collect();
+                    print_json(&std.to_json(&ws));
+                } else {
                     print_json(&std.standardized);
+                }
+                Ok(())
+            } else if sargs.get_flag("full") {
+                fcs_read_std_text(filepath, &conf)
+                    .map(|t| {
+                        let (std, ws) = t.resolve(|ws| ws);
+                        print_json(&std.to_json(&ws));
+                    })
+                    .map_err(handle_failure)
+            } else {
+                fcs_read_std_text(filepath, &conf)
+                    .map(handle_warnings)
+                    .map(|std| {
+                        print_json(&std.standardized);
+                    })
+                    .map_err(handle_failure)
+            }
+        }
+
+        Some(("subsample", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            let outpath = sargs.get_one::<PathBuf>("OUTPUT_PATH").unwrap();
+            let size = match (
+                sargs.get_one::<usize>("events"),
+                sargs.get_one::<f64>("fraction"),
+            ) {
+                (Some(n), _) => SubsampleSize::Count(*n),
+                (_, Some(f)) => SubsampleSize::Fraction(*f),
+                _ => {
+                    eprintln!("ERROR: one of --events or --fraction is required");
+                    return Err(());
+                }
+            };
+            let method = if sargs.get_flag("systematic") {
+                SubsampleMethod::Systematic
+            } else if sargs.get_flag("head") {
+                SubsampleMethod::Head
+            } else {
+                SubsampleMethod::Random {
+                    seed: *sargs.get_one::<u64>("seed").unwrap(),
+                }
+            };
+
+            let res = fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+            let core = res.dataset.standardized.core;
+            let sub = core.subsample(size, method);
+            let write_conf = config::WriteConfig {
+                track_changes: Some(ChangeTracker::new(&core)),
+                ..config::WriteConfig::default()
+            };
+            fcs_write_dataset(outpath, &sub, &write_conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)
+        }
+
+        Some(("convert", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            let outpath = sargs.get_one::<PathBuf>("OUTPUT_PATH").unwrap();
+            let version: Version = format!("FCS{}", sargs.get_one::<String>("to").unwrap())
+                .parse()
+                .ok()
+                .unwrap();
+            let datatype = sargs
+                .get_one::<String>("datatype")
+                .map(|x| x.parse::<AlphaNumType>().ok().unwrap());
+            let overrides = sargs
+                .get_many::<String>("meas-datatype")
+                .into_iter()
+                .flatten()
+                .map(|x| {
+                    x.split_once('=')
+                        .and_then(|(n, t)| Some((n.parse().ok()?, t.parse().ok()?)))
+                        .ok_or_else(|| {
+                            eprintln!("ERROR: datatype must be like NAME=TYPE, got '{x}'");
+                        })
                 })
+                .collect::<Result<Vec<(Shortname, NumType)>, _>>()?;
+
+            let res = fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+            let mut core = res.dataset.standardized.core;
+            let tracker = ChangeTracker::new(&core);
+            if let Some(cyt) = sargs.get_one::<String>("cyt") {
+                core.set_cyt(Some(Cyt::from(cyt.clone())))
+                    .map_err(|e| eprintln!("ERROR: {e}"))?;
+            }
+            let new = fcs_convert_dataset(core, version, sargs.get_flag("force"))
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+            let write_conf = config::WriteConfig {
+                track_changes: Some(tracker),
+                target_datatype: config::TargetDatatype {
+                    datatype,
+                    overrides,
+                },
+                ..config::WriteConfig::default()
+            };
+            fcs_write_dataset(outpath, &new, &write_conf)
+                .map(handle_warnings)
                 .map_err(handle_failure)
         }
 
+        Some(("normalize", sargs)) => {
+            let mut conf = config::NormalizeConfig::default();
+            conf.read.standard.raw.header = config::HeaderConfig {
+                max_other: sargs.get_one::<usize>("max-other").copied(),
+                other_width: sargs
+                    .get_one::<u8>("other-width")
+                    .copied()
+                    .map(|x| x.try_into().unwrap())
+                    .unwrap_or_default(),
+                allow_negative: sargs.get_flag("allow-negative"),
+                squish_offsets: sargs.get_flag("squish-offsets"),
+                ..conf.read.standard.raw.header
+            };
+            set_flag(
+                &mut conf.read.standard.raw.allow_duplicated_stext,
+                sargs,
+                "allow-dup-stext",
+            );
+            set_flag(
+                &mut conf.read.standard.raw.ignore_stext,
+                sargs,
+                "ignore-stext",
+            );
+            let outpath = sargs.get_one::<PathBuf>("OUTPUT_PATH").unwrap();
+            conf.force = !sargs.get_flag("strict");
+            if sargs.get_flag("big-endian") {
+                conf.endian = Endian::Big;
+            }
+            conf.write.target_datatype.datatype = sargs
+                .get_one::<String>("datatype")
+                .map(|x| x.parse::<AlphaNumType>().ok().unwrap());
+
+            fcs_normalize(filepath, outpath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)
+        }
+
+        Some(("merge", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            let outpath = sargs.get_one::<PathBuf>("OUTPUT_PATH").unwrap();
+
+            let res = fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+            let core = res.dataset.standardized.core;
+            let others = sargs
+                .get_many::<PathBuf>("append")
+                .unwrap()
+                .map(|p| {
+                    fcs_read_std_dataset(p, &conf)
+                        .map(handle_warnings)
+                        .map_err(handle_failure)
+                        .map(|r| r.dataset.standardized.core)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let merged = core.concat(&others[..]).map_err(|e| {
+                eprintln!("ERROR: {e}");
+            })?;
+            let write_conf = config::WriteConfig {
+                track_changes: Some(ChangeTracker::new(&core)),
+                ..config::WriteConfig::default()
+            };
+            fcs_write_dataset(outpath, &merged, &write_conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)
+        }
+
+        Some(("split", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            let prefix = sargs.get_one::<PathBuf>("OUTPUT_PREFIX").unwrap();
+
+            let res = fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+            let core = res.dataset.standardized.core;
+            let print_err = |e: SplitError| eprintln!("ERROR: {e}");
+            let parts = if sargs.get_flag("trigger") {
+                let Some(mask) = core.trigger_mask() else {
+                    eprintln!("ERROR: $TR is not set or its measurement is not in DATA");
+                    return Err(());
+                };
+                core.split_mask(&mask[..])
+                    .map(|(x, y)| vec![x, y])
+                    .map_err(print_err)?
+            } else if let Some(k) = sargs.get_one::<NonZeroUsize>("chunk") {
+                let n = core.nrows();
+                let ranges: Vec<_> = (0..n)
+                    .step_by(k.get())
+                    .map(|i| i..(i + k.get()).min(n))
+                    .collect();
+                core.split_ranges(&ranges[..]).map_err(print_err)?
+            } else {
+                let ranges = sargs
+                    .get_many::<String>("range")
+                    .into_iter()
+                    .flatten()
+                    .map(|r| {
+                        r.split_once(':')
+                            .and_then(|(a, b)| Some(a.parse().ok()?..b.parse().ok()?))
+                            .ok_or_else(|| {
+                                eprintln!("ERROR: range must be like START:END, got '{r}'");
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if ranges.is_empty() {
+                    eprintln!("ERROR: one of --range, --chunk, or --trigger is required");
+                    return Err(());
+                }
+                core.split_ranges(&ranges[..]).map_err(print_err)?
+            };
+            let write_conf = config::WriteConfig {
+                track_changes: Some(ChangeTracker::new(&core)),
+                ..config::WriteConfig::default()
+            };
+            for (i, part) in parts.iter().enumerate() {
+                let mut name = prefix.as_os_str().to_owned();
+                name.push(format!("{}.fcs", i + 1));
+                fcs_write_dataset(&PathBuf::from(name), part, &write_conf)
+                    .map(handle_warnings)
+                    .map_err(handle_failure)?;
+            }
+            Ok(())
+        }
+
+        Some(("anonymize", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            let mut anon = Anonymizer::identifying();
+            if let Some(ks) = sargs.get_many::<String>("key") {
+                anon.remove.extend(ks.cloned());
+            }
+            if let Some(ks) = sargs.get_many::<String>("hash") {
+                anon.hash.extend(ks.cloned());
+            }
+            if let Some(salt) = sargs.get_one::<String>("salt") {
+                anon.salt = salt.clone();
+            }
+            anon.remove_nonstandard = sargs.get_flag("remove-nonstandard");
+            conf.standard.raw.anonymize = anon;
+            let outpath = sargs.get_one::<PathBuf>("OUTPUT_PATH").unwrap();
+
+            let res = fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map_err(handle_failure)?;
+            for m in res.parse.keyword_repairs.iter() {
+                eprintln!("{m}");
+            }
+            let write_conf = config::WriteConfig::default();
+            let ds = &res.dataset.standardized;
+            if sargs.get_flag("copy-data") {
+                fcs_write_dataset_copy_data(filepath, outpath, ds, &write_conf)
+            } else {
+                fcs_write_dataset(outpath, &ds.core, &write_conf)
+            }
+            .map(handle_warnings)
+            .map_err(handle_failure)
+        }
+
         Some(("data", sargs)) => {
-            let mut conf = config::DataReadConfig::default();
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
 
             conf.standard.raw.header = config::HeaderConfig {
                 max_other: sargs.get_one::<usize>("max-other").copied(),
@@ -395,17 +1398,329 @@ fn main() -> Result<(), ()> {
 
             // get_text_delta(sargs);
             // TODO add DATA delta adjust
-            conf.standard.raw.allow_duplicated_stext = sargs.get_flag("allow-dup-stext");
-            conf.standard.raw.ignore_stext = sargs.get_flag("ignore-stext");
-            conf.standard.raw.trim_value_whitespace = sargs.get_flag("trim-whitespace");
+            set_flag(
+                &mut conf.standard.raw.allow_duplicated_stext,
+                sargs,
+                "allow-dup-stext",
+            );
+            set_flag(&mut conf.standard.raw.ignore_stext, sargs, "ignore-stext");
+            set_flag(
+                &mut conf.standard.raw.trim_value_whitespace,
+                sargs,
+                "trim-whitespace",
+            );
+            conf.standard.raw.collect_metrics = sargs.get_flag("metrics");
+            if let Some(ms) = sargs.get_many::<String>("measurement") {
+                let mut names = vec![];
+                for m in ms {
+                    match m.parse::<Shortname>() {
+                        Ok(n) => names.push(n),
+                        Err(e) => {
+                            eprintln!("ERROR: {e}");
+                            return Err(());
+                        }
+                    }
+                }
+                conf.reader.selection.measurements = Some(names);
+            }
+            let skip = sargs.get_one::<usize>("skip").copied();
+            let events = sargs.get_one::<usize>("events").copied();
+            if skip.is_some() || events.is_some() {
+                let start = skip.unwrap_or(0);
+                let end = events.map_or(usize::MAX, |n| start.saturating_add(n));
+                conf.reader.selection.event_range = Some(start..end);
+            }
+            if let Some(n) = sargs.get_one::<usize>("sample") {
+                let method = if sargs.get_flag("sample-stride") {
+                    SubsampleMethod::Systematic
+                } else {
+                    SubsampleMethod::Random {
+                        seed: *sargs.get_one::<u64>("sample-seed").unwrap(),
+                    }
+                };
+                conf.reader.selection.subsample = Some((SubsampleSize::Count(*n), method));
+            }
+            let format = sargs.get_one::<String>("format").unwrap();
+            let delim = if format == "csv" {
+                ","
+            } else {
+                sargs.get_one::<String>("delimiter").unwrap()
+            };
+
+            let res = if let Some(p) = sargs.get_one::<PathBuf>("report") {
+                handle_with_report(fcs_read_std_dataset(filepath, &conf), p)?.0
+            } else {
+                fcs_read_std_dataset(filepath, &conf)
+                    .map(handle_warnings)
+                    .map_err(handle_failure)?
+            };
+            if let Some(m) = &res.parse.metrics {
+                print_metrics(m);
+            }
+            if format == "json" {
+                print_parsed_data_json(&res)
+            } else {
+                print_parsed_data(&res, delim)
+            }
+            Ok(())
+        }
+
+        Some(("stats", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            let delim = sargs.get_one::<String>("delimiter").unwrap();
+            let json = sargs.get_flag("json");
+            let raw = if sargs.get_flag("raw") {
+                None
+            } else {
+                Some(&[][..])
+            };
+            let percentiles: Vec<_> = sargs
+                .get_many::<f64>("percentile")
+                .map_or(DEFAULT_PERCENTILES.to_vec(), |ps| ps.copied().collect());
+
+            fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map(|res| {
+                    let ss = res.dataset.standardized.core.summary(raw, &percentiles);
+                    if json {
+                        print_json(&ss)
+                    } else {
+                        print_summary(&ss, &percentiles, delim)
+                    }
+                })
+                .map_err(handle_failure)
+        }
+
+        Some(("ranges", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
             let delim = sargs.get_one::<String>("delimiter").unwrap();
+            let json = sargs.get_flag("json");
+            let only_truncated = sargs.get_flag("truncated");
 
             fcs_read_std_dataset(filepath, &conf)
                 .map(handle_warnings)
-                .map(|res| print_parsed_data(&res, delim))
+                .map(|res| {
+                    let mut xs = res.dataset.standardized.core.range_audit();
+                    if only_truncated {
+                        xs.retain(|x| x.truncated);
+                    }
+                    if json {
+                        print_json(&xs)
+                    } else {
+                        print_range_audit(&xs, delim)
+                    }
+                })
                 .map_err(handle_failure)
         }
 
+        Some(("histogram", sargs)) => {
+            let mut conf = config::DataReadConfig::from_profile(profile.unwrap_or_default());
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            if let Some(ms) = sargs.get_many::<String>("measurement") {
+                let mut names = vec![];
+                for m in ms {
+                    match m.parse::<Shortname>() {
+                        Ok(n) => names.push(n),
+                        Err(e) => {
+                            eprintln!("ERROR: {e}");
+                            return Err(());
+                        }
+                    }
+                }
+                conf.reader.selection.measurements = Some(names);
+            }
+            let scale = match sargs
+                .get_one::<String>("scale")
+                .unwrap()
+                .parse::<BinScale>()
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    eprintln!("ERROR: {e}");
+                    return Err(());
+                }
+            };
+            let bins = *sargs.get_one::<NonZeroUsize>("bins").unwrap();
+            let delim = sargs.get_one::<String>("delimiter").unwrap();
+            let json = sargs.get_flag("json");
+            let raw = if sargs.get_flag("raw") {
+                None
+            } else {
+                Some(&[][..])
+            };
+
+            fcs_read_std_dataset(filepath, &conf)
+                .map(handle_warnings)
+                .map(|res| {
+                    let hs = res.dataset.standardized.core.histograms(raw, bins, &scale);
+                    if json {
+                        print_json(&hs)
+                    } else {
+                        print_histograms(&hs, delim)
+                    }
+                })
+                .map_err(handle_failure)
+        }
+
+        Some(("validate", sargs)) => {
+            let mut conf = if sargs.get_flag("lenient") {
+                config::DataReadConfig::from_profile(config::Profile::Lenient)
+            } else {
+                config::DataReadConfig::from_profile(profile.unwrap_or(config::Profile::Strict))
+            };
+
+            set_raw_text_args(&mut conf.standard.raw, sargs);
+            conf.standard.time.datetime_tolerance = sargs
+                .get_one::<u32>("datetime-tolerance")
+                .map(|x| chrono::TimeDelta::seconds((*x).into()));
+            set_flag(
+                &mut conf.shared.warnings_are_errors,
+                sargs,
+                "warnings-are-errors",
+            );
+            let strict = conf.shared.warnings_are_errors;
+
+            let report = fcs_validate(filepath, &conf);
+            let nerr = report.errors.len();
+            let nwarn = report.warnings.len();
+            let valid = report.is_valid() && !(strict && nwarn > 0);
+            if sargs.get_flag("json") {
+                print_json(&report);
+            } else {
+                for e in report.errors.iter() {
+                    eprintln!("ERROR: {e}");
+                }
+                print_warnings(report.warnings);
+                if valid {
+                    println!("OK");
+                } else {
+                    eprintln!("INVALID: {nerr} error(s) and {nwarn} warning(s) found");
+                }
+            }
+            if valid {
+                Ok(())
+            } else {
+                Err(())
+            }
+        }
+
+        Some(("scan", sargs)) => {
+            let mut conf = config::RawTextReadConfig::from_profile(profile.unwrap_or_default());
+            set_flag(&mut conf.allow_duplicated_stext, sargs, "allow-dup-stext");
+            set_flag(&mut conf.ignore_stext, sargs, "ignore-stext");
+            set_flag(&mut conf.trim_value_whitespace, sargs, "trim-whitespace");
+            let entries = fcs_scan_dir(filepath, &conf).map_err(|e| eprintln!("ERROR: {e}"))?;
+            if sargs.get_flag("json") {
+                print_json(&entries);
+            } else {
+                let delim = sargs.get_one::<String>("delimiter").unwrap();
+                let fmt_opt = |x: Option<String>| x.unwrap_or_default();
+                println!(
+                    "{}",
+                    ["path", "version", "tot", "par", "cyt", "date"].join(delim)
+                );
+                for e in entries {
+                    for err in e.errors.iter() {
+                        eprintln!("ERROR: {}: {err}", e.path.display());
+                    }
+                    if let Some(s) = e.summary {
+                        let row = [
+                            e.path.display().to_string(),
+                            s.version.to_string(),
+                            fmt_opt(s.tot.map(|x| x.to_string())),
+                            fmt_opt(s.par.map(|x| x.to_string())),
+                            fmt_opt(s.cyt.map(|x| x.to_string())),
+                            fmt_opt(s.date.map(|x| x.to_string())),
+                        ];
+                        println!("{}", row.join(delim));
+                    }
+                }
+            }
+            Ok(())
+        }
+
         _ => Ok(()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal FCS 3.0 file with one 8-bit measurement.
+    fn minimal_fcs(data: &[u8]) -> Vec<u8> {
+        let text_begin = 58;
+        let mut data_begin = text_begin;
+        loop {
+            let data_end = data_begin + data.len() - 1;
+            let text = format!(
+                "/$BEGINANALYSIS/0/$ENDANALYSIS/0/$BEGINSTEXT/0/$ENDSTEXT/0\
+                 /$BEGINDATA/{data_begin}/$ENDDATA/{data_end}/$BYTEORD/1\
+                 /$CYT/FACS/$DATATYPE/I/$MODE/L/$NEXTDATA/0/$PAR/1/$TOT/{}\
+                 /$P1B/8/$P1E/0,0/$P1N/FSC/$P1R/256/",
+                data.len()
+            );
+            let next_begin = text_begin + text.len();
+            if next_begin == data_begin {
+                let header = format!(
+                    "FCS3.0    {:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+                    text_begin,
+                    next_begin - 1,
+                    data_begin,
+                    data_end,
+                    0,
+                    0
+                );
+                let mut buf = header.into_bytes();
+                buf.extend(text.as_bytes());
+                buf.extend(data);
+                return buf;
+            }
+            data_begin = next_begin;
+        }
+    }
+
+    #[test]
+    fn test_set_flag_keeps_profile() {
+        let cmd = Command::new("test").arg(arg!(--"allow-dup-stext" "allow duplicated STEXT"));
+        let set = |args: &[&str], x: bool| {
+            let mut y = x;
+            set_flag(
+                &mut y,
+                &cmd.clone().get_matches_from(args),
+                "allow-dup-stext",
+            );
+            y
+        };
+        assert!(set(&["test"], true));
+        assert!(!set(&["test"], false));
+        assert!(set(&["test", "--allow-dup-stext"], false));
+    }
+
+    #[test]
+    fn test_edit_keywords_refuses_broken_text() {
+        let dir = std::env::temp_dir().join(format!("fireflow-cli-kws-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p_in = dir.join("in.fcs");
+        let p_out = dir.join("out.fcs");
+        fs::write(&p_in, minimal_fcs(&[1, 2, 3])).unwrap();
+        let read_conf = config::DataReadConfig::default();
+        let mut raw = fcs_read_raw_text(&p_in, &read_conf.standard.raw)
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+        raw.keywords.set("$P1B", "x".into()).unwrap();
+        let res = edit_keywords(&p_in, &p_out, raw.clone(), &read_conf, false, false);
+        let refused = !p_out.exists();
+        let forced = edit_keywords(&p_in, &p_out, raw, &read_conf, false, true);
+        let written = p_out.exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(res.is_err());
+        assert!(refused);
+        assert!(forced.is_ok());
+        assert!(written);
+    }
+}
@@ -1,33 +1,55 @@
 use crate::config::*;
 use crate::core::*;
+use crate::crc::{h_check_crc, rewrite_crc};
 use crate::data::*;
+use crate::datahash::{h_check_data_hash, DATA_HASH_KEY};
 use crate::error::*;
+use crate::fcs_error::FcsError;
 use crate::header::*;
+use crate::limits::*;
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
+use crate::scratch::ParserScratch;
 use crate::segment::*;
+use crate::source::FcsSource;
+#[cfg(feature = "tokio")]
+use crate::source::SparseSource;
+use crate::text::byteord::{ByteOrd, Endian};
 use crate::text::keywords::*;
 use crate::text::parser::*;
+use crate::text::registry::remove_registered;
+use crate::text::spillover::{Spillover, SpilloverMatrix};
 use crate::text::timestamps::*;
+use crate::text::typed::*;
+use crate::text::vendor::{find_spillover, VendorKeywords, LEGACY_SPILLOVER_KEYS};
 use crate::validated::dataframe::FCSDataFrame;
+use crate::validated::nonstandard::NonStdKey;
+use crate::validated::repair::{rename_offsets, KeywordModification};
 use crate::validated::standard::*;
 
 use chrono::NaiveDate;
 use itertools::Itertools;
+use nonempty::NonEmpty;
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fs;
-use std::io::{BufReader, Read, Seek};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::mem;
 use std::num::ParseIntError;
 use std::path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Result of reading a full standardized dataset from an FCS file.
+pub type FCSResult =
+    IOTerminalResult<StdDatasetOutput, StdDatasetWarning, StdDatasetError, StdDatasetFailure>;
 
 /// Read HEADER from an FCS file.
 pub fn fcs_read_header(
-    p: &path::PathBuf,
+    p: &path::Path,
     conf: &HeaderConfig,
 ) -> IOTerminalResult<Header, (), HeaderError, HeaderFailure> {
-    fs::File::options()
-        .read(true)
-        .open(p)
+    FcsSource::open(p)
         .into_deferred()
         .def_and_maybe(|file| {
             let mut reader = BufReader::new(file);
@@ -38,7 +60,7 @@ pub fn fcs_read_header(
 
 /// Read HEADER and key/value pairs from TEXT in an FCS file.
 pub fn fcs_read_raw_text(
-    p: &path::PathBuf,
+    p: &path::Path,
     conf: &RawTextReadConfig,
 ) -> IOTerminalResult<RawTEXTOutput, ParseRawTEXTWarning, HeaderOrRawError, RawTEXTFailure> {
     read_fcs_raw_text_inner(p, conf)
@@ -48,7 +70,7 @@ pub fn fcs_read_raw_text(
 
 /// Read HEADER and standardized TEXT from an FCS file.
 pub fn fcs_read_std_text(
-    p: &path::PathBuf,
+    p: &path::Path,
     conf: &StdTextReadConfig,
 ) -> IOTerminalResult<StdTEXTOutput, StdTEXTWarning, StdTEXTError, StdTEXTFailure> {
     read_fcs_raw_text_inner(p, &conf.raw)
@@ -58,9 +80,383 @@ pub fn fcs_read_std_text(
         .def_terminate(StdTEXTFailure)
 }
 
+/// Read HEADER, standardized TEXT, and raw keywords from an FCS file.
+///
+/// Like [fcs_read_std_text], this will stop after TEXT and never read DATA.
+/// Additionally, this will keep a copy of all keywords as they appeared in
+/// TEXT, which are otherwise consumed when standardizing.
+pub fn fcs_read_metadata(
+    p: &path::Path,
+    conf: &StdTextReadConfig,
+) -> IOTerminalResult<MetadataOutput, StdTEXTWarning, StdTEXTError, StdTEXTFailure> {
+    read_fcs_raw_text_inner(p, &conf.raw)
+        .def_map_value(|(x, _)| x)
+        .def_io_into()
+        .def_and_maybe(|raw| {
+            let keywords = raw.keywords.clone();
+            raw.into_std_text(conf)
+                .def_map_value(|text| MetadataOutput { text, keywords })
+                .def_inner_into()
+                .def_errors_liftio()
+        })
+        .def_terminate(StdTEXTFailure)
+}
+
+/// Read HEADER and a few keywords from TEXT which summarize an FCS file.
+///
+/// This is meant for quickly indexing many files. Only HEADER and TEXT are
+/// read, and TEXT is not standardized. Keywords in the summary which are
+/// missing or cannot be parsed will be None.
+pub fn fcs_scan_header(
+    p: &path::Path,
+    conf: &RawTextReadConfig,
+) -> IOTerminalResult<HeaderSummary, ParseRawTEXTWarning, HeaderOrRawError, RawTEXTFailure> {
+    scan_header_inner(p, conf).def_terminate(RawTEXTFailure)
+}
+
+/// Read only the spillover matrix from an FCS file.
+///
+/// Only HEADER and TEXT are read, and TEXT is not standardized. For 3.1 and
+/// later, this is taken from $SPILLOVER. For older versions, non-standard
+/// keywords commonly used for the same matrix (ie "SPILL" or "SPILLOVER") are
+/// used instead. Return None if no valid matrix is found.
+pub fn fcs_read_spillover(
+    p: &path::Path,
+    conf: &RawTextReadConfig,
+) -> IOTerminalResult<Option<SpilloverMatrix>, ParseRawTEXTWarning, HeaderOrRawError, RawTEXTFailure>
+{
+    read_fcs_raw_text_inner(p, conf)
+        .def_map_value(|(raw, _)| {
+            let kws = &raw.keywords;
+            match raw.version {
+                Version::FCS2_0 | Version::FCS3_0 => {
+                    find_spillover(&kws.nonstd, &LEGACY_SPILLOVER_KEYS)
+                }
+                Version::FCS3_1 | Version::FCS3_2 => {
+                    get_opt::<Spillover>(&kws.std, Spillover::std())
+                        .ok()
+                        .flatten()
+                }
+            }
+            .map(|s| s.to_matrix())
+        })
+        .def_terminate(RawTEXTFailure)
+}
+
+/// Summarize all FCS files in a directory.
+///
+/// Files are found by their extension (case-insensitive, either '.fcs' or
+/// '.fcs' followed by '.gz' or '.zst') and are not searched recursively. Files are scanned in parallel using [`fcs_scan_header`] and
+/// returned in order of their paths. An error will only be returned if the
+/// directory itself cannot be read.
+pub fn fcs_scan_dir(dir: &path::Path, conf: &RawTextReadConfig) -> io::Result<Vec<ScanEntry>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let p = entry?.path();
+        if p.is_file() && has_fcs_extension(&p) {
+            paths.push(p);
+        }
+    }
+    paths.sort();
+    let nthreads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = paths.len().div_ceil(nthreads).max(1);
+    let entries = thread::scope(|s| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|ps| {
+                s.spawn(|| {
+                    ps.iter()
+                        .map(|p| ScanEntry::new(p, conf))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+    Ok(entries)
+}
+
+fn has_fcs_extension(p: &path::Path) -> bool {
+    let is_fcs = |q: &path::Path| q.extension().is_some_and(|x| x.eq_ignore_ascii_case("fcs"));
+    is_fcs(p)
+        || (p
+            .extension()
+            .is_some_and(|x| x.eq_ignore_ascii_case("gz") || x.eq_ignore_ascii_case("zst"))
+            && is_fcs(&p.with_extension("")))
+}
+
+/// Convert a standardized dataset to another FCS version.
+///
+/// See [AnyCore::try_convert_version] for details.
+pub fn fcs_convert_dataset(
+    core: AnyCoreDataset,
+    to: Version,
+    force: bool,
+) -> TerminalResult<AnyCoreDataset, MetarootConvertWarning, AnyConvertError, ConvertFailure> {
+    core.try_convert_version(to, force)
+        .def_terminate(ConvertFailure { to })
+}
+
+/// Read an FCS file of any version, convert it to 3.2, and write it.
+///
+/// Keywords will be repaired as given by [`NormalizeConfig::read`], which is
+/// permissive by default. The dataset will then be converted to 3.2 (see
+/// [AnyCore::try_convert_version]) and written with the byte order in
+/// [`NormalizeConfig::endian`]. DATA will also be converted if
+/// [`WriteConfig::target_datatype`] is given.
+pub fn fcs_normalize(
+    p_in: &path::Path,
+    p_out: &path::Path,
+    conf: &NormalizeConfig,
+) -> IOTerminalResult<(), NormalizeWarning, NormalizeError, NormalizeFailure> {
+    let read: IODeferredResult<_, StdDatasetWarning, StdDatasetError> =
+        read_fcs_raw_text_inner(p_in, &conf.read.standard.raw)
+            .def_io_into()
+            .def_and_maybe(|(raw, mut h)| {
+                raw.into_std_dataset(&mut h, &conf.read, &mut ParserScratch::default())
+                    .def_io_into()
+            });
+    read.def_io_into()
+        .def_and_maybe(|out| {
+            out.dataset
+                .standardized
+                .core
+                .try_convert_version(Version::FCS3_2, conf.force)
+                .def_inner_into()
+                .def_errors_liftio()
+        })
+        .def_and_maybe(|mut core| {
+            if let AnyCoreDataset::FCS3_2(x) = &mut core {
+                x.set_big_endian(conf.endian == Endian::Big);
+            }
+            fs::File::create(p_out)
+                .into_deferred()
+                .def_and_maybe(|file| {
+                    let mut h = BufWriter::new(file);
+                    core.h_write(&mut h, &conf.write)
+                        .def_and_maybe(|_| h.flush().into_deferred())
+                        .def_io_into()
+                })
+        })
+        .def_terminate(NormalizeFailure)
+}
+
+/// Write a standardized dataset to an FCS file.
+pub fn fcs_write_dataset(
+    p: &path::Path,
+    core: &AnyCoreDataset,
+    conf: &WriteConfig,
+) -> IOTerminalResult<(), NewDataLayoutWarning, StdWriterError, WriteFailure> {
+    write_dataset_inner(p, core, conf).def_terminate(WriteFailure)
+}
+
+/// Write a standardized dataset to a byte vector.
+///
+/// Same as [`fcs_write_dataset`] but does not require filesystem access.
+pub fn fcs_write_dataset_to_bytes(
+    core: &AnyCoreDataset,
+    conf: &WriteConfig,
+) -> IOTerminalResult<Vec<u8>, NewDataLayoutWarning, StdWriterError, WriteFailure> {
+    let mut h = BufWriter::new(vec![]);
+    let res = core
+        .h_write(&mut h, conf)
+        .def_and_maybe(|_| h.flush().into_deferred());
+    // ASSUME buffer is empty since it was flushed above if writing succeeded
+    let (buf, _) = h.into_parts();
+    res.def_map_value(|_| buf).def_terminate(WriteFailure)
+}
+
+/// Write a standardized dataset to an FCS file, copying DATA from the original.
+///
+/// DATA will be re-read from 'p_in' using the offsets in 'dataset' and written
+/// verbatim if it still matches the dataframe and its layout; otherwise DATA
+/// will be serialized from the dataframe (see [`AnyCore::h_write_with_data`]).
+/// This is useful for archival workflows where only TEXT has been changed and
+/// DATA should be preserved byte-for-byte. ANALYSIS and OTHER are always
+/// written verbatim.
+pub fn fcs_write_dataset_copy_data(
+    p_in: &path::Path,
+    p_out: &path::Path,
+    dataset: &DatasetWithSegments,
+    conf: &WriteConfig,
+) -> IOTerminalResult<(), NewDataLayoutWarning, StdWriterError, WriteFailure> {
+    FcsSource::open(p_in)
+        .and_then(|file| {
+            let mut buf = vec![];
+            let mut r = BufReader::new(file);
+            dataset.data_seg.inner.h_read_contents(&mut r, &mut buf)?;
+            Ok(buf)
+        })
+        .and_then(|buf| fs::File::create(p_out).map(|file| (buf, file)))
+        .into_deferred()
+        .def_and_maybe(|(buf, file)| {
+            let mut h = BufWriter::new(file);
+            dataset
+                .core
+                .h_write_with_data(&mut h, conf, Some(&buf))
+                .def_and_maybe(|_| h.flush().into_deferred())
+        })
+        .def_terminate(WriteFailure)
+}
+
+/// Standardize TEXT from keywords which have already been read.
+///
+/// This is useful when keywords need to be inspected or modified prior to
+/// standardization.
+pub fn fcs_std_text_from_raw(
+    raw: RawTEXTOutput,
+    conf: &StdTextReadConfig,
+) -> TerminalResult<StdTEXTOutput, StdTEXTWarning, StdTEXTError, StdTEXTFailure> {
+    raw.into_std_text(conf)
+        .def_inner_into()
+        .def_terminate(StdTEXTFailure)
+}
+
+/// Overwrite primary TEXT of an FCS file with (possibly modified) keywords.
+///
+/// This is a minimal patch which leaves everything outside primary TEXT
+/// untouched aside from the end offset in HEADER and the CRC (if present). This
+/// is only possible if the new TEXT is no longer than the original, there is no
+/// supplemental TEXT, and all keywords were read without error (otherwise they
+/// would be lost). Return false without writing anything if any of these are
+/// not true. See [`fcs_edit_text`] for a version which falls back to writing
+/// the entire file.
+///
+/// Unused bytes after the new TEXT will be filled with spaces. Keywords will be
+/// written in sorted order with the original delimiter.
+///
+/// The HEADER offsets in 'raw' are assumed to be those actually in the file
+/// (ie no offset corrections were used when reading).
+pub fn fcs_patch_text(
+    p_in: &path::Path,
+    p_out: &path::Path,
+    raw: &RawTEXTOutput,
+) -> io::Result<bool> {
+    let parse = &raw.parse;
+    if parse
+        .supp_text
+        .as_ref()
+        .is_some_and(|s| !s.inner.is_empty())
+        || !parse.non_ascii.is_empty()
+        || !parse.byte_pairs.is_empty()
+        || parse.nonstandard_skipped > 0
+    {
+        return Ok(false);
+    }
+    let Some(seg) = parse.header_segments.text.try_as_generic() else {
+        return Ok(false);
+    };
+    let (begin, end) = (seg.begin as usize, seg.end as usize);
+    let text = raw.keywords.to_text_bytes(parse.delimiter);
+    let mut buf = vec![];
+    FcsSource::open(p_in)?.read_to_end(&mut buf)?;
+    if end >= buf.len() || text.len() > end - begin + 1 || text.is_empty() {
+        return Ok(false);
+    }
+    let new_end = begin + text.len() - 1;
+    buf[begin..=new_end].copy_from_slice(&text);
+    buf[(new_end + 1)..=end].fill(32);
+    // end TEXT offset is the second offset after the version and spaces
+    let w = HEADER_OFFSET_WIDTH;
+    let i = VERSION_LEN + HEADER_SPACE_LEN + w;
+    buf[i..(i + w)].copy_from_slice(format!("{new_end:>w$}").as_bytes());
+    // 2.0 has no CRC
+    if let Some(last) = raw
+        .last_segment_end()
+        .filter(|_| raw.version != Version::FCS2_0)
+    {
+        rewrite_crc(&mut buf, last as usize);
+    }
+    fs::write(p_out, buf)?;
+    Ok(true)
+}
+
+/// Write (possibly modified) keywords from TEXT to an FCS file.
+///
+/// TEXT will be patched in place with [`fcs_patch_text`] if possible.
+/// Otherwise, DATA and ANALYSIS will be read from 'p_in' using the keywords in
+/// 'raw', and the dataset will be standardized and written in full with
+/// [`fcs_write_dataset`]. In the latter case, keywords which start with '$'
+/// but are not part of the standard for the version in 'raw', as well as any
+/// which could not be read, will not be written.
+///
+/// Return true if TEXT was patched in place. 'p_in' and 'p_out' may be the
+/// same file.
+pub fn fcs_edit_text(
+    p_in: &path::Path,
+    p_out: &path::Path,
+    raw: RawTEXTOutput,
+    read_conf: &DataReadConfig,
+    write_conf: &WriteConfig,
+) -> IOTerminalResult<bool, EditTextWarning, EditTextError, EditTextFailure> {
+    fcs_patch_text(p_in, p_out, &raw)
+        .into_deferred()
+        .def_and_maybe(|patched| {
+            if patched {
+                return Ok(Tentative::new1(true));
+            }
+            let hs = raw.parse.header_segments;
+            read_std_dataset_with_keywords_inner(
+                p_in,
+                raw.version,
+                raw.keywords,
+                hs.data,
+                hs.analysis,
+                &hs.other[..],
+                read_conf,
+            )
+            .def_io_into()
+            .def_and_maybe(|out| {
+                write_dataset_inner(p_out, &out.standardized.core, write_conf).def_io_into()
+            })
+            .def_map_value(|_| false)
+        })
+        .def_terminate(EditTextFailure)
+}
+
+/// Check an FCS file for conformance without reading DATA.
+///
+/// This will read HEADER and TEXT, check that the keywords describing DATA and
+/// ANALYSIS are consistent with each other and with HEADER, and standardize
+/// TEXT according to the version in HEADER. Unlike the other readers, this
+/// will not stop at the first failed step, so the report will contain as many
+/// issues as can be found.
+///
+/// The level of strictness is determined by the configuration; use
+/// [`DataReadConfig::from_profile`] to select a strict or lenient check. If
+/// [`TimeConfig::datetime_tolerance`] is set, time-related keywords will also
+/// be cross-checked, and any disagreements will be reported as warnings.
+pub fn fcs_validate(p: &path::Path, conf: &DataReadConfig) -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+    let Some((raw, _)) = report.absorb(read_fcs_raw_text_inner(p, &conf.standard.raw)) else {
+        return report;
+    };
+    let kws = &raw.keywords.std;
+    let segs = &raw.parse.header_segments;
+    report.version = Some(raw.version);
+    report.absorb(kws_to_data_reader(raw.version, kws, segs.data, conf));
+    report.absorb(kws_to_analysis_reader(
+        raw.version,
+        kws,
+        segs.analysis,
+        &conf.reader,
+    ));
+    let text = report.absorb(raw.into_std_text(&conf.standard));
+    if let (Some(t), Some(tol)) = (text, conf.standard.time.datetime_tolerance) {
+        let (_, ws, _) = t.standardized.acquisition_interval(tol).into_parts();
+        report
+            .warnings
+            .extend(ws.into_iter().map(|w| w.to_string()));
+    }
+    report
+}
+
 /// Read dataset from FCS file using standardized TEXT.
 pub fn fcs_read_raw_dataset(
-    p: &path::PathBuf,
+    p: &path::Path,
     conf: &DataReadConfig,
 ) -> IOTerminalResult<RawDatasetOutput, RawDatasetWarning, RawDatasetError, RawDatasetFailure> {
     read_fcs_raw_text_inner(p, &conf.standard.raw)
@@ -78,18 +474,184 @@ pub fn fcs_read_raw_dataset(
             .def_map_value(|dataset| RawDatasetOutput { text: raw, dataset })
             .def_io_into()
         })
-        .def_terminate(RawDatasetFailure)
+        .def_terminate_strict(RawDatasetFailure, conf.shared.warnings_are_errors)
+}
+
+/// Read histogram counts from an FCS file whose $MODE is "C" or "U".
+///
+/// Such files cannot be read as events; see [`HistogramData`] for how counts
+/// are arranged. Only the keywords needed to read DATA are used, so TEXT will
+/// not be standardized.
+pub fn fcs_read_histogram(
+    p: &path::Path,
+    conf: &RawTextReadConfig,
+) -> IOTerminalResult<HistogramData, ParseRawTEXTWarning, ReadHistogramError, ReadHistogramFailure>
+{
+    read_fcs_raw_text_inner(p, conf)
+        .def_io_into()
+        .def_and_maybe(|(raw, mut h)| {
+            HistogramData::h_read(
+                &mut h,
+                raw.version,
+                &raw.keywords.std,
+                raw.parse.header_segments.data,
+            )
+            .map_err(|e| e.inner_into())
+            .into_deferred()
+        })
+        .def_terminate(ReadHistogramFailure)
 }
 
 /// Read dataset from FCS file using raw key/value pairs from TEXT.
-pub fn fcs_read_std_dataset(
-    p: &path::PathBuf,
+pub fn fcs_read_std_dataset(p: &path::Path, conf: &DataReadConfig) -> FCSResult {
+    fcs_read_std_dataset_with_scratch(p, conf, &mut ParserScratch::default())
+}
+
+/// Read dataset from FCS file, reusing buffers from previous reads.
+///
+/// Same as [`fcs_read_std_dataset`] but buffers for TEXT and DATA are taken
+/// from 'scratch' and left there afterward. This is useful when reading many
+/// files in a row. See [`ParserScratch`] for details.
+pub fn fcs_read_std_dataset_with_scratch(
+    p: &path::Path,
     conf: &DataReadConfig,
-) -> IOTerminalResult<StdDatasetOutput, StdDatasetWarning, StdDatasetError, StdDatasetFailure> {
-    read_fcs_raw_text_inner(p, &conf.standard.raw)
+    scratch: &mut ParserScratch,
+) -> FCSResult {
+    FcsSource::open(p)
+        .into_deferred()
+        .def_and_maybe(|file| {
+            let mut h = BufReader::new(file);
+            h_read_std_dataset(&mut h, conf, scratch)
+        })
+        .def_terminate_strict(StdDatasetFailure, conf.shared.warnings_are_errors)
+}
+
+/// Read dataset from bytes holding a complete FCS file.
+///
+/// Same as [`fcs_read_std_dataset`] but does not require filesystem access.
+pub fn fcs_read_std_dataset_from_bytes(bytes: &[u8], conf: &DataReadConfig) -> FCSResult {
+    let mut h = BufReader::new(io::Cursor::new(bytes));
+    h_read_std_dataset(&mut h, conf, &mut ParserScratch::default())
+        .def_terminate_strict(StdDatasetFailure, conf.shared.warnings_are_errors)
+}
+
+/// Read dataset from FCS file asynchronously.
+///
+/// Same as [`fcs_read_std_dataset_from_async_reader`] using a file opened
+/// with tokio. Unlike [`fcs_read_std_dataset`], compressed files are not
+/// supported.
+#[cfg(feature = "tokio")]
+pub async fn fcs_read_std_dataset_async(p: &path::Path, conf: &DataReadConfig) -> FCSResult {
+    match tokio::fs::File::open(p).await {
+        Ok(mut file) => fcs_read_std_dataset_from_async_reader(&mut file, conf).await,
+        Err(e) => Err(e).into_deferred().def_terminate(StdDatasetFailure),
+    }
+}
+
+/// Read dataset from an asynchronous reader.
+///
+/// HEADER and TEXT are fetched first, followed by the DATA, ANALYSIS and OTHER
+/// segments they point to. Bytes outside these segments are never read, but
+/// each segment is fetched in full before being parsed synchronously as with
+/// [`fcs_read_std_dataset_from_bytes`].
+#[cfg(feature = "tokio")]
+pub async fn fcs_read_std_dataset_from_async_reader<R>(
+    h: &mut R,
+    conf: &DataReadConfig,
+) -> FCSResult
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+{
+    let res = async {
+        let mut src = SparseSource::new(h).await?;
+        let raw = h_read_sparse(h, &mut src, |s| {
+            let mut r = BufReader::new(s);
+            RawTEXTOutput::h_read(&mut r, &conf.standard.raw, &mut ParserScratch::default())
+        })
+        .await?;
+        if let Ok(tnt) = raw {
+            for (begin, end) in tnt.value().dataset_segments() {
+                src.fetch(h, begin, end).await?;
+            }
+        }
+        h_read_sparse(h, &mut src, |s| {
+            let mut r = BufReader::new(s);
+            h_read_std_dataset(&mut r, conf, &mut ParserScratch::default())
+        })
+        .await
+    };
+    res.await
+        .into_deferred()
+        .def_and_maybe(|x| x)
+        .def_terminate_strict(StdDatasetFailure, conf.shared.warnings_are_errors)
+}
+
+/// Read HEADER and standardized TEXT from an asynchronous reader.
+///
+/// Same as [`fcs_read_std_text_from_bytes`] but only HEADER and TEXT are read
+/// from the stream.
+#[cfg(feature = "tokio")]
+pub async fn fcs_read_std_text_from_async_reader<R>(
+    h: &mut R,
+    conf: &StdTextReadConfig,
+) -> IOTerminalResult<StdTEXTOutput, StdTEXTWarning, StdTEXTError, StdTEXTFailure>
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+{
+    let res = async {
+        let mut src = SparseSource::new(h).await?;
+        h_read_sparse(h, &mut src, |s| {
+            let mut r = BufReader::new(s);
+            RawTEXTOutput::h_read(&mut r, &conf.raw, &mut ParserScratch::default())
+                .def_io_into()
+                .def_and_maybe(|raw| raw.into_std_text(conf).def_inner_into().def_errors_liftio())
+        })
+        .await
+    };
+    res.await
+        .into_deferred()
+        .def_and_maybe(|x| x)
+        .def_terminate(StdTEXTFailure)
+}
+
+/// Read HEADER and standardized TEXT from bytes holding an FCS file.
+///
+/// Same as [`fcs_read_std_text`] but does not require filesystem access. Bytes
+/// need only extend to the end of TEXT.
+pub fn fcs_read_std_text_from_bytes(
+    bytes: &[u8],
+    conf: &StdTextReadConfig,
+) -> IOTerminalResult<StdTEXTOutput, StdTEXTWarning, StdTEXTError, StdTEXTFailure> {
+    let mut h = BufReader::new(io::Cursor::new(bytes));
+    RawTEXTOutput::h_read(&mut h, &conf.raw, &mut ParserScratch::default())
         .def_io_into()
-        .def_and_maybe(|(raw, mut h)| raw.into_std_dataset(&mut h, conf).def_io_into())
-        .def_terminate(StdDatasetFailure)
+        .def_and_maybe(|raw| raw.into_std_text(conf).def_inner_into().def_errors_liftio())
+        .def_terminate(StdTEXTFailure)
+}
+
+/// Read all standardized datasets from FCS file by following $NEXTDATA.
+///
+/// Reading stops once $NEXTDATA is 0, missing, or points at or beyond the end
+/// of the file. Each dataset is read with the same configuration, and any
+/// error in one dataset will fail the whole chain.
+/// Since $NEXTDATA is relative to the start of its dataset, it is an error if
+/// it points anywhere before the end of the current primary TEXT, as this
+/// would imply overlapping (or looping) datasets.
+pub fn fcs_read_all_std_datasets(
+    p: &path::Path,
+    conf: &DataReadConfig,
+) -> IOTerminalResult<Vec<StdDatasetOutput>, StdDatasetWarning, StdDatasetError, StdDatasetFailure>
+{
+    FcsSource::open(p)
+        .into_deferred()
+        .def_and_maybe(|file| {
+            let mut h = BufReader::new(OffsetReader {
+                inner: file,
+                base: 0,
+            });
+            h_read_all_std_datasets(&mut h, conf)
+        })
+        .def_terminate_strict(StdDatasetFailure, conf.shared.warnings_are_errors)
 }
 
 /// Read DATA/ANALYSIS in FCS file using provided keywords.
@@ -107,9 +669,7 @@ pub fn fcs_read_raw_dataset_with_keywords(
     DatasetWithKwsError,
     RawDatasetWithKwsFailure,
 > {
-    fs::File::options()
-        .read(true)
-        .open(p)
+    FcsSource::open(&p)
         .into_deferred()
         .def_and_maybe(|file| {
             let mut h = BufReader::new(file);
@@ -123,14 +683,14 @@ pub fn fcs_read_raw_dataset_with_keywords(
                 conf,
             )
         })
-        .def_terminate(RawDatasetWithKwsFailure)
+        .def_terminate_strict(RawDatasetWithKwsFailure, conf.shared.warnings_are_errors)
 }
 
 /// Read DATA/ANALYSIS in FCS file using provided keywords to be standardized.
 pub fn fcs_read_std_dataset_with_keywords(
-    p: &path::PathBuf,
+    p: &path::Path,
     version: Version,
-    mut kws: ValidKeywords,
+    kws: ValidKeywords,
     data_seg: HeaderDataSegment,
     analysis_seg: HeaderAnalysisSegment,
     other_segs: Vec<OtherSegment>,
@@ -141,36 +701,70 @@ pub fn fcs_read_std_dataset_with_keywords(
     StdDatasetFromRawError,
     StdDatasetWithKwsFailure,
 > {
-    fs::File::options()
-        .read(true)
-        .open(p)
-        .into_deferred()
-        .def_and_maybe(|file| {
-            let mut h = BufReader::new(file);
-            AnyCoreDataset::parse_raw(
-                &mut h,
-                version,
-                &mut kws.std,
-                kws.nonstd,
-                data_seg,
-                analysis_seg,
-                &other_segs[..],
-                conf,
-            )
-            .def_map_value(|(core, d_seg, a_seg)| StdDatasetWithKwsOutput {
+    read_std_dataset_with_keywords_inner(
+        p,
+        version,
+        kws,
+        data_seg,
+        analysis_seg,
+        &other_segs[..],
+        conf,
+    )
+    .def_terminate_strict(StdDatasetWithKwsFailure, conf.shared.warnings_are_errors)
+}
+
+fn read_std_dataset_with_keywords_inner(
+    p: &path::Path,
+    version: Version,
+    mut kws: ValidKeywords,
+    data_seg: HeaderDataSegment,
+    analysis_seg: HeaderAnalysisSegment,
+    other_segs: &[OtherSegment],
+    conf: &DataReadConfig,
+) -> IODeferredResult<StdDatasetWithKwsOutput, StdDatasetFromRawWarning, StdDatasetFromRawError> {
+    FcsSource::open(p).into_deferred().def_and_maybe(|file| {
+        let mut h = BufReader::new(file);
+        AnyCoreDataset::parse_raw(
+            &mut h,
+            version,
+            &mut kws.std,
+            kws.nonstd,
+            data_seg,
+            analysis_seg,
+            other_segs,
+            conf,
+            &mut ParserScratch::default(),
+        )
+        .def_and_tentatively(|(core, d_seg, a_seg)| {
+            let (pseudostandard_typed, es) =
+                promote_keywords(&mut kws.std, &conf.standard.pseudostandard_types);
+            let (unused_standard_keywords, reg_es) =
+                remove_registered(&mut kws.std, &conf.standard.keyword_registry);
+            let vendor = core.vendor_keywords(conf.standard.vendor);
+            let out = StdDatasetWithKwsOutput {
                 standardized: DatasetWithSegments {
                     core,
                     data_seg: d_seg,
                     analysis_seg: a_seg,
                 },
                 pseudostandard: kws.std,
-            })
+                pseudostandard_typed,
+                unused_standard_keywords,
+                vendor,
+            };
+            let ws = es
+                .into_iter()
+                .map(LookupMeasWarning::from)
+                .chain(reg_es.into_iter().map(LookupMeasWarning::from))
+                .map(|e| e.into())
+                .collect();
+            Tentative::new(out, ws, vec![])
         })
-        .def_terminate(StdDatasetWithKwsFailure)
+    })
 }
 
 /// Output from parsing the TEXT segment.
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct RawTEXTOutput {
     /// FCS version
     pub version: Version,
@@ -182,6 +776,50 @@ pub struct RawTEXTOutput {
     pub parse: RawTEXTParseData,
 }
 
+/// Output of scanning HEADER and TEXT for a few summary keywords.
+#[derive(Clone, Serialize)]
+pub struct HeaderSummary {
+    /// FCS version
+    pub version: Version,
+
+    /// Offsets from HEADER
+    pub segments: HeaderSegments,
+
+    /// Value of $TOT
+    pub tot: Option<Tot>,
+
+    /// Value of $PAR
+    pub par: Option<Par>,
+
+    /// Value of $CYT
+    pub cyt: Option<Cyt>,
+
+    /// Value of $DATE
+    pub date: Option<FCSDate>,
+}
+
+/// One file scanned by [`fcs_scan_dir`].
+#[derive(Clone, Serialize)]
+pub struct ScanEntry {
+    /// Path to the scanned file
+    pub path: path::PathBuf,
+
+    /// Summary of the file, or None if HEADER or TEXT could not be read
+    pub summary: Option<HeaderSummary>,
+
+    /// Issues encountered when reading HEADER or TEXT
+    pub errors: Vec<FcsError>,
+}
+
+/// Output of reading metadata only (HEADER and TEXT).
+pub struct MetadataOutput {
+    /// Standardized TEXT
+    pub text: StdTEXTOutput,
+
+    /// All keywords from TEXT prior to standardization
+    pub keywords: ValidKeywords,
+}
+
 /// Output of parsing the TEXT segment and standardizing keywords.
 pub struct StdTEXTOutput {
     /// Standardized data from TEXT
@@ -199,13 +837,83 @@ pub struct StdTEXTOutput {
     /// TEXT values for $BEGIN/ENDANALYSIS
     pub analysis: SegmentKeywords,
 
-    /// Keywords that start with '$' that are not part of the standard
+    /// Keywords that start with '$' that are not part of any standard
     pub pseudostandard: StdKeywords,
 
+    /// Pseudostandard keywords parsed according to their declared types
+    pub pseudostandard_typed: TypedKeywords,
+
+    /// Keywords in [`StdTextReadConfig::keyword_registry`] which were not used.
+    ///
+    /// These are usually for a different version, indexed beyond $PAR, or
+    /// registered vendor keywords. They are not included in 'pseudostandard'.
+    pub unused_standard_keywords: StdKeywords,
+
+    /// Values from vendor-specific non-standard keywords, if any
+    pub vendor: Option<VendorKeywords>,
+
+    /// Nonstandard keywords which were assigned to measurements using
+    /// [`StdTextReadConfig::nonstandard_measurement_patterns`]
+    pub claimed_nonstandard: Vec<NonStdClaim>,
+
     /// Miscellaneous data from parsing TEXT
     pub parse: RawTEXTParseData,
 }
 
+impl StdTEXTOutput {
+    /// Return a JSON document with everything obtained from TEXT.
+    ///
+    /// The top-level fields are 'version', 'header', 'metadata',
+    /// 'measurements', 'nonstandard', 'claimed_nonstandard', 'pseudostandard',
+    /// 'pseudostandard_typed', 'unused_standard_keywords', 'vendor', 'tot',
+    /// 'timestep', 'data', 'analysis', 'parse', and 'warnings'. These names are
+    /// stable and may be relied upon downstream.
+    ///
+    /// 'warnings' should be those emitted while producing this output.
+    pub fn to_json<W: fmt::Display>(&self, warnings: &[W]) -> serde_json::Value {
+        let core = &self.standardized;
+        let nonstandard: BTreeMap<_, _> = core
+            .raw_keywords(None, None)
+            .into_iter()
+            .filter(|(k, _)| !k.starts_with(char::from(STD_PREFIX)))
+            .collect();
+        let pseudostandard: BTreeMap<&str, _> = self
+            .pseudostandard
+            .iter()
+            .map(|(k, v)| (k.as_ref(), v))
+            .collect();
+        let pseudostandard_typed: BTreeMap<&str, _> = self
+            .pseudostandard_typed
+            .iter()
+            .map(|(k, v)| (k.as_ref(), v))
+            .collect();
+        let unused_standard_keywords: BTreeMap<&str, _> = self
+            .unused_standard_keywords
+            .iter()
+            .map(|(k, v)| (k.as_ref(), v))
+            .collect();
+        let ws: Vec<_> = warnings.iter().map(|w| w.to_string()).collect();
+        serde_json::json!({
+            "version": core.version(),
+            "header": &self.parse.header_segments,
+            "metadata": core,
+            "measurements": core.measurements(),
+            "nonstandard": nonstandard,
+            "claimed_nonstandard": &self.claimed_nonstandard,
+            "pseudostandard": pseudostandard,
+            "pseudostandard_typed": pseudostandard_typed,
+            "unused_standard_keywords": unused_standard_keywords,
+            "vendor": &self.vendor,
+            "tot": &self.tot,
+            "timestep": &self.timestep,
+            "data": &self.data,
+            "analysis": &self.analysis,
+            "parse": &self.parse,
+            "warnings": ws,
+        })
+    }
+}
+
 /// Output of parsing one raw dataset (TEXT+DATA) from an FCS file.
 pub struct RawDatasetOutput {
     /// Output from parsing HEADER+TEXT
@@ -229,8 +937,20 @@ pub struct StdDatasetWithKwsOutput {
     /// DATA+ANALYSIS
     pub standardized: DatasetWithSegments,
 
-    /// Keywords that start with '$' that are not part of the standard
+    /// Keywords that start with '$' that are not part of any standard
     pub pseudostandard: StdKeywords,
+
+    /// Pseudostandard keywords parsed according to their declared types
+    pub pseudostandard_typed: TypedKeywords,
+
+    /// Keywords in [`StdTextReadConfig::keyword_registry`] which were not used.
+    ///
+    /// These are usually for a different version, indexed beyond $PAR, or
+    /// registered vendor keywords. They are not included in 'pseudostandard'.
+    pub unused_standard_keywords: StdKeywords,
+
+    /// Values from vendor-specific non-standard keywords, if any
+    pub vendor: Option<VendorKeywords>,
 }
 
 /// Output of using keywords to read raw TEXT+DATA
@@ -285,9 +1005,127 @@ pub struct RawTEXTParseData {
     /// These have either a key or value or both that is not a UTF-8 string.
     /// Included here for debugging
     pub byte_pairs: BytesPairs,
+
+    /// Number of non-standard keywords which were skipped.
+    ///
+    /// Will always be 0 unless non-standard keywords were not stored.
+    pub nonstandard_skipped: usize,
+
+    /// Keys whose values had binary contamination.
+    ///
+    /// Will always be empty unless values were converted lossily.
+    pub contaminated_values: Vec<String>,
+
+    /// The exact bytes of primary TEXT, if requested.
+    ///
+    /// These correspond to the primary TEXT offsets in HEADER.
+    pub raw_text: Option<Vec<u8>>,
+
+    /// The exact bytes of supplemental TEXT, if requested and present.
+    ///
+    /// These correspond to the offsets in 'supp_text'.
+    pub raw_supp_text: Option<Vec<u8>>,
+
+    /// The contents of each OTHER segment, if requested.
+    ///
+    /// These correspond to the OTHER offsets in 'header_segments'.
+    #[serde(skip)]
+    pub others: Option<Others>,
+
+    /// Changes made to keywords by user-supplied repair and anonymization rules.
+    pub keyword_repairs: Vec<KeywordModification>,
+
+    /// Position of each key in the file.
+    ///
+    /// Offsets are relative to the start of the file and point to the first
+    /// byte of the key. Keys which were skipped, could not be decoded, or were
+    /// inserted by repair rules will not be present.
+    pub keyword_offsets: KeywordOffsets,
+
+    /// Timings and byte counts collected while reading, if requested.
+    pub metrics: Option<ParseMetrics>,
+}
+
+/// Timings and byte counts collected while reading a dataset.
+///
+/// Byte counts are taken from the segments that were read and do not include
+/// any bytes skipped between them.
+#[derive(Clone, Serialize)]
+pub struct ParseMetrics {
+    /// Number of bytes read from HEADER, TEXT, and (if read) DATA, ANALYSIS,
+    /// and OTHER.
+    pub bytes_read: u64,
+
+    /// Time spent reading HEADER
+    pub header_time: Duration,
+
+    /// Time spent reading and splitting primary and supplemental TEXT
+    pub text_time: Duration,
+
+    /// Time spent standardizing TEXT and reading DATA, ANALYSIS, and OTHER.
+    ///
+    /// Will be None if DATA was not read.
+    pub data_time: Option<Duration>,
+
+    /// Number of events read from DATA, if DATA was read
+    pub events: Option<usize>,
+}
+
+impl ParseMetrics {
+    /// Return the time spent in all phases.
+    pub fn total_time(&self) -> Duration {
+        self.header_time + self.text_time + self.data_time.unwrap_or_default()
+    }
+
+    /// Return the number of events read per second spent reading DATA.
+    pub fn events_per_second(&self) -> Option<f64> {
+        self.events
+            .zip(self.data_time)
+            .map(|(n, t)| (n as f64, t.as_secs_f64()))
+            .filter(|(_, t)| *t > 0.0)
+            .map(|(n, t)| n / t)
+    }
+}
+
+/// Result of checking an FCS file for conformance.
+#[derive(Default, Serialize)]
+pub struct ConformanceReport {
+    /// Version from HEADER, if it could be read
+    pub version: Option<Version>,
+
+    /// Issues which make the file non-conformant.
+    ///
+    /// Which issues are errors depends on the "allow_*" flags used for the
+    /// check.
+    pub errors: Vec<FcsError>,
+
+    /// Issues which do not make the file non-conformant
+    pub warnings: Vec<String>,
+}
+
+impl ConformanceReport {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Add all warnings and errors from a result and return its value if any.
+    fn absorb<V, W, E>(&mut self, res: DeferredResult<V, W, E>) -> Option<V>
+    where
+        W: fmt::Display,
+        FcsError: From<E>,
+    {
+        let (value, ws, es) = match res {
+            Ok(tnt) => tnt.map(Some).into_parts(),
+            Err(f) => f.unfail().map(|_| None).into_parts(),
+        };
+        self.warnings.extend(ws.into_iter().map(|w| w.to_string()));
+        self.errors.extend(es.into_iter().map(FcsError::from));
+        value
+    }
 }
 
 /// Raw TEXT values for $BEGIN/END* keywords
+#[derive(Clone, Serialize)]
 pub struct SegmentKeywords {
     pub begin: Option<String>,
     pub end: Option<String>,
@@ -311,6 +1149,8 @@ pub struct RawTEXTFailure;
 
 pub struct RawDatasetFailure;
 
+pub struct ReadHistogramFailure;
+
 pub struct RawDatasetWithKwsFailure;
 
 pub struct StdTEXTFailure;
@@ -319,6 +1159,16 @@ pub struct StdDatasetFailure;
 
 pub struct StdDatasetWithKwsFailure;
 
+pub struct WriteFailure;
+
+pub struct ConvertFailure {
+    to: Version,
+}
+
+pub struct NormalizeFailure;
+
+pub struct EditTextFailure;
+
 enum_from_disp!(
     pub StdTEXTWarning,
     [Raw, ParseRawTEXTWarning],
@@ -339,8 +1189,41 @@ enum_from_disp!(
 
 enum_from_disp!(
     pub StdDatasetError,
-    [Raw, HeaderOrRawError],
-    [Std, StdDatasetFromRawError]
+    [Raw, Box<HeaderOrRawError>],
+    [Std, StdDatasetFromRawError],
+    [Nextdata, NextdataOverlapError]
+);
+
+impl From<HeaderOrRawError> for StdDatasetError {
+    fn from(value: HeaderOrRawError) -> Self {
+        Self::Raw(Box::new(value))
+    }
+}
+
+enum_from_disp!(
+    pub EditTextWarning,
+    [Read, StdDatasetFromRawWarning],
+    [Write, NewDataLayoutWarning]
+);
+
+enum_from_disp!(
+    pub EditTextError,
+    [Read, StdDatasetFromRawError],
+    [Write, StdWriterError]
+);
+
+enum_from_disp!(
+    pub NormalizeWarning,
+    [Read, StdDatasetWarning],
+    [Convert, MetarootConvertWarning],
+    [Write, NewDataLayoutWarning]
+);
+
+enum_from_disp!(
+    pub NormalizeError,
+    [Read, StdDatasetError],
+    [Convert, AnyConvertError],
+    [Write, StdWriterError]
 );
 
 enum_from_disp!(
@@ -351,20 +1234,33 @@ enum_from_disp!(
 );
 
 enum_from_disp!(
-    pub RawDatasetError,
+    pub ReadHistogramError,
     [Raw, HeaderOrRawError],
+    [Read, HistogramError]
+);
+
+enum_from_disp!(
+    pub RawDatasetError,
+    [Raw, Box<HeaderOrRawError>],
     [Std, LookupKeysError],
     [Read, DatasetWithKwsError]
 );
 
+impl From<HeaderOrRawError> for RawDatasetError {
+    fn from(value: HeaderOrRawError) -> Self {
+        Self::Raw(Box::new(value))
+    }
+}
+
 enum_from_disp!(
     pub ParseRawTEXTWarning,
     [Char, DelimCharError],
     [Keywords, ParseKeywordsIssue],
     [SuppOffsets, STextSegmentWarning],
     [Nextdata, ParseKeyError<ParseIntError>],
-    [Nonstandard, NonstandardError]
-
+    [Nonstandard, NonstandardError],
+    [Contaminated, ContaminatedValuesWarning],
+    [CodePage, UnsupportedCodePageWarning]
 );
 
 enum_from_disp!(
@@ -383,7 +1279,8 @@ enum_from_disp!(
 enum_from_disp!(
     pub ReadRawDatasetWarning,
     [DataReader, RawToReaderWarning],
-    [AnalysisReader, NewAnalysisReaderWarning]
+    [AnalysisReader, NewAnalysisReaderWarning],
+    [ReadData, ReadDataWarning]
 );
 
 enum_from_disp!(
@@ -456,15 +1353,23 @@ enum_from_disp!(
 
 pub struct NoTEXTWordsError;
 
+#[derive(Debug)]
+pub struct LiteralDelimFallback;
+
+#[derive(Debug)]
+pub struct EmptyValueWarning(pub Vec<u8>);
+
 enum_from_disp!(
     #[derive(Debug)]
     pub ParseKeywordsIssue,
     [BlankKey, BlankKeyError],
     [BlankValue, BlankValueError],
+    [EmptyValue, EmptyValueWarning],
     [Uneven, UnevenWordsError],
     [Final, FinalDelimError],
     [Unique, KeywordInsertError],
     [Bound, DelimBoundError],
+    [Fallback, LiteralDelimFallback],
     // this is only for supp TEXT but seems less wasteful/convoluted to put here
     [Mismatch, DelimMismatch]
 
@@ -484,29 +1389,170 @@ pub struct DelimMismatch {
 
 pub struct NonAsciiKeyError(String);
 
-pub struct NonUtf8KeywordError {
-    key: Vec<u8>,
-    value: Vec<u8>,
+pub struct ContaminatedValuesWarning(NonEmpty<String>);
+
+/// The code page in $UNICODE which cannot be decoded
+pub struct UnsupportedCodePageWarning(u32);
+
+pub struct NonUtf8KeywordError {
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+pub struct NonstandardError;
+
+pub struct NextdataOverlapError {
+    nextdata: u32,
+    text_end: u64,
+}
+
+fn read_fcs_raw_text_inner(
+    p: &path::Path,
+    conf: &RawTextReadConfig,
+) -> DeferredResult<
+    (RawTEXTOutput, BufReader<FcsSource>),
+    ParseRawTEXTWarning,
+    ImpureError<HeaderOrRawError>,
+> {
+    FcsSource::open(p).into_deferred().def_and_maybe(|file| {
+        let mut h = BufReader::new(file);
+        RawTEXTOutput::h_read(&mut h, conf, &mut ParserScratch::default()).def_map_value(|x| (x, h))
+    })
+}
+
+fn scan_header_inner(
+    p: &path::Path,
+    conf: &RawTextReadConfig,
+) -> DeferredResult<HeaderSummary, ParseRawTEXTWarning, ImpureError<HeaderOrRawError>> {
+    read_fcs_raw_text_inner(p, conf).def_map_value(|(raw, _)| {
+        let kws = &raw.keywords.std;
+        HeaderSummary {
+            version: raw.version,
+            segments: raw.parse.header_segments,
+            tot: get_opt(kws, Tot::std()).ok().flatten(),
+            par: get_opt(kws, Par::std()).ok().flatten(),
+            cyt: get_opt(kws, Cyt::std()).ok().flatten(),
+            date: get_opt(kws, FCSDate::std()).ok().flatten(),
+        }
+    })
+}
+
+impl ScanEntry {
+    fn new(p: &path::Path, conf: &RawTextReadConfig) -> Self {
+        let (summary, errors) = match scan_header_inner(p, conf) {
+            Ok(tnt) => {
+                let (x, _, es) = tnt.into_parts();
+                (es.is_empty().then_some(x), es)
+            }
+            Err(f) => (None, f.unfail().into_parts().2),
+        };
+        Self {
+            path: p.to_path_buf(),
+            summary,
+            errors: errors.into_iter().map(FcsError::from).collect(),
+        }
+    }
+}
+
+fn h_read_std_dataset<R: Read + Seek>(
+    h: &mut BufReader<R>,
+    conf: &DataReadConfig,
+    scratch: &mut ParserScratch,
+) -> IODeferredResult<StdDatasetOutput, StdDatasetWarning, StdDatasetError> {
+    RawTEXTOutput::h_read(h, &conf.standard.raw, scratch)
+        .def_io_into()
+        .def_and_maybe(|raw| raw.into_std_dataset(h, conf, scratch).def_io_into())
+}
+
+/// Run a parser on a sparse source until it reads nothing unfetched.
+///
+/// Each time the parser reads bytes which have not been fetched, they are
+/// fetched and the parser is run again from the start.
+#[cfg(feature = "tokio")]
+async fn h_read_sparse<R, F, T>(h: &mut R, src: &mut SparseSource, mut f: F) -> io::Result<T>
+where
+    R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    F: FnMut(&mut SparseSource) -> T,
+{
+    loop {
+        let res = f(src);
+        if !src.fetch_missing(h).await? {
+            return Ok(res);
+        }
+    }
+}
+
+fn h_read_all_std_datasets<R: Read + Seek>(
+    h: &mut BufReader<OffsetReader<R>>,
+    conf: &DataReadConfig,
+) -> IODeferredResult<Vec<StdDatasetOutput>, StdDatasetWarning, StdDatasetError> {
+    let mut acc = Tentative::new1(vec![]);
+    let mut base = 0;
+    let mut scratch = ParserScratch::default();
+    let file_len = match h.get_mut().inner.seek(io::SeekFrom::End(0)) {
+        Ok(x) => x,
+        Err(e) => return Err(DeferredFailure::new1(ImpureError::IO(e)).absorb(acc)),
+    };
+    loop {
+        h.get_mut().base = base;
+        if let Err(e) = h.seek(io::SeekFrom::Start(0)) {
+            return Err(DeferredFailure::new1(ImpureError::IO(e)).absorb(acc));
+        }
+        let res: IODeferredResult<_, StdDatasetWarning, StdDatasetError> =
+            RawTEXTOutput::h_read(h, &conf.standard.raw, &mut scratch)
+                .def_io_into()
+                .def_and_maybe(|raw| raw.into_std_dataset(h, conf, &mut scratch).def_io_into());
+        let mut next = None;
+        match res {
+            Ok(tnt) => {
+                let dataset = tnt.map(|x| {
+                    let text_end = x.parse.header_segments.text.inner.try_next_byte();
+                    next = x.parse.nextdata.filter(|n| *n > 0).map(|n| (n, text_end));
+                    x
+                });
+                acc = acc.zip_with(dataset, |mut xs, x| {
+                    xs.push(x);
+                    xs
+                });
+            }
+            Err(f) => return Err(f.absorb(acc)),
+        }
+        match next {
+            None => return Ok(acc),
+            // some writers set $NEXTDATA to the end of the file rather than 0
+            Some((nextdata, _)) if base + u64::from(nextdata) >= file_len => return Ok(acc),
+            Some((nextdata, Some(text_end))) if u64::from(nextdata) < text_end => {
+                let e = NextdataOverlapError { nextdata, text_end };
+                return Err(DeferredFailure::new1(ImpureError::Pure(e.into())).absorb(acc));
+            }
+            Some((nextdata, _)) => base += u64::from(nextdata),
+        }
+    }
+}
+
+/// A reader which treats an offset in its inner reader as position 0.
+///
+/// All offsets in HEADER and TEXT are relative to the start of their dataset,
+/// so this allows any dataset in a file to be read as if it were the first.
+struct OffsetReader<R> {
+    inner: R,
+    base: u64,
 }
 
-pub struct NonstandardError;
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
 
-fn read_fcs_raw_text_inner(
-    p: &path::PathBuf,
-    conf: &RawTextReadConfig,
-) -> DeferredResult<
-    (RawTEXTOutput, BufReader<fs::File>),
-    ParseRawTEXTWarning,
-    ImpureError<HeaderOrRawError>,
-> {
-    fs::File::options()
-        .read(true)
-        .open(p)
-        .into_deferred()
-        .def_and_maybe(|file| {
-            let mut h = BufReader::new(file);
-            RawTEXTOutput::h_read(&mut h, conf).def_map_value(|x| (x, h))
-        })
+impl<R: Seek> Seek for OffsetReader<R> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let p = match pos {
+            io::SeekFrom::Start(x) => io::SeekFrom::Start(self.base + x),
+            _ => pos,
+        };
+        self.inner.seek(p).map(|x| x.saturating_sub(self.base))
+    }
 }
 
 fn h_read_dataset_from_kws<R: Read + Seek>(
@@ -526,31 +1572,93 @@ fn h_read_dataset_from_kws<R: Read + Seek>(
         .def_errors_liftio();
     data_res.def_zip(analysis_res).def_and_maybe(|(dr, ar)| {
         let or = OthersReader { segs: other_segs };
-        h_read_data_and_analysis(h, dr, ar, or)
-            .map(
-                |(data, analysis, others, d_seg, a_seg)| RawDatasetWithKwsOutput {
+        h_read_data_and_analysis(h, dr, ar, or, &mut ParserScratch::default())
+            .into_deferred()
+            .def_map_errors(|e: ImpureError<ReadDataError>| e.inner_into())
+            .def_and_tentatively(|(data, analysis, others, d_seg, a_seg, read_ws)| {
+                let ws = read_ws
+                    .into_iter()
+                    .filter(|w| conf.reader.warn_overrange || !w.is_overrange())
+                    .map(|w| w.into())
+                    .collect();
+                let out = RawDatasetWithKwsOutput {
                     data,
                     analysis,
                     others,
                     data_seg: d_seg,
                     analysis_seg: a_seg,
-                },
-            )
-            .into_deferred()
-            .def_map_errors(|e: ImpureError<ReadDataError>| e.inner_into())
+                };
+                Tentative::new(out, ws, vec![])
+            })
     })
 }
 
 impl RawTEXTOutput {
+    /// Return byte ranges of DATA, ANALYSIS and OTHER which may need reading.
+    ///
+    /// Each range is half-open. Ranges come from both HEADER and TEXT, and
+    /// those in TEXT are taken as-is without any validation or correction.
+    #[cfg(feature = "tokio")]
+    fn dataset_segments(&self) -> Vec<(u64, u64)> {
+        fn range<T: Copy + Into<u64>>(s: &Segment<T>) -> Option<(u64, u64)> {
+            s.try_coords()
+                .zip(s.try_next_byte())
+                .map(|((b, _), e)| (b.into(), e))
+        }
+        let hs = &self.parse.header_segments;
+        let std = &self.keywords.std;
+        let from_text = |b: StdKey, e: StdKey| {
+            let get = |k| {
+                std.get(&k)
+                    .and_then(|x: &String| x.trim().parse::<u64>().ok())
+            };
+            get(b).zip(get(e)).map(|(x, y)| (x, y.saturating_add(1)))
+        };
+        [
+            range(&hs.data.inner),
+            range(&hs.analysis.inner),
+            from_text(Begindata::std(), Enddata::std()),
+            from_text(Beginanalysis::std(), Endanalysis::std()),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(hs.other.iter().filter_map(|x| range(&x.inner)))
+        .collect()
+    }
+
     fn h_read<R: Read + Seek>(
         h: &mut BufReader<R>,
         conf: &RawTextReadConfig,
+        scratch: &mut ParserScratch,
     ) -> DeferredResult<Self, ParseRawTEXTWarning, ImpureError<HeaderOrRawError>> {
+        let start = conf.collect_metrics.then(Instant::now);
         Header::h_read(h, &conf.header)
             .mult_to_deferred()
             .def_map_errors(|e: ImpureError<HeaderError>| e.inner_into())
             .def_and_maybe(|header| {
-                h_read_raw_text_from_header(h, header, conf).def_map_errors(|e| e.inner_into())
+                let header_metrics = start.map(|t| (t.elapsed(), h.stream_position().ok()));
+                h_read_raw_text_from_header(h, header, conf, &mut scratch.text)
+                    .def_map_errors(|e| e.inner_into())
+                    .def_map_value(|mut raw| {
+                        if let Some((t, (header_time, header_bytes))) = start.zip(header_metrics) {
+                            let hs = &raw.parse.header_segments;
+                            let others_bytes =
+                                raw.parse.others.as_ref().map_or(0, |_| {
+                                    hs.other.iter().map(|o| o.inner.len()).sum::<u64>()
+                                });
+                            let text_bytes = hs.text.inner.len()
+                                + raw.parse.supp_text.map_or(0, |s| s.inner.len())
+                                + others_bytes;
+                            raw.parse.metrics = Some(ParseMetrics {
+                                bytes_read: header_bytes.unwrap_or(0) + text_bytes,
+                                header_time,
+                                text_time: t.elapsed().saturating_sub(header_time),
+                                data_time: None,
+                                events: None,
+                            });
+                        }
+                        raw
+                    })
             })
     }
 
@@ -559,8 +1667,17 @@ impl RawTEXTOutput {
         conf: &StdTextReadConfig,
     ) -> DeferredResult<StdTEXTOutput, LookupMeasWarning, LookupKeysError> {
         let mut kws = self.keywords;
-        AnyCoreTEXT::parse_raw(self.version, &mut kws.std, kws.nonstd, conf).def_map_value(
-            |standardized| {
+        let offsets = &self.parse.keyword_offsets;
+        AnyCoreTEXT::parse_raw(self.version, &mut kws.std, kws.nonstd, conf)
+            .def_map_errors(|mut e| {
+                e.locate(offsets);
+                e
+            })
+            .def_map_warnings(|mut w| {
+                w.locate(offsets);
+                w
+            })
+            .def_and_tentatively(|standardized| {
                 let std = &mut kws.std;
                 let tot = std.remove(&Tot::std());
                 let timestep = std.remove(&Timestep::std());
@@ -572,30 +1689,58 @@ impl RawTEXTOutput {
                     begin: std.remove(&Beginanalysis::std()),
                     end: std.remove(&Endanalysis::std()),
                 };
-                StdTEXTOutput {
+                let (pseudostandard_typed, es) = promote_keywords(std, &conf.pseudostandard_types);
+                let (unused_standard_keywords, reg_es) =
+                    remove_registered(std, &conf.keyword_registry);
+                let vendor = standardized.vendor_keywords(conf.vendor);
+                let claimed_nonstandard = standardized.nonstandard_claims();
+                let out = StdTEXTOutput {
                     parse: self.parse,
                     standardized,
+                    claimed_nonstandard,
                     tot,
                     timestep,
                     data,
                     analysis,
                     pseudostandard: kws.std,
-                }
-            },
-        )
+                    pseudostandard_typed,
+                    unused_standard_keywords,
+                    vendor,
+                };
+                let ws = es
+                    .into_iter()
+                    .map(LookupMeasWarning::from)
+                    .chain(reg_es.into_iter().map(LookupMeasWarning::from))
+                    .collect();
+                Tentative::new(out, ws, vec![])
+            })
     }
 
     fn into_std_dataset<R: Read + Seek>(
         self,
         h: &mut BufReader<R>,
         conf: &DataReadConfig,
+        scratch: &mut ParserScratch,
     ) -> DeferredResult<
         StdDatasetOutput,
         StdDatasetFromRawWarning,
         ImpureError<StdDatasetFromRawError>,
     > {
+        let start = self.parse.metrics.is_some().then(Instant::now);
+        let version = self.version;
         let mut kws = self.keywords;
-        AnyCoreDataset::parse_raw(
+        let guess = if conf.reader.guess_byteord {
+            match h_guess_byteord(h, version, &kws.std, self.parse.header_segments.data) {
+                Ok(x) => x,
+                Err(e) => return Err(DeferredFailure::new1(e.into())),
+            }
+        } else {
+            None
+        };
+        if let Some(g) = &guess {
+            kws.std.insert(ByteOrd::std(), g.value.clone());
+        }
+        let mut res = AnyCoreDataset::parse_raw(
             h,
             self.version,
             &mut kws.std,
@@ -604,18 +1749,177 @@ impl RawTEXTOutput {
             self.parse.header_segments.analysis,
             &self.parse.header_segments.other[..],
             conf,
+            scratch,
         )
-        .def_map_value(|(core, data_seg, analysis_seg)| StdDatasetOutput {
-            dataset: StdDatasetWithKwsOutput {
-                standardized: DatasetWithSegments {
-                    core,
-                    data_seg,
-                    analysis_seg,
-                },
-                pseudostandard: kws.std,
-            },
-            parse: self.parse,
+        .def_map_errors(|mut e| {
+            e.locate(&self.parse.keyword_offsets);
+            e
         })
+        .def_map_warnings(|mut w| {
+            w.locate(&self.parse.keyword_offsets);
+            w
+        })
+        .def_and_tentatively(|(core, data_seg, analysis_seg)| {
+            let (pseudostandard_typed, es) =
+                promote_keywords(&mut kws.std, &conf.standard.pseudostandard_types);
+            let (unused_standard_keywords, reg_es) =
+                remove_registered(&mut kws.std, &conf.standard.keyword_registry);
+            let vendor = core.vendor_keywords(conf.standard.vendor);
+            let mut parse = self.parse;
+            if let Some((m, t)) = parse.metrics.as_mut().zip(start) {
+                m.bytes_read += data_seg.inner.len()
+                    + analysis_seg.inner.len()
+                    + parse
+                        .header_segments
+                        .other
+                        .iter()
+                        .map(|o| o.inner.len())
+                        .sum::<u64>();
+                m.data_time = Some(t.elapsed());
+                m.events = Some(core.nrows());
+            }
+            let out = StdDatasetOutput {
+                dataset: StdDatasetWithKwsOutput {
+                    standardized: DatasetWithSegments {
+                        core,
+                        data_seg,
+                        analysis_seg,
+                    },
+                    pseudostandard: kws.std,
+                    pseudostandard_typed,
+                    unused_standard_keywords,
+                    vendor,
+                },
+                parse,
+            };
+            let ws = es
+                .into_iter()
+                .map(LookupMeasWarning::from)
+                .chain(reg_es.into_iter().map(LookupMeasWarning::from))
+                .map(|e| e.into())
+                .collect();
+            Tentative::new(out, ws, vec![])
+        });
+        if let Some(g) = guess {
+            res.def_push_warning(g.into());
+        }
+        if let Some(mode) = res
+            .as_ref()
+            .ok()
+            .map(|t| t.value().dataset.standardized.core.mode())
+            .filter(|m| *m != Mode::List)
+        {
+            let e = NonListModeError(mode);
+            if conf.reader.allow_non_list_mode {
+                res.def_push_warning(e.into());
+            } else {
+                res.def_push_error(ImpureError::Pure(e.into()));
+            }
+        }
+        if conf.reader.check_crc && version != Version::FCS2_0 {
+            let end = res.as_ref().ok().and_then(|t| t.value().last_segment_end());
+            match end.map(|x| h_check_crc(h, x)) {
+                Some(Ok(Some(e))) => {
+                    if conf.reader.allow_crc_mismatch {
+                        res.def_push_warning(e.into());
+                    } else {
+                        res.def_push_error(ImpureError::Pure(e.into()));
+                    }
+                }
+                Some(Err(e)) => res.def_push_error(e.into()),
+                _ => (),
+            }
+        }
+        if conf.reader.check_data_hash {
+            let stored = res.as_ref().ok().and_then(|t| {
+                let ds = &t.value().dataset.standardized;
+                let key = NonStdKey::from_unchecked(DATA_HASH_KEY);
+                ds.core.nonstandard_keywords().get(&key).map(|v| {
+                    let coords = ds.data_seg.try_as_generic().map(|x| (x.begin, x.end));
+                    (v.clone(), coords)
+                })
+            });
+            match stored.map(|(v, c)| h_check_data_hash(h, c, &v)) {
+                Some(Ok(Some(e))) => {
+                    if conf.reader.allow_data_hash_mismatch {
+                        res.def_push_warning(e.into());
+                    } else {
+                        res.def_push_error(ImpureError::Pure(e.into()));
+                    }
+                }
+                Some(Err(e)) => res.def_push_error(e.into()),
+                _ => (),
+            }
+        }
+        res
+    }
+}
+
+fn write_dataset_inner(
+    p: &path::Path,
+    core: &AnyCoreDataset,
+    conf: &WriteConfig,
+) -> IODeferredResult<(), NewDataLayoutWarning, StdWriterError> {
+    fs::File::create(p).into_deferred().def_and_maybe(|file| {
+        let mut h = BufWriter::new(file);
+        core.h_write(&mut h, conf)
+            .def_and_maybe(|_| h.flush().into_deferred())
+    })
+}
+
+impl RawTEXTOutput {
+    /// Return the offset of the last byte of the last segment in this dataset.
+    ///
+    /// Offsets for DATA and ANALYSIS will be taken from TEXT if present,
+    /// falling back to HEADER.
+    fn last_segment_end(&self) -> Option<u64> {
+        let hs = &self.parse.header_segments;
+        let from_text = |k: &str| {
+            self.keywords
+                .get(k)
+                .ok()
+                .flatten()
+                .and_then(|x| x.trim().parse::<u64>().ok())
+                .filter(|x| *x > 0)
+        };
+        [
+            hs.text.try_as_generic(),
+            self.parse
+                .supp_text
+                .as_ref()
+                .and_then(|x| x.try_as_generic()),
+            hs.data.try_as_generic(),
+            hs.analysis.try_as_generic(),
+        ]
+        .into_iter()
+        .chain(hs.other.iter().map(|x| x.try_as_generic()))
+        .flatten()
+        .map(|x| x.end)
+        .chain(from_text("$ENDDATA"))
+        .chain(from_text("$ENDANALYSIS"))
+        .max()
+    }
+}
+
+impl StdDatasetOutput {
+    /// Return the offset of the last byte of the last segment in this dataset.
+    fn last_segment_end(&self) -> Option<u64> {
+        let hs = &self.parse.header_segments;
+        let ds = &self.dataset.standardized;
+        [
+            hs.text.try_as_generic(),
+            self.parse
+                .supp_text
+                .as_ref()
+                .and_then(|x| x.try_as_generic()),
+            ds.data_seg.try_as_generic(),
+            ds.analysis_seg.try_as_generic(),
+        ]
+        .into_iter()
+        .chain(hs.other.iter().map(|x| x.try_as_generic()))
+        .flatten()
+        .map(|x| x.end)
+        .max()
     }
 }
 
@@ -657,19 +1961,44 @@ fn kws_to_analysis_reader(
     }
 }
 
+/// Decode values for keys listed in $UNICODE using its code page (3.0 only).
+///
+/// Only values which could not be decoded initially will be affected. Return
+/// the code page if it is not supported.
+fn decode_unicode(kws: &mut ParsedKeywords, version: Version) -> Option<u32> {
+    if version != Version::FCS3_0 {
+        return None;
+    }
+    let unicode = kws.std.get(&Unicode::std())?.parse::<Unicode>().ok()?;
+    if !unicode.is_supported() {
+        return Some(unicode.page);
+    }
+    kws.decode_byte_pairs(|k, v| unicode.has_key(k).then(|| unicode.decode(v)).flatten());
+    None
+}
+
 fn h_read_raw_text_from_header<R: Read + Seek>(
     h: &mut BufReader<R>,
     header: Header,
     conf: &RawTextReadConfig,
+    buf: &mut Vec<u8>,
 ) -> DeferredResult<RawTEXTOutput, ParseRawTEXTWarning, ImpureError<ParseRawTEXTError>> {
-    let mut buf = vec![];
+    buf.clear();
     let ptext_seg = header.segments.text;
-    ptext_seg
-        .inner
-        .h_read_contents(h, &mut buf)
-        .into_deferred()?;
+    ptext_seg.inner.h_read_contents(h, buf).into_deferred()?;
+
+    let raw_text = conf.keep_raw_text.then(|| buf.clone());
+
+    let others = if conf.keep_others {
+        let or = OthersReader {
+            segs: &header.segments.other[..],
+        };
+        Some(or.h_read(h).map_err(|e| DeferredFailure::new1(e.into()))?)
+    } else {
+        None
+    };
 
-    let tnt_delim = split_first_delim(&buf, conf)
+    let tnt_delim = split_first_delim(buf, conf)
         .def_inner_into()
         .def_errors_liftio()?;
 
@@ -678,7 +2007,11 @@ fn h_read_raw_text_from_header<R: Read + Seek>(
         split_raw_primary_text(kws, delim, bytes, conf)
             .def_inner_into()
             .def_errors_liftio()
-            .def_map_value(|_kws| (delim, _kws))
+            .def_map_value(|mut _kws| {
+                // offsets are relative to the byte after the first delimiter
+                _kws.shift_offsets(segment_begin(&ptext_seg.inner) + 1);
+                (delim, _kws)
+            })
     })?;
 
     let tnt_all_kws = tnt_primary.and_maybe(|(delim, mut kws)| {
@@ -687,7 +2020,7 @@ fn h_read_raw_text_from_header<R: Read + Seek>(
             // positive pseudostandard keyword error later
             let _ = kws.std.remove(&Beginstext::std());
             let _ = kws.std.remove(&Endstext::std());
-            Ok(Tentative::new1((delim, kws, None)))
+            Ok(Tentative::new1((delim, kws, None, None)))
         } else {
             lookup_stext_offsets(&mut kws.std, header.version, ptext_seg, conf)
                 .errors_into()
@@ -695,26 +2028,45 @@ fn h_read_raw_text_from_header<R: Read + Seek>(
                 .warnings_into()
                 .map(|s| (s, kws))
                 .and_maybe(|(maybe_supp_seg, _kws)| {
+                    let mut raw_supp_text = None;
                     let tnt_supp_kws = if let Some(seg) = maybe_supp_seg {
                         buf.clear();
                         seg.inner
-                            .h_read_contents(h, &mut buf)
+                            .h_read_contents(h, buf)
                             .map_err(|e| DeferredFailure::new1(e.into()))?;
-                        split_raw_supp_text(_kws, delim, &buf, conf)
+                        raw_supp_text = conf.keep_raw_text.then(|| buf.clone());
+                        split_raw_supp_text(_kws, delim, buf, segment_begin(&seg.inner), conf)
                             .inner_into()
                             .errors_liftio()
                     } else {
                         Tentative::new1(_kws)
                     };
-                    Ok(tnt_supp_kws.map(|k| (delim, k, maybe_supp_seg)))
+                    Ok(tnt_supp_kws.map(|k| (delim, k, maybe_supp_seg, raw_supp_text)))
                 })
         }
     })?;
 
-    let out = tnt_all_kws.and_tentatively(|(delimiter, mut kws, supp_text_seg)| {
+    let out = tnt_all_kws.and_tentatively(|(delimiter, mut kws, supp_text_seg, raw_supp_text)| {
+        let codepage = decode_unicode(&mut kws, header.version);
+        let anonymize = !conf.anonymize.is_empty();
+        let keyword_repairs = if conf.keyword_repairs.is_empty() && !anonymize {
+            vec![]
+        } else {
+            let mut valid = ValidKeywords {
+                std: mem::take(&mut kws.std),
+                nonstd: mem::take(&mut kws.nonstd),
+            };
+            let mut mods = conf.keyword_repairs.apply(&mut valid);
+            mods.extend(conf.anonymize.apply(&mut valid));
+            kws.std = valid.std;
+            kws.nonstd = valid.nonstd;
+            rename_offsets(&mods[..], &mut kws.offsets);
+            mods
+        };
         repair_keywords(&mut kws.std, conf);
         let mut tnt_parse = lookup_nextdata(&kws.std, conf.allow_missing_nextdata)
             .errors_into()
+            .warnings_into::<ParseRawTEXTWarning>()
             .map(|nextdata| RawTEXTParseData {
                 header_segments: header.segments,
                 supp_text: supp_text_seg,
@@ -722,6 +2074,14 @@ fn h_read_raw_text_from_header<R: Read + Seek>(
                 delimiter,
                 non_ascii: kws.non_ascii,
                 byte_pairs: kws.byte_pairs,
+                nonstandard_skipped: kws.nonstd_skipped,
+                contaminated_values: kws.contaminated,
+                raw_text: raw_text.filter(|_| !anonymize),
+                raw_supp_text: raw_supp_text.filter(|_| !anonymize),
+                others,
+                keyword_repairs,
+                keyword_offsets: kws.offsets,
+                metrics: None,
             });
 
         // throw errors if we found any non-ascii keywords and we want to know
@@ -770,6 +2130,15 @@ fn h_read_raw_text_from_header<R: Read + Seek>(
             }
         });
 
+        // warn if $UNICODE has a code page we can't decode
+        tnt_parse.eval_warning(|_| codepage.map(|x| UnsupportedCodePageWarning(x).into()));
+
+        // warn about any values which were converted lossily
+        tnt_parse.eval_warning(|pd| {
+            NonEmpty::from_slice(&pd.contaminated_values[..])
+                .map(|xs| ContaminatedValuesWarning(xs).into())
+        });
+
         tnt_parse
             .inner_into()
             .map(|parse| RawTEXTOutput {
@@ -792,7 +2161,7 @@ fn split_first_delim<'a>(
 ) -> DeferredResult<(u8, &'a [u8]), DelimCharError, DelimVerifyError> {
     if let Some((delim, rest)) = bytes.split_first() {
         let mut tnt = Tentative::new1((*delim, rest));
-        if !(1..=126).contains(delim) {
+        if !is_valid_delim(*delim) {
             tnt.push_error_or_warning(DelimCharError(*delim), !conf.allow_non_ascii_delim);
         }
         Ok(tnt)
@@ -818,10 +2187,17 @@ fn split_raw_supp_text(
     kws: ParsedKeywords,
     delim: u8,
     bytes: &[u8],
+    begin: u64,
     conf: &RawTextReadConfig,
 ) -> Tentative<ParsedKeywords, ParseKeywordsIssue, ParseSupplementalTEXTError> {
     if let Some((byte0, rest)) = bytes.split_first() {
-        let mut tnt = split_raw_text_inner(kws, *byte0, rest, conf).errors_into();
+        let supp = ParsedKeywords::default();
+        let mut tnt = split_raw_text_inner(supp, *byte0, rest, conf)
+            .map(|mut _supp| {
+                _supp.shift_offsets(begin + 1);
+                _supp
+            })
+            .errors_into();
         if *byte0 != delim {
             let x = DelimMismatch {
                 delim,
@@ -833,7 +2209,18 @@ fn split_raw_supp_text(
                 tnt.push_warning(x.into());
             }
         }
-        tnt
+        tnt.and_tentatively(|supp_kws| {
+            let mut all_kws = kws;
+            let conflicts = all_kws.merge_supp(supp_kws, conf);
+            let mut ret = Tentative::new1(all_kws);
+            for c in conflicts {
+                match c {
+                    Leveled::Error(e) => ret.push_error(ParseKeywordsIssue::from(e).into()),
+                    Leveled::Warning(w) => ret.push_warning(w.into()),
+                }
+            }
+            ret
+        })
     } else {
         // if empty do nothing, this is expected for most files
         Tentative::new1(kws)
@@ -848,6 +2235,18 @@ fn split_raw_text_inner(
 ) -> Tentative<ParsedKeywords, ParseKeywordsIssue, ParseKeywordsIssue> {
     if conf.use_literal_delims {
         split_raw_text_literal_delim(kws, delim, bytes, conf)
+    } else if conf.fallback_literal_delims {
+        let escaped = split_raw_text_escaped_delim(kws.clone(), delim, bytes, conf);
+        if escaped.errors().is_empty() {
+            return escaped;
+        }
+        let mut literal = split_raw_text_literal_delim(kws, delim, bytes, conf);
+        if literal.errors().is_empty() {
+            literal.push_warning(LiteralDelimFallback.into());
+            literal
+        } else {
+            escaped
+        }
     } else {
         split_raw_text_escaped_delim(kws, delim, bytes, conf)
     }
@@ -874,12 +2273,17 @@ fn split_raw_text_literal_delim(
     let mut it = bytes.split(|x| *x == delim);
     let mut prev_was_blank = false;
     let mut prev_was_key = false;
+    // position of the start of the current word
+    let mut pos = 0;
 
     while let Some(key) = it.next() {
+        let key_pos = pos;
+        pos += key.len() + 1;
         prev_was_key = true;
         prev_was_blank = key.is_empty();
         if key.is_empty() {
             if let Some(value) = it.next() {
+                pos += value.len() + 1;
                 prev_was_key = false;
                 prev_was_blank = value.is_empty();
                 push_issue(conf.allow_empty, BlankKeyError.into());
@@ -889,14 +2293,20 @@ fn split_raw_text_literal_delim(
                 break;
             }
         } else if let Some(value) = it.next() {
+            pos += value.len() + 1;
             prev_was_key = false;
             prev_was_blank = value.is_empty();
-            if value.is_empty() {
+            if value.is_empty() && !conf.keep_empty_values {
                 push_issue(conf.allow_empty, BlankValueError(key.to_vec()).into());
-            } else if let Err(lvl) = kws.insert(key, value, conf) {
-                match lvl.inner_into() {
-                    Leveled::Error(e) => push_issue(false, e),
-                    Leveled::Warning(w) => push_issue(true, w),
+            } else {
+                if value.is_empty() {
+                    push_issue(true, EmptyValueWarning(key.to_vec()).into());
+                }
+                if let Err(lvl) = kws.insert(key, value, key_pos, conf) {
+                    match lvl.inner_into() {
+                        Leveled::Error(e) => push_issue(false, e),
+                        Leveled::Warning(w) => push_issue(true, w),
+                    }
                 }
             }
         } else {
@@ -935,8 +2345,8 @@ fn split_raw_text_escaped_delim(
         }
     };
 
-    let mut push_pair = |_ews: &mut (Vec<_>, Vec<_>), kb: &Vec<_>, vb: &Vec<_>| {
-        if let Err(lvl) = kws.insert(kb, vb, conf) {
+    let mut push_pair = |_ews: &mut (Vec<_>, Vec<_>), kb: &Vec<_>, vb: &Vec<_>, kpos| {
+        if let Err(lvl) = kws.insert(kb, vb, kpos, conf) {
             match lvl.inner_into() {
                 Leveled::Error(e) => push_issue(_ews, false, e),
                 Leveled::Warning(w) => push_issue(_ews, true, w),
@@ -956,8 +2366,13 @@ fn split_raw_text_escaped_delim(
     let mut consec_blanks = 0;
     let mut keybuf: Vec<u8> = vec![];
     let mut valuebuf: Vec<u8> = vec![];
+    // positions of the start of the current segment and key
+    let mut pos = 0;
+    let mut key_pos = 0;
 
     for segment in bytes.split(|x| *x == delim) {
+        let seg_pos = pos;
+        pos += segment.len() + 1;
         if segment.is_empty() {
             consec_blanks += 1;
         } else {
@@ -965,15 +2380,17 @@ fn split_raw_text_escaped_delim(
                 // Previous number of delimiters is odd, treat this as a word
                 // boundary
                 if !valuebuf.is_empty() {
-                    push_pair(&mut ews, &keybuf, &valuebuf);
+                    push_pair(&mut ews, &keybuf, &valuebuf, key_pos);
                     keybuf.clear();
                     valuebuf.clear();
                     keybuf.extend_from_slice(segment);
+                    key_pos = seg_pos;
                 } else if !keybuf.is_empty() {
                     valuebuf.extend_from_slice(segment);
                 } else {
                     // this should only be reached on first iteration
                     keybuf.extend_from_slice(segment);
+                    key_pos = seg_pos;
                 }
                 if consec_blanks > 0 {
                     push_issue(
@@ -1039,12 +2456,17 @@ fn split_raw_text_escaped_delim(
     if valuebuf.is_empty() {
         push_issue(&mut ews, conf.allow_odd, UnevenWordsError.into());
     } else {
-        push_pair(&mut ews, &keybuf, &valuebuf);
+        push_pair(&mut ews, &keybuf, &valuebuf, key_pos);
     }
 
     Tentative::new(kws, ews.0, ews.1)
 }
 
+/// Return the offset of the first byte of a segment (0 if empty).
+fn segment_begin<T: Copy + Into<u64>>(seg: &Segment<T>) -> u64 {
+    seg.as_u64().try_coords().map_or(0, |(b, _)| b)
+}
+
 fn repair_keywords(kws: &mut StdKeywords, conf: &RawTextReadConfig) {
     for (key, v) in kws.iter_mut() {
         // TODO generalized this and possibly put in a trait
@@ -1116,7 +2538,9 @@ impl fmt::Display for DelimCharError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "delimiter must be ASCII character 1-126 inclusive, got {}",
+            "delimiter must be ASCII character {}-{} inclusive, got {}",
+            DELIM_RANGE.start(),
+            DELIM_RANGE.end(),
             self.0
         )
     }
@@ -1134,6 +2558,16 @@ impl fmt::Display for BlankKeyError {
     }
 }
 
+impl fmt::Display for EmptyValueWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "keeping key with blank value as empty string, key was '{}'",
+            String::from_utf8_lossy(&self.0)
+        )
+    }
+}
+
 impl fmt::Display for BlankValueError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
@@ -1178,6 +2612,28 @@ impl fmt::Display for DelimMismatch {
     }
 }
 
+impl fmt::Display for UnsupportedCodePageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "code page {} in $UNICODE is not supported, keywords \
+             listed there were not decoded",
+            self.0
+        )
+    }
+}
+
+impl fmt::Display for ContaminatedValuesWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "values with non-UTF-8 or control characters were converted \
+             lossily for keys: {}",
+            self.0.iter().join(", ")
+        )
+    }
+}
+
 impl fmt::Display for NonAsciiKeyError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "non-ASCII key encountered and dropped: {}", self.0)
@@ -1203,6 +2659,27 @@ impl fmt::Display for NonstandardError {
     }
 }
 
+impl fmt::Display for LiteralDelimFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "could not split TEXT with escaped delimiters, \
+             used literal delimiters instead"
+        )
+    }
+}
+
+impl fmt::Display for NextdataOverlapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "$NEXTDATA ({}) points to a dataset which would overlap with \
+             the current primary TEXT (ending at {})",
+            self.nextdata, self.text_end
+        )
+    }
+}
+
 impl fmt::Display for HeaderFailure {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "could not parse HEADER")
@@ -1221,15 +2698,139 @@ impl fmt::Display for StdTEXTFailure {
     }
 }
 
+impl fmt::Display for ConvertFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not convert dataset to {}", self.to)
+    }
+}
+
+impl fmt::Display for NormalizeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not normalize FCS file to 3.2")
+    }
+}
+
+impl fmt::Display for EditTextFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not write edited TEXT")
+    }
+}
+
+impl fmt::Display for WriteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not write FCS file")
+    }
+}
+
 impl fmt::Display for StdDatasetFailure {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "could not read DATA with standardized TEXT")
     }
 }
 
+impl fmt::Display for ReadHistogramFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not read histograms from DATA")
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::text::registry::{KeyStatus, KeywordSpec};
+
+    /// A file with 'head' at the start, 'tail' at 'offset' and zeros between.
+    ///
+    /// This mimics a sparse file so that offsets beyond 4GB may be tested
+    /// without needing that much memory or disk.
+    struct SparseFile {
+        head: Vec<u8>,
+        offset: u64,
+        tail: Vec<u8>,
+        pos: u64,
+    }
+
+    impl Read for SparseFile {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let head_len = self.head.len() as u64;
+            let end = self.offset + self.tail.len() as u64;
+            let (src, n): (Option<&[u8]>, u64) = if self.pos < head_len {
+                let i = self.pos as usize;
+                (Some(&self.head[i..]), head_len - self.pos)
+            } else if self.pos < self.offset {
+                (None, self.offset - self.pos)
+            } else if self.pos < end {
+                let i = (self.pos - self.offset) as usize;
+                (Some(&self.tail[i..]), end - self.pos)
+            } else {
+                (None, 0)
+            };
+            let m = buf.len().min(usize::try_from(n).unwrap_or(usize::MAX));
+            match src {
+                Some(xs) => buf[..m].copy_from_slice(&xs[..m]),
+                None => buf[..m].fill(0),
+            }
+            self.pos += m as u64;
+            Ok(m)
+        }
+    }
+
+    impl Seek for SparseFile {
+        fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+            let end = self.offset + self.tail.len() as u64;
+            self.pos = match pos {
+                io::SeekFrom::Start(x) => Some(x),
+                io::SeekFrom::End(x) => end.checked_add_signed(x),
+                io::SeekFrom::Current(x) => self.pos.checked_add_signed(x),
+            }
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+            Ok(self.pos)
+        }
+    }
+
+    #[test]
+    fn test_read_data_beyond_4gb() {
+        let begin = u64::from(u32::MAX) + 1000;
+        let data = [1_u8, 2, 3, 4];
+        let text = format!(
+            "/$BEGINANALYSIS/0/$ENDANALYSIS/0/$BEGINSTEXT/0/$ENDSTEXT/0\
+             /$BEGINDATA/{begin}/$ENDDATA/{}/$BYTEORD/1,2,3,4/$CYT/FACS\
+             /$DATATYPE/I/$MODE/L/$NEXTDATA/0/$PAR/1/$TOT/4\
+             /$P1B/8/$P1E/0,0/$P1N/FSC/$P1R/256/",
+            begin + 3
+        );
+        let header = format!(
+            "FCS3.1    {:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+            58,
+            58 + text.len() - 1,
+            0,
+            0,
+            0,
+            0
+        );
+        let mut h = BufReader::new(SparseFile {
+            head: [header.into_bytes(), text.into_bytes()].concat(),
+            offset: begin,
+            tail: data.to_vec(),
+            pos: 0,
+        });
+        let conf = DataReadConfig::default();
+        let out = h_read_std_dataset(&mut h, &conf, &mut ParserScratch::default())
+            .def_terminate(StdDatasetFailure)
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+        let cols: Vec<_> = out
+            .dataset
+            .standardized
+            .core
+            .as_data()
+            .iter_columns()
+            .map(|c| c.to_f64s())
+            .collect();
+        assert_eq!(vec![vec![1.0, 2.0, 3.0, 4.0]], cols);
+    }
 
     #[test]
     fn test_split_text_escape() {
@@ -1252,4 +2853,442 @@ mod tests {
         assert!(es.is_empty(), "errors: {:?}", es);
         assert!(ws.is_empty(), "warnings: {:?}", ws);
     }
+
+    #[test]
+    fn test_split_text_offsets() {
+        let kws = ParsedKeywords::default();
+        let conf = RawTextReadConfig::default();
+        let bytes = "$P4F/700//75 BP/$P4N/FL1/".as_bytes();
+        let out = split_raw_text_escaped_delim(kws, 47, bytes, &conf);
+        let offsets = &out.value().offsets;
+        assert_eq!(Some(&0), offsets.get("$P4F"));
+        assert_eq!(Some(&16), offsets.get("$P4N"));
+    }
+
+    #[test]
+    fn test_split_text_keep_empty() {
+        let conf = RawTextReadConfig {
+            use_literal_delims: true,
+            keep_empty_values: true,
+            ..RawTextReadConfig::default()
+        };
+        let bytes = "$P4S//$P4N/FL1/".as_bytes();
+        let out = split_raw_text_literal_delim(ParsedKeywords::default(), 47, bytes, &conf);
+        let std = &out.value().std;
+        assert_eq!(
+            Some(&String::new()),
+            std.get(&"$P4S".parse::<StdKey>().unwrap())
+        );
+        assert_eq!(2, std.len());
+        assert!(out.errors().is_empty(), "errors: {:?}", out.errors());
+        assert_eq!(1, out.warnings().len());
+    }
+
+    /// Build a minimal FCS 3.0 file with one 8-bit measurement.
+    pub(crate) fn minimal_fcs_3_0(events: &[u8]) -> Vec<u8> {
+        fcs_3_0_uint8(&["FSC"], events)
+    }
+
+    /// Build an FCS 3.0 file with 8-bit measurements named 'names'.
+    ///
+    /// 'data' holds each event in order, and its length must be a multiple of
+    /// the number of measurements.
+    pub(crate) fn fcs_3_0_uint8(names: &[&str], data: &[u8]) -> Vec<u8> {
+        fcs_uint8("FCS3.0", names, data, &[])
+    }
+
+    /// Build an FCS file with 8-bit measurements and additional keywords.
+    ///
+    /// Empty names will have no $PnN. See [`fcs_3_0_uint8`] for 'data'.
+    pub(crate) fn fcs_uint8(
+        version: &str,
+        names: &[&str],
+        data: &[u8],
+        extra: &[(&str, &str)],
+    ) -> Vec<u8> {
+        fcs_uint8_with_others(version, names, data, extra, &[])
+    }
+
+    /// Build an FCS file like [`fcs_uint8`] with OTHER segments after DATA.
+    fn fcs_uint8_with_others(
+        version: &str,
+        names: &[&str],
+        data: &[u8],
+        extra: &[(&str, &str)],
+        others: &[&[u8]],
+    ) -> Vec<u8> {
+        let text_begin = 58 + 16 * others.len();
+        let mut data_begin = text_begin;
+        let par = names.len();
+        let meas: String = names
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                let j = i + 1;
+                let name = if n.is_empty() {
+                    String::new()
+                } else {
+                    format!("$P{j}N/{n}/")
+                };
+                format!("$P{j}B/8/$P{j}E/0,0/{name}$P{j}R/256/")
+            })
+            .collect();
+        let other: String = extra.iter().map(|(k, v)| format!("{k}/{v}/")).collect();
+        let byteord = if version < "FCS3.1" { "1" } else { "1,2,3,4" };
+        loop {
+            let data_end = data_begin + data.len() - 1;
+            let text = format!(
+                "/$BEGINANALYSIS/0/$ENDANALYSIS/0/$BEGINSTEXT/0/$ENDSTEXT/0\
+                 /$BEGINDATA/{data_begin}/$ENDDATA/{data_end}/$BYTEORD/{byteord}\
+                 /$CYT/FACS/$DATATYPE/I/$MODE/L/$NEXTDATA/0/$PAR/{par}/$TOT/{}/{meas}{other}",
+                data.len() / par
+            );
+            let next_begin = text_begin + text.len();
+            if next_begin == data_begin {
+                let header = format!(
+                    "{version}    {:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+                    text_begin,
+                    next_begin - 1,
+                    data_begin,
+                    data_end,
+                    0,
+                    0
+                );
+                let mut buf = header.into_bytes();
+                let mut other_begin = data_end + 1;
+                for o in others {
+                    let other_end = other_begin + o.len() - 1;
+                    buf.extend(format!("{other_begin:>8}{other_end:>8}").as_bytes());
+                    other_begin = other_end + 1;
+                }
+                buf.extend(text.as_bytes());
+                buf.extend(data);
+                buf.extend(others.concat());
+                return buf;
+            }
+            data_begin = next_begin;
+        }
+    }
+
+    #[test]
+    fn test_read_minimal_dataset() {
+        let bytes = minimal_fcs_3_0(&[1, 2, 3]);
+        let conf = DataReadConfig::default();
+        let (out, _) = fcs_read_std_dataset_from_bytes(&bytes, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        assert_eq!(3, out.dataset.standardized.core.nrows());
+    }
+
+    #[test]
+    fn test_warnings_are_errors() {
+        // $MODE is deprecated in 3.2, which is a warning by default
+        let bytes = fcs_uint8("FCS3.2", &["FSC"], &[1, 2], &[]);
+        let mut conf = DataReadConfig::default();
+        assert!(fcs_read_std_dataset_from_bytes(&bytes, &conf).is_ok());
+        conf.shared.warnings_are_errors = true;
+        let res = fcs_read_std_dataset_from_bytes(&bytes, &conf);
+        let (nwarn, single) = res
+            .err()
+            .unwrap()
+            .resolve(|ws| ws.len(), |f| matches!(f, Failure::Single(_)));
+        assert!(nwarn > 0);
+        assert!(single);
+    }
+
+    #[test]
+    fn test_keyword_registry() {
+        let bytes = fcs_uint8(
+            "FCS3.0",
+            &["FSC", "SSC"],
+            &[1, 2],
+            &[("$P5B", "x"), ("$LASER1POWER", "20")],
+        );
+        let mut conf = DataReadConfig::from_profile(Profile::Strict);
+        assert!(fcs_read_std_dataset_from_bytes(&bytes, &conf).is_err());
+
+        let spec = KeywordSpec::new("LASERnPOWER", [KeyStatus::Optional; 4]).unwrap();
+        conf.standard.keyword_registry.register(spec);
+        let (out, ws) = fcs_read_std_dataset_from_bytes(&bytes, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|w| w);
+        let unused = &out.dataset.unused_standard_keywords;
+        assert_eq!(2, unused.len());
+        assert!(unused.contains_key("LASER1POWER"));
+        // indexed beyond $PAR but still checked against the type for $PnB
+        assert!(ws.iter().any(|w| matches!(
+            w,
+            StdDatasetWarning::Std(StdDatasetFromRawWarning::TEXT(
+                LookupMeasWarning::Registered(e)
+            )) if e.key.as_ref() == "P5B"
+        )));
+    }
+
+    #[test]
+    fn test_write_header_and_byteord() {
+        let bytes = fcs_3_0_uint8(&["FSC", "SSC"], &[1, 2, 3, 4]);
+        let conf = DataReadConfig::default();
+        let (out, _) = fcs_read_std_dataset_from_bytes(&bytes, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        let write_conf = WriteConfig {
+            target_datatype: TargetDatatype {
+                datatype: Some(AlphaNumType::Single),
+                ..TargetDatatype::default()
+            },
+            ..WriteConfig::default()
+        };
+        let (buf, _) = fcs_write_dataset_to_bytes(&out.dataset.standardized.core, &write_conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        // version is followed by four spaces and each offset is right-aligned
+        // in eight characters
+        assert_eq!(b"FCS3.0    ", &buf[..10]);
+        assert_eq!(b"      58", &buf[10..18]);
+        let (new, _) = fcs_read_std_dataset_from_bytes(&buf, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        let core = new.dataset.standardized.core;
+        let kws = core.raw_keywords(None, None);
+        // $BYTEORD is 1-based
+        assert_eq!(Some("1,2,3,4"), kws.get("$BYTEORD").map(String::as_str));
+        assert_eq!(2, core.nrows());
+    }
+
+    #[test]
+    fn test_read_all_datasets_written() {
+        let bytes = fcs_3_0_uint8(&["FSC", "SSC"], &[1, 2, 3, 4]);
+        let conf = DataReadConfig::default();
+        let (out, _) = fcs_read_std_dataset_from_bytes(&bytes, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        let core = out.dataset.standardized.core;
+        let dir = std::env::temp_dir().join(format!("fireflow-all-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("written.fcs");
+        fcs_write_dataset(&p, &core, &WriteConfig::default())
+            .ok()
+            .unwrap();
+        let raw = fcs_read_raw_text(&p, &conf.standard.raw)
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+        let res = fcs_read_all_std_datasets(&p, &conf);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(Some(0), raw.parse.nextdata);
+        let (xs, _) = res.ok().unwrap().resolve(|_| ());
+        assert_eq!(1, xs.len());
+        assert_eq!(2, xs[0].dataset.standardized.core.nrows());
+    }
+
+    #[test]
+    fn test_read_all_datasets_nextdata_at_eof() {
+        let bytes = fcs_3_0_uint8(&["FSC"], &[1, 2, 3]);
+        let conf = DataReadConfig::default();
+        let (out, _) = fcs_read_std_dataset_from_bytes(&bytes, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        let (mut buf, _) =
+            fcs_write_dataset_to_bytes(&out.dataset.standardized.core, &WriteConfig::default())
+                .ok()
+                .unwrap()
+                .resolve(|_| ());
+        // point $NEXTDATA at the end of the file as older versions did
+        let zero = format!("$NEXTDATA\x1e{:0>20}", 0);
+        let i = buf
+            .windows(zero.len())
+            .position(|w| w == zero.as_bytes())
+            .unwrap();
+        let eof = format!("$NEXTDATA\x1e{:0>20}", buf.len());
+        buf[i..(i + eof.len())].copy_from_slice(eof.as_bytes());
+        let dir = std::env::temp_dir().join(format!("fireflow-eof-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("eof.fcs");
+        fs::write(&p, &buf).unwrap();
+        let res = fcs_read_all_std_datasets(&p, &conf);
+        fs::remove_dir_all(&dir).unwrap();
+        let (xs, _) = res.ok().unwrap().resolve(|_| ());
+        assert_eq!(1, xs.len());
+    }
+
+    /// Write a dataset with two 8-bit measurements to 'p' and read its TEXT.
+    fn write_for_edit(p: &path::Path, write_conf: &WriteConfig) -> RawTEXTOutput {
+        let bytes = fcs_3_0_uint8(&["FSC", "SSC"], &[1, 2, 3, 4]);
+        let conf = DataReadConfig::default();
+        let (out, _) = fcs_read_std_dataset_from_bytes(&bytes, &conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        fcs_write_dataset(p, &out.dataset.standardized.core, write_conf)
+            .ok()
+            .unwrap();
+        fcs_read_raw_text(p, &conf.standard.raw)
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0
+    }
+
+    #[test]
+    fn test_read_others_with_text() {
+        let bytes = fcs_uint8_with_others("FCS3.0", &["FSC"], &[1, 2], &[], &[b"vendor"]);
+        let read_others = |keep_others| {
+            let conf = RawTextReadConfig {
+                keep_others,
+                ..Default::default()
+            };
+            let mut h = BufReader::new(io::Cursor::new(&bytes));
+            RawTEXTOutput::h_read(&mut h, &conf, &mut ParserScratch::default())
+                .ok()
+                .unwrap()
+                .value()
+                .parse
+                .others
+                .clone()
+        };
+        assert!(read_others(false).is_none());
+        let others = read_others(true).unwrap();
+        assert_eq!(
+            vec![b"vendor".to_vec()],
+            others.0.into_iter().map(|o| o.0).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_set_empty_keyword() {
+        let mut kws = ValidKeywords::default();
+        assert!(kws.set("$CYT", String::new()).is_err());
+        kws.set("$CYT", "FACS".into()).unwrap();
+        kws.std
+            .insert("$SRC".parse::<StdKey>().unwrap(), String::new());
+        assert_eq!(b"/$CYT/FACS/".to_vec(), kws.to_text_bytes(b'/'));
+    }
+
+    #[test]
+    fn test_edit_text_in_place_with_crc() {
+        let dir = std::env::temp_dir().join(format!("fireflow-patch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("patch.fcs");
+        let write_conf = WriteConfig {
+            write_crc: true,
+            ..WriteConfig::default()
+        };
+        let mut raw = write_for_edit(&p, &write_conf);
+        raw.keywords.set("$CYT", "X".into()).unwrap();
+        let mut read_conf = DataReadConfig::default();
+        read_conf.reader.check_crc = true;
+        let patched = fcs_edit_text(&p, &p, raw, &read_conf, &write_conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+        let res = fcs_read_std_dataset(&p, &read_conf);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(patched);
+        let (out, _) = res.ok().unwrap().resolve(|_| ());
+        let kws = out.dataset.standardized.core.raw_keywords(None, None);
+        assert_eq!(Some("X"), kws.get("$CYT").map(String::as_str));
+    }
+
+    #[test]
+    fn test_edit_text_rewrite() {
+        let dir = std::env::temp_dir().join(format!("fireflow-rewrite-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("rewrite.fcs");
+        let write_conf = WriteConfig::default();
+        let mut raw = write_for_edit(&p, &write_conf);
+        raw.keywords
+            .set("$COM", "a comment too long to fit in TEXT".into())
+            .unwrap();
+        let read_conf = DataReadConfig::default();
+        let patched = fcs_edit_text(&p, &p, raw, &read_conf, &write_conf)
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+        let res = fcs_read_std_dataset(&p, &read_conf);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!patched);
+        let (out, _) = res.ok().unwrap().resolve(|_| ());
+        let core = out.dataset.standardized.core;
+        let kws = core.raw_keywords(None, None);
+        assert_eq!(
+            Some("a comment too long to fit in TEXT"),
+            kws.get("$COM").map(String::as_str)
+        );
+        let cols: Vec<_> = core.as_data().iter_columns().map(|c| c.to_f64s()).collect();
+        assert_eq!(vec![vec![1.0, 3.0], vec![2.0, 4.0]], cols);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_read_dataset_async() {
+        let bytes = minimal_fcs_3_0(&[1, 2, 3, 4]);
+        let conf = DataReadConfig::default();
+        let mut h = io::Cursor::new(bytes.clone());
+        let (from_reader, _) = fcs_read_std_dataset_from_async_reader(&mut h, &conf)
+            .await
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        assert_eq!(4, from_reader.dataset.standardized.core.nrows());
+
+        let dir = std::env::temp_dir().join(format!("fireflow-async-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("minimal.fcs");
+        fs::write(&p, &bytes).unwrap();
+        let res = fcs_read_std_dataset_async(&p, &conf).await;
+        fs::remove_dir_all(&dir).unwrap();
+        let (from_path, _) = res.ok().unwrap().resolve(|_| ());
+        assert_eq!(4, from_path.dataset.standardized.core.nrows());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_read_async_skips_trailing_bytes() {
+        let mut bytes = minimal_fcs_3_0(&[1, 2, 3, 4]);
+        let n = bytes.len() as u64;
+        bytes.resize(0x100000, b' ');
+        let mut h = io::Cursor::new(bytes);
+        let (out, _) = fcs_read_std_dataset_from_async_reader(&mut h, &DataReadConfig::default())
+            .await
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        assert_eq!(4, out.dataset.standardized.core.nrows());
+        assert!(h.position() <= n + 0x10000);
+
+        h.set_position(0);
+        let (text, _) = fcs_read_std_text_from_async_reader(&mut h, &StdTextReadConfig::default())
+            .await
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        assert_eq!(1, text.standardized.par().0);
+        assert!(h.position() <= n + 0x10000);
+    }
+
+    #[test]
+    fn test_parse_metrics_rate() {
+        let mut m = ParseMetrics {
+            bytes_read: 0,
+            header_time: Duration::from_millis(1),
+            text_time: Duration::from_millis(2),
+            data_time: None,
+            events: None,
+        };
+        assert_eq!(None, m.events_per_second());
+        m.data_time = Some(Duration::from_millis(500));
+        m.events = Some(1000);
+        assert_eq!(Some(2000.0), m.events_per_second());
+        assert_eq!(Duration::from_millis(503), m.total_time());
+    }
 }
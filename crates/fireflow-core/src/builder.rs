@@ -0,0 +1,353 @@
+//! Builders to create FCS 3.1 datasets from scratch.
+//!
+//! Each measurement is described with a [`MeasurementBuilder`] and added to a
+//! [`DatasetBuilder`] along with its data. Nothing is validated until
+//! [`DatasetBuilder::build`], which will return all problems found at once.
+//!
+//! ```ignore
+//! let df = DatasetBuilder::new(AlphaNumType::Single)
+//!     .measurement(
+//!         MeasurementBuilder::new("FSC-A")
+//!             .bytes(4)
+//!             .range(262144)
+//!             .data(F32Column::from(vec![1.0, 2.0, 3.0])),
+//!     )
+//!     .build()?;
+//! ```
+
+use crate::config::SharedConfig;
+use crate::core::*;
+use crate::data::NewDataLayoutError;
+use crate::text::byteord::Width;
+use crate::text::float_or_int::NanFloatOrInt;
+use crate::text::keywords::*;
+use crate::text::named_vec::{InsertCenterError, NonUniqueKeyError};
+use crate::text::optionalkw::Identity;
+use crate::text::ranged_float::{PositiveFloat, RangedFloatError};
+use crate::text::scale::{LogRangeError, Scale};
+use crate::validated::dataframe::AnyFCSColumn;
+use crate::validated::shortname::*;
+
+use nonempty::NonEmpty;
+use std::fmt;
+
+/// Builder for one measurement and its data.
+pub struct MeasurementBuilder {
+    name: String,
+    bytes: Option<u8>,
+    range: Option<f64>,
+    scale: Option<(f32, f32)>,
+    timestep: Option<f32>,
+    longname: Option<String>,
+    column: Option<AnyFCSColumn>,
+}
+
+/// Builder for an FCS 3.1 dataset.
+pub struct DatasetBuilder {
+    datatype: AlphaNumType,
+    is_big: bool,
+    mode: Mode,
+    measurements: Vec<MeasurementBuilder>,
+}
+
+impl MeasurementBuilder {
+    /// Start a new measurement with the given name ($PnN).
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            bytes: None,
+            range: None,
+            scale: None,
+            timestep: None,
+            longname: None,
+            column: None,
+        }
+    }
+
+    /// Set the width of this measurement in bytes.
+    ///
+    /// This will be converted to bits for $PnB unless $DATATYPE is ASCII. May
+    /// be omitted if $DATATYPE is F or D, in which case it will be 4 or 8
+    /// respectively.
+    pub fn bytes(mut self, n: u8) -> Self {
+        self.bytes = Some(n);
+        self
+    }
+
+    /// Set $PnR.
+    pub fn range(mut self, r: impl Into<f64>) -> Self {
+        self.range = Some(r.into());
+        self
+    }
+
+    /// Set $PnE to a log scale with the given decades and offset.
+    ///
+    /// Defaults to linear if not given.
+    pub fn log_scale(mut self, decades: f32, offset: f32) -> Self {
+        self.scale = Some((decades, offset));
+        self
+    }
+
+    /// Make this the time measurement with the given $TIMESTEP.
+    pub fn timestep(mut self, t: f32) -> Self {
+        self.timestep = Some(t);
+        self
+    }
+
+    /// Set $PnS.
+    pub fn longname(mut self, s: impl Into<String>) -> Self {
+        self.longname = Some(s.into());
+        self
+    }
+
+    /// Set the data for this measurement.
+    pub fn data(mut self, column: impl Into<AnyFCSColumn>) -> Self {
+        self.column = Some(column.into());
+        self
+    }
+
+    fn width(&self, datatype: AlphaNumType) -> Option<Width> {
+        let bytes = self.bytes.or(match datatype {
+            AlphaNumType::Single => Some(4),
+            AlphaNumType::Double => Some(8),
+            _ => None,
+        })?;
+        let w = match datatype {
+            AlphaNumType::Ascii => bytes,
+            _ => bytes.saturating_mul(8),
+        };
+        Some(Some(w).into())
+    }
+
+    fn common(&self, datatype: AlphaNumType) -> Result<(Width, Range), Vec<BuildError>> {
+        let name = &self.name;
+        let width = self
+            .width(datatype)
+            .ok_or(BuildError::MissingBytes(name.clone()));
+        let range = self
+            .range
+            .ok_or(BuildError::MissingRange(name.clone()))
+            .and_then(|r| Range::try_from(r).map_err(|e| BuildError::Range(name.clone(), e)));
+        match (width, range) {
+            (Ok(w), Ok(r)) => Ok((w, r)),
+            (w, r) => Err(w.err().into_iter().chain(r.err()).collect()),
+        }
+    }
+
+    fn into_temporal(
+        self,
+        datatype: AlphaNumType,
+        t: f32,
+    ) -> Result<(Temporal3_1, Option<AnyFCSColumn>), Vec<BuildError>> {
+        let ts = PositiveFloat::try_from(t).map_err(|e| BuildError::Timestep(self.name.clone(), e));
+        match (self.common(datatype), ts) {
+            (Ok((w, r)), Ok(x)) => {
+                let mut m = Temporal3_1::new(w, r, x.into());
+                m.common.longname = self.longname.map(Longname::from).into();
+                Ok((m, self.column))
+            }
+            (c, x) => Err(c
+                .err()
+                .unwrap_or_default()
+                .into_iter()
+                .chain(x.err())
+                .collect()),
+        }
+    }
+
+    fn into_optical(
+        self,
+        datatype: AlphaNumType,
+    ) -> Result<(Optical3_1, Option<AnyFCSColumn>), Vec<BuildError>> {
+        let scale = self.scale.map_or(Ok(Scale::Linear), |(d, o)| {
+            Scale::try_new_log(d, o).map_err(|e| BuildError::Scale(self.name.clone(), e))
+        });
+        match (self.common(datatype), scale) {
+            (Ok((w, r)), Ok(s)) => {
+                let mut m = Optical3_1::new(w, r, s);
+                m.common.longname = self.longname.map(Longname::from).into();
+                Ok((m, self.column))
+            }
+            (c, s) => Err(c
+                .err()
+                .unwrap_or_default()
+                .into_iter()
+                .chain(s.err())
+                .collect()),
+        }
+    }
+}
+
+impl DatasetBuilder {
+    /// Start a new dataset with the given $DATATYPE.
+    ///
+    /// Byte order will be little endian and $MODE will be L.
+    pub fn new(datatype: AlphaNumType) -> Self {
+        Self {
+            datatype,
+            is_big: false,
+            mode: Mode::List,
+            measurements: vec![],
+        }
+    }
+
+    /// Set $BYTEORD to big endian if true.
+    pub fn big_endian(mut self, is_big: bool) -> Self {
+        self.is_big = is_big;
+        self
+    }
+
+    /// Set $MODE.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Add a measurement after all others added so far.
+    pub fn measurement(mut self, m: MeasurementBuilder) -> Self {
+        self.measurements.push(m);
+        self
+    }
+
+    /// Return a new dataset if all measurements and data are valid.
+    ///
+    /// Every measurement must have data, and all columns must be the same
+    /// length. Widths must be consistent with $DATATYPE such that the dataset
+    /// can be written.
+    pub fn build(self) -> Result<CoreDataset3_1, NonEmpty<BuildError>> {
+        let datatype = self.datatype;
+        let mut text = CoreTEXT3_1::new(datatype, self.is_big, self.mode);
+        let mut columns = vec![];
+        let mut errors = vec![];
+        for m in self.measurements {
+            let name = match m.name.parse::<Shortname>() {
+                Ok(n) => n,
+                Err(e) => {
+                    errors.push(BuildError::Name(e));
+                    continue;
+                }
+            };
+            let timestep = m.timestep;
+            let res = if let Some(t) = timestep {
+                m.into_temporal(datatype, t).and_then(|(x, c)| {
+                    text.push_temporal(name.clone(), x)
+                        .map_err(|e| vec![e.into()])
+                        .map(|_| c)
+                })
+            } else {
+                m.into_optical(datatype).and_then(|(x, c)| {
+                    text.push_optical(Identity(name.clone()), x)
+                        .map_err(|e| vec![e.into()])
+                        .map(|_| c)
+                })
+            };
+            match res {
+                Ok(Some(c)) => columns.push(c),
+                Ok(None) => errors.push(BuildError::MissingData(name)),
+                Err(es) => errors.extend(es),
+            }
+        }
+        if let Some(es) = NonEmpty::from_vec(errors) {
+            return Err(es);
+        }
+        let dataset = text
+            .into_coredataset(columns, Analysis(vec![]), Others::default())
+            .map_err(|e| NonEmpty::new(e.into()))?;
+        match dataset.as_data_layout(&SharedConfig::default()) {
+            Ok(_) => Ok(dataset),
+            Err(f) => {
+                let (_, _, es) = f.unfail().into_parts();
+                // ASSUME failure always has at least one error
+                Err(NonEmpty::from_vec(es.into_iter().map(BuildError::Layout).collect()).unwrap())
+            }
+        }
+    }
+}
+
+/// Error encountered when building a dataset
+pub enum BuildError {
+    Name(ShortnameError),
+    MissingBytes(String),
+    MissingRange(String),
+    MissingData(Shortname),
+    Range(String, NanFloatOrInt),
+    Scale(String, LogRangeError),
+    Timestep(String, RangedFloatError),
+    NonUnique(NonUniqueKeyError),
+    Temporal(InsertCenterError),
+    Columns(ColumsnToDataframeError),
+    Layout(NewDataLayoutError),
+}
+
+impl From<NonUniqueKeyError> for BuildError {
+    fn from(value: NonUniqueKeyError) -> Self {
+        Self::NonUnique(value)
+    }
+}
+
+impl From<InsertCenterError> for BuildError {
+    fn from(value: InsertCenterError) -> Self {
+        Self::Temporal(value)
+    }
+}
+
+impl From<ColumsnToDataframeError> for BuildError {
+    fn from(value: ColumsnToDataframeError) -> Self {
+        Self::Columns(value)
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Name(e) => e.fmt(f),
+            Self::MissingBytes(n) => write!(f, "width in bytes not given for '{n}'"),
+            Self::MissingRange(n) => write!(f, "range not given for '{n}'"),
+            Self::MissingData(n) => write!(f, "data not given for '{n}'"),
+            Self::Range(n, e) => write!(f, "invalid range for '{n}': {e}"),
+            Self::Scale(n, e) => write!(f, "invalid scale for '{n}': {e}"),
+            Self::Timestep(n, e) => write!(f, "invalid timestep for '{n}': {e}"),
+            Self::NonUnique(e) => e.fmt(f),
+            Self::Temporal(e) => e.fmt(f),
+            Self::Columns(e) => e.fmt(f),
+            Self::Layout(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validated::dataframe::F32Column;
+
+    #[test]
+    fn test_build_float() {
+        let res = DatasetBuilder::new(AlphaNumType::Single)
+            .measurement(
+                MeasurementBuilder::new("FSC-A")
+                    .bytes(4)
+                    .range(262144)
+                    .data(F32Column::from(vec![1.0, 2.0, 3.0])),
+            )
+            .measurement(
+                MeasurementBuilder::new("Time")
+                    .range(1024)
+                    .timestep(0.01)
+                    .data(F32Column::from(vec![0.0, 1.0, 2.0])),
+            )
+            .build();
+        let df = res.ok().unwrap();
+        assert_eq!(df.par().0, 2);
+        assert_eq!(AnyCoreDataset::from(df).nrows(), 3);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let res = DatasetBuilder::new(AlphaNumType::Integer)
+            .measurement(MeasurementBuilder::new("FSC-A").range(1024))
+            .measurement(MeasurementBuilder::new("SSC-A").bytes(2))
+            .build();
+        let es = res.err().unwrap();
+        assert_eq!(es.len(), 2);
+    }
+}
@@ -5,21 +5,39 @@
 /// error. This greatly simplifies the API and internally reduces the likelihood
 /// of "flipped flags."
 ///
+/// The "lenient-default" feature will instead make 'Default' return
+/// configurations with all "allow_*" flags set to true (see ['Profile']).
+/// Any profile may still be selected at runtime with 'from_profile', or with
+/// the 'pedantic' and 'permissive' shortcuts.
+///
 /// Internal to the library, the main question that matters for whether to throw
 /// a warning or error should be "does this adhere to the standard." If not, its
 /// an error. This will work in most cases, with a few exceptions where the
 /// standard is unclear.
+use crate::core::{ChangeTracker, SubsampleMethod, SubsampleSize};
 use crate::header::Version;
 use crate::segment::*;
+use crate::text::byteord::Endian;
+use crate::text::keywords::{AlphaNumType, NumType};
+use crate::text::registry::KeywordRegistry;
+use crate::text::timestamps::LabTimezone;
+use crate::text::typed::KeywordTypes;
+use crate::text::vendor::VendorProfile;
 use crate::validated::datepattern::DatePattern;
 use crate::validated::nonstandard::NonStdMeasPattern;
 use crate::validated::other_width::OtherWidth;
 use crate::validated::pattern::TimePattern;
+use crate::validated::repair::{Anonymizer, KeywordRepairs};
 use crate::validated::shortname::*;
 use crate::validated::textdelim::TEXTDelim;
 
+use chrono::TimeDelta;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
 /// Instructions for reading the DATA segment.
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct DataReadConfig {
     /// Instructions to read and standardize TEXT.
     pub standard: StdTextReadConfig,
@@ -32,7 +50,7 @@ pub struct DataReadConfig {
 }
 
 /// Instructions for reading the DATA/ANALYSIS segments
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct ReaderConfig {
     /// If true, allow event width to not perfectly divide DATA.
     ///
@@ -42,6 +60,14 @@ pub struct ReaderConfig {
     /// Does not apply to delimited ASCII, which does not have a fixed width.
     pub allow_uneven_event_width: bool,
 
+    /// If true, try to explain and fix DATA not being divisible by event width.
+    ///
+    /// The remainder will be compared to common mistakes (ENDDATA being off by
+    /// one, trailing CRLF, or the CRC being included in DATA) and the most
+    /// likely will be used to determine the number of events. If no
+    /// explanation matches, fall back to 'allow_uneven_event_width'.
+    pub fix_uneven_event_width: bool,
+
     /// If true, allow $TOT to not match number of events in DATA.
     ///
     /// For all but delimited ASCII layouts, $TOT is unnecessary and can be
@@ -61,11 +87,103 @@ pub struct ReaderConfig {
     /// missing these will be taken from HEADER.
     pub allow_missing_required_offsets: bool,
 
+    /// If true, guess $BYTEORD from DATA if it is missing or invalid.
+    ///
+    /// A sample of events will be read as both big and little endian, and the
+    /// byte order for which more values fall within their $PnR will be used.
+    /// The chosen byte order and the reason for choosing it will be emitted as
+    /// a warning. If neither byte order is more plausible, $BYTEORD will be
+    /// left as-is. Only applies to numeric layouts when reading DATA.
+    pub guess_byteord: bool,
+
+    /// If true, warn when integer values in DATA exceed their $PnR.
+    ///
+    /// Integers are clamped to the exact value of $PnR (which need not be a
+    /// power of two) such that any value greater than $PnR is replaced by it.
+    /// This is always done, but these values are otherwise silently changed.
+    /// If true, emit a warning with the number of affected events for each
+    /// measurement.
+    pub warn_overrange: bool,
+
+    /// If true, parse ASCII values in DATA leniently.
+    ///
+    /// Surrounding whitespace will be trimmed (as is common for space-padded
+    /// fixed-width values) and decimals without an exponent (eg "12.5") will
+    /// be rounded to the nearest integer. Otherwise each value must be an
+    /// unsigned integer with nothing else around it.
+    pub lenient_ascii: bool,
+
+    /// If true, check the CRC following the last segment (3.0+).
+    ///
+    /// The CRC is optional, so nothing will be checked if it is missing or
+    /// all zeros. Otherwise it will be compared to the CRC computed over the
+    /// entire dataset, which requires reading the dataset a second time.
+    pub check_crc: bool,
+
+    /// If true, only warn if the CRC does not match.
+    ///
+    /// Only has an effect if 'check_crc' is true.
+    pub allow_crc_mismatch: bool,
+
+    /// If true, check the SHA-256 of DATA if stored in TEXT.
+    ///
+    /// This is only checked if the non-standard keyword
+    /// [`DATA_HASH_KEY`] is present, which may be written by setting
+    /// [`WriteConfig::data_hash`]. This requires reading DATA a second time.
+    ///
+    /// [`DATA_HASH_KEY`]: crate::datahash::DATA_HASH_KEY
+    pub check_data_hash: bool,
+
+    /// If true, only warn if the SHA-256 of DATA does not match.
+    ///
+    /// Only has an effect if 'check_data_hash' is true.
+    pub allow_data_hash_mismatch: bool,
+
+    /// If true, only warn if $MODE is not "L" (list mode).
+    ///
+    /// Histogram modes ("C" and "U") store counts rather than events in DATA,
+    /// which will be misread if DATA is parsed as events. If true, DATA will be
+    /// read as if $MODE were "L" anyways. Use [`fcs_read_histogram`] to read
+    /// these correctly.
+    ///
+    /// [`fcs_read_histogram`]: crate::api::fcs_read_histogram
+    pub allow_non_list_mode: bool,
+
     /// Corrections for DATA offsets in TEXT segment
     pub data: TEXTCorrection<DataSegmentId>,
 
     /// Corrections for ANALYSIS offsets in TEXT segment
     pub analysis: TEXTCorrection<AnalysisSegmentId>,
+
+    /// Subset of measurements and events to read from DATA.
+    pub selection: ReadSelection,
+}
+
+/// Measurements and events to read from DATA.
+///
+/// Unselected measurements will be removed from the dataset along with their
+/// columns. For all but delimited ASCII, unselected columns and events will be
+/// skipped entirely rather than parsed.
+#[derive(Clone, Default)]
+pub struct ReadSelection {
+    /// Names of measurements to read, or all measurements if None.
+    ///
+    /// Names which do not match any measurement will be an error. Measurements
+    /// will stay in their original order regardless of the order here.
+    pub measurements: Option<Vec<Shortname>>,
+
+    /// Range of events (starting at 0) to read, or all events if None.
+    ///
+    /// Will be truncated to the number of events in DATA.
+    pub event_range: Option<Range<usize>>,
+
+    /// Subsample events within [`Self::event_range`], or keep all if None.
+    ///
+    /// Unlike [`AnyCoreDataset::subsample`], this will not record the
+    /// subsample in non-standard keywords.
+    ///
+    /// [`AnyCoreDataset::subsample`]: crate::core::AnyCoreDataset::subsample
+    pub subsample: Option<(SubsampleSize, SubsampleMethod)>,
 }
 
 /// Configuration for writing an FCS file
@@ -98,13 +216,407 @@ pub struct WriteConfig {
     ///
     /// Only has an effect if `check_conversion` is true. If this is also true,
     /// any lossy conversion will halt immediately and return an error to the
-    /// user.
+    /// user. This also applies to `target_datatype`, in which case float
+    /// measurements with values that are not whole numbers will not be
+    /// converted to integers.
     pub disallow_lossy_conversions: bool,
 
+    /// Order in which keywords will be written to TEXT.
+    ///
+    /// Regardless of this setting, required keywords will always precede
+    /// optional keywords, since the latter may need to be written to
+    /// supplemental TEXT.
+    pub keyword_order: KeywordOrder,
+
+    /// If given, write min/max/mean of each column as non-standard keywords.
+    ///
+    /// These will be named like "<prefix>Pn_MIN", "<prefix>Pn_MAX", and
+    /// "<prefix>Pn_MEAN" where 'n' is the measurement index. For example, a
+    /// prefix of "FCSRW_" will produce "FCSRW_P3_MAX" for measurement 3. This
+    /// allows consumers to display ranges without reading DATA. Writing will
+    /// fail if the prefix starts with '$' or contains the delimiter. Existing
+    /// keywords with these names will be dropped, including those for
+    /// measurements which no longer exist. Nothing is written if DATA is empty.
+    pub column_stats_prefix: Option<String>,
+
+    /// If true, write a CRC after the last segment (3.0+).
+    ///
+    /// Otherwise write nothing after the last segment, which the standard
+    /// allows. This has no effect for 2.0.
+    pub write_crc: bool,
+
+    /// If true, write a SHA-256 of DATA as a non-standard keyword.
+    ///
+    /// This will also write the version of this library and the time of
+    /// writing. See [`crate::datahash`] for the keywords used. Any existing
+    /// keywords with these names will be replaced. If false, existing keywords
+    /// will be written as-is, which may not match DATA if it was modified.
+    pub data_hash: bool,
+
+    /// Datatypes to convert DATA to before writing.
+    ///
+    /// By default DATA is written with its current layout. See
+    /// [`TargetDatatype`].
+    pub target_datatype: TargetDatatype,
+
+    /// If given, record modifications made since this snapshot (3.1+).
+    ///
+    /// If the dataset differs from the snapshot, $LAST_MODIFIED,
+    /// $LAST_MODIFIER, and $ORIGINALITY will be updated before writing. See
+    /// [`AnyCoreDataset::with_tracked_changes`].
+    ///
+    /// [`AnyCoreDataset::with_tracked_changes`]: crate::core::AnyCoreDataset::with_tracked_changes
+    pub track_changes: Option<ChangeTracker>,
+
+    /// Value for $LAST_MODIFIER when recording modifications.
+    ///
+    /// Only used if `track_changes` is given. If None, $LAST_MODIFIER will be
+    /// left as is.
+    pub last_modifier: Option<String>,
+
     /// Shared configuration options
     pub shared: SharedConfig,
 }
 
+/// Datatypes to use for DATA when writing.
+///
+/// When converting a measurement, $PnB and $PnR will be updated. Integer and
+/// ASCII measurements will be given the smallest width which can hold both
+/// $PnR and the largest value in DATA. Float measurements will keep $PnR.
+#[derive(Clone, Default)]
+pub struct TargetDatatype {
+    /// New $DATATYPE for all measurements, or None to keep the current
+    /// datatypes
+    pub datatype: Option<AlphaNumType>,
+
+    /// New $PnDATATYPE for measurements with the given $PnN.
+    ///
+    /// These take precedence over `datatype`. Only valid for 3.2.
+    pub overrides: Vec<(Shortname, NumType)>,
+}
+
+impl TargetDatatype {
+    /// Return true if this will not change any datatypes.
+    pub fn is_empty(&self) -> bool {
+        self.datatype.is_none() && self.overrides.is_empty()
+    }
+}
+
+/// Configuration for reading, converting to 3.2, and writing an FCS file.
+///
+/// See [`fcs_normalize`].
+///
+/// [`fcs_normalize`]: crate::api::fcs_normalize
+#[derive(Clone)]
+pub struct NormalizeConfig {
+    /// Configuration for reading.
+    ///
+    /// The default is [`DataReadConfig::permissive`] with whitespace trimming,
+    /// log scale fixes, $PAR inference, and uneven event width fixes turned on.
+    pub read: DataReadConfig,
+
+    /// If true, drop keywords which cannot be converted to 3.2 with a warning.
+    ///
+    /// Otherwise these will be errors. Defaults to true.
+    pub force: bool,
+
+    /// Byte order for DATA, defaults to little endian
+    pub endian: Endian,
+
+    /// Configuration for writing.
+    ///
+    /// Set [`WriteConfig::target_datatype`] to also normalize datatypes.
+    pub write: WriteConfig,
+}
+
+impl Default for NormalizeConfig {
+    fn default() -> Self {
+        let mut read = DataReadConfig::permissive();
+        read.standard.raw.trim_value_whitespace = true;
+        read.standard.fix_log_scale_offsets = true;
+        read.standard.infer_par = true;
+        read.reader.fix_uneven_event_width = true;
+        Self {
+            read,
+            force: true,
+            endian: Endian::Little,
+            write: WriteConfig::default(),
+        }
+    }
+}
+
+/// Set of defaults used to construct configurations for reading.
+///
+/// Strict will cause any deviation from the standard to be an error, whereas
+/// lenient will downgrade all errors which may be downgraded to warnings.
+/// Pedantic is strict and additionally sets
+/// [`StdTextReadConfig::disallow_deprecated`] and
+/// [`SharedConfig::warnings_are_errors`], and checks the CRC and DATA hash if
+/// present. Apart from these, profiles only affect "allow_*" flags; repairs
+/// and offset corrections are never turned on by any profile.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Pedantic,
+    Strict,
+    Lenient,
+}
+
+/// Profile used to implement 'Default' for reading configurations.
+///
+/// This is lenient if the "lenient-default" feature is enabled, and strict
+/// otherwise. Since features are additive, "lenient-default" takes precedence
+/// over "strict-default" when both are enabled.
+pub const DEFAULT_PROFILE: Profile = if cfg!(feature = "lenient-default") {
+    Profile::Lenient
+} else {
+    Profile::Strict
+};
+
+impl Default for Profile {
+    fn default() -> Self {
+        DEFAULT_PROFILE
+    }
+}
+
+impl FromStr for Profile {
+    type Err = ProfileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pedantic" => Ok(Self::Pedantic),
+            "strict" => Ok(Self::Strict),
+            "lenient" | "permissive" => Ok(Self::Lenient),
+            "default" => Ok(DEFAULT_PROFILE),
+            _ => Err(ProfileError(s.to_string())),
+        }
+    }
+}
+
+pub struct ProfileError(String);
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "profile must be 'pedantic', 'strict', 'lenient', 'permissive', \
+             or 'default', got '{}'",
+            self.0
+        )
+    }
+}
+
+impl DataReadConfig {
+    pub fn from_profile(p: Profile) -> Self {
+        Self {
+            standard: StdTextReadConfig::from_profile(p),
+            shared: SharedConfig {
+                warnings_are_errors: p == Profile::Pedantic,
+                ..SharedConfig::default()
+            },
+            reader: ReaderConfig::from_profile(p),
+        }
+    }
+
+    /// Return configuration which fails on any deviation from the standard.
+    pub fn pedantic() -> Self {
+        Self::from_profile(Profile::Pedantic)
+    }
+
+    /// Return configuration which downgrades all possible errors to warnings.
+    pub fn permissive() -> Self {
+        Self::from_profile(Profile::Lenient)
+    }
+}
+
+impl ReaderConfig {
+    pub fn from_profile(p: Profile) -> Self {
+        let lenient = p == Profile::Lenient;
+        Self {
+            allow_uneven_event_width: lenient,
+            fix_uneven_event_width: false,
+            allow_tot_mismatch: lenient,
+            allow_header_text_offset_mismatch: lenient,
+            allow_missing_required_offsets: lenient,
+            guess_byteord: false,
+            warn_overrange: false,
+            lenient_ascii: lenient,
+            check_crc: p == Profile::Pedantic,
+            allow_crc_mismatch: lenient,
+            check_data_hash: p == Profile::Pedantic,
+            allow_data_hash_mismatch: lenient,
+            allow_non_list_mode: lenient,
+            data: TEXTCorrection::default(),
+            analysis: TEXTCorrection::default(),
+            selection: ReadSelection::default(),
+        }
+    }
+}
+
+impl RawTextReadConfig {
+    pub fn from_profile(p: Profile) -> Self {
+        let lenient = p == Profile::Lenient;
+        Self {
+            header: HeaderConfig::default(),
+            stext_correction: TEXTCorrection::default(),
+            allow_duplicated_stext: lenient,
+            ignore_stext: false,
+            use_literal_delims: false,
+            fallback_literal_delims: lenient,
+            allow_non_ascii_delim: lenient,
+            allow_missing_final_delim: lenient,
+            allow_nonunique: lenient,
+            nonunique_last_wins: false,
+            allow_odd: lenient,
+            allow_empty: lenient,
+            keep_empty_values: false,
+            allow_delim_at_boundary: lenient,
+            allow_non_utf8: lenient,
+            allow_non_ascii_keywords: lenient,
+            allow_missing_stext: lenient,
+            allow_stext_own_delim: lenient,
+            stext_takes_precedence: false,
+            allow_missing_nextdata: lenient,
+            trim_value_whitespace: false,
+            date_pattern: None,
+            skip_nonstandard: false,
+            keep_raw_text: false,
+            keep_others: false,
+            collect_metrics: false,
+            lossy_values: lenient,
+            text_encoding: TextEncoding::default(),
+            keyword_repairs: KeywordRepairs::default(),
+            anonymize: Anonymizer::default(),
+        }
+    }
+}
+
+impl StdTextReadConfig {
+    pub fn from_profile(p: Profile) -> Self {
+        Self {
+            raw: RawTextReadConfig::from_profile(p),
+            time: TimeConfig::default(),
+            lab_timezone: None,
+            datetime_timezone: None,
+            shortname_prefix: ShortnamePrefix::default(),
+            allow_pseudostandard: p == Profile::Lenient,
+            pseudostandard_types: KeywordTypes::default(),
+            keyword_registry: KeywordRegistry::default(),
+            vendor: VendorProfile::default(),
+            disallow_deprecated: p == Profile::Pedantic,
+            fix_log_scale_offsets: false,
+            infer_par: false,
+            force_linear_scale: vec![],
+            allowed_features: vec![],
+            nonstandard_measurement_patterns: vec![],
+        }
+    }
+
+    /// Return configuration which fails on any deviation from the standard.
+    pub fn pedantic() -> Self {
+        Self::from_profile(Profile::Pedantic)
+    }
+
+    /// Return configuration which downgrades all possible errors to warnings.
+    pub fn permissive() -> Self {
+        Self::from_profile(Profile::Lenient)
+    }
+}
+
+impl Default for DataReadConfig {
+    fn default() -> Self {
+        Self::from_profile(DEFAULT_PROFILE)
+    }
+}
+
+impl Default for ReaderConfig {
+    fn default() -> Self {
+        Self::from_profile(DEFAULT_PROFILE)
+    }
+}
+
+impl Default for RawTextReadConfig {
+    fn default() -> Self {
+        Self::from_profile(DEFAULT_PROFILE)
+    }
+}
+
+impl Default for StdTextReadConfig {
+    fn default() -> Self {
+        Self::from_profile(DEFAULT_PROFILE)
+    }
+}
+
+/// Ordering for keywords when writing TEXT
+#[derive(Clone, Copy, Default)]
+pub enum KeywordOrder {
+    /// Write keywords in the order they are stored.
+    ///
+    /// This is metadata keywords followed by measurement keywords grouped by
+    /// index, with keywords in each group appearing in no particular order.
+    #[default]
+    Unsorted,
+
+    /// Sort all keywords using a numeric-aware ordering.
+    ///
+    /// Any digits in a keyword will be compared as numbers, so $P2B will come
+    /// before $P10B.
+    Natural,
+
+    /// Sort metadata keywords first, then measurement keywords by index.
+    ///
+    /// Both groups will be sorted using the same ordering as ['Natural'].
+    Grouped,
+}
+
+/// Encoding used to decode keys and values in TEXT
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// Decode everything as UTF-8.
+    ///
+    /// This is what the standard requires (ASCII for 2.0 and 3.0 except for
+    /// values listed in $UNICODE, which are UTF-8 and thus also covered).
+    /// Keywords which are not valid UTF-8 will not be decoded; see
+    /// 'lossy_values' to keep these anyways.
+    #[default]
+    Utf8,
+
+    /// Decode everything as Latin-1 (ISO-8859-1).
+    ///
+    /// This never fails since every byte maps to a character.
+    Latin1,
+
+    /// Decode as UTF-8 and fall back to Latin-1 if this fails.
+    ///
+    /// This is useful for older files which have instrument or operator names
+    /// with accented characters written in a Latin-1 code page.
+    Utf8OrLatin1,
+}
+
+impl FromStr for TextEncoding {
+    type Err = TextEncodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf8" | "utf-8" => Ok(Self::Utf8),
+            "latin1" | "latin-1" => Ok(Self::Latin1),
+            "utf8-latin1" => Ok(Self::Utf8OrLatin1),
+            _ => Err(TextEncodingError(s.to_string())),
+        }
+    }
+}
+
+pub struct TextEncodingError(String);
+
+impl fmt::Display for TextEncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "encoding must be 'utf8', 'latin1', or 'utf8-latin1', got '{}'",
+            self.0
+        )
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct HeaderConfig {
     /// Override the version
@@ -175,7 +687,7 @@ pub struct HeaderConfig {
 
 /// Instructions for reading the TEXT segment as raw key/value pairs.
 // TODO add correction for $NEXTDATA
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct RawTextReadConfig {
     /// Config for reading HEADER
     pub header: HeaderConfig,
@@ -214,6 +726,16 @@ pub struct RawTextReadConfig {
     /// files, this is the correct interpretation, albeit not compliant.
     pub use_literal_delims: bool,
 
+    /// If true, retry splitting TEXT with literal delimiters if escaped
+    /// delimiters fail.
+    ///
+    /// Files with blank values or unescaped delimiters at word boundaries will
+    /// often fail to split when delimiters are assumed to be escaped. With this
+    /// set, such files will be split again as if [`use_literal_delims`] were
+    /// true, and this result will be used (with a warning) if it has no
+    /// errors. Has no effect if [`use_literal_delims`] is already true.
+    pub fallback_literal_delims: bool,
+
     /// If true, allow delimiter to be character outside 1-126.
     pub allow_non_ascii_delim: bool,
 
@@ -222,11 +744,17 @@ pub struct RawTextReadConfig {
 
     /// If true, allow non-unique keys to be present in TEXT.
     ///
-    /// In any case, only the first value for a given key will be used. Setting
-    /// this to true merely changes a duplicate key to emit a warning and not
-    /// an error.
+    /// Setting this to true merely changes a duplicate key to emit a warning
+    /// and not an error. Either way, the warning or error will include both
+    /// values. Which value is kept is controlled by 'nonunique_last_wins'.
     pub allow_nonunique: bool,
 
+    /// If true, the last value for a non-unique key in TEXT will be used.
+    ///
+    /// By default, the first value is kept. Only meaningful if
+    /// 'allow_nonunique' is also true, since otherwise duplicates are errors.
+    pub nonunique_last_wins: bool,
+
     /// If true, allow TEXT to contain an odd number of words.
     ///
     /// Regardless, the final "dangling" word in the case of an odd number
@@ -237,10 +765,20 @@ pub struct RawTextReadConfig {
     ///
     /// Only relevant if [`use_literal_delims`] is also true since blank values
     /// cannot exist when delimiters are escaped. Blank values will be dropped
-    /// regardless of this flag; setting it to false will trigger an error,
-    /// otherwise a warning.
+    /// regardless of this flag unless [`keep_empty_values`] is true; setting
+    /// it to false will trigger an error, otherwise a warning.
     pub allow_empty: bool,
 
+    /// If true, keep keys with blank values as empty strings.
+    ///
+    /// Many writers emit empty values (ie "K//" with "/" as the delimiter)
+    /// for keywords they have no value for. If true, these will be kept with
+    /// an empty string value and a warning will be emitted regardless of
+    /// [`allow_empty`]. Like [`allow_empty`], this only applies when
+    /// delimiters are parsed literally. Values which become blank after
+    /// trimming with [`trim_value_whitespace`] will also be kept.
+    pub keep_empty_values: bool,
+
     /// If true, allow delimiters at word boundaries.
     ///
     /// Only relevant if [`literal_delims`] is false. While delimiters
@@ -277,6 +815,13 @@ pub struct RawTextReadConfig {
     /// If true, allow STEXT to use a different delimiter than TEXT.
     pub allow_stext_own_delim: bool,
 
+    /// If true, keywords in STEXT will replace those in TEXT with the same key.
+    ///
+    /// Keys in both segments with different values are always reported as
+    /// conflicts (errors unless 'allow_nonunique' is true). By default, the
+    /// value from primary TEXT is kept.
+    pub stext_takes_precedence: bool,
+
     /// If true, allow $NEXTDATA to be missing.
     ///
     /// This is a required keyword in all versions. However, most files only
@@ -312,6 +857,66 @@ pub struct RawTextReadConfig {
     /// supplied, $DATE will be parsed according to the standard pattern which
     /// is '%d-%b-%Y'.
     pub date_pattern: Option<DatePattern>,
+
+    /// If true, do not store non-standard keywords.
+    ///
+    /// Non-standard keywords will only be counted, which may save considerable
+    /// memory for files with many vendor-specific keywords if only standard
+    /// keywords and data are needed. Note that this will also make duplicated
+    /// non-standard keys undetectable and will cause all non-standard
+    /// measurement keywords to be empty.
+    pub skip_nonstandard: bool,
+
+    /// If true, keep a copy of the exact bytes of primary and supplemental TEXT.
+    ///
+    /// This is useful for debugging or for editing TEXT without otherwise
+    /// altering its contents.
+    pub keep_raw_text: bool,
+
+    /// If true, read the contents of any OTHER segments along with TEXT.
+    ///
+    /// These are otherwise only read along with DATA and ANALYSIS.
+    pub keep_others: bool,
+
+    /// If true, record timings and byte counts while reading.
+    ///
+    /// This will time reading HEADER, TEXT, and DATA (when applicable) and
+    /// count the number of bytes read from each. These are returned as
+    /// 'metrics' in the parse output and are meant to help diagnose slow
+    /// reads, for instance on network filesystems. If false, no clocks are
+    /// read at all.
+    pub collect_metrics: bool,
+
+    /// If true, keep values with binary contamination rather than dropping them.
+    ///
+    /// Values are considered contaminated if they are not valid UTF-8 or if
+    /// they have NUL or other non-printing control characters (besides tab,
+    /// carriage return, and newline). Such values will have control
+    /// characters removed and invalid UTF-8 sequences replaced with U+FFFD,
+    /// and their keys will be listed in the parse output along with a warning.
+    /// Otherwise, invalid UTF-8 values are dropped and values with control
+    /// characters are kept as-is.
+    pub lossy_values: bool,
+
+    /// Encoding used to decode keys and values.
+    ///
+    /// Values which cannot be decoded are either dropped or converted lossily
+    /// depending on 'lossy_values'.
+    pub text_encoding: TextEncoding,
+
+    /// Rules to rename, rewrite, or insert keywords before standardization.
+    ///
+    /// These are applied after TEXT (and supplemental TEXT) is parsed and may
+    /// be used to fix misspelled keys or bad values which would otherwise
+    /// cause errors. All changes will be listed in the parse output.
+    pub keyword_repairs: KeywordRepairs,
+
+    /// Rules to remove or hash identifying keywords before standardization.
+    ///
+    /// These are applied after 'keyword_repairs' and listed along with them in
+    /// the parse output. If any rules are given, the exact bytes of TEXT will
+    /// not be kept regardless of 'keep_raw_text'.
+    pub anonymize: Anonymizer,
     // TODO add two lists which will convert matching nonstandard keys to
     // standard and vice versa
 }
@@ -324,10 +929,20 @@ pub struct TimeConfig {
     /// If matched, the time measurement must conform to the requirements of the
     /// target FCS version, such as having $TIMESTEP present and having a PnE
     /// set to '0,0'.
+    ///
+    /// For 3.2, a measurement with $PnTYPE set to 'Time' will be used as the
+    /// time measurement instead of any measurement matching this pattern.
     pub pattern: Option<TimePattern>,
 
     /// If true, allow time to not be present even if we specify ['pattern'].
     pub allow_missing: bool,
+
+    /// Maximum difference between $DATE/$BTIM/$ETIM and
+    /// $BEGINDATETIME/$ENDDATETIME when cross-checking these in a
+    /// conformance report.
+    ///
+    /// If None, these keywords will not be cross-checked.
+    pub datetime_tolerance: Option<TimeDelta>,
     // /// If true, will allow $PnE to not be linear (ie "0,0").
     // ///
     // /// $PnE will not be used regardless. This will merely throw an error if
@@ -339,7 +954,7 @@ pub struct TimeConfig {
 }
 
 /// Instructions for reading the TEXT segment in a standardized structure.
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct StdTextReadConfig {
     /// Instructions to read HEADER and TEXT.
     pub raw: RawTextReadConfig,
@@ -347,6 +962,20 @@ pub struct StdTextReadConfig {
     /// Time-related options.
     pub time: TimeConfig,
 
+    /// Time zone in which $BTIM, $ETIM, and $DATE were recorded.
+    ///
+    /// If given, this will be attached to the timestamps such that they may be
+    /// converted to absolute date-times. These keywords otherwise have no time
+    /// zone.
+    pub lab_timezone: Option<LabTimezone>,
+
+    /// Time zone for $BEGINDATETIME and $ENDDATETIME values without an offset.
+    ///
+    /// The standard requires these to have a UTC offset, but many files omit
+    /// it. Such values will be interpreted in this time zone, or in UTC if
+    /// not given. A warning will be issued in either case.
+    pub datetime_timezone: Option<LabTimezone>,
+
     /// Prefix to use when filling in missing $PnN values.
     ///
     /// This is only applicable to 2.0 and 3.0 since $PnN became required in
@@ -363,7 +992,31 @@ pub struct StdTextReadConfig {
     /// standard.
     pub allow_pseudostandard: bool,
 
-    /// If true, throw an error if TEXT includes any deprecated features.
+    /// Pseudostandard keywords which should be parsed to a specific type.
+    ///
+    /// Keywords listed here will not be considered pseudostandard for the
+    /// purposes of 'allow_pseudostandard'. If present and valid, these will be
+    /// removed from the pseudostandard keywords and returned as typed values.
+    /// Those which fail to parse will be left as-is with a warning.
+    pub pseudostandard_types: KeywordTypes,
+
+    /// Keywords known to be part of some version or vendor extension.
+    ///
+    /// Keywords in this registry which are not absent in the version being
+    /// read will not be considered pseudostandard. Any which are not used to
+    /// build the standardized TEXT will be returned as unused standard
+    /// keywords, with a warning if they have a type and fail to parse.
+    pub keyword_registry: KeywordRegistry,
+
+    /// Vendor whose non-standard keywords should be parsed.
+    ///
+    /// If not off, known non-standard keywords for this vendor (such as "SPILL"
+    /// or laser settings) will be parsed and returned in addition to the
+    /// standardized TEXT. These keywords will otherwise be left as-is.
+    pub vendor: VendorProfile,
+
+    /// If true, throw an error if TEXT includes any deprecated keywords or
+    /// values.
     ///
     /// If false, merely throw a warning.
     pub disallow_deprecated: bool,
@@ -376,21 +1029,45 @@ pub struct StdTextReadConfig {
     /// value of the log scale, 0 is meaningless.
     ///
     /// This fix will replace Y in such cases with 1.0, such that the value
-    /// becomes 'X,1.0'.
+    /// becomes 'X,1.0'. A warning will be issued for each fixed keyword.
     pub fix_log_scale_offsets: bool,
 
-    /// If supplied, this pattern will be used to group "nonstandard" keywords
-    /// with matching measurements.
+    /// If true, infer $PAR from the $PnB keywords if these disagree.
+    ///
+    /// Some files have a $PAR which does not match the number of measurements
+    /// actually present. If true, $PAR will be replaced by the largest 'n'
+    /// such that $P1B through $PnB are all present, and a warning will be
+    /// issued. Nothing will be replaced if no $PnB are present.
+    pub infer_par: bool,
+
+    /// Names of measurements whose $PnE should be treated as linear.
+    ///
+    /// Some files write a log $PnE for measurements which are actually
+    /// linear. $PnE for each measurement whose $PnN is in this list will be
+    /// replaced with '0,0' with a warning.
+    pub force_linear_scale: Vec<Shortname>,
+
+    /// Non-standard values of $PnFEATURE to accept.
+    ///
+    /// $PnFEATURE (3.2+) should be 'Area', 'Width', or 'Height', but imaging
+    /// cytometers may use others such as 'Eccentricity'. Values in this list
+    /// will be kept and written as-is; others will be dropped with a warning.
+    pub allowed_features: Vec<String>,
+
+    /// Patterns used to group "nonstandard" keywords with matching
+    /// measurements.
     ///
     /// Usually this will be something like '^P%n.+' where '%n' will be
     /// substituted with the measurement index before using it as a regular
     /// expression to match keywords. It should not start with a "$" and must
-    /// contain a literal '%n'.
+    /// contain a literal '%n'. Patterns may also be literal prefixes and may
+    /// be case-insensitive (see [`NonStdMeasPattern`]).
     ///
     /// This will matching something like 'P7FOO' which would be 'FOO' for
     /// measurement 7. These may be used when converting between different
-    /// FCS versions.
-    pub nonstandard_measurement_pattern: Option<NonStdMeasPattern>,
+    /// FCS versions. Patterns are tried in order and each keyword will be
+    /// given to the first measurement matched by the first matching pattern.
+    pub nonstandard_measurement_patterns: Vec<NonStdMeasPattern>,
     // TODO add repair stuff
 }
 
@@ -422,4 +1099,45 @@ pub struct SharedConfig {
     /// Note: this flag has nothing to do with the bitmask being applied to the
     /// actual data being read. This will happen regardless.
     pub disallow_bitmask_truncation: bool,
+
+    /// If true, round integer widths which are not multiples of 8 up to the
+    /// nearest byte.
+    ///
+    /// FCS 2.0 and 3.0 allow $PnB to be any number of bits, and some legacy
+    /// instruments use values like 10 or 12. In practice, such values are
+    /// nearly always stored in whole bytes (ie 12 bits in a 16-bit word) with
+    /// the unused bits either zero or removed by the bitmask from $PnR. If
+    /// true, treat these widths as the next multiple of 8 bits, where $BYTEORD
+    /// refers to the padded width. Data packed across byte boundaries is not
+    /// supported.
+    ///
+    /// Only applies when $DATATYPE=I in FCS 2.0 and 3.0.
+    pub pad_integer_widths: bool,
+
+    /// How to handle ASCII layouts where only some $PnB are '*'.
+    ///
+    /// The standard requires either all $PnB to be '*' (delimited) or none
+    /// (fixed width), but some legacy exports use '*' for only the time
+    /// measurement. By default this is an error. Otherwise a warning will be
+    /// issued and the layout will be made using the given strategy. This also
+    /// applies when writing, in which case $PnB will be written as-is.
+    ///
+    /// Only applies when $DATATYPE=A.
+    pub mixed_ascii_widths: MixedAsciiWidths,
+}
+
+/// Strategy for ASCII layouts with both variable and fixed $PnB
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum MixedAsciiWidths {
+    /// Do not read the file
+    #[default]
+    Error,
+
+    /// Treat all measurements as delimited, ignoring fixed $PnB
+    Delimited,
+
+    /// Give each variable measurement a fixed width derived from $PnR.
+    ///
+    /// This will be the number of digits in the maximum value ($PnR - 1).
+    FromRange,
 }
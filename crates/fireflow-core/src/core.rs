@@ -1,40 +1,56 @@
 use crate::config::*;
+use crate::crc::*;
 use crate::data::*;
+use crate::datahash::*;
 use crate::error::*;
 use crate::header::*;
+use crate::limits::{HEADER_LEN, MAX_HEADER_OFFSET, MAX_PAR_2_0};
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one, newtype_from};
+use crate::scratch::ParserScratch;
 use crate::segment::*;
+use crate::stats::{
+    BinScale, ColumnStats, Histogram, MeasurementHistogram, MeasurementSummary, RangeAudit,
+};
 use crate::text::byteord::*;
 use crate::text::compensation::*;
 use crate::text::datetimes::*;
 use crate::text::float_or_int::*;
+use crate::text::hardware::*;
 use crate::text::index::*;
 use crate::text::keywords::*;
 use crate::text::named_vec::*;
 use crate::text::optionalkw::*;
 use crate::text::parser::*;
+use crate::text::registry::{KeyStatus, RegisteredKeywordError};
 use crate::text::scale::*;
 use crate::text::spillover::*;
 use crate::text::timestamps::*;
+use crate::text::typed::TypedKeywordError;
 use crate::text::unstainedcenters::*;
+use crate::text::vendor::{find_spillover, VendorKeywords, VendorProfile, LEGACY_SPILLOVER_KEYS};
+use crate::transform::{Transform, TransformError, TransformSpec};
 use crate::validated::ascii_uint::Uint8DigitOverflow;
 use crate::validated::dataframe::*;
 use crate::validated::nonstandard::*;
 use crate::validated::shortname::*;
 use crate::validated::standard::*;
 
-use chrono::Timelike;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta, Timelike};
 use itertools::Itertools;
 use nalgebra::DMatrix;
 use nonempty::NonEmpty;
 use serde::ser::SerializeStruct;
 use serde::Serialize;
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{BufReader, BufWriter, Read, Seek, Write};
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
+use std::ops;
 use std::str::FromStr;
 
 /// Represents the minimal data required to write an FCS file.
@@ -215,6 +231,161 @@ pub struct Optical<X> {
     pub specific: X,
 }
 
+/// A version-agnostic view of one measurement
+///
+/// Fields which are only defined for optical measurements will be None for the
+/// time measurement.
+#[derive(Serialize)]
+pub struct MeasurementView<'a> {
+    /// Position of this measurement (ie "n" in $Pn* keywords)
+    pub index: MeasIndex,
+
+    /// Value for $PnN (which may be missing in 2.0 and 3.0)
+    pub shortname: Option<&'a Shortname>,
+
+    /// Value for $PnS
+    pub longname: Option<&'a Longname>,
+
+    /// Value for $PnB
+    pub width: Width,
+
+    /// Value for $PnR
+    pub range: &'a Range,
+
+    /// True if this is the time measurement
+    pub is_temporal: bool,
+
+    /// Value for $PnF
+    pub filter: Option<&'a Filter>,
+
+    /// Value for $PnO
+    pub power: Option<&'a Power>,
+
+    /// Value for $PnD
+    pub detector_type: Option<&'a DetectorType>,
+
+    /// Value for $PnP
+    pub percent_emitted: Option<&'a PercentEmitted>,
+
+    /// Value for $PnV
+    pub detector_voltage: Option<&'a DetectorVoltage>,
+}
+
+/// A version-agnostic summary of one measurement as a table row
+///
+/// Unlike [`MeasurementView`], this owns its values and includes keywords
+/// which only exist in some versions; these will be None (or empty) where
+/// not applicable. Fields which are only defined for optical measurements will
+/// also be None for the time measurement.
+#[derive(Clone, Serialize)]
+pub struct MeasurementRow {
+    /// Position of this measurement (ie "n" in $Pn* keywords)
+    pub index: MeasIndex,
+
+    /// Value for $PnN (which may be missing in 2.0 and 3.0)
+    pub shortname: Option<Shortname>,
+
+    /// Value for $PnS
+    pub longname: Option<Longname>,
+
+    /// Value for $PnB
+    pub width: Width,
+
+    /// Value for $PnR
+    pub range: Range,
+
+    /// True if this is the time measurement
+    pub is_temporal: bool,
+
+    /// Value for $TIMESTEP (time measurement only)
+    pub timestep: Option<Timestep>,
+
+    /// Value for $PnE
+    pub scale: Option<Scale>,
+
+    /// Value for $PnG
+    pub gain: Option<f32>,
+
+    /// Values for $PnL
+    pub wavelengths: Vec<u32>,
+
+    /// Value for $PnF
+    pub filter: Option<Filter>,
+
+    /// Value for $PnO
+    pub power: Option<Power>,
+
+    /// Value for $PnD
+    pub detector_type: Option<DetectorType>,
+
+    /// Value for $PnP
+    pub percent_emitted: Option<PercentEmitted>,
+
+    /// Value for $PnV
+    pub detector_voltage: Option<DetectorVoltage>,
+}
+
+/// Nonstandard keywords which belong to one measurement
+#[derive(Clone, Serialize)]
+pub struct NonStdClaim {
+    /// Position of the measurement which owns these keywords
+    pub index: MeasIndex,
+
+    /// Value for $PnN, or a name made from the prefix if missing
+    pub shortname: Shortname,
+
+    /// Keywords owned by this measurement, sorted
+    pub keys: Vec<NonStdKey>,
+}
+
+/// Acquisition metadata for a dataset regardless of version
+#[derive(Clone, Serialize)]
+pub struct AcquisitionInfo {
+    /// Start of acquisition from $BEGINDATETIME or $DATE+$BTIM
+    pub begin: Option<DateTime<FixedOffset>>,
+
+    /// End of acquisition from $ENDDATETIME or $DATE+$ETIM
+    pub end: Option<DateTime<FixedOffset>>,
+
+    /// Number of events in DATA
+    pub total_events: usize,
+
+    /// Value for $VOL (3.1+)
+    pub vol: Option<Vol>,
+
+    /// Value for $FLOWRATE (3.2+)
+    pub flowrate: Option<Flowrate>,
+}
+
+/// Keywords needed to convert raw values of a measurement to scaled values
+#[derive(Clone, Copy, Default)]
+pub struct ScaleParams {
+    /// Value for $PnE (may be missing in 2.0)
+    pub scale: Option<Scale>,
+
+    /// Value for $PnG (3.0+)
+    pub gain: Option<f32>,
+
+    /// Slope and offset from $PnCALIBRATION (3.1+, offset is always 0 in 3.1)
+    pub calibration: Option<(f32, f32)>,
+}
+
+impl ScaleParams {
+    /// Convert a raw value to a scaled value given $PnR.
+    ///
+    /// Log-scaled values are computed from $PnE alone; $PnG is only applied
+    /// to linear values as per the standard. $PnCALIBRATION (if present) is
+    /// then applied on top of either.
+    pub fn apply(&self, x: f64, range: f64) -> f64 {
+        let y = match self.scale {
+            Some(s @ Scale::Log(_)) => s.apply(x, range),
+            _ => self.gain.map_or(x, |g| x / f64::from(g)),
+        };
+        self.calibration
+            .map_or(y, |(m, b)| f64::from(m) * y + f64::from(b))
+    }
+}
+
 /// Minimal TEXT data for any supported FCS version
 #[derive(Clone)]
 pub enum AnyCore<A, D, O> {
@@ -297,6 +468,155 @@ impl<A, D, O> AnyCore<A, D, O> {
         match_anycore!(self, x, { x.all_shortnames() })
     }
 
+    /// Return all keywords as key/value pairs.
+    ///
+    /// See [`VersionedCore::raw_keywords`].
+    pub fn raw_keywords(
+        &self,
+        want_req: Option<bool>,
+        want_meta: Option<bool>,
+    ) -> HashMap<String, String> {
+        match_anycore!(self, x, { x.raw_keywords(want_req, want_meta) })
+    }
+
+    /// Show $CYT, which is optional except in 3.2
+    pub fn cyt(&self) -> Option<&str> {
+        match self {
+            Self::FCS2_0(x) => x.metaroot.specific.cyt.as_ref_opt().map(|c| c.0.as_str()),
+            Self::FCS3_0(x) => x.metaroot.specific.cyt.as_ref_opt().map(|c| c.0.as_str()),
+            Self::FCS3_1(x) => x.metaroot.specific.cyt.as_ref_opt().map(|c| c.0.as_str()),
+            Self::FCS3_2(x) => Some(x.metaroot.specific.cyt.0.as_str()),
+        }
+    }
+
+    /// Set $CYT.
+    ///
+    /// Return error if 'cyt' is None and version is 3.2, where $CYT is
+    /// required.
+    pub fn set_cyt(&mut self, cyt: Option<Cyt>) -> Result<(), NoCytError> {
+        match self {
+            Self::FCS2_0(x) => x.metaroot.specific.cyt = cyt.into(),
+            Self::FCS3_0(x) => x.metaroot.specific.cyt = cyt.into(),
+            Self::FCS3_1(x) => x.metaroot.specific.cyt = cyt.into(),
+            Self::FCS3_2(x) => x.metaroot.specific.cyt = cyt.ok_or(NoCytError)?,
+        }
+        Ok(())
+    }
+
+    /// Set $PLATEID, $PLATENAME, and $WELLID.
+    ///
+    /// Return error if version is 2.0 or 3.0, which lack these keywords.
+    pub fn set_plate(&mut self, plate: PlateData) -> Result<(), KeywordVersionError> {
+        match self {
+            Self::FCS2_0(_) | Self::FCS3_0(_) => {
+                return Err(KeywordVersionError("$PLATEID", self.version()));
+            }
+            Self::FCS3_1(x) => x.metaroot.specific.plate = plate,
+            Self::FCS3_2(x) => x.metaroot.specific.plate = plate,
+        }
+        Ok(())
+    }
+
+    /// Show non-standard keywords (not including those for measurements)
+    pub fn nonstandard_keywords(&self) -> &NonStdKeywords {
+        match_anycore!(self, x, { &x.metaroot.nonstandard_keywords })
+    }
+
+    /// Show $MODE, which is always "L" for 3.2.
+    pub fn mode(&self) -> Mode {
+        match_anycore!(self, x, { x.metaroot.specific.mode() })
+    }
+
+    /// Cross-check $DATE/$BTIM/$ETIM against $BEGINDATETIME/$ENDDATETIME.
+    ///
+    /// The latter only exist in 3.2, so for earlier versions this will only
+    /// check for midnight rollover. See [`Datetimes::reconcile`] for details.
+    pub fn acquisition_interval(&self, tolerance: TimeDelta) -> TemporalTentative {
+        match_anycore!(self, x, {
+            x.metaroot.specific.acquisition_interval(tolerance)
+        })
+    }
+
+    /// Show $VOL, which only exists in 3.1+
+    pub fn vol(&self) -> Option<Vol> {
+        match self {
+            Self::FCS2_0(_) | Self::FCS3_0(_) => None,
+            Self::FCS3_1(x) => x.metaroot.specific.vol.0,
+            Self::FCS3_2(x) => x.metaroot.specific.vol.0,
+        }
+    }
+
+    /// Show $FLOWRATE, which only exists in 3.2+
+    pub fn flowrate(&self) -> Option<&Flowrate> {
+        match self {
+            Self::FCS3_2(x) => x.metaroot.specific.flowrate.as_ref_opt(),
+            _ => None,
+        }
+    }
+
+    /// Show spillover matrix from non-standard keywords (2.0 and 3.0 only).
+    ///
+    /// $SPILLOVER was added in 3.1, but many older files store the same matrix
+    /// in non-standard keywords like "SPILL" or "SPILLOVER". Return the first
+    /// of these which is valid, ignoring case.
+    pub fn legacy_spillover(&self) -> Option<Spillover> {
+        match self {
+            Self::FCS2_0(_) | Self::FCS3_0(_) => {
+                find_spillover(self.nonstandard_keywords(), &LEGACY_SPILLOVER_KEYS)
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse vendor-specific values from non-standard keywords.
+    ///
+    /// See [`VendorKeywords::from_keywords`].
+    pub fn vendor_keywords(&self, profile: VendorProfile) -> Option<VendorKeywords> {
+        VendorKeywords::from_keywords(profile, self.cyt(), self.nonstandard_keywords())
+    }
+
+    /// Show $GATE, $GATING, $RnI, $RnW, and $Gm* (if present)
+    pub fn gating(&self) -> Option<GatingData<'_>> {
+        match self {
+            Self::FCS2_0(x) => x.applied_gates().map(GatingData::FCS2_0),
+            Self::FCS3_0(x) => x.applied_gates().map(GatingData::FCS3_0),
+            Self::FCS3_1(x) => x.applied_gates().map(GatingData::FCS3_0),
+            Self::FCS3_2(x) => x.applied_gates().map(GatingData::FCS3_2),
+        }
+    }
+
+    /// Show $DATATYPE
+    pub fn datatype(&self) -> AlphaNumType {
+        match_anycore!(self, x, { x.metaroot.datatype() })
+    }
+
+    /// Show $PAR
+    pub fn par(&self) -> Par {
+        match_anycore!(self, x, { x.par() })
+    }
+
+    /// Return a version-agnostic view of all measurements
+    pub fn measurements(&self) -> Vec<MeasurementView<'_>> {
+        match_anycore!(self, x, { x.measurement_views() })
+    }
+
+    /// Return a version-agnostic table of all measurements
+    pub fn meas_table(&self) -> Vec<MeasurementRow> {
+        match_anycore!(self, x, { x.meas_table() })
+    }
+
+    /// Return nonstandard keywords attached to each measurement.
+    ///
+    /// Measurements without nonstandard keywords are skipped.
+    pub fn nonstandard_claims(&self) -> Vec<NonStdClaim> {
+        match_anycore!(self, x, { x.nonstandard_claims() })
+    }
+
+    /// Return lasers and detectors as described by $PnL/$PnO/$PnV/$PnF.
+    pub fn optical_config(&self) -> OpticalConfig {
+        OpticalConfig::from_rows(&self.meas_table())
+    }
+
     // pub fn text_segment(
     //     &self,
     //     tot: Tot,
@@ -313,9 +633,21 @@ impl<A, D, O> AnyCore<A, D, O> {
         match_anycore!(self, x, { x.print_meas_table(delim) })
     }
 
+    pub fn check_hardware(
+        &self,
+        hw: &InstrumentHardware,
+    ) -> MultiResult<(), HardwareConflictError> {
+        match_anycore!(self, x, { x.check_hardware(hw) })
+    }
+
+    /// Print $SPILLOVER as a table.
+    ///
+    /// For 2.0 and 3.0, fall back to the matrix in non-standard keywords (see
+    /// [`AnyCore::legacy_spillover`]).
     pub fn print_spillover_table(&self, delim: &str) {
+        let legacy = self.legacy_spillover();
         let res = match_anycore!(self, x, { x.metaroot.specific.as_spillover() })
-            .as_ref()
+            .or(legacy.as_ref())
             .map(|s| s.print_table(delim));
         if res.is_none() {
             println!("None")
@@ -323,7 +655,117 @@ impl<A, D, O> AnyCore<A, D, O> {
     }
 }
 
+impl<A, D, O> AnyCore<A, D, O>
+where
+    A: Clone,
+    D: Clone,
+    O: Clone,
+{
+    /// Summarize what would happen if this were converted to each other version.
+    ///
+    /// Conversion will be lossless for a given version if it has no errors.
+    /// Errors include keywords (measurement or otherwise) which are not
+    /// present in the target version and would be lost, any values which are
+    /// invalid in the target version, and any limits of the target version
+    /// which would be exceeded.
+    pub fn compatibility(&self) -> Vec<VersionCompatibility>
+    where
+        D: EventCount,
+    {
+        match self {
+            Self::FCS2_0(x) => vec![
+                x.compatibility_to::<InnerMetaroot3_0>(),
+                x.compatibility_to::<InnerMetaroot3_1>(),
+                x.compatibility_to::<InnerMetaroot3_2>(),
+            ],
+            Self::FCS3_0(x) => vec![
+                x.compatibility_to::<InnerMetaroot2_0>(),
+                x.compatibility_to::<InnerMetaroot3_1>(),
+                x.compatibility_to::<InnerMetaroot3_2>(),
+            ],
+            Self::FCS3_1(x) => vec![
+                x.compatibility_to::<InnerMetaroot2_0>(),
+                x.compatibility_to::<InnerMetaroot3_0>(),
+                x.compatibility_to::<InnerMetaroot3_2>(),
+            ],
+            Self::FCS3_2(x) => vec![
+                x.compatibility_to::<InnerMetaroot2_0>(),
+                x.compatibility_to::<InnerMetaroot3_0>(),
+                x.compatibility_to::<InnerMetaroot3_1>(),
+            ],
+        }
+    }
+
+    /// Convert to another FCS version.
+    ///
+    /// Keywords will be mapped to their equivalents in the target version and
+    /// required keywords will be filled with defaults where these can be
+    /// derived. Keywords which cannot be converted will be returned as errors
+    /// unless 'force' is true, in which case they will be dropped with a
+    /// warning. The result will also be checked against the limits of the
+    /// target version (see [`VersionedCoreDataset::check_version_limits`]).
+    /// Converting to the current version does nothing.
+    pub fn try_convert_version(
+        self,
+        to: Version,
+        force: bool,
+    ) -> DeferredResult<Self, MetarootConvertWarning, AnyConvertError>
+    where
+        D: EventCount,
+    {
+        match (self, to) {
+            (Self::FCS2_0(x), Version::FCS3_0) => (*x).convert_to::<InnerMetaroot3_0>(force),
+            (Self::FCS2_0(x), Version::FCS3_1) => (*x).convert_to::<InnerMetaroot3_1>(force),
+            (Self::FCS2_0(x), Version::FCS3_2) => (*x).convert_to::<InnerMetaroot3_2>(force),
+            (Self::FCS3_0(x), Version::FCS2_0) => (*x).convert_to::<InnerMetaroot2_0>(force),
+            (Self::FCS3_0(x), Version::FCS3_1) => (*x).convert_to::<InnerMetaroot3_1>(force),
+            (Self::FCS3_0(x), Version::FCS3_2) => (*x).convert_to::<InnerMetaroot3_2>(force),
+            (Self::FCS3_1(x), Version::FCS2_0) => (*x).convert_to::<InnerMetaroot2_0>(force),
+            (Self::FCS3_1(x), Version::FCS3_0) => (*x).convert_to::<InnerMetaroot3_0>(force),
+            (Self::FCS3_1(x), Version::FCS3_2) => (*x).convert_to::<InnerMetaroot3_2>(force),
+            (Self::FCS3_2(x), Version::FCS2_0) => (*x).convert_to::<InnerMetaroot2_0>(force),
+            (Self::FCS3_2(x), Version::FCS3_0) => (*x).convert_to::<InnerMetaroot3_0>(force),
+            (Self::FCS3_2(x), Version::FCS3_1) => (*x).convert_to::<InnerMetaroot3_1>(force),
+            (x, _) => Ok(Tentative::new1(x)),
+        }
+    }
+}
+
 impl AnyCoreTEXT {
+    /// Convert to a dataset with no events.
+    pub fn into_coredataset_empty(self) -> AnyCoreDataset {
+        match_anycore!(self, x, { (*x).into_coredataset_empty().into() })
+    }
+
+    /// Add an optical measurement with linear $PnE to the end.
+    ///
+    /// $PAR and $PnN will reflect the new measurement when written. Return
+    /// error if name is non-unique.
+    pub fn add_measurement(
+        &mut self,
+        n: Shortname,
+        width: Width,
+        range: Range,
+    ) -> Result<(), NonUniqueKeyError> {
+        let s = Scale::Linear;
+        match self {
+            Self::FCS2_0(x) => x.push_optical(Some(n).into(), Optical2_0::new(width, range)),
+            Self::FCS3_0(x) => x.push_optical(Some(n).into(), Optical3_0::new(width, range, s)),
+            Self::FCS3_1(x) => x.push_optical(Identity(n), Optical3_1::new(width, range, s)),
+            Self::FCS3_2(x) => x.push_optical(Identity(n), Optical3_2::new(width, range, s)),
+        }
+        .map(|_| ())
+    }
+
+    /// Remove a measurement by name.
+    ///
+    /// Measurements after the removed one will be renumbered, and $TR,
+    /// $SPILLOVER, $COMP, and $UNSTAINEDCENTERS will no longer refer to it.
+    /// Return the removed index if found.
+    pub fn remove_measurement(&mut self, n: &Shortname) -> Option<MeasIndex> {
+        match_anycore!(self, x, { x.remove_measurement_by_name(n).map(|(i, _)| i) })
+    }
+
     pub(crate) fn parse_raw(
         version: Version,
         std: &mut StdKeywords,
@@ -352,6 +794,337 @@ impl AnyCoreDataset {
         match_anycore!(self, x, { &x.data })
     }
 
+    /// Add an optical measurement with linear $PnE and its column to the end.
+    ///
+    /// See [`AnyCoreTEXT::add_measurement`]. Return error if name is
+    /// non-unique or column length does not match the number of events.
+    pub fn add_measurement(
+        &mut self,
+        n: Shortname,
+        width: Width,
+        range: Range,
+        col: AnyFCSColumn,
+    ) -> Result<(), PushOpticalError> {
+        let s = Scale::Linear;
+        match self {
+            Self::FCS2_0(x) => x.push_optical(Some(n).into(), Optical2_0::new(width, range), col),
+            Self::FCS3_0(x) => {
+                x.push_optical(Some(n).into(), Optical3_0::new(width, range, s), col)
+            }
+            Self::FCS3_1(x) => x.push_optical(Identity(n), Optical3_1::new(width, range, s), col),
+            Self::FCS3_2(x) => x.push_optical(Identity(n), Optical3_2::new(width, range, s), col),
+        }
+        .map(|_| ())
+    }
+
+    /// Remove a measurement and its column by name.
+    ///
+    /// See [`AnyCoreTEXT::remove_measurement`].
+    pub fn remove_measurement(&mut self, n: &Shortname) -> Option<MeasIndex> {
+        match_anycore!(self, x, { x.remove_measurement_by_name(n).map(|(i, _)| i) })
+    }
+
+    /// Consume this dataset and return DATA.
+    ///
+    /// Useful for giving columns back to a [`ParserScratch`] once a dataset
+    /// is no longer needed.
+    pub fn into_data(self) -> FCSDataFrame {
+        match_anycore!(self, x, { x.data })
+    }
+
+    /// Return the column in DATA whose $PnN matches 'name'.
+    pub fn column(&self, name: &str) -> Option<&AnyFCSColumn> {
+        match_anycore!(self, x, { x.column(name) })
+    }
+
+    /// Return the column in DATA whose $PnN matches 'name' as a custom type.
+    pub fn column_as<T: CustomColumn>(&self, name: &str) -> Option<Result<T, DecodeColumnError>> {
+        match_anycore!(self, x, { x.column_as(name) })
+    }
+
+    /// Return each column in DATA alongside its $PnN.
+    ///
+    /// See [`VersionedCoreDataset::named_columns`].
+    pub fn named_columns(&self) -> Vec<(Shortname, &AnyFCSColumn)> {
+        match_anycore!(self, x, { x.named_columns().collect() })
+    }
+
+    /// Return true for each event whose trigger measurement exceeds $TR.
+    ///
+    /// See [`VersionedCoreDataset::trigger_mask`].
+    pub fn trigger_mask(&self) -> Option<Vec<bool>> {
+        match_anycore!(self, x, { x.trigger_mask() })
+    }
+
+    /// Return DATA with only the events whose trigger measurement exceeds $TR.
+    ///
+    /// See [`VersionedCoreDataset::triggered_data`].
+    pub fn triggered_data(&self) -> Option<FCSDataFrame> {
+        match_anycore!(self, x, { x.triggered_data() })
+    }
+
+    /// Return subset membership for each event as encoded in ANALYSIS.
+    ///
+    /// See [`VersionedCoreDataset::subset_membership`].
+    pub fn subset_membership(&self) -> Option<Result<Vec<Option<Vec<bool>>>, SubsetDecodeError>> {
+        match_anycore!(self, x, { x.subset_membership() })
+    }
+
+    /// Return events within each gating region and within $GATING.
+    ///
+    /// Return None if $GATING is not set. See [`GatingRegions::membership`].
+    pub fn gate_membership(&self) -> Option<Result<GateMembership, GateEvalError>> {
+        let df = self.as_data();
+        self.gating().map(|g| match g {
+            GatingData::FCS2_0(x) => x.regions.membership(df),
+            GatingData::FCS3_0(x) => x.regions.membership(df),
+            GatingData::FCS3_2(x) => x.regions.membership(df),
+        })
+    }
+
+    /// Return the number of events in DATA.
+    pub fn nrows(&self) -> usize {
+        self.as_data().nrows()
+    }
+
+    /// Return start/end of acquisition, event count, $VOL, and $FLOWRATE.
+    ///
+    /// Start and end will be taken from $BEGINDATETIME/$ENDDATETIME if present
+    /// and from $DATE/$BTIM/$ETIM otherwise. See
+    /// [`AnyCore::acquisition_interval`] for how these are reconciled.
+    pub fn acquisition_info(
+        &self,
+        tolerance: TimeDelta,
+    ) -> Tentative<AcquisitionInfo, TemporalWarning, Infallible> {
+        self.acquisition_interval(tolerance)
+            .map(|i| AcquisitionInfo {
+                begin: i.map(|x| x.begin),
+                end: i.and_then(|x| x.end),
+                total_events: self.nrows(),
+                vol: self.vol(),
+                flowrate: self.flowrate().cloned(),
+            })
+    }
+
+    /// Return DATA with $SPILLOVER or $COMP/$DFCmTOn applied.
+    ///
+    /// See [`VersionedCoreDataset::compensated_data`].
+    pub fn compensated_data(&self) -> Option<Result<FCSDataFrame, CompensateError>> {
+        match_anycore!(self, x, { x.compensated_data() })
+    }
+
+    /// Return DATA with values converted to scaled floats.
+    ///
+    /// See [`VersionedCoreDataset::scaled_data`].
+    pub fn scaled_data(&self, raw: &[Shortname]) -> FCSDataFrame {
+        match_anycore!(self, x, { x.scaled_data(raw) })
+    }
+
+    /// Return statistics for each measurement.
+    ///
+    /// See [`VersionedCoreDataset::summary`].
+    pub fn summary(
+        &self,
+        raw: Option<&[Shortname]>,
+        percentiles: &[f64],
+    ) -> Vec<MeasurementSummary> {
+        match_anycore!(self, x, { x.summary(raw, percentiles) })
+    }
+
+    /// Compare $PnR to $PnB and DATA for each integer measurement.
+    ///
+    /// See [`VersionedCoreDataset::range_audit`].
+    pub fn range_audit(&self) -> Vec<RangeAudit> {
+        match_anycore!(self, x, { x.range_audit() })
+    }
+
+    /// Return DATA with logicle or arcsinh transforms applied.
+    ///
+    /// See [`VersionedCoreDataset::transformed_data`].
+    pub fn transformed_data(
+        &self,
+        specs: &[(Shortname, TransformSpec)],
+    ) -> Result<FCSDataFrame, TransformDataError> {
+        match_anycore!(self, x, { x.transformed_data(specs) })
+    }
+
+    /// Return a histogram for each measurement.
+    ///
+    /// See [`VersionedCoreDataset::histograms`].
+    pub fn histograms(
+        &self,
+        raw: Option<&[Shortname]>,
+        bins: NonZeroUsize,
+        scale: &BinScale,
+    ) -> Vec<MeasurementHistogram> {
+        match_anycore!(self, x, { x.histograms(raw, bins, scale) })
+    }
+
+    /// Return a histogram for one measurement.
+    ///
+    /// See [`VersionedCoreDataset::histogram`].
+    pub fn histogram(
+        &self,
+        name: &Shortname,
+        raw: Option<&[Shortname]>,
+        bins: NonZeroUsize,
+        scale: &BinScale,
+    ) -> Option<Histogram> {
+        match_anycore!(self, x, { x.histogram(name, raw, bins, scale) })
+    }
+
+    /// Return a copy of this dataset with a subset of its events.
+    pub fn subsample(&self, size: SubsampleSize, method: SubsampleMethod) -> Self {
+        match_anycore!(self, x, { x.subsample(size, method).into() })
+    }
+
+    /// Return copies of this dataset with events in each of the given ranges.
+    ///
+    /// See [`VersionedCoreDataset::split_ranges`].
+    pub fn split_ranges(&self, ranges: &[ops::Range<usize>]) -> Result<Vec<Self>, SplitError> {
+        match_anycore!(self, x, {
+            x.split_ranges(ranges)
+                .map(|xs| xs.into_iter().map(|y| y.into()).collect())
+        })
+    }
+
+    /// Return copies of this dataset with and without events in a mask.
+    ///
+    /// See [`VersionedCoreDataset::split_mask`].
+    pub fn split_mask(&self, mask: &[bool]) -> Result<(Self, Self), SplitError> {
+        match_anycore!(self, x, {
+            x.split_mask(mask).map(|(y, z)| (y.into(), z.into()))
+        })
+    }
+
+    /// Return a new dataset with events from other datasets appended.
+    ///
+    /// All datasets must have the same version. See
+    /// [`VersionedCoreDataset::concat`].
+    pub fn concat(&self, others: &[AnyCoreDataset]) -> Result<Self, ConcatError> {
+        macro_rules! go {
+            ($x:expr, $var:ident) => {{
+                let ys = others
+                    .iter()
+                    .enumerate()
+                    .map(|(i, o)| match o {
+                        Self::$var(y) => Ok(y.as_ref()),
+                        _ => Err(ConcatError::Version(i, o.version())),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                $x.concat(&ys[..]).map(|c| c.into())
+            }};
+        }
+        match self {
+            Self::FCS2_0(x) => go!(x, FCS2_0),
+            Self::FCS3_0(x) => go!(x, FCS3_0),
+            Self::FCS3_1(x) => go!(x, FCS3_1),
+            Self::FCS3_2(x) => go!(x, FCS3_2),
+        }
+    }
+
+    /// Return event times in seconds.
+    ///
+    /// See [`VersionedCoreDataset::time_column`].
+    pub fn time_column(
+        &self,
+        name: Option<&Shortname>,
+        order: TimeOrder,
+    ) -> Result<Vec<f64>, TimeColumnError> {
+        match_anycore!(self, x, { x.time_column(name, order) })
+    }
+
+    /// Write this dataset (HEADER+TEXT+DATA+ANALYSIS+OTHER) to a handle
+    ///
+    /// If [`WriteConfig::track_changes`] is given, modifications will be
+    /// recorded before writing (see [`Self::with_tracked_changes`]).
+    /// If [`WriteConfig::target_datatype`] is given, measurements will be
+    /// converted before writing (see [`Self::set_target_datatype`]).
+    pub fn h_write<W: Write>(
+        &self,
+        h: &mut BufWriter<W>,
+        conf: &WriteConfig,
+    ) -> IODeferredResult<(), NewDataLayoutWarning, StdWriterError> {
+        self.h_write_with_data(h, conf, None)
+    }
+
+    /// Write this dataset to a handle, copying DATA verbatim if possible.
+    ///
+    /// See [`VersionedCoreDataset::h_write_with_data`] and [`Self::h_write`].
+    pub fn h_write_with_data<W: Write>(
+        &self,
+        h: &mut BufWriter<W>,
+        conf: &WriteConfig,
+        raw_data: Option<&[u8]>,
+    ) -> IODeferredResult<(), NewDataLayoutWarning, StdWriterError> {
+        if let Some(new) = self.with_tracked_changes(conf) {
+            return new.h_write_with_data(
+                h,
+                &WriteConfig {
+                    track_changes: None,
+                    ..conf.clone()
+                },
+                raw_data,
+            );
+        }
+        if !conf.target_datatype.is_empty() {
+            let mut new = self.clone();
+            let lossless = conf.disallow_lossy_conversions;
+            if let Err(e) = new.set_target_datatype(&conf.target_datatype, lossless) {
+                return Err(DeferredFailure::new1(ImpureError::Pure(e.into())));
+            }
+            // DATA will have a different layout so the original bytes cannot
+            // be reused
+            return new.h_write_with_data(
+                h,
+                &WriteConfig {
+                    target_datatype: TargetDatatype::default(),
+                    ..conf.clone()
+                },
+                None,
+            );
+        }
+        match_anycore!(self, x, { x.h_write_with_data(h, conf, raw_data) })
+    }
+
+    /// Convert measurements to new datatypes.
+    ///
+    /// See [`TargetDatatype`] for how $PnB and $PnR are updated. If 'lossless'
+    /// is true, return error if any float measurement has values which are not
+    /// whole numbers and would be converted to integers.
+    pub fn set_target_datatype(
+        &mut self,
+        t: &TargetDatatype,
+        lossless: bool,
+    ) -> Result<(), TargetDatatypeError> {
+        match_anycore!(self, x, { x.set_target_datatype(t, lossless) })
+    }
+
+    /// Return a copy with modifications recorded if any were made.
+    ///
+    /// Compare this dataset to the snapshot in [`WriteConfig::track_changes`].
+    /// If TEXT or DATA differ, set $LAST_MODIFIED to now, set $LAST_MODIFIER
+    /// to [`WriteConfig::last_modifier`] if given, and update $ORIGINALITY to
+    /// DataModified if DATA changed. Otherwise $ORIGINALITY will become
+    /// NonDataModified if it was Original or not given.
+    ///
+    /// Return None if nothing changed, no snapshot was given, or the version
+    /// is 2.0 or 3.0, which lack these keywords.
+    pub fn with_tracked_changes(&self, conf: &WriteConfig) -> Option<Self> {
+        let changes = conf.track_changes.as_ref()?.changes(self);
+        if !changes.any() {
+            return None;
+        }
+        let modifier = conf.last_modifier.clone();
+        let mut new = self.clone();
+        match &mut new {
+            Self::FCS3_1(x) => x.log_modification(modifier, changes.data),
+            Self::FCS3_2(x) => x.log_modification(modifier, changes.data),
+            _ => return None,
+        }
+        Some(new)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn parse_raw<R: Read + Seek>(
         h: &mut BufReader<R>,
@@ -362,6 +1135,7 @@ impl AnyCoreDataset {
         analysis_seg: HeaderAnalysisSegment,
         other_segs: &[OtherSegment],
         conf: &DataReadConfig,
+        scratch: &mut ParserScratch,
     ) -> IODeferredResult<
         (Self, AnyDataSegment, AnyAnalysisSegment),
         StdDatasetFromRawWarning,
@@ -376,6 +1150,7 @@ impl AnyCoreDataset {
                 analysis_seg,
                 other_segs,
                 conf,
+                scratch,
             )
             .def_map_value(|(x, y, z)| (x.into(), y, z)),
             Version::FCS3_0 => CoreDataset3_0::new_dataset_from_raw(
@@ -386,6 +1161,7 @@ impl AnyCoreDataset {
                 analysis_seg,
                 other_segs,
                 conf,
+                scratch,
             )
             .def_map_value(|(x, y, z)| (x.into(), y, z)),
             Version::FCS3_1 => CoreDataset3_1::new_dataset_from_raw(
@@ -396,6 +1172,7 @@ impl AnyCoreDataset {
                 analysis_seg,
                 other_segs,
                 conf,
+                scratch,
             )
             .def_map_value(|(x, y, z)| (x.into(), y, z)),
             Version::FCS3_2 => CoreDataset3_2::new_dataset_from_raw(
@@ -406,6 +1183,7 @@ impl AnyCoreDataset {
                 analysis_seg,
                 other_segs,
                 conf,
+                scratch,
             )
             .def_map_value(|(x, y, z)| (x.into(), y, z)),
         }
@@ -752,6 +1530,17 @@ pub struct AppliedGates3_2 {
     pub regions: GatingRegions<PrefixedMeasIndex>,
 }
 
+/// The gating keywords for any supported FCS version
+///
+/// This includes $GATE, $GATING, $RnI, $RnW, and $Gm* where applicable.
+#[derive(Clone, Copy, Serialize)]
+pub enum GatingData<'a> {
+    FCS2_0(&'a AppliedGates2_0),
+    /// 3.0 and 3.1 share the same gating keywords
+    FCS3_0(&'a AppliedGates3_0),
+    FCS3_2(&'a AppliedGates3_2),
+}
+
 /// The $GATING/$RnI/$RnW keywords in a unified bundle.
 ///
 /// All regions in $GATING are assumed to have corresponding $RnI/$RnW keywords,
@@ -795,6 +1584,32 @@ pub struct BivariateRegion<I> {
     pub y_index: I,
 }
 
+/// Events within each region and within the $GATING expression
+#[derive(Clone, Serialize)]
+pub struct GateMembership {
+    /// Membership for each region referenced by $GATING
+    pub regions: Vec<RegionMembership>,
+
+    /// True for each event satisfying $GATING
+    pub mask: Vec<bool>,
+
+    /// Number of events satisfying $GATING
+    pub count: usize,
+}
+
+/// Events within one region given by an $RnI/$RnW keyword pair
+#[derive(Clone, Serialize)]
+pub struct RegionMembership {
+    /// The 'n' in $RnI/$RnW
+    pub index: RegionIndex,
+
+    /// True for each event within this region
+    pub mask: Vec<bool>,
+
+    /// Number of events within this region
+    pub count: usize,
+}
+
 /// A bundle for $PKn and $PKNn (2.0-3.1)
 ///
 /// It makes little sense to have only one of these since they both collectively
@@ -814,9 +1629,8 @@ pub struct PeakData {
 /// These describe what is sometimes present in the ANALYSIS segment for 3.0 and
 /// 3.1. In these versions, it was similar to TEXT which had key/value pairs. In
 /// 3.2, these keywords were removed and the ANALYSIS segment became a free-form
-/// bytestring. This library currently makes no attempt to interpret the
-/// ANALYSIS segment given the CS* keywords, but may add this in the future if
-/// the need arises.
+/// bytestring. See [`SubsetData::membership`] for how the ANALYSIS segment is
+/// interpreted given the CS* keywords.
 #[derive(Clone, Default)]
 pub struct SubsetData {
     /// Value of $CSBITS if given
@@ -991,6 +1805,23 @@ pub trait Versioned {
     fn fcs_version() -> Version;
 }
 
+/// DATA which may or may not have a known number of events.
+pub trait EventCount {
+    fn event_count(&self) -> Option<usize>;
+}
+
+impl EventCount for () {
+    fn event_count(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl EventCount for FCSDataFrame {
+    fn event_count(&self) -> Option<usize> {
+        Some(self.nrows())
+    }
+}
+
 pub(crate) trait LookupMetaroot: Sized + VersionedMetaroot {
     fn lookup_shortname(
         kws: &mut StdKeywords,
@@ -1057,12 +1888,33 @@ pub trait VersionedMetaroot: Sized {
     where
         F: Fn(&mut Compensation) -> Result<X, ClearOptional>;
 
+    /// Update measurement indices in $RnI after measurements are reordered.
+    ///
+    /// 'order' gives the current index of the measurement to put in each
+    /// position.
+    fn reorder_region_indices(&mut self, order: &[usize]);
+
+    /// Update measurement indices in $RnI after a measurement is removed.
+    ///
+    /// If any region refers to the removed measurement, all gating keywords
+    /// will be removed since $GATING cannot refer to a missing region.
+    fn remove_region_index(&mut self, i: MeasIndex);
+
     fn timestamps_valid(&self) -> bool;
 
     fn datetimes_valid(&self) -> bool;
 
     fn byteord(&self) -> Self::D;
 
+    /// Return $MODE, which is always "L" for versions without it.
+    fn mode(&self) -> Mode;
+
+    /// Cross-check time-related keywords and return the acquisition interval.
+    fn acquisition_interval(&self, tolerance: TimeDelta) -> TemporalTentative;
+
+    /// Return $CSMODE/$CSVBITS/$CSVnFLAG and byte order if present (3.0, 3.1)
+    fn as_subset(&self) -> Option<(&SubsetData, Option<Endian>)>;
+
     fn keywords_req_inner(&self) -> impl Iterator<Item = (String, String)>;
 
     fn keywords_opt_inner(&self) -> impl Iterator<Item = (String, String)>;
@@ -1145,6 +1997,12 @@ pub trait VersionedOptical: Sized + Versioned {
     fn datatype(&self) -> Option<NumType>;
 
     fn can_convert_to_temporal(&self, i: MeasIndex) -> MultiResult<(), OpticalToTemporalError>;
+
+    /// Return all excitation wavelengths ($PnL) for this measurement.
+    fn wavelengths(&self) -> Vec<u32>;
+
+    /// Return keywords needed to scale raw values for this measurement.
+    fn scale_params(&self) -> ScaleParams;
 }
 
 pub(crate) trait LookupOptical: Sized + VersionedOptical {
@@ -1171,6 +2029,11 @@ pub trait VersionedTemporal: Sized {
 
 pub(crate) trait LookupTemporal: VersionedTemporal {
     fn lookup_specific(kws: &mut StdKeywords, n: MeasIndex) -> LookupResult<Self>;
+
+    /// Return true if $PnTYPE marks this measurement as time (3.2+).
+    fn has_temporal_type(_: &StdKeywords, _: MeasIndex) -> bool {
+        false
+    }
 }
 
 pub trait TemporalFromOptical<O: VersionedOptical>: Sized {
@@ -1288,7 +2151,7 @@ impl CommonMeasurement {
     fn layout_data(&self) -> ColumnLayoutData<()> {
         ColumnLayoutData {
             width: self.width,
-            range: self.range,
+            range: self.range.clone(),
             datatype: (),
         }
     }
@@ -1564,15 +2427,14 @@ where
 
     fn lookup_metaroot(
         kws: &mut StdKeywords,
-        ms: &Measurements<M::N, M::T, M::O>,
+        par: Par,
+        names: &HashSet<&Shortname>,
         nonstd: NonStdPairs,
         conf: &StdTextReadConfig,
     ) -> LookupResult<Self>
     where
         M: LookupMetaroot,
     {
-        let par = Par(ms.len());
-        let names: HashSet<_> = ms.indexed_names().map(|(_, n)| n).collect();
         let a = Abrt::lookup_opt(kws, false);
         let co = Com::lookup_opt(kws, false);
         let ce = Cells::lookup_opt(kws, false);
@@ -1585,14 +2447,14 @@ where
         let sm = Smno::lookup_opt(kws, false);
         let sr = Src::lookup_opt(kws, false);
         let sy = Sys::lookup_opt(kws, false);
-        let t = Trigger::lookup_opt(kws, &names);
+        let t = Trigger::lookup_opt(kws, names);
         a.zip5(co, ce, e, f)
             .zip5(i, l, o, p)
             .zip5(sm, sr, sy, t)
             .and_maybe(
                 |(((abrt, com, cells, exp, fil), inst, lost, op, proj), smno, src, sys, tr)| {
                     let mut dt = AlphaNumType::lookup_req(kws);
-                    let s = M::lookup_specific(kws, par, &names, conf);
+                    let s = M::lookup_specific(kws, par, names, conf);
                     dt.def_eval_warning(|datatype| {
                         if *datatype == AlphaNumType::Ascii
                             && M::O::fcs_version() >= Version::FCS3_1
@@ -1674,6 +2536,11 @@ where
         });
     }
 
+    fn reorder_indices(&mut self, order: &[usize]) {
+        self.specific.with_compensation(|c| c.reorder(order));
+        self.specific.reorder_region_indices(order);
+    }
+
     fn remove_trigger_by_name(&mut self, n: &Shortname) -> bool {
         if self.tr.as_ref_opt().is_some_and(|m| &m.measurement == n) {
             self.tr = None.into();
@@ -1688,7 +2555,13 @@ where
         let s = &mut self.specific;
         s.with_spillover(|x| x.remove_by_name(n));
         s.with_unstainedcenters(|u| u.remove(n));
+        self.remove_index(i);
+    }
+
+    fn remove_index(&mut self, i: MeasIndex) {
+        let s = &mut self.specific;
         s.with_compensation(|c| c.remove_by_index(i));
+        s.remove_region_index(i);
     }
 }
 
@@ -1862,6 +2735,24 @@ where
             .collect()
     }
 
+    /// Check $PnL and $PnF of all optical measurements against an instrument.
+    ///
+    /// Return all conflicts found, which likely indicate that these keywords
+    /// were not entered correctly.
+    pub fn check_hardware(
+        &self,
+        hw: &InstrumentHardware,
+    ) -> MultiResult<(), HardwareConflictError> {
+        let es: Vec<_> = self
+            .measurements
+            .iter_non_center_values()
+            .flat_map(|(i, m)| {
+                hw.check_measurement(i, &m.specific.wavelengths(), m.filter.as_ref_opt())
+            })
+            .collect();
+        NonEmpty::from_vec(es).map_or(Ok(()), Err)
+    }
+
     /// Get measurement name for $TR keyword
     pub fn trigger_name(&self) -> Option<&Shortname> {
         self.metaroot.tr.as_ref_opt().map(|x| &x.measurement)
@@ -1929,6 +2820,35 @@ where
         self.measurements.iter_all_names().collect()
     }
 
+    /// Return columns and rows to read from DATA given a selection.
+    fn data_selection(
+        &self,
+        sel: &ReadSelection,
+    ) -> Result<DataSelection, MissingMeasurementNameError> {
+        let columns = if let Some(ns) = sel.measurements.as_ref() {
+            let names = self.all_shortnames();
+            if let Some(n) = ns.iter().find(|n| !names.contains(n)) {
+                return Err(MissingMeasurementNameError(n.clone()));
+            }
+            Some(names.iter().map(|n| ns.contains(n)).collect())
+        } else {
+            None
+        };
+        Ok(DataSelection {
+            columns,
+            rows: sel.event_range.clone(),
+            sample: sel.subsample,
+        })
+    }
+
+    /// Remove all measurements not in mask without touching DATA.
+    fn retain_measurements_inner(&mut self, mask: &[bool]) {
+        for (i, _) in mask.iter().enumerate().rev().filter(|(_, k)| !**k) {
+            // ASSUME this won't fail since mask has one entry per measurement
+            let _ = self.remove_measurement_by_index_inner(i.into());
+        }
+    }
+
     /// Set all $PnN keywords to list of names.
     ///
     /// The length of the names must match the number of measurements. Any
@@ -2148,6 +3068,55 @@ where
         })
     }
 
+    /// Rename a measurement with a given name.
+    ///
+    /// Measurements without $PnN may be referred to by their default name.
+    /// $TR, $SPILLOVER, and $UNSTAINEDCENTERS will be updated to use the new
+    /// name. Return error if the old name does not exist or the new name is
+    /// not unique. Return pair of old and new name on success.
+    pub fn rename_channel(
+        &mut self,
+        old: &Shortname,
+        new: Shortname,
+    ) -> Result<(Shortname, Shortname), RenameChannelError> {
+        let i = self
+            .all_shortnames()
+            .iter()
+            .position(|n| n == old)
+            .ok_or_else(|| MissingMeasurementNameError(old.clone()))?;
+        self.rename_measurement(i.into(), <M::N as MightHave>::wrap(new))
+            .map_err(|e| e.into())
+    }
+
+    fn reorder_channels_inner(
+        &mut self,
+        order: &[Shortname],
+    ) -> Result<Vec<usize>, ReorderChannelsError> {
+        let names = self.all_shortnames();
+        let is = order
+            .iter()
+            .map(|n| {
+                names
+                    .iter()
+                    .position(|m| m == n)
+                    .ok_or_else(|| MissingMeasurementNameError(n.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let index: Vec<MeasIndex> = is.iter().map(|i| (*i).into()).collect();
+        self.measurements.reorder(&index[..])?;
+        // measurements without $PnN will have a new default name, so update
+        // anything that referred to the old one
+        let mapping = is
+            .iter()
+            .map(|i| names[*i].clone())
+            .zip(self.all_shortnames())
+            .filter(|(old, new)| old != new)
+            .collect();
+        self.metaroot.reassign_all(&mapping);
+        self.metaroot.reorder_indices(&is[..]);
+        Ok(is)
+    }
+
     /// Rename time measurement if it exists
     pub fn rename_temporal(&mut self, name: Shortname) -> Option<Shortname> {
         self.measurements.rename_center(name)
@@ -2247,7 +3216,7 @@ where
     pub fn ranges(&self) -> Vec<Range> {
         self.measurements
             .iter_common_values()
-            .map(|(_, x)| x.range)
+            .map(|(_, x)| x.range.clone())
             .collect()
     }
 
@@ -2256,6 +3225,182 @@ where
         Par(self.measurements.len())
     }
 
+    /// Return a version-agnostic view of all measurements
+    pub fn measurement_views(&self) -> Vec<MeasurementView<'_>> {
+        self.measurements
+            .iter()
+            .map(|(index, e)| {
+                e.both(
+                    |t| MeasurementView {
+                        index,
+                        shortname: Some(&t.key),
+                        longname: t.value.common.longname.as_ref_opt(),
+                        width: t.value.common.width,
+                        range: &t.value.common.range,
+                        is_temporal: true,
+                        filter: None,
+                        power: None,
+                        detector_type: None,
+                        percent_emitted: None,
+                        detector_voltage: None,
+                    },
+                    |o| MeasurementView {
+                        index,
+                        shortname: M::N::as_opt(&o.key),
+                        longname: o.value.common.longname.as_ref_opt(),
+                        width: o.value.common.width,
+                        range: &o.value.common.range,
+                        is_temporal: false,
+                        filter: o.value.filter.as_ref_opt(),
+                        power: o.value.power.as_ref_opt(),
+                        detector_type: o.value.detector_type.as_ref_opt(),
+                        percent_emitted: o.value.percent_emitted.as_ref_opt(),
+                        detector_voltage: o.value.detector_voltage.as_ref_opt(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Return a version-agnostic table of all measurements
+    pub fn meas_table(&self) -> Vec<MeasurementRow> {
+        self.measurements
+            .iter()
+            .map(|(index, e)| {
+                e.both(
+                    |t| MeasurementRow {
+                        index,
+                        shortname: Some(t.key.clone()),
+                        longname: t.value.common.longname.as_ref_opt().cloned(),
+                        width: t.value.common.width,
+                        range: t.value.common.range.clone(),
+                        is_temporal: true,
+                        timestep: t.value.specific.timestep(),
+                        scale: None,
+                        gain: None,
+                        wavelengths: vec![],
+                        filter: None,
+                        power: None,
+                        detector_type: None,
+                        percent_emitted: None,
+                        detector_voltage: None,
+                    },
+                    |o| {
+                        let params = o.value.specific.scale_params();
+                        MeasurementRow {
+                            index,
+                            shortname: M::N::as_opt(&o.key).cloned(),
+                            longname: o.value.common.longname.as_ref_opt().cloned(),
+                            width: o.value.common.width,
+                            range: o.value.common.range.clone(),
+                            is_temporal: false,
+                            timestep: None,
+                            scale: params.scale,
+                            gain: params.gain,
+                            wavelengths: o.value.specific.wavelengths(),
+                            filter: o.value.filter.as_ref_opt().cloned(),
+                            power: o.value.power.as_ref_opt().cloned(),
+                            detector_type: o.value.detector_type.as_ref_opt().cloned(),
+                            percent_emitted: o.value.percent_emitted.as_ref_opt().cloned(),
+                            detector_voltage: o.value.detector_voltage.as_ref_opt().cloned(),
+                        }
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Return nonstandard keywords attached to each measurement.
+    pub fn nonstandard_claims(&self) -> Vec<NonStdClaim> {
+        let ms = &self.measurements;
+        ms.iter_common_values::<CommonMeasurement>()
+            .zip(ms.iter_all_names())
+            .filter(|((_, x), _)| !x.nonstandard_keywords.is_empty())
+            .map(|((index, x), shortname)| {
+                let mut keys: Vec<_> = x.nonstandard_keywords.keys().cloned().collect();
+                keys.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+                NonStdClaim {
+                    index,
+                    shortname,
+                    keys,
+                }
+            })
+            .collect()
+    }
+
+    /// Check limits of this version, assuming this will be written as is.
+    fn check_own_version_limits(&self) -> BiTentative<(), VersionLimitError>
+    where
+        D: EventCount,
+    {
+        let nevents = self.data.event_count();
+        let data_begin = self.data_begin(Tot(nevents.unwrap_or(0)), vec![], vec![], None);
+        self.check_version_limits_inner(M::O::fcs_version(), nevents, data_begin)
+    }
+
+    /// Check limits of another version.
+    ///
+    /// 'nevents' is the number of events to be written, if known, and
+    /// 'data_begin' is the offset at which DATA will start.
+    fn check_version_limits_inner(
+        &self,
+        to: Version,
+        nevents: Option<usize>,
+        data_begin: u64,
+    ) -> BiTentative<(), VersionLimitError> {
+        let mut ws = vec![];
+        let mut es = vec![];
+        let par = self.par().0;
+        let is_ascii = self.metaroot.datatype == AlphaNumType::Ascii;
+
+        // Many 2.0 readers assume $Pn* indices have no more than 2 digits;
+        // this is not in the standard so only warn
+        if to == Version::FCS2_0 && par > MAX_PAR_2_0 {
+            ws.extend((MAX_PAR_2_0..par).map(|i| VersionLimitError::IndexDigits(i.into())));
+        }
+
+        // ASCII measurements are backed by u64 and thus may have no more than
+        // 20 digits.
+        let widths = self.widths();
+        if is_ascii {
+            for (i, w) in widths.iter().enumerate() {
+                if let Width::Fixed(x) = w
+                    && Chars::try_from(*x).is_err()
+                {
+                    es.push(VersionLimitError::AsciiWidth(i.into(), *w));
+                }
+            }
+        }
+
+        // 2.0 only has HEADER to store DATA offsets, so DATA must end at or
+        // before the maximum HEADER offset.
+        if to == Version::FCS2_0
+            && let Some(events) = nevents
+        {
+            let event_width: Option<u64> = widths
+                .iter()
+                .map(|w| match w {
+                    Width::Fixed(x) => {
+                        let n = u64::from(u8::from(*x));
+                        Some(if is_ascii { n } else { n / 8 })
+                    }
+                    Width::Variable => None,
+                })
+                .sum();
+            if let Some(w) = event_width.filter(|x| *x > 0) {
+                let max_events = (u64::from(MAX_HEADER_OFFSET) + 1).saturating_sub(data_begin) / w;
+                if events as u64 > max_events {
+                    es.push(VersionLimitError::TooManyEvents {
+                        events,
+                        max_events: max_events as usize,
+                    });
+                }
+            }
+        }
+
+        Tentative::new((), ws, es)
+    }
+
     /// Convert to another FCS version.
     ///
     /// Conversion may fail if some required keywords in the target version
@@ -2321,6 +3466,90 @@ where
             })
     }
 
+    /// Summarize what would happen if this were converted to another version.
+    ///
+    /// Only TEXT is converted, so DATA is not copied. Limits of the target
+    /// version are checked using the number of events in DATA, if any.
+    fn compatibility_to<ToM>(&self) -> VersionCompatibility
+    where
+        Metaroot<M>: Clone,
+        NamedVec<M::N, <M::N as MightHave>::Wrapper<Shortname>, Temporal<M::T>, Optical<M::O>>:
+            Clone,
+        D: EventCount,
+        M::N: Clone,
+        ToM: VersionedMetaroot,
+        ToM: ConvertFromMetaroot<M>,
+        ToM::O: VersionedOptical,
+        ToM::T: VersionedTemporal,
+        ToM::N: MightHave,
+        ToM::N: Clone,
+        ToM::O: ConvertFromOptical<M::O>,
+        ToM::T: ConvertFromTemporal<M::T>,
+        <ToM::N as MightHave>::Wrapper<Shortname>: TryFrom<<M::N as MightHave>::Wrapper<Shortname>>,
+        VersionedConvertError<M::N, ToM::N>: fmt::Display,
+    {
+        let nevents = self.data.event_count();
+        let text: VersionedCoreTEXT<M> =
+            CoreTEXT::new_unchecked(self.metaroot.clone(), self.measurements.clone());
+        let (ws, es) = match text.try_convert::<ToM>(false) {
+            Ok(tnt) => {
+                let (x, ws, es) = tnt.into_parts();
+                let mut warnings: Vec<_> = ws.iter().map(|w| w.to_string()).collect();
+                let mut errors: Vec<_> = es.iter().map(|e| e.to_string()).collect();
+                let data_begin = x.data_begin(Tot(nevents.unwrap_or(0)), vec![], vec![], None);
+                let (_, lws, les) = x
+                    .check_version_limits_inner(ToM::O::fcs_version(), nevents, data_begin)
+                    .into_parts();
+                warnings.extend(lws.iter().map(|w| w.to_string()));
+                errors.extend(les.iter().map(|e| e.to_string()));
+                (warnings, errors)
+            }
+            Err(f) => {
+                let (_, ws, es) = f.unfail_with(()).into_parts();
+                (
+                    ws.iter().map(|w| w.to_string()).collect(),
+                    es.iter().map(|e| e.to_string()).collect(),
+                )
+            }
+        };
+        VersionCompatibility {
+            version: ToM::O::fcs_version(),
+            warnings: ws,
+            errors: es,
+        }
+    }
+
+    /// Convert to another version and wrap the result in [AnyCore].
+    fn convert_to<ToM>(
+        self,
+        force: bool,
+    ) -> DeferredResult<AnyCore<A, D, O>, MetarootConvertWarning, AnyConvertError>
+    where
+        M::N: Clone,
+        ToM: VersionedMetaroot,
+        ToM: ConvertFromMetaroot<M>,
+        ToM::O: VersionedOptical,
+        ToM::T: VersionedTemporal,
+        ToM::N: MightHave,
+        ToM::N: Clone,
+        ToM::O: ConvertFromOptical<M::O>,
+        ToM::T: ConvertFromTemporal<M::T>,
+        <ToM::N as MightHave>::Wrapper<Shortname>: TryFrom<<M::N as MightHave>::Wrapper<Shortname>>,
+        AnyConvertError: From<VersionedConvertError<M::N, ToM::N>>,
+        AnyCore<A, D, O>: From<VersionedCore<A, D, O, ToM>>,
+        D: EventCount,
+    {
+        self.try_convert::<ToM>(force)
+            .def_map_errors(|e| e.into())
+            .def_and_tentatively(|x| {
+                let (_, ws, es) = x.check_own_version_limits().into_parts();
+                let mut tnt = Tentative::new1(x.into());
+                tnt.extend_warnings(ws.into_iter().map(MetarootConvertWarning::Limit).collect());
+                tnt.extend_errors(es.into_iter().map(AnyConvertError::Limit).collect());
+                tnt
+            })
+    }
+
     #[allow(clippy::type_complexity)]
     fn remove_measurement_by_name_inner(
         &mut self,
@@ -2340,10 +3569,14 @@ where
         index: MeasIndex,
     ) -> Result<EitherPair<M::N, Temporal<M::T>, Optical<M::O>>, ElementIndexError> {
         let res = self.measurements.remove_index(index)?;
-        if let Element::NonCenter(left) = &res {
-            if let Some(n) = M::N::as_opt(&left.key) {
-                self.metaroot.remove_name_index(n, index);
-            }
+        let name = match &res {
+            Element::Center(c) => Some(&c.key),
+            Element::NonCenter(nc) => M::N::as_opt(&nc.key),
+        };
+        if let Some(n) = name {
+            self.metaroot.remove_name_index(n, index);
+        } else {
+            self.metaroot.remove_index(index);
         }
         Ok(res)
     }
@@ -2383,22 +3616,53 @@ where
         Ok(())
     }
 
+    /// Return the offset at which DATA would begin if written.
+    ///
+    /// This assumes DATA immediately follows TEXT, which is the case for all
+    /// files written by this library.
+    fn data_begin(
+        &self,
+        tot: Tot,
+        other_lens: Vec<u64>,
+        extra: Vec<(String, String)>,
+        stats_prefix: Option<&str>,
+    ) -> u64 {
+        let order = KeywordOrder::default();
+        self.header_and_raw_keywords(tot, 0, 0, other_lens, order, extra, stats_prefix)
+            .ok()
+            .and_then(|x| x.header.text.inner.try_next_byte())
+            .unwrap_or(u64::from(HEADER_LEN))
+    }
+
+    /// Make HEADER and TEXT keywords to be written.
+    ///
+    /// 'extra' keywords will replace any existing keywords with the same key.
+    /// If 'stats_prefix' is given, any existing column stats keywords with
+    /// this prefix will be dropped, since these are assumed to be stale.
+    #[allow(clippy::too_many_arguments)]
     fn header_and_raw_keywords(
         &self,
         tot: Tot,
         data_len: u64,
         analysis_len: u64,
         other_lens: Vec<u64>,
+        order: KeywordOrder,
+        extra: Vec<(String, String)>,
+        stats_prefix: Option<&str>,
     ) -> Result<HeaderKeywordsToWrite, Uint8DigitOverflow> {
-        let req: Vec<_> = self
+        let req_meta: Vec<_> = self
             .req_meta_keywords()
             .chain([ReqMetarootKey::pair(&tot)])
-            .chain(self.req_meas_keywords())
-            .collect();
-        let opt: Vec<_> = self
-            .opt_meta_keywords()
-            .chain(self.opt_meas_keywords())
             .collect();
+        let req = order_keywords(req_meta, self.req_meas_keywords().collect(), order);
+        let keep = |k: &String| {
+            extra.iter().all(|(e, _)| e != k)
+                && stats_prefix.is_none_or(|p| !is_column_stats_key(p, k))
+        };
+        let mut opt_meta: Vec<_> = self.opt_meta_keywords().filter(|(k, _)| keep(k)).collect();
+        let opt_meas: Vec<_> = self.opt_meas_keywords().filter(|(k, _)| keep(k)).collect();
+        opt_meta.extend(extra);
+        let opt = order_keywords(opt_meta, opt_meas, order);
         if M::O::fcs_version() == Version::FCS2_0 {
             make_data_offset_keywords_2_0(req, opt, data_len, analysis_len, other_lens)
         } else {
@@ -2455,7 +3719,7 @@ where
             .map(|(i, n)| (Shortname::std(i.into()).to_string(), n.to_string()))
     }
 
-    fn meas_table(&self, delim: &str) -> Vec<String>
+    fn meas_table_lines(&self, delim: &str) -> Vec<String>
     where
         M::T: Clone,
         M::O: OpticalFromTemporal<M::T>,
@@ -2493,7 +3757,7 @@ where
         M::T: Clone,
         M::O: OpticalFromTemporal<M::T>,
     {
-        for e in self.meas_table(delim) {
+        for e in self.meas_table_lines(delim) {
             println!("{}", e);
         }
     }
@@ -2514,35 +3778,41 @@ where
         M::T: LookupTemporal,
         M::O: LookupOptical,
     {
-        // Use nonstandard measurement pattern to assign keyvals to their
-        // measurement if they match. Only capture one warning because if the
-        // pattern is wrong for one measurement it is probably wrong for all of
-        // them.
-        let tnt = if let Some(pat) = conf.nonstandard_measurement_pattern.as_ref() {
+        // Use nonstandard measurement patterns to assign keyvals to their
+        // measurement if they match. Only capture one warning per pattern
+        // because if the pattern is wrong for one measurement it is probably
+        // wrong for all of them.
+        let mut pat_ws = vec![];
+        let mut pss = vec![];
+        for pat in conf.nonstandard_measurement_patterns.iter() {
             let res = (0..par.0)
                 .map(|n| pat.from_index(n.into()))
                 .collect::<Result<Vec<_>, _>>();
             match res {
-                Ok(ps) => {
-                    let mut meta_nonstd = vec![];
-                    let mut meas_nonstds = vec![vec![]; par.0];
-                    for (k, v) in nonstd {
-                        if let Some(j) = ps.iter().position(|p| p.is_match(k.as_ref())) {
-                            meas_nonstds[j].push((k, v));
-                        } else {
-                            meta_nonstd.push((k, v));
-                        }
-                    }
-                    Tentative::new1((meta_nonstd, meas_nonstds))
-                }
-                Err(w) => Tentative::new((nonstd, vec![vec![]; par.0]), vec![w.into()], vec![]),
+                Ok(ps) => pss.push(ps),
+                Err(w) => pat_ws.push(w.into()),
             }
-        } else {
-            Tentative::new1((nonstd, vec![vec![]; par.0]))
-        };
+        }
+        let mut meta_kws = vec![];
+        let mut meas_kws = vec![vec![]; par.0];
+        for (k, v) in nonstd {
+            if let Some(j) = pss
+                .iter()
+                .find_map(|ps| ps.iter().position(|p| p.is_match(k.as_ref())))
+            {
+                meas_kws[j].push((k, v));
+            } else {
+                meta_kws.push((k, v));
+            }
+        }
+        let nonstd_tnt = Tentative::new((meta_kws, meas_kws), pat_ws, vec![]);
+
+        // If any measurement has $PnTYPE set to "Time" (3.2+), use the first
+        // one as the time measurement regardless of the time pattern.
+        let typed_time = (0..par.0).find(|n| M::T::has_temporal_type(kws, (*n).into()));
 
         // then iterate over each measurement and look for standardized keys
-        tnt.and_maybe(|(meta_nonstd, meas_nonstds)| {
+        nonstd_tnt.and_maybe(|(meta_nonstd, meas_nonstds)| {
             meas_nonstds
                 .into_iter()
                 .enumerate()
@@ -2552,7 +3822,22 @@ where
                     // totally fail if not found since this is required. If it
                     // does exist, also check if it matches the time pattern and
                     // use it as the time measurement if it does.
-                    M::lookup_shortname(kws, i).def_and_maybe(|wrapped| {
+                    let name_res = M::lookup_shortname(kws, i);
+                    let tnt = match name_res {
+                        Ok(tnt) => tnt,
+                        // If $PnN is missing or invalid, we can't tell if this
+                        // is the time measurement, but still look up the rest
+                        // as if it were optical so that all problems with this
+                        // measurement are reported at once.
+                        Err(f) => {
+                            let o = Optical::<M::O>::lookup_optical(kws, i, meas_nonstd, conf);
+                            return Err(match o {
+                                Ok(t) => f.absorb(t),
+                                Err(g) => f.mappend(g),
+                            });
+                        }
+                    };
+                    tnt.and_maybe(|wrapped| {
                         // TODO if more than one name matches the time pattern
                         // this will give a cryptic "cannot find $TIMESTEP" for
                         // each subsequent match, which is not helpful. Probably
@@ -2561,12 +3846,19 @@ where
                         // will know it is trying to find $TIMESTEP in a
                         // nonsense measurement.
                         let key = M::N::unwrap(wrapped).and_then(|name| {
-                            if let Some(tp) = conf.time.pattern.as_ref() {
-                                if tp.0.as_inner().is_match(name.as_ref()) {
-                                    return Ok(name);
-                                }
+                            let is_time = match typed_time {
+                                Some(j) => j == n,
+                                None => conf
+                                    .time
+                                    .pattern
+                                    .as_ref()
+                                    .is_some_and(|tp| tp.0.as_inner().is_match(name.as_ref())),
+                            };
+                            if is_time {
+                                Ok(name)
+                            } else {
+                                Err(M::N::wrap(name))
                             }
-                            Err(M::N::wrap(name))
                         });
                         // Once we checked $PnN, pull all the rest of the
                         // standardized keywords from the hashtable and collect
@@ -2579,8 +3871,18 @@ where
                             // channel to optical channel, which is more general
                             Ok(name) => Temporal::lookup_temporal(kws, i, meas_nonstd)
                                 .def_map_value(|t| Element::Center((name, t))),
-                            Err(k) => Optical::lookup_optical(kws, i, meas_nonstd, conf)
-                                .def_map_value(|m| Element::NonCenter((k, m))),
+                            Err(k) => {
+                                let forced = M::N::as_opt(&k)
+                                    .filter(|x| conf.force_linear_scale.contains(x))
+                                    .and_then(|_| Scale::force_linear(kws, i));
+                                let mut optical =
+                                    Optical::lookup_optical(kws, i, meas_nonstd, conf)
+                                        .def_map_value(|m| Element::NonCenter((k, m)));
+                                if let Some(w) = forced {
+                                    optical.def_push_warning(w.into());
+                                }
+                                optical
+                            }
                         }
                     })
                 })
@@ -2671,6 +3973,22 @@ where
     M: VersionedMetaroot,
     M::N: Clone,
 {
+    /// Check if this would violate limits of another FCS version.
+    ///
+    /// Since there is no DATA, limits on the number of events will be checked
+    /// using the given value, which would normally be $TOT. The size of TEXT
+    /// is estimated using the current version, so this may be slightly more
+    /// strict than necessary. Limits which are only conventions of common
+    /// readers rather than the standard will be returned as warnings.
+    pub fn check_version_limits(
+        &self,
+        to: Version,
+        tot: Tot,
+    ) -> BiTentative<(), VersionLimitError> {
+        let data_begin = self.data_begin(tot, vec![], vec![], None);
+        self.check_version_limits_inner(to, Some(tot.0), data_begin)
+    }
+
     /// Make a new CoreTEXT from raw keywords.
     ///
     /// Return any errors encountered, including messing required keywords,
@@ -2685,8 +4003,11 @@ where
         M::T: LookupTemporal,
         M::O: LookupOptical,
     {
+        // Replace $PAR if it disagrees with the measurements actually present
+        let inferred = if conf.infer_par { infer_par(kws) } else { None };
+
         // Lookup $PAR first since we need this to get the measurements
-        Par::lookup_req(kws).def_inner_into().def_and_maybe(|par| {
+        let mut res = Par::lookup_req(kws).def_inner_into().def_and_maybe(|par| {
             // $NEXTDATA/$BEGINSTEXT/$ENDSTEXT should have already been
             // processed when we read the TEXT; remove them so they don't
             // trigger false positives later when we test for pseudostandard keys
@@ -2694,14 +4015,35 @@ where
             let _ = kws.remove(&Beginstext::std());
             let _ = kws.remove(&Endstext::std());
 
-            // Lookup measurements and metaroot with $PAR
+            // Get all $PnN up front (without consuming them) so that metaroot
+            // keywords which reference measurements can still be checked if
+            // the measurements themselves fail.
+            let raw_names: Vec<Shortname> = (0..par.0)
+                .flat_map(|i| kws.get(&Shortname::std(i.into())))
+                .flat_map(|v| v.parse().ok())
+                .collect();
+
+            // Lookup measurements and metaroot with $PAR. Lookup metaroot
+            // regardless of whether measurements succeed so that all errors
+            // are reported at once.
             let ns: Vec<_> = nonstd.into_iter().collect();
-            let mut tnt_core =
-                Self::lookup_measurements(kws, par, ns, conf).def_and_maybe(|(ms, meta_ns)| {
-                    Metaroot::lookup_metaroot(kws, &ms, meta_ns, conf)
+            let mut tnt_core = match Self::lookup_measurements(kws, par, ns, conf) {
+                Ok(tnt) => tnt.and_maybe(|(ms, meta_ns)| {
+                    let names = ms.indexed_names().map(|(_, n)| n).collect();
+                    Metaroot::lookup_metaroot(kws, par, &names, meta_ns, conf)
                         .def_map_value(|metaroot| CoreTEXT::new_unchecked(metaroot, ms))
                         .def_warnings_into()
-                })?;
+                }),
+                Err(f) => {
+                    let names = raw_names.iter().collect();
+                    let m = Metaroot::<M>::lookup_metaroot(kws, par, &names, vec![], conf)
+                        .def_warnings_into();
+                    Err(match m {
+                        Ok(t) => f.absorb(t),
+                        Err(g) => f.mappend(g),
+                    })
+                }
+            }?;
 
             // Check that the time measurement is present if we want it
             tnt_core.eval_error(|core| {
@@ -2723,7 +4065,12 @@ where
                     || k == &Beginanalysis::std()
                     || k == &Endanalysis::std()
                     || k == &Tot::std()
-                    || k == &Timestep::std())
+                    || k == &Timestep::std()
+                    || conf.pseudostandard_types.contains_key(k)
+                    || conf
+                        .keyword_registry
+                        .status(k.as_ref(), M::O::fcs_version())
+                        != KeyStatus::Absent)
                 {
                     let e = PseudostandardError(k.clone());
                     if conf.allow_pseudostandard {
@@ -2735,7 +4082,17 @@ where
             }
 
             Ok(tnt_core)
-        })
+        });
+        if let Some(w) = inferred {
+            res.def_push_warning(w.into());
+        }
+        if conf.disallow_deprecated {
+            res.def_promote_warnings(|w| match w {
+                LookupMeasWarning::Parse(LookupKeysWarning::Dep(d)) => Err(d.into()),
+                x => Ok(x),
+            });
+        }
+        res
     }
 
     /// Remove a measurement matching the given name.
@@ -2760,6 +4117,18 @@ where
         self.remove_measurement_by_index_inner(index)
     }
 
+    /// Reorder measurements by name.
+    ///
+    /// 'order' must include each name exactly once; measurements without $PnN
+    /// may be referred to by their default name. $Pn* keywords will be
+    /// renumbered to match the new order, and any keywords which refer to
+    /// measurements by name or index will be updated. Return error if a name
+    /// does not exist or if measurements without $PnN would get default names
+    /// that are not unique.
+    pub fn reorder_channels(&mut self, order: &[Shortname]) -> Result<(), ReorderChannelsError> {
+        self.reorder_channels_inner(order).map(|_| ())
+    }
+
     /// Add time measurement to the end of the measurement vector.
     ///
     /// Return error if time measurement already exists or name is non-unique.
@@ -2826,6 +4195,33 @@ where
         Ok(self.into_coredataset_unchecked(data, analysis, others))
     }
 
+    /// Make new CoreDataset from CoreTEXT with no events.
+    ///
+    /// DATA will have one empty column per measurement with the type given by
+    /// the data layout, and ANALYSIS and OTHER will be empty. If the layout is
+    /// invalid, columns will be 64-bit integers. Writing the result will
+    /// produce a TEXT-only file with $TOT=0 and an empty DATA segment.
+    pub fn into_coredataset_empty(self) -> VersionedCoreDataset<M>
+    where
+        M::L: VersionedDataLayout,
+    {
+        let par = self.par().0;
+        let data = self
+            .as_data_layout(&SharedConfig::default())
+            .ok()
+            .and_then(|tnt| tnt.into_parts().0.empty_dataframe())
+            .filter(|df| df.ncols() == par)
+            .unwrap_or_else(|| {
+                let columns = (0..par)
+                    .map(|_| FCSColumn::<u64>::from(vec![]).into())
+                    .collect();
+                // ASSUME this will never fail since all columns are the same
+                // length
+                FCSDataFrame::try_new(columns).unwrap()
+            });
+        self.into_coredataset_unchecked(data, Analysis(vec![]), Others::default())
+    }
+
     pub(crate) fn into_coredataset_unchecked(
         self,
         data: FCSDataFrame,
@@ -2848,6 +4244,206 @@ where
     M::N: Clone,
     M::L: VersionedDataLayout,
 {
+    /// Check if this dataset would violate limits of another FCS version.
+    ///
+    /// These limits are also checked when converting and writing, so this is
+    /// only needed to check a version before committing to it. Limits which
+    /// are only conventions of common readers will be returned as warnings.
+    pub fn check_version_limits(&self, to: Version) -> BiTentative<(), VersionLimitError> {
+        let nevents = self.data.nrows();
+        let other_lens = self.others.0.iter().map(|x| x.0.len() as u64).collect();
+        let data_begin = self.data_begin(Tot(nevents), other_lens, vec![], None);
+        self.check_version_limits_inner(to, Some(nevents), data_begin)
+    }
+
+    /// Return a new dataset with events from other datasets appended.
+    ///
+    /// Each dataset must have the same set of $PnN, and each measurement must
+    /// have the same $PnB, $PnE, and datatype ($PnDATATYPE or $DATATYPE if not
+    /// given) in all datasets. The time measurement must also have the same
+    /// $TIMESTEP. Columns in other datasets are matched to this one by $PnN,
+    /// so their order may differ. All other keywords are taken from this
+    /// dataset, and ANALYSIS and OTHER are dropped since they cannot be merged.
+    ///
+    /// Provenance will be recorded in non-standard keywords (see
+    /// [`CONCAT_EVENTS_KEY`] and [`CONCAT_FILE_PREFIX`]).
+    pub fn concat(&self, others: &[&Self]) -> Result<Self, ConcatError>
+    where
+        Self: Clone,
+    {
+        let names = self.all_shortnames();
+        let params = self.concat_params();
+        let datatype_key = if M::O::fcs_version() == Version::FCS3_2 {
+            "$PnDATATYPE"
+        } else {
+            "$DATATYPE"
+        };
+        let mut orders = vec![];
+        for (i, o) in others.iter().enumerate() {
+            let other_names = o.all_shortnames();
+            let order = names
+                .iter()
+                .map(|n| other_names.iter().position(|m| m == n))
+                .collect::<Option<Vec<_>>>()
+                .filter(|_| other_names.len() == names.len())
+                .ok_or(ConcatError::Names(i))?;
+            let other_params = o.concat_params();
+            for (k, j) in order.iter().enumerate() {
+                let (x, y) = (&params[k], &other_params[*j]);
+                let mismatch = if x.width != y.width {
+                    Some("$PnB")
+                } else if x.scale != y.scale {
+                    Some("$PnE")
+                } else if x.datatype != y.datatype {
+                    Some(datatype_key)
+                } else if x.timestep != y.timestep {
+                    Some("$TIMESTEP")
+                } else {
+                    None
+                };
+                if let Some(key) = mismatch {
+                    return Err(ConcatError::Incompatible {
+                        index: i,
+                        name: names[k].clone(),
+                        key,
+                    });
+                }
+            }
+            orders.push((&o.data, order));
+        }
+        let mut new = self.clone();
+        new.data = self.data.concat_rows(&orders[..]);
+        new.analysis = Analysis(vec![]);
+        new.others = Others::default();
+        let nevents = [&self.data]
+            .into_iter()
+            .chain(others.iter().map(|o| &o.data))
+            .map(|d| d.nrows())
+            .join(",");
+        let files: Vec<_> = [self]
+            .into_iter()
+            .chain(others.iter().copied())
+            .map(|o| o.metaroot.fil.as_ref_opt().map(|f| f.0.clone()))
+            .collect();
+        let kws = &mut new.metaroot.nonstandard_keywords;
+        kws.retain(|k, _| !is_concat_file_key(k.as_ref()));
+        kws.insert(NonStdKey::from_unchecked(CONCAT_EVENTS_KEY), nevents);
+        for (n, f) in files.into_iter().enumerate() {
+            if let Some(fil) = f {
+                let k = format!("{CONCAT_FILE_PREFIX}{}", n + 1);
+                kws.insert(NonStdKey::into_unchecked(k), fil);
+            }
+        }
+        Ok(new)
+    }
+
+    fn concat_params(&self) -> Vec<ConcatParams> {
+        let datatype = self.metaroot.datatype;
+        self.measurements
+            .iter()
+            .map(|(_, e)| {
+                e.both(
+                    |t| ConcatParams {
+                        width: t.value.common.width,
+                        scale: None,
+                        datatype: t.value.specific.datatype().map_or(datatype, Into::into),
+                        timestep: t.value.specific.timestep(),
+                    },
+                    |o| ConcatParams {
+                        width: o.value.common.width,
+                        scale: o.value.specific.scale_params().scale,
+                        datatype: o.value.specific.datatype().map_or(datatype, Into::into),
+                        timestep: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Return copies of this dataset with events in each of the given ranges.
+    ///
+    /// Ranges are indexed from 0 and exclude their end; they may overlap and
+    /// need not be in order. All keywords, ANALYSIS, and OTHER will be copied
+    /// to each new dataset, and the range will be recorded in [`SPLIT_KEY`].
+    /// $TOT and offsets will be computed from the new DATA when writing.
+    pub fn split_ranges(&self, ranges: &[ops::Range<usize>]) -> Result<Vec<Self>, SplitError>
+    where
+        Self: Clone,
+    {
+        let n = self.data.nrows();
+        ranges
+            .iter()
+            .map(|r| {
+                if r.start > r.end || r.end > n {
+                    return Err(SplitError::Range(r.clone(), n));
+                }
+                let rows: Vec<_> = r.clone().collect();
+                let value = if r.is_empty() {
+                    format!("0/{n}")
+                } else {
+                    format!("{}-{}/{n}", r.start + 1, r.end)
+                };
+                Ok(self.with_rows(&rows[..], value))
+            })
+            .collect()
+    }
+
+    /// Return copies of this dataset with and without events in a mask.
+    ///
+    /// The mask must have one entry for each event, and the first dataset will
+    /// have those which are true. See [`Self::split_ranges`] for how other
+    /// data is copied.
+    pub fn split_mask(&self, mask: &[bool]) -> Result<(Self, Self), SplitError>
+    where
+        Self: Clone,
+    {
+        let n = self.data.nrows();
+        if mask.len() != n {
+            return Err(SplitError::MaskLength(mask.len(), n));
+        }
+        let (xs, ys): (Vec<_>, Vec<_>) = (0..n).partition(|i| mask[*i]);
+        let x = self.with_rows(&xs[..], format!("{}/{n}", xs.len()));
+        let y = self.with_rows(&ys[..], format!("{}/{n}", ys.len()));
+        Ok((x, y))
+    }
+
+    fn with_rows(&self, rows: &[usize], split: String) -> Self
+    where
+        Self: Clone,
+    {
+        let mut new = self.clone();
+        new.data = self.data.select_rows(rows);
+        new.metaroot
+            .nonstandard_keywords
+            .insert(NonStdKey::from_unchecked(SPLIT_KEY), split);
+        new
+    }
+
+    /// Return a copy of this dataset with a subset of its events.
+    ///
+    /// Events will remain in their original order. $TOT will be updated when
+    /// writing since it is computed from DATA. The subsample will be recorded
+    /// in non-standard keywords (see ['SUBSAMPLE_KEY'] and
+    /// ['SUBSAMPLE_METHOD_KEY']).
+    pub fn subsample(&self, size: SubsampleSize, method: SubsampleMethod) -> Self
+    where
+        Self: Clone,
+    {
+        let n = self.data.nrows();
+        let k = size.nevents(n);
+        let rows = method.select(n, k);
+        let mut new = self.clone();
+        new.data = self.data.select_rows(&rows[..]);
+        let kws = &mut new.metaroot.nonstandard_keywords;
+        kws.insert(NonStdKey::from_unchecked(SUBSAMPLE_KEY), format!("{k}/{n}"));
+        kws.insert(
+            NonStdKey::from_unchecked(SUBSAMPLE_METHOD_KEY),
+            method.to_string(),
+        );
+        new
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new_dataset_from_raw<R: Read + Seek>(
         h: &mut BufReader<R>,
         kws: &mut StdKeywords,
@@ -2856,6 +4452,7 @@ where
         analysis_seg: HeaderAnalysisSegment,
         other_segs: &[OtherSegment],
         conf: &DataReadConfig,
+        scratch: &mut ParserScratch,
         // TODO wrap this in a nice struct
     ) -> IODeferredResult<
         (Self, AnyDataSegment, AnyAnalysisSegment),
@@ -2870,7 +4467,7 @@ where
         CoreTEXT::new_text_from_raw(kws, nonstd, &conf.standard)
             .def_inner_into()
             .def_errors_liftio()
-            .def_and_maybe(|text| {
+            .def_and_maybe(|mut text| {
                 text.as_data_layout(&conf.shared)
                     .def_inner_into()
                     .def_errors_liftio()
@@ -2883,87 +4480,647 @@ where
                             M::L::as_analysis_reader(kws, analysis_seg, &conf.reader)
                                 .def_inner_into()
                                 .def_errors_liftio();
-                        data_res.def_zip(analysis_res).def_and_maybe(|(dr, ar)| {
-                            let or = OthersReader { segs: other_segs };
-                            h_read_data_and_analysis(h, dr, ar, or)
-                                .map(|(data, analysis, others, d_seg, a_seg)| {
-                                    let c = Core {
-                                        metaroot: text.metaroot,
-                                        measurements: text.measurements,
-                                        data,
-                                        analysis,
-                                        others,
-                                    };
-                                    (c, d_seg, a_seg)
-                                })
-                                .into_deferred::<_, StdDatasetFromRawWarning>()
-                                .def_io_into()
-                        })
+                        let sel_res = text
+                            .data_selection(&conf.reader.selection)
+                            .into_deferred::<StdDatasetFromRawError, _>()
+                            .def_errors_liftio();
+                        data_res
+                            .def_zip(analysis_res)
+                            .def_zip(sel_res)
+                            .def_and_maybe(|((dr, ar), sel)| {
+                                let or = OthersReader { segs: other_segs };
+                                let mask = sel.columns.clone();
+                                h_read_data_and_analysis(h, dr.with_selection(sel), ar, or, scratch)
+                                    .into_deferred::<_, StdDatasetFromRawWarning>()
+                                    .def_io_into()
+                                    .def_and_tentatively(
+                                        |(data, analysis, others, d_seg, a_seg, read_ws)| {
+                                            if let Some(m) = mask {
+                                                text.retain_measurements_inner(&m);
+                                            }
+                                            let c = Core {
+                                                metaroot: text.metaroot,
+                                                measurements: text.measurements,
+                                                data,
+                                                analysis,
+                                                others,
+                                            };
+                                            let ws = read_ws
+                                                .into_iter()
+                                                .filter(|w| {
+                                                    conf.reader.warn_overrange || !w.is_overrange()
+                                                })
+                                                .map(|w| w.into())
+                                                .collect();
+                                            Tentative::new((c, d_seg, a_seg), ws, vec![])
+                                        },
+                                    )
+                            })
                     })
             })
     }
 
-    /// Write this dataset (HEADER+TEXT+DATA+ANALYSIS+OTHER) to a handle
-    pub fn h_write<W: Write>(
+    /// Write this dataset (HEADER+TEXT+DATA+ANALYSIS+OTHER) to a handle
+    pub fn h_write<W: Write>(
+        &self,
+        h: &mut BufWriter<W>,
+        conf: &WriteConfig,
+    ) -> IODeferredResult<(), NewDataLayoutWarning, StdWriterError> {
+        self.h_write_with_data(h, conf, None)
+    }
+
+    /// Write this dataset to a handle, copying DATA verbatim if possible.
+    ///
+    /// If 'raw_data' is given, is exactly as long as DATA would be if
+    /// serialized from the dataframe, and decodes to the same values as the
+    /// dataframe with the current layout, write it in place of the dataframe.
+    /// This allows DATA read from a file to be written byte-for-byte, which
+    /// otherwise may not be the case (for instance, ASCII values which are
+    /// padded differently). If the dataframe or layout has changed such that
+    /// any of these do not hold, DATA will be serialized as usual.
+    ///
+    /// TEXT will always be serialized from the keywords, and ANALYSIS and OTHER
+    /// will always be written as-is.
+    pub fn h_write_with_data<W: Write>(
+        &self,
+        h: &mut BufWriter<W>,
+        conf: &WriteConfig,
+        raw_data: Option<&[u8]>,
+    ) -> IODeferredResult<(), NewDataLayoutWarning, StdWriterError> {
+        let df = &self.data;
+        let others = &self.others;
+        let delim = conf.delim.inner();
+        let tot = Tot(df.nrows());
+        let analysis_len = self.analysis.0.len() as u64;
+        let other_lens: Vec<_> = others.0.iter().map(|o| o.0.len() as u64).collect();
+        let stats_prefix = conf.column_stats_prefix.as_deref();
+        let mut stats = stats_prefix.map_or(vec![], |p| column_stats_keywords(df, p));
+
+        stats_prefix
+            .map_or(Ok(()), |p| ColumnStatsPrefixError::check(p, delim))
+            .into_deferred()
+            .def_and_maybe(|()| self.as_data_layout(&conf.shared).def_errors_into())
+            .def_errors_liftio()
+            .def_and_maybe(|layout| {
+                layout
+                    .as_writer(df, conf)
+                    .mult_to_deferred()
+                    .def_errors_liftio()
+            })
+            .def_and_maybe(|mut writer| {
+                let data_len = writer.nbytes() as u64;
+
+                // serialize DATA up front if it needs to be hashed before TEXT
+                // is written, unless the original bytes can be used
+                let raw = raw_data.filter(|r| {
+                    r.len() as u64 == data_len
+                        && self
+                            .as_data_layout(&conf.shared)
+                            .is_ok_and(|tnt| tnt.into_parts().0.matches_data(r, df))
+                });
+                let serialized = if conf.data_hash && raw.is_none() {
+                    let mut buf = BufWriter::new(vec![]);
+                    writer
+                        .h_write(&mut buf)
+                        .and_then(|()| buf.into_inner().map_err(|e| e.into_error()))
+                        .map(Some)
+                        .map_err(|e| DeferredFailure::new1(ImpureError::IO(e)))?
+                } else {
+                    None
+                };
+                let data_bytes = raw.or(serialized.as_deref());
+                if conf.data_hash {
+                    stats.extend(provenance_keywords(data_bytes.unwrap_or_default()));
+                }
+
+                // check limits before computing offsets, which would otherwise
+                // fail with a less informative overflow; limits which are only
+                // reader conventions were already reported when converting
+                let version = M::O::fcs_version();
+                let data_begin =
+                    self.data_begin(tot, other_lens.clone(), stats.clone(), stats_prefix);
+                let (_, _, limit_errors) = self
+                    .check_version_limits_inner(version, Some(tot.0), data_begin)
+                    .into_parts();
+                if let Some(es) = NonEmpty::from_vec(limit_errors) {
+                    return Err(DeferredFailure::new2(
+                        es.map(|e| ImpureError::Pure(StdWriterError::Limit(e))),
+                    ));
+                }
+
+                let hdr_kws = self
+                    .header_and_raw_keywords(
+                        tot,
+                        data_len,
+                        analysis_len,
+                        other_lens,
+                        conf.keyword_order,
+                        stats,
+                        stats_prefix,
+                    )
+                    .map_err(ImpureError::Pure)
+                    .map_err(|e| e.inner_into())
+                    .map_err(DeferredFailure::new1)?;
+
+                let compute_crc = conf.write_crc && version != Version::FCS2_0;
+
+                let mut go = || {
+                    let mut ch = BufWriter::new(CrcWriter::new(&mut *h, compute_crc));
+
+                    // write HEADER
+                    hdr_kws.header.h_write(&mut ch, version)?;
+
+                    // write OTHER
+                    for o in others.0.iter() {
+                        ch.write_all(&o.0)?;
+                    }
+
+                    // write primary TEXT
+                    hdr_kws.primary.h_write(&mut ch, delim)?;
+
+                    // write supplemental TEXT
+                    if !hdr_kws.supplemental.0.is_empty() {
+                        hdr_kws.supplemental.h_write(&mut ch, delim)?;
+                    }
+
+                    // write DATA, copying original or hashed bytes if present
+                    match data_bytes {
+                        Some(r) => ch.write_all(r)?,
+                        None => writer.h_write(&mut ch)?,
+                    }
+
+                    // write ANALYSIS
+                    ch.write_all(&self.analysis.0)?;
+
+                    // write CRC (if applicable)
+                    ch.flush()?;
+                    if let Some(crc) = ch.get_ref().crc {
+                        ch.write_all(&crc.to_bytes())?;
+                    }
+                    ch.flush()
+                };
+
+                go().into_deferred()
+            })
+    }
+
+    /// Return reference to dataframe representing DATA
+    pub fn data(&self) -> &FCSDataFrame {
+        &self.data
+    }
+
+    /// Return the column in DATA whose $PnN matches 'name'.
+    pub fn column(&self, name: &str) -> Option<&AnyFCSColumn> {
+        let i = self
+            .measurements
+            .iter_all_names()
+            .position(|n| n.as_ref() == name)?;
+        self.data.iter_columns().nth(i)
+    }
+
+    /// Return the column in DATA whose $PnN matches 'name' as a custom type.
+    pub fn column_as<T: CustomColumn>(&self, name: &str) -> Option<Result<T, DecodeColumnError>> {
+        self.column(name).map(T::from_fcs_column)
+    }
+
+    /// Return each column in DATA alongside its $PnN.
+    ///
+    /// Measurements without $PnN (possible in 2.0 and 3.0) will be given
+    /// names using the shortname prefix.
+    pub fn named_columns(&self) -> impl Iterator<Item = (Shortname, &AnyFCSColumn)> {
+        self.measurements
+            .iter_all_names()
+            .zip(self.data.iter_columns())
+    }
+
+    /// Return true for each event whose trigger measurement exceeds $TR.
+    ///
+    /// Return None if $TR is not set or its measurement is not in DATA.
+    pub fn trigger_mask(&self) -> Option<Vec<bool>> {
+        let tr = self.metaroot.tr.as_ref_opt()?;
+        self.column(tr.measurement.as_ref())
+            .map(|c| c.above_threshold(tr.threshold))
+    }
+
+    /// Return subset membership for each event as encoded in ANALYSIS.
+    ///
+    /// Return None if $CSMODE is not set. See [`SubsetData::membership`].
+    pub fn subset_membership(&self) -> Option<Result<Vec<Option<Vec<bool>>>, SubsetDecodeError>> {
+        self.metaroot
+            .specific
+            .as_subset()
+            .map(|(s, e)| s.membership(&self.analysis, e, self.data.nrows()))
+    }
+
+    /// Return DATA with only the events whose trigger measurement exceeds $TR.
+    ///
+    /// See [`Self::trigger_mask`].
+    pub fn triggered_data(&self) -> Option<FCSDataFrame> {
+        self.trigger_mask().map(|mask| {
+            let rows: Vec<_> = mask
+                .into_iter()
+                .enumerate()
+                .filter(|(_, x)| *x)
+                .map(|(i, _)| i)
+                .collect();
+            self.data.select_rows(&rows[..])
+        })
+    }
+
+    /// Return DATA with $SPILLOVER or $COMP/$DFCmTOn applied.
+    ///
+    /// Return None if neither matrix is present. Only one of these can be
+    /// present for any given version.
+    pub fn compensated_data(&self) -> Option<Result<FCSDataFrame, CompensateError>> {
+        let s = &self.metaroot.specific;
+        s.as_spillover()
+            .map(|x| self.apply_spillover(x))
+            .or_else(|| s.as_compensation().map(|x| self.apply_compensation(x)))
+    }
+
+    /// Return DATA with a spillover matrix applied.
+    ///
+    /// Each name in the matrix must match a $PnN. Measurements not in the
+    /// matrix will be returned unchanged; those in the matrix will be
+    /// converted to 64-bit floats.
+    pub fn apply_spillover(&self, s: &Spillover) -> Result<FCSDataFrame, CompensateError> {
+        let names = self.all_shortnames();
+        let mut indices = vec![];
+        let mut unknown = vec![];
+        for m in s.measurements() {
+            match names.iter().position(|n| n == m) {
+                Some(i) => indices.push(i),
+                None => unknown.push(m.clone()),
+            }
+        }
+        if let Some(ns) = NonEmpty::from_vec(unknown) {
+            return Err(CompensateError::UnknownNames(ns));
+        }
+        let inv = s
+            .matrix()
+            .map(f64::from)
+            .try_inverse()
+            .ok_or(CompensateError::Singular)?;
+        Ok(self.data.compensate(&indices, &inv))
+    }
+
+    /// Return DATA with a compensation matrix applied.
+    ///
+    /// The matrix must have the same number of rows and columns as $PAR. All
+    /// measurements will be converted to 64-bit floats.
+    pub fn apply_compensation(&self, c: &Compensation) -> Result<FCSDataFrame, CompensateError> {
+        let par = self.par().0;
+        let n = c.matrix().ncols();
+        if n != par {
+            return Err(CompensateError::WrongSize { matrix: n, par });
+        }
+        // compensation matrices map columns (source) onto rows (target), which
+        // is the transpose of a spillover matrix
+        let inv = c
+            .matrix()
+            .transpose()
+            .map(f64::from)
+            .try_inverse()
+            .ok_or(CompensateError::Singular)?;
+        let indices: Vec<_> = (0..par).collect();
+        Ok(self.data.compensate(&indices, &inv))
+    }
+
+    /// Return event times in seconds.
+    ///
+    /// Times are taken from the time measurement and multiplied by $TIMESTEP,
+    /// which is taken to be 1 if not present (as in 2.0). If 'name' is given,
+    /// the measurement with this $PnN will be used instead, which is useful if
+    /// the time measurement was not identified when standardizing TEXT;
+    /// $TIMESTEP will still be applied if present. Times will be checked
+    /// against 'order'.
+    pub fn time_column(
+        &self,
+        name: Option<&Shortname>,
+        order: TimeOrder,
+    ) -> Result<Vec<f64>, TimeColumnError> {
+        let temporal = self.measurements.as_center();
+        let i = if let Some(n) = name {
+            self.all_shortnames()
+                .iter()
+                .position(|x| x == n)
+                .ok_or_else(|| TimeColumnError::UnknownName(n.clone()))?
+        } else {
+            temporal
+                .as_ref()
+                .map(|t| t.index.into())
+                .ok_or(TimeColumnError::Missing)?
+        };
+        let step = temporal
+            .and_then(|t| t.value.specific.timestep())
+            .map_or(1.0, |ts| f64::from(f32::from(ts.0)));
+        let times: Vec<_> = self
+            .data
+            .iter_columns()
+            .nth(i)
+            .map(|c| c.to_f64s())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|x| x * step)
+            .collect();
+        order.check(&times[..])?;
+        Ok(times)
+    }
+
+    /// Return DATA with values converted to scaled floats.
+    ///
+    /// Each optical measurement will be transformed using $PnE, $PnG, and
+    /// $PnCALIBRATION where present. Measurements whose $PnN is in 'raw' and
+    /// the time measurement will be left untransformed, although all columns
+    /// will be converted to 64-bit floats regardless.
+    pub fn scaled_data(&self, raw: &[Shortname]) -> FCSDataFrame {
+        let columns = self
+            .measurements
+            .iter()
+            .zip(self.all_shortnames())
+            .zip(self.data.iter_columns())
+            .map(|(((_, e), name), c)| {
+                let (params, range) = e.both(
+                    |_| (None, None),
+                    |o| {
+                        let r = match o.value.common.range.0 {
+                            FloatOrInt::Float(x) => x,
+                            FloatOrInt::Int(x) => x as f64,
+                        };
+                        (Some(o.value.specific.scale_params()), Some(r))
+                    },
+                );
+                match params.zip(range).filter(|_| !raw.contains(&name)) {
+                    Some((p, r)) => c.map_f64(|x| p.apply(x, r)),
+                    None => c.map_f64(|x| x),
+                }
+            })
+            .collect();
+        // all columns are mapped from an existing valid dataframe so this
+        // should never fail
+        FCSDataFrame::try_new(columns).unwrap_or_default()
+    }
+
+    /// Return DATA with logicle or arcsinh transforms applied.
+    ///
+    /// Values are first scaled as in [`Self::scaled_data`], after which each
+    /// measurement named in 'specs' is transformed and the rest are left as
+    /// scaled. Parameters not given in a spec are derived from $PnR and $PnE
+    /// (see [`TransformSpec`]); T must be given for the time measurement.
+    pub fn transformed_data(
+        &self,
+        specs: &[(Shortname, TransformSpec)],
+    ) -> Result<FCSDataFrame, TransformDataError> {
+        let names = self.all_shortnames();
+        if let Some((n, _)) = specs.iter().find(|(n, _)| !names.contains(n)) {
+            return Err(MissingMeasurementNameError(n.clone()).into());
+        }
+        let scaled = self.scaled_data(&[]);
+        let columns = self
+            .measurements
+            .iter()
+            .zip(names)
+            .zip(scaled.iter_columns())
+            .map(|(((_, e), name), c)| {
+                let Some((_, spec)) = specs.iter().find(|(n, _)| *n == name) else {
+                    return Ok(c.clone());
+                };
+                let (top, decades) = e.both(
+                    |_| (None, None),
+                    |o| {
+                        let r = match o.value.common.range.0 {
+                            FloatOrInt::Float(x) => x,
+                            FloatOrInt::Int(x) => x as f64,
+                        };
+                        let p = o.value.specific.scale_params();
+                        let d = match p.scale {
+                            Some(Scale::Log(l)) => Some(f64::from(l.decades())),
+                            _ => None,
+                        };
+                        (Some(p.apply(r, r)), d)
+                    },
+                );
+                spec.resolve(top, decades)
+                    .map(|t| t.apply_column(c))
+                    .map_err(|error| MeasTransformError { name, error })
+            })
+            .collect::<Result<_, _>>()?;
+        // all columns are mapped from an existing valid dataframe so this
+        // should never fail
+        Ok(FCSDataFrame::try_new(columns).unwrap_or_default())
+    }
+
+    /// Return statistics for each measurement along with $PnN and $PnS.
+    ///
+    /// If 'raw' is None, statistics will be computed from DATA as stored.
+    /// Otherwise they will be computed from [`Self::scaled_data`] with 'raw'
+    /// as the measurements to leave untransformed.
+    pub fn summary(
         &self,
-        h: &mut BufWriter<W>,
-        conf: &WriteConfig,
-    ) -> IODeferredResult<(), NewDataLayoutWarning, StdWriterError> {
-        let df = &self.data;
-        let others = &self.others;
-        let delim = conf.delim.inner();
-        let tot = Tot(df.nrows());
-        let analysis_len = self.analysis.0.len() as u64;
-        let other_lens = others.0.iter().map(|o| o.0.len() as u64).collect();
-
-        self.as_data_layout(&conf.shared)
-            .def_errors_into()
-            .def_errors_liftio()
-            .def_and_maybe(|layout| {
-                layout
-                    .as_writer(df, conf)
-                    .mult_to_deferred()
-                    .def_errors_liftio()
+        raw: Option<&[Shortname]>,
+        percentiles: &[f64],
+    ) -> Vec<MeasurementSummary> {
+        let scaled = raw.map(|r| self.scaled_data(r));
+        let df = scaled.as_ref().unwrap_or(&self.data);
+        let ms = &self.measurements;
+        ms.iter_common_values::<CommonMeasurement>()
+            .zip(ms.iter_all_names())
+            .zip(df.iter_columns())
+            .map(|(((index, x), shortname), c)| MeasurementSummary {
+                index,
+                shortname,
+                longname: x.longname.as_ref_opt().cloned(),
+                stats: ColumnStats::from_column(c, percentiles),
             })
-            .def_and_maybe(|mut writer| {
-                let data_len = writer.nbytes() as u64;
-                let hdr_kws = self
-                    .header_and_raw_keywords(tot, data_len, analysis_len, other_lens)
-                    .map_err(ImpureError::Pure)
-                    .map_err(|e| e.inner_into())
-                    .map_err(DeferredFailure::new1)?;
+            .collect()
+    }
 
-                let mut go = || {
-                    // write HEADER
-                    hdr_kws.header.h_write(h, M::O::fcs_version())?;
+    /// Compare $PnR to $PnB and DATA for each integer measurement.
+    ///
+    /// $PnR is often larger than $PnB can hold (eg 2^128 with 32 bits), in
+    /// which case the bitmask is silently clamped to the largest value $PnB
+    /// can hold. Return the bitmask actually used for each measurement along
+    /// with the largest value in DATA so that truncation can be detected.
+    /// Measurements which are not integers are skipped. Return nothing if
+    /// the layout is invalid.
+    pub fn range_audit(&self) -> Vec<RangeAudit> {
+        let bitmasks = self
+            .as_data_layout(&SharedConfig::default())
+            .map_or(vec![], |t| t.into_parts().0.bitmasks());
+        let ms = &self.measurements;
+        ms.layout_data()
+            .into_iter()
+            .zip(ms.iter_all_names())
+            .zip(self.data.iter_columns())
+            .zip(bitmasks)
+            .enumerate()
+            .filter_map(|(i, (((c, shortname), col), bitmask))| {
+                bitmask.map(|b| RangeAudit::new(i.into(), shortname, c.width, c.range, b, col))
+            })
+            .collect()
+    }
 
-                    // write OTHER
-                    for o in others.0.iter() {
-                        h.write_all(&o.0)?;
-                    }
+    /// Return the datatype of each measurement after applying 'overrides'.
+    fn target_datatypes(
+        &self,
+        mut dts: Vec<AlphaNumType>,
+        overrides: &[(Shortname, NumType)],
+    ) -> Result<Vec<AlphaNumType>, TargetDatatypeError> {
+        let names: Vec<_> = self.measurements.iter_all_names().collect();
+        for (n, dt) in overrides {
+            let i = names
+                .iter()
+                .position(|x| x == n)
+                .ok_or_else(|| TargetDatatypeError::Name(n.clone()))?;
+            dts[i] = (*dt).into();
+        }
+        Ok(dts)
+    }
 
-                    // write primary TEXT
-                    hdr_kws.primary.h_write(h, delim)?;
+    /// Return error if a float measurement would lose values as an integer.
+    fn check_target_loss(&self, dts: &[AlphaNumType]) -> Result<(), TargetDatatypeError> {
+        self.data
+            .iter_columns()
+            .zip(dts)
+            .position(|(c, dt)| {
+                matches!(dt, AlphaNumType::Integer | AlphaNumType::Ascii) && !c.is_whole()
+            })
+            .map_or(Ok(()), |i| Err(TargetDatatypeError::Lossy(i.into())))
+    }
 
-                    // write supplemental TEXT
-                    if !hdr_kws.supplemental.0.is_empty() {
-                        hdr_kws.supplemental.h_write(h, delim)?;
+    /// Return layout for each measurement converted to the given datatypes.
+    fn target_columns(&self, dts: Vec<AlphaNumType>) -> Vec<MixedColumnSetter> {
+        self.measurements
+            .layout_data()
+            .into_iter()
+            .zip(self.data.iter_columns())
+            .zip(dts)
+            .map(|((c, col), dt)| {
+                // largest value which needs to be stored, either from $PnR or
+                // DATA, whichever is bigger
+                let top = || {
+                    let r = match c.range.0 {
+                        FloatOrInt::Int(x) => x,
+                        FloatOrInt::Float(x) => x.ceil() as u64,
+                    };
+                    r.saturating_sub(1).max(col.max_u64().unwrap_or(0))
+                };
+                let r = match c.range.0 {
+                    FloatOrInt::Int(x) => x as f64,
+                    FloatOrInt::Float(x) => x,
+                };
+                match dt {
+                    AlphaNumType::Ascii => {
+                        let x = top();
+                        MixedColumnSetter::Ascii(RangeSetter {
+                            width: Chars::from_max(x),
+                            range: x.saturating_add(1),
+                        })
                     }
+                    AlphaNumType::Integer => {
+                        let x = top();
+                        MixedColumnSetter::Uint(RangeSetter {
+                            width: Bytes::from_max(x),
+                            range: x.saturating_add(1),
+                        })
+                    }
+                    AlphaNumType::Single => MixedColumnSetter::Float(r as f32),
+                    AlphaNumType::Double => MixedColumnSetter::Double(r),
+                }
+            })
+            .collect()
+    }
 
-                    // write DATA
-                    writer.h_write(h)?;
-
-                    // write ANALYSIS
-                    h.write_all(&self.analysis.0)
-                };
+    /// Convert all measurements to one datatype.
+    fn set_target_datatype_uniform(
+        &mut self,
+        dt: AlphaNumType,
+        lossless: bool,
+    ) -> Result<(), TargetDatatypeError> {
+        let dts = vec![dt; self.par().0];
+        if lossless {
+            self.check_target_loss(&dts)?;
+        }
+        let xs = self.target_columns(dts);
+        let ranges = || {
+            xs.iter()
+                .map(|x| match x {
+                    MixedColumnSetter::Float(r) => Range::try_from(f64::from(*r)),
+                    MixedColumnSetter::Double(r) => Range::try_from(*r),
+                    MixedColumnSetter::Ascii(s) => Ok(s.range.into()),
+                    MixedColumnSetter::Uint(s) => Ok(s.range.into()),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(TargetDatatypeError::Range)
+        };
+        match dt {
+            AlphaNumType::Ascii => {
+                let ys = xs
+                    .iter()
+                    .filter_map(|x| match x {
+                        MixedColumnSetter::Ascii(s) => Some(*s),
+                        _ => None,
+                    })
+                    .collect();
+                self.set_data_ascii_inner(ys)
+            }
+            AlphaNumType::Integer => {
+                let ys = xs
+                    .iter()
+                    .filter_map(|x| match x {
+                        MixedColumnSetter::Uint(s) => Some(*s),
+                        _ => None,
+                    })
+                    .collect();
+                self.set_data_integer_inner(ys)
+            }
+            AlphaNumType::Single => self.set_to_floating_point(false, ranges()?),
+            AlphaNumType::Double => self.set_to_floating_point(true, ranges()?),
+        }
+        .map_err(TargetDatatypeError::Length)
+    }
 
-                go().into_deferred()
+    /// Return a histogram for each measurement along with $PnN and $PnS.
+    ///
+    /// 'raw' has the same meaning as in [`Self::summary`].
+    pub fn histograms(
+        &self,
+        raw: Option<&[Shortname]>,
+        bins: NonZeroUsize,
+        scale: &BinScale,
+    ) -> Vec<MeasurementHistogram> {
+        let scaled = raw.map(|r| self.scaled_data(r));
+        let df = scaled.as_ref().unwrap_or(&self.data);
+        let ms = &self.measurements;
+        ms.iter_common_values::<CommonMeasurement>()
+            .zip(ms.iter_all_names())
+            .zip(df.iter_columns())
+            .map(|(((index, x), shortname), c)| MeasurementHistogram {
+                index,
+                shortname,
+                longname: x.longname.as_ref_opt().cloned(),
+                histogram: Histogram::from_column(c, bins, scale),
             })
+            .collect()
     }
 
-    /// Return reference to dataframe representing DATA
-    pub fn data(&self) -> &FCSDataFrame {
-        &self.data
+    /// Return a histogram for the measurement with a given $PnN.
+    ///
+    /// Return None if no measurement has this name. 'raw' has the same
+    /// meaning as in [`Self::summary`].
+    pub fn histogram(
+        &self,
+        name: &Shortname,
+        raw: Option<&[Shortname]>,
+        bins: NonZeroUsize,
+        scale: &BinScale,
+    ) -> Option<Histogram> {
+        let i = self.all_shortnames().iter().position(|n| n == name)?;
+        let scaled = raw.map(|r| self.scaled_data(r));
+        let df = scaled.as_ref().unwrap_or(&self.data);
+        df.iter_columns()
+            .nth(i)
+            .map(|c| Histogram::from_column(c, bins, scale))
     }
 
     /// Add columns to this dataset.
@@ -3009,6 +5166,15 @@ where
         Ok(res)
     }
 
+    /// Reorder measurements and DATA columns by name.
+    ///
+    /// See [`VersionedCoreTEXT::reorder_channels`].
+    pub fn reorder_channels(&mut self, order: &[Shortname]) -> Result<(), ReorderChannelsError> {
+        let is = self.reorder_channels_inner(order)?;
+        self.data = self.data.select_columns(&is[..]);
+        Ok(())
+    }
+
     /// Add time measurement to the end of the measurement vector.
     ///
     /// Return error if time measurement already exists or name is non-unique.
@@ -3128,6 +5294,27 @@ macro_rules! comp_methods {
     };
 }
 
+macro_rules! applied_gates_methods {
+    ($gatetype:ident) => {
+        /// Show $GATE, $GATING, $RnI, $RnW, and $Gm* (where applicable)
+        pub fn applied_gates(&self) -> Option<&$gatetype> {
+            self.metaroot.specific.applied_gates.as_ref_opt()
+        }
+
+        /// Set $GATE, $GATING, $RnI, $RnW, and $Gm* (where applicable)
+        ///
+        /// Measurement indices in each region are not checked against $PAR.
+        pub fn set_applied_gates(&mut self, gates: $gatetype) {
+            self.metaroot.specific.applied_gates = Some(gates).into();
+        }
+
+        /// Clear all gating keywords
+        pub fn unset_applied_gates(&mut self) {
+            self.metaroot.specific.applied_gates = None.into();
+        }
+    };
+}
+
 macro_rules! timestamp_methods {
     ($timetype:ident) => {
         pub fn timestamps(&self) -> &Timestamps<$timetype> {
@@ -3171,6 +5358,84 @@ macro_rules! spillover_methods {
         pub fn unset_spillover(&mut self) {
             self.metaroot.specific.spillover = None.into();
         }
+
+        /// Add a measurement to $SPILLOVER with no spillover into or from others
+        ///
+        /// Name must be one of the measurement names (ie $PnN) and must not
+        /// already be in the matrix. Return Ok(false) if $SPILLOVER is not set.
+        pub fn insert_spillover_measurement(
+            &mut self,
+            n: Shortname,
+        ) -> Result<bool, SetSpilloverError> {
+            if !self.all_shortnames().contains(&n) {
+                return Err(SpilloverLinkError.into());
+            }
+            match self.metaroot.specific.spillover.0.as_mut() {
+                Some(s) => s.insert(n).map(|_| true).map_err(|e| e.into()),
+                None => Ok(false),
+            }
+        }
+
+        /// Remove a measurement from $SPILLOVER
+        ///
+        /// Return true if the measurement was present. Return error if this
+        /// would make the matrix smaller than 2x2.
+        pub fn remove_spillover_measurement(
+            &mut self,
+            n: &Shortname,
+        ) -> Result<bool, SpilloverError> {
+            self.metaroot
+                .specific
+                .spillover
+                .0
+                .as_mut()
+                .map_or(Ok(false), |s| s.remove(n))
+        }
+
+        /// Set one coefficient in $SPILLOVER
+        ///
+        /// Return Ok(false) if $SPILLOVER is not set.
+        pub fn set_spillover_value(
+            &mut self,
+            from: &Shortname,
+            to: &Shortname,
+            x: f32,
+        ) -> Result<bool, SpilloverError> {
+            self.metaroot
+                .specific
+                .spillover
+                .0
+                .as_mut()
+                .map_or(Ok(false), |s| s.set(from, to, x).map(|_| true))
+        }
+
+        /// Set decimal places used when writing $SPILLOVER
+        ///
+        /// See [`Spillover::set_precision`]. Has no effect if $SPILLOVER is not
+        /// set.
+        pub fn set_spillover_precision(&mut self, precision: Option<usize>) {
+            if let Some(s) = self.metaroot.specific.spillover.0.as_mut() {
+                s.set_precision(precision);
+            }
+        }
+    };
+}
+
+macro_rules! modification_methods {
+    () => {
+        /// Record a modification in $LAST_MODIFIED, $LAST_MODIFIER, and
+        /// $ORIGINALITY.
+        ///
+        /// $LAST_MODIFIED will be set to the current local time. Set
+        /// 'data_modified' if DATA was changed (see [ModificationData::record]).
+        pub(crate) fn log_modification(&mut self, modifier: Option<String>, data_modified: bool) {
+            let now = chrono::Local::now().naive_local();
+            self.metaroot.specific.modification.record(
+                now,
+                modifier.map(LastModifier::from),
+                data_modified,
+            );
+        }
     };
 }
 
@@ -3308,6 +5573,8 @@ impl<A, D, O> Core2_0<A, D, O> {
 
     timestamp_methods!(FCSTime);
 
+    applied_gates_methods!(AppliedGates2_0);
+
     non_time_get_set!(
         wavelengths,
         set_wavelengths,
@@ -3338,6 +5605,8 @@ impl<A, D, O> Core3_0<A, D, O> {
 
     timestamp_methods!(FCSTime60);
 
+    applied_gates_methods!(AppliedGates3_0);
+
     non_time_get_set!(gains, set_gains, Gain, [specific], gain, PnG);
 
     non_time_get_set!(
@@ -3353,6 +5622,7 @@ impl<A, D, O> Core3_0<A, D, O> {
 impl<A, D, O> Core3_1<A, D, O> {
     scale_get_set!(Scale, Scale::Linear);
     spillover_methods!();
+    modification_methods!();
 
     /// Set data layout to be integers for all measurements.
     pub fn set_data_integer(&mut self, xs: Vec<NumRangeSetter>) -> Result<(), KeyLengthError> {
@@ -3381,6 +5651,8 @@ impl<A, D, O> Core3_1<A, D, O> {
 
     timestamp_methods!(FCSTime100);
 
+    applied_gates_methods!(AppliedGates3_0);
+
     display_methods!();
 
     non_time_get_set!(gains, set_gains, Gain, [specific], gain, PnG);
@@ -3405,6 +5677,8 @@ impl<A, D, O> Core3_1<A, D, O> {
 }
 
 impl<A, D, O> Core3_2<A, D, O> {
+    modification_methods!();
+
     /// Show $UNSTAINEDCENTERS
     pub fn unstained_centers(&self) -> Option<&UnstainedCenters> {
         self.metaroot
@@ -3599,6 +5873,8 @@ impl<A, D, O> Core3_2<A, D, O> {
 
     timestamp_methods!(FCSTime100);
 
+    applied_gates_methods!(AppliedGates3_2);
+
     display_methods!();
 
     non_time_get_set!(gains, set_gains, Gain, [specific], gain, PnG);
@@ -3827,20 +6103,120 @@ macro_rules! coredataset_set_measurements3_1 {
     };
 }
 
+macro_rules! coredataset_set_target_datatype2_0 {
+    ($version:expr) => {
+        /// Convert measurements to a new datatype.
+        ///
+        /// All integer measurements will have the largest width needed by any
+        /// of them, and $BYTEORD will be updated to match the new width while
+        /// keeping its endianness if possible. Return error if $PnDATATYPE is
+        /// given since this is only valid in 3.2.
+        pub fn set_target_datatype(
+            &mut self,
+            t: &TargetDatatype,
+            lossless: bool,
+        ) -> Result<(), TargetDatatypeError> {
+            if !t.overrides.is_empty() {
+                return Err(TargetDatatypeError::Overrides($version));
+            }
+            let Some(dt) = t.datatype else {
+                return Ok(());
+            };
+            self.set_target_datatype_uniform(dt, lossless)?;
+            if dt == AlphaNumType::Ascii {
+                return Ok(());
+            }
+            let cs = self.measurements.layout_data();
+            let n = cs
+                .iter()
+                .filter_map(|c| Bytes::try_from(c.width).ok())
+                .max_by_key(|w| u8::from(*w));
+            if let Some(n) = n {
+                let e = self
+                    .metaroot
+                    .specific
+                    .byteord
+                    .as_endian()
+                    .unwrap_or(Endian::Little);
+                let byteord = ByteOrd::from_endian(e, n);
+                if dt == AlphaNumType::Integer {
+                    let rs = cs
+                        .into_iter()
+                        .map(|c| match c.range.0 {
+                            FloatOrInt::Int(x) => x,
+                            FloatOrInt::Float(x) => x as u64,
+                        })
+                        .collect();
+                    self.set_data_integer(rs, byteord)
+                        .map_err(TargetDatatypeError::Length)?;
+                } else {
+                    self.metaroot.specific.byteord = byteord;
+                }
+            }
+            Ok(())
+        }
+    };
+}
+
 impl CoreDataset2_0 {
     coredataset_set_measurements2_0!(RawInput2_0);
+    coredataset_set_target_datatype2_0!(Version::FCS2_0);
 }
 
 impl CoreDataset3_0 {
     coredataset_set_measurements2_0!(RawInput3_0);
+    coredataset_set_target_datatype2_0!(Version::FCS3_0);
 }
 
 impl CoreDataset3_1 {
     coredataset_set_measurements3_1!(RawInput3_1);
+
+    /// Convert measurements to a new datatype.
+    ///
+    /// Return error if $PnDATATYPE is given since this is only valid in 3.2.
+    pub fn set_target_datatype(
+        &mut self,
+        t: &TargetDatatype,
+        lossless: bool,
+    ) -> Result<(), TargetDatatypeError> {
+        if !t.overrides.is_empty() {
+            return Err(TargetDatatypeError::Overrides(Version::FCS3_1));
+        }
+        if let Some(dt) = t.datatype {
+            self.set_target_datatype_uniform(dt, lossless)?;
+        }
+        Ok(())
+    }
 }
 
 impl CoreDataset3_2 {
     coredataset_set_measurements3_1!(RawInput3_2);
+
+    /// Convert measurements to new datatypes.
+    ///
+    /// Measurements in 'overrides' will be given $PnDATATYPE if they differ
+    /// from $DATATYPE, which will be set to the most common datatype.
+    /// Measurements not in 'overrides' will keep their current datatype if
+    /// 'datatype' is None.
+    pub fn set_target_datatype(
+        &mut self,
+        t: &TargetDatatype,
+        lossless: bool,
+    ) -> Result<(), TargetDatatypeError> {
+        if t.is_empty() || self.par().0 == 0 {
+            return Ok(());
+        }
+        let current = match t.datatype {
+            Some(dt) => vec![dt; self.par().0],
+            None => self.datatypes(),
+        };
+        let dts = self.target_datatypes(current, &t.overrides[..])?;
+        if lossless {
+            self.check_target_loss(&dts)?;
+        }
+        let xs = self.target_columns(dts);
+        self.set_data_mixed(xs).map_err(TargetDatatypeError::Length)
+    }
 }
 
 impl UnstainedData {
@@ -3883,6 +6259,67 @@ impl SubsetData {
         })
     }
 
+    /// Decode subset membership for each event from the ANALYSIS segment.
+    ///
+    /// Each event is assumed to have one unsigned integer in ANALYSIS which is
+    /// $CSVBITS wide (rounded up to the nearest byte) in the given byte order.
+    /// An event belongs to subset n if any bits in $CSVnFLAG are also set in
+    /// this integer.
+    ///
+    /// Return a vector with one element per subset (ie $CSMODE), each of which
+    /// is a vector with one boolean per event, or None if $CSVnFLAG is missing.
+    pub fn membership(
+        &self,
+        analysis: &Analysis,
+        endian: Option<Endian>,
+        nevents: usize,
+    ) -> Result<Vec<Option<Vec<bool>>>, SubsetDecodeError> {
+        let bits = self
+            .bits
+            .as_ref_opt()
+            .ok_or(SubsetDecodeError::MissingBits)?
+            .0;
+        if bits == 0 || bits > 64 {
+            return Err(SubsetDecodeError::Bits(bits));
+        }
+        let width = bits.div_ceil(8) as usize;
+        let order = match endian {
+            Some(e) => e,
+            None if width == 1 => Endian::Little,
+            None => return Err(SubsetDecodeError::ByteOrd),
+        };
+        let expected = width * nevents;
+        if analysis.0.len() != expected {
+            return Err(SubsetDecodeError::Length {
+                expected,
+                actual: analysis.0.len(),
+            });
+        }
+        let words: Vec<_> = analysis
+            .0
+            .chunks_exact(width)
+            .map(|xs| {
+                let mut buf = [0_u8; 8];
+                if order == Endian::Big {
+                    buf[8 - width..].copy_from_slice(xs);
+                    u64::from_be_bytes(buf)
+                } else {
+                    buf[..width].copy_from_slice(xs);
+                    u64::from_le_bytes(buf)
+                }
+            })
+            .collect();
+        let res = self
+            .flags
+            .iter()
+            .map(|f| {
+                f.as_ref_opt()
+                    .map(|flag| words.iter().map(|w| w & u64::from(flag.0) > 0).collect())
+            })
+            .collect();
+        Ok(res)
+    }
+
     fn opt_keywords(&self) -> impl Iterator<Item = (String, String)> {
         let m = CSMode(self.flags.len());
         self.flags
@@ -4261,9 +6698,81 @@ impl<I> GatingRegions<I> {
             regions: self.regions.map(|(ri, r)| (ri, r.inner_into())),
         }
     }
+
+    fn map_indices<F>(&mut self, f: F)
+    where
+        I: Copy,
+        F: Fn(I) -> I,
+    {
+        for (_, r) in self.regions.iter_mut() {
+            r.map_indices(&f);
+        }
+    }
+
+    /// Map indices, or return error if 'f' returns None for any index.
+    fn try_map_indices<F>(&mut self, f: F) -> Result<(), ClearOptional>
+    where
+        I: Copy,
+        F: Fn(I) -> Option<I>,
+    {
+        if self
+            .regions
+            .iter()
+            .flat_map(|(_, r)| r.indices())
+            .any(|i| f(i).is_none())
+        {
+            return Err(ClearOptional);
+        }
+        self.map_indices(|i| f(i).unwrap_or(i));
+        Ok(())
+    }
+}
+
+/// Return the position of a measurement after measurements are reordered.
+///
+/// 'order' gives the current index of the measurement to put in each position.
+fn reordered_index(order: &[usize], i: MeasIndex) -> MeasIndex {
+    let j = usize::from(i);
+    order.iter().position(|k| *k == j).unwrap_or(j).into()
+}
+
+/// Return the position of a measurement after another is removed.
+///
+/// Return None if the measurement is the one which was removed.
+fn shifted_index(removed: MeasIndex, i: MeasIndex) -> Option<MeasIndex> {
+    let (r, j) = (usize::from(removed), usize::from(i));
+    match j.cmp(&r) {
+        Ordering::Less => Some(i),
+        Ordering::Equal => None,
+        Ordering::Greater => Some((j - 1).into()),
+    }
 }
 
 impl<I> Region<I> {
+    fn indices(&self) -> Vec<I>
+    where
+        I: Copy,
+    {
+        match self {
+            Self::Univariate(r) => vec![r.index],
+            Self::Bivariate(r) => vec![r.x_index, r.y_index],
+        }
+    }
+
+    fn map_indices<F>(&mut self, f: F)
+    where
+        I: Copy,
+        F: Fn(I) -> I,
+    {
+        match self {
+            Self::Univariate(r) => r.index = f(r.index),
+            Self::Bivariate(r) => {
+                r.x_index = f(r.x_index);
+                r.y_index = f(r.y_index);
+            }
+        }
+    }
+
     pub(crate) fn try_new(r_index: RegionGateIndex<I>, window: RegionWindow) -> Option<Self> {
         match (r_index, window) {
             (RegionGateIndex::Univariate(index), RegionWindow::Univariate(gate)) => {
@@ -4350,31 +6859,155 @@ impl<I> Region<I> {
         }
     }
 
-    pub(crate) fn try_map<F, J, E>(self, f: F) -> Result<Region<J>, E>
-    where
-        F: FnMut(I) -> Result<J, E>,
-    {
+    pub(crate) fn try_map<F, J, E>(self, f: F) -> Result<Region<J>, E>
+    where
+        F: FnMut(I) -> Result<J, E>,
+    {
+        match self {
+            Self::Univariate(x) => Ok(Region::Univariate(x.try_map(f)?)),
+            Self::Bivariate(x) => Ok(Region::Bivariate(x.try_map(f)?)),
+        }
+    }
+
+    pub(crate) fn inner_into<J>(self) -> Region<J>
+    where
+        J: From<I>,
+    {
+        self.map(|i| i.into())
+    }
+
+    pub(crate) fn flatten(self) -> NonEmpty<I> {
+        match self {
+            Self::Univariate(r) => NonEmpty::new(r.index),
+            Self::Bivariate(r) => (r.x_index, vec![r.y_index]).into(),
+        }
+    }
+}
+
+impl<I> GatingRegions<I>
+where
+    I: Copy,
+    PrefixedMeasIndex: TryFrom<I>,
+{
+    /// Return the events within each region and within $GATING.
+    ///
+    /// Regions are evaluated against DATA as stored, so gate values are
+    /// assumed to be in the same units as DATA. Univariate regions include
+    /// both bounds. Bivariate regions with two vertices are treated as a
+    /// rectangle with these vertices as opposite corners; those with more are
+    /// treated as a polygon in which each point must be enclosed by an odd
+    /// number of edges. Regions on gated measurements ($Gm*) cannot be
+    /// evaluated since these are not stored in DATA.
+    pub fn membership(&self, df: &FCSDataFrame) -> Result<GateMembership, GateEvalError> {
+        let mut regions: Vec<RegionMembership> = vec![];
+        for (ri, r) in self.regions.iter() {
+            if regions.iter().all(|x| x.index != *ri) {
+                let mask = r.membership(*ri, df)?;
+                regions.push(RegionMembership::new(*ri, mask));
+            }
+        }
+        let mask = gating_mask(&self.gating, &regions[..])?;
+        let count = mask.iter().filter(|x| **x).count();
+        Ok(GateMembership {
+            regions,
+            mask,
+            count,
+        })
+    }
+}
+
+impl<I> Region<I>
+where
+    I: Copy,
+    PrefixedMeasIndex: TryFrom<I>,
+{
+    fn membership(&self, ri: RegionIndex, df: &FCSDataFrame) -> Result<Vec<bool>, GateEvalError> {
+        let column = |i: I| {
+            let mi = PrefixedMeasIndex::try_from(i)
+                .map_err(|_| GateEvalError::GatedMeasurement(ri))?
+                .0;
+            df.iter_columns()
+                .nth(mi.into())
+                .map(|c| c.to_f64s())
+                .ok_or(GateEvalError::MissingMeasurement(ri, mi))
+        };
         match self {
-            Self::Univariate(x) => Ok(Region::Univariate(x.try_map(f)?)),
-            Self::Bivariate(x) => Ok(Region::Bivariate(x.try_map(f)?)),
+            Self::Univariate(r) => {
+                let lower = float_or_int_to_f64(r.gate.lower);
+                let upper = float_or_int_to_f64(r.gate.upper);
+                let xs = column(r.index)?;
+                Ok(xs.into_iter().map(|x| lower <= x && x <= upper).collect())
+            }
+            Self::Bivariate(r) => {
+                let vs: Vec<_> = r
+                    .vertices
+                    .iter()
+                    .map(|v| (float_or_int_to_f64(v.x), float_or_int_to_f64(v.y)))
+                    .collect();
+                let xs = column(r.x_index)?;
+                let ys = column(r.y_index)?;
+                Ok(xs
+                    .into_iter()
+                    .zip(ys)
+                    .map(|(x, y)| polygon_contains(&vs[..], x, y))
+                    .collect())
+            }
         }
     }
+}
 
-    pub(crate) fn inner_into<J>(self) -> Region<J>
-    where
-        J: From<I>,
-    {
-        self.map(|i| i.into())
+impl RegionMembership {
+    fn new(index: RegionIndex, mask: Vec<bool>) -> Self {
+        let count = mask.iter().filter(|x| **x).count();
+        Self { index, mask, count }
     }
+}
 
-    pub(crate) fn flatten(self) -> NonEmpty<I> {
-        match self {
-            Self::Univariate(r) => NonEmpty::new(r.index),
-            Self::Bivariate(r) => (r.x_index, vec![r.y_index]).into(),
+fn gating_mask(g: &Gating, regions: &[RegionMembership]) -> Result<Vec<bool>, GateEvalError> {
+    let zip = |a: &Gating, b: &Gating, f: fn(bool, bool) -> bool| {
+        let xs = gating_mask(a, regions)?;
+        let ys = gating_mask(b, regions)?;
+        Ok(xs.into_iter().zip(ys).map(|(x, y)| f(x, y)).collect())
+    };
+    match g {
+        Gating::Region(ri) => regions
+            .iter()
+            .find(|x| x.index == *ri)
+            .map(|x| x.mask.clone())
+            .ok_or(GateEvalError::MissingRegion(*ri)),
+        Gating::Not(a) => Ok(gating_mask(a, regions)?.into_iter().map(|x| !x).collect()),
+        Gating::And(a, b) => zip(a, b, |x, y| x && y),
+        Gating::Or(a, b) => zip(a, b, |x, y| x || y),
+    }
+}
+
+fn polygon_contains(vs: &[(f64, f64)], x: f64, y: f64) -> bool {
+    match vs {
+        [(x0, y0), (x1, y1)] => {
+            x0.min(*x1) <= x && x <= x0.max(*x1) && y0.min(*y1) <= y && y <= y0.max(*y1)
+        }
+        _ => {
+            // even-odd rule: count edges crossed by a ray going right from the
+            // point
+            let mut inside = false;
+            for (i, (xi, yi)) in vs.iter().enumerate() {
+                let (xj, yj) = vs[(i + vs.len() - 1) % vs.len()];
+                if (*yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                    inside = !inside;
+                }
+            }
+            inside
         }
     }
 }
 
+fn float_or_int_to_f64(x: FloatOrInt) -> f64 {
+    match x {
+        FloatOrInt::Float(y) => y,
+        FloatOrInt::Int(y) => y as f64,
+    }
+}
+
 impl TryFrom<MeasOrGateIndex> for PrefixedMeasIndex {
     type Error = RegionToMeasIndexError;
     fn try_from(value: MeasOrGateIndex) -> Result<Self, Self::Error> {
@@ -4480,6 +7113,30 @@ impl From<AppliedGates3_2> for AppliedGates3_0 {
 }
 
 impl ModificationData {
+    /// Record a modification made at the given time.
+    ///
+    /// $LAST_MODIFIER will only be updated if 'modifier' is given. If
+    /// 'data_modified' is true, $ORIGINALITY will become DataModified,
+    /// otherwise it will become NonDataModified if it was Original or not
+    /// given.
+    pub(crate) fn record(
+        &mut self,
+        at: NaiveDateTime,
+        modifier: Option<LastModifier>,
+        data_modified: bool,
+    ) {
+        self.last_modified = Some(ModifiedDateTime(at)).into();
+        if modifier.is_some() {
+            self.last_modifier = modifier.into();
+        }
+        let ori = match self.originality.0 {
+            _ if data_modified => Originality::DataModified,
+            None | Some(Originality::Original) => Originality::NonDataModified,
+            Some(x) => x,
+        };
+        self.originality = Some(ori).into();
+    }
+
     fn lookup<E>(kws: &mut StdKeywords) -> LookupTentative<Self, E> {
         let lmr = LastModifier::lookup_opt(kws, false);
         let lmd = ModifiedDateTime::lookup_opt(kws, false);
@@ -4557,7 +7214,7 @@ impl PlateData {
         [
             OptMetarootKey::pair_opt(&self.wellid),
             OptMetarootKey::pair_opt(&self.platename),
-            OptMetarootKey::pair_opt(&self.platename),
+            OptMetarootKey::pair_opt(&self.plateid),
         ]
         .into_iter()
         .flat_map(|(k, v)| v.map(|x| (k, x)))
@@ -5366,7 +8023,7 @@ impl ConvertFromMetaroot<InnerMetaroot2_0> for InnerMetaroot3_2 {
         lossless: bool,
     ) -> MetarootConvertResult<Self> {
         let b = value.byteord.try_into().into_deferred();
-        let c = value.cyt.0.ok_or(NoCytError).into_deferred();
+        let c = value.cyt.0.ok_or(ConvertNoCytError).into_deferred();
         let mut res = b.def_zip(c).def_map_value(|(byteord, cyt)| Self {
             byteord,
             cyt,
@@ -5417,7 +8074,7 @@ impl ConvertFromMetaroot<InnerMetaroot3_0> for InnerMetaroot3_2 {
                 })
                 .and_maybe(|ag| {
                     let b = value.byteord.try_into().into_deferred();
-                    let c = value.cyt.0.ok_or(NoCytError).into_deferred();
+                    let c = value.cyt.0.ok_or(ConvertNoCytError).into_deferred();
                     b.def_zip(c).def_map_value(|(byteord, cyt)| Self {
                         byteord,
                         cyt,
@@ -5461,7 +8118,7 @@ impl ConvertFromMetaroot<InnerMetaroot3_1> for InnerMetaroot3_2 {
             value
                 .cyt
                 .0
-                .ok_or(NoCytError)
+                .ok_or(ConvertNoCytError)
                 .into_deferred()
                 .def_map_value(|cyt| Self {
                     byteord: value.byteord,
@@ -5839,10 +8496,10 @@ impl LookupOptical for InnerOptical3_2 {
         let de = DetectorName::lookup_opt(kws, i.into(), false);
         let ta = Tag::lookup_opt(kws, i.into(), false);
         let m = OpticalType::lookup_opt(kws, i.into(), false);
-        let f = Feature::lookup_opt(kws, i.into(), false);
+        let f = Feature::lookup_allowed(kws, i.into(), &conf.allowed_features[..]);
         let a = Analyte::lookup_opt(kws, i.into(), false);
         let da = NumType::lookup_opt(kws, i.into(), false);
-        g.zip5(w, c, d, de).zip5(ta, m, f, a).zip(da).and_maybe(
+        let mut res = g.zip5(w, c, d, de).zip5(ta, m, f, a).zip(da).and_maybe(
             |(
                 (
                     (gain, wavelengths, calibration, display, detector_name),
@@ -5869,7 +8526,33 @@ impl LookupOptical for InnerOptical3_2 {
                     }
                 })
             },
-        )
+        );
+        res.def_eval_warning(|o| o.check_type_scale(i));
+        res
+    }
+}
+
+impl InnerOptical3_2 {
+    /// Return warning if $PnTYPE denotes categorical values which are scaled.
+    ///
+    /// Classification and index values are labels rather than intensities, so
+    /// $PnE should be linear and $PnG should not be anything other than 1.
+    fn check_type_scale(&self, i: MeasIndex) -> Option<LookupKeysWarning> {
+        let t = self.measurement_type.as_ref_opt()?;
+        let categorical = matches!(t, OpticalType::Classification | OpticalType::Index);
+        let has_gain = self
+            .gain
+            .as_ref_opt()
+            .is_some_and(|g| f32::from(g.0) != 1.0);
+        if categorical && (self.scale != Scale::Linear || has_gain) {
+            let w = OpticalTypeScaleWarning {
+                index: i,
+                measurement_type: t.clone(),
+            };
+            Some(LookupRelationalWarning::from(w).into())
+        } else {
+            None
+        }
     }
 }
 
@@ -5937,9 +8620,25 @@ impl LookupTemporal for InnerTemporal3_2 {
                 })
             })
     }
+
+    fn has_temporal_type(kws: &StdKeywords, i: MeasIndex) -> bool {
+        kws.get(&TemporalType::std(i.into()))
+            .is_some_and(|v| v.parse::<TemporalType>().is_ok())
+    }
 }
 
 impl VersionedOptical for InnerOptical2_0 {
+    fn scale_params(&self) -> ScaleParams {
+        ScaleParams {
+            scale: self.scale.0,
+            ..ScaleParams::default()
+        }
+    }
+
+    fn wavelengths(&self) -> Vec<u32> {
+        self.wavelength.0.iter().map(|x| x.0).collect()
+    }
+
     fn datatype(&self) -> Option<NumType> {
         None
     }
@@ -5974,6 +8673,18 @@ impl VersionedOptical for InnerOptical2_0 {
 }
 
 impl VersionedOptical for InnerOptical3_0 {
+    fn scale_params(&self) -> ScaleParams {
+        ScaleParams {
+            scale: Some(self.scale),
+            gain: self.gain.0.map(|g| g.0.into()),
+            calibration: None,
+        }
+    }
+
+    fn wavelengths(&self) -> Vec<u32> {
+        self.wavelength.0.iter().map(|x| x.0).collect()
+    }
+
     fn datatype(&self) -> Option<NumType> {
         None
     }
@@ -6010,6 +8721,22 @@ impl VersionedOptical for InnerOptical3_0 {
 }
 
 impl VersionedOptical for InnerOptical3_1 {
+    fn scale_params(&self) -> ScaleParams {
+        ScaleParams {
+            scale: Some(self.scale),
+            gain: self.gain.0.map(|g| g.0.into()),
+            calibration: self.calibration.0.as_ref().map(|c| (c.slope.into(), 0.0)),
+        }
+    }
+
+    fn wavelengths(&self) -> Vec<u32> {
+        self.wavelengths
+            .0
+            .as_ref()
+            .map(|x| x.0.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
     fn datatype(&self) -> Option<NumType> {
         None
     }
@@ -6049,6 +8776,26 @@ impl VersionedOptical for InnerOptical3_1 {
 }
 
 impl VersionedOptical for InnerOptical3_2 {
+    fn scale_params(&self) -> ScaleParams {
+        ScaleParams {
+            scale: Some(self.scale),
+            gain: self.gain.0.map(|g| g.0.into()),
+            calibration: self
+                .calibration
+                .0
+                .as_ref()
+                .map(|c| (c.slope.into(), c.offset)),
+        }
+    }
+
+    fn wavelengths(&self) -> Vec<u32> {
+        self.wavelengths
+            .0
+            .as_ref()
+            .map(|x| x.0.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
     fn datatype(&self) -> Option<NumType> {
         self.datatype.0.as_ref().copied()
     }
@@ -6360,11 +9107,12 @@ pub type NumRangeSetter = RangeSetter<Bytes>;
 
 impl NumRangeSetter {
     fn truncated(&self) -> (Width, Range) {
+        let bits = u32::from(u8::from(self.width)) * 8;
         (
             self.width.into(),
             2_u64
-                .pow(u8::from(self.width).into())
-                .min(self.range)
+                .checked_pow(bits)
+                .map_or(self.range, |m| m.min(self.range))
                 .into(),
         )
     }
@@ -6402,7 +9150,7 @@ impl LookupMetaroot for InnerMetaroot2_0 {
     ) -> LookupResult<Self> {
         let co = Compensation2_0::lookup(kws, par);
         let cy = Cyt::lookup_opt(kws, false);
-        let t = Timestamps::lookup(kws, false);
+        let t = Timestamps::lookup(kws, false, conf.lab_timezone);
         let g = AppliedGates2_0::lookup(kws, conf);
         co.zip4(cy, t, g)
             .and_maybe(|(comp, cyt, timestamps, applied_gates)| {
@@ -6438,7 +9186,7 @@ impl LookupMetaroot for InnerMetaroot3_0 {
         let cy = Cyt::lookup_opt(kws, false);
         let sn = Cytsn::lookup_opt(kws, false);
         let su = SubsetData::lookup(kws, false);
-        let t = Timestamps::lookup(kws, false);
+        let t = Timestamps::lookup(kws, false, conf.lab_timezone);
         let u = Unicode::lookup_opt(kws, false);
         let g = AppliedGates3_0::lookup(kws, false, conf);
         co.zip4(cy, sn, su).zip4(t, u, g).and_maybe(
@@ -6481,7 +9229,7 @@ impl LookupMetaroot for InnerMetaroot3_1 {
         let su = SubsetData::lookup(kws, true);
         let md = ModificationData::lookup(kws);
         let p = PlateData::lookup(kws, false);
-        let t = Timestamps::lookup(kws, false);
+        let t = Timestamps::lookup(kws, false, conf.lab_timezone);
         let v = Vol::lookup_opt(kws, false);
         let g = AppliedGates3_0::lookup(kws, true, conf);
         cy.zip5(sp, sn, su, md).zip5(p, t, v, g).and_maybe(
@@ -6533,10 +9281,10 @@ impl LookupMetaroot for InnerMetaroot3_2 {
         kws: &mut StdKeywords,
         _: Par,
         names: &HashSet<&Shortname>,
-        _: &StdTextReadConfig,
+        conf: &StdTextReadConfig,
     ) -> LookupResult<Self> {
         let ca = CarrierData::lookup(kws);
-        let d = Datetimes::lookup(kws);
+        let d = Datetimes::lookup(kws, conf.datetime_timezone);
         let f = Flowrate::lookup_opt(kws, false);
         let md = ModificationData::lookup(kws);
         // Only L is allowed as of 3.2, so pull the value and check it if given.
@@ -6546,7 +9294,7 @@ impl LookupMetaroot for InnerMetaroot3_2 {
         let sp = Spillover::lookup_opt(kws, names);
         let sn = Cytsn::lookup_opt(kws, false);
         let p = PlateData::lookup(kws, true);
-        let t = Timestamps::lookup(kws, false);
+        let t = Timestamps::lookup(kws, false, conf.lab_timezone);
         let u = UnstainedData::lookup(kws, names);
         let v = Vol::lookup_opt(kws, false);
         let g = AppliedGates3_2::lookup(kws);
@@ -6598,6 +9346,18 @@ impl VersionedMetaroot for InnerMetaroot2_0 {
         self.byteord.clone()
     }
 
+    fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn acquisition_interval(&self, tolerance: TimeDelta) -> TemporalTentative {
+        Datetimes::default().reconcile(&self.timestamps, tolerance)
+    }
+
+    fn as_subset(&self) -> Option<(&SubsetData, Option<Endian>)> {
+        None
+    }
+
     fn as_unstainedcenters(&self) -> Option<&UnstainedCenters> {
         None
     }
@@ -6631,6 +9391,10 @@ impl VersionedMetaroot for InnerMetaroot2_0 {
         self.comp.mut_or_unset(|c| f(&mut c.0))
     }
 
+    fn reorder_region_indices(&mut self, _: &[usize]) {}
+
+    fn remove_region_index(&mut self, _: MeasIndex) {}
+
     fn timestamps_valid(&self) -> bool {
         self.timestamps.valid()
     }
@@ -6696,6 +9460,20 @@ impl VersionedMetaroot for InnerMetaroot3_0 {
         self.byteord.clone()
     }
 
+    fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn acquisition_interval(&self, tolerance: TimeDelta) -> TemporalTentative {
+        Datetimes::default().reconcile(&self.timestamps, tolerance)
+    }
+
+    fn as_subset(&self) -> Option<(&SubsetData, Option<Endian>)> {
+        self.subset
+            .as_ref_opt()
+            .map(|x| (x, self.byteord.as_endian()))
+    }
+
     fn as_unstainedcenters(&self) -> Option<&UnstainedCenters> {
         None
     }
@@ -6729,6 +9507,25 @@ impl VersionedMetaroot for InnerMetaroot3_0 {
         self.comp.mut_or_unset(|c| f(&mut c.0))
     }
 
+    fn reorder_region_indices(&mut self, order: &[usize]) {
+        self.applied_gates.mut_or_unset(|ag| {
+            ag.regions.map_indices(|i| match i {
+                MeasOrGateIndex::Meas(m) => MeasOrGateIndex::Meas(reordered_index(order, m)),
+                MeasOrGateIndex::Gate(_) => i,
+            });
+            Ok(())
+        });
+    }
+
+    fn remove_region_index(&mut self, removed: MeasIndex) {
+        self.applied_gates.mut_or_unset(|ag| {
+            ag.regions.try_map_indices(|i| match i {
+                MeasOrGateIndex::Meas(m) => shifted_index(removed, m).map(MeasOrGateIndex::Meas),
+                MeasOrGateIndex::Gate(_) => Some(i),
+            })
+        });
+    }
+
     fn timestamps_valid(&self) -> bool {
         self.timestamps.valid()
     }
@@ -6806,6 +9603,18 @@ impl VersionedMetaroot for InnerMetaroot3_1 {
         self.byteord
     }
 
+    fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn acquisition_interval(&self, tolerance: TimeDelta) -> TemporalTentative {
+        Datetimes::default().reconcile(&self.timestamps, tolerance)
+    }
+
+    fn as_subset(&self) -> Option<(&SubsetData, Option<Endian>)> {
+        self.subset.as_ref_opt().map(|x| (x, Some(self.byteord)))
+    }
+
     fn as_unstainedcenters(&self) -> Option<&UnstainedCenters> {
         None
     }
@@ -6839,6 +9648,25 @@ impl VersionedMetaroot for InnerMetaroot3_1 {
         None
     }
 
+    fn reorder_region_indices(&mut self, order: &[usize]) {
+        self.applied_gates.mut_or_unset(|ag| {
+            ag.regions.map_indices(|i| match i {
+                MeasOrGateIndex::Meas(m) => MeasOrGateIndex::Meas(reordered_index(order, m)),
+                MeasOrGateIndex::Gate(_) => i,
+            });
+            Ok(())
+        });
+    }
+
+    fn remove_region_index(&mut self, removed: MeasIndex) {
+        self.applied_gates.mut_or_unset(|ag| {
+            ag.regions.try_map_indices(|i| match i {
+                MeasOrGateIndex::Meas(m) => shifted_index(removed, m).map(MeasOrGateIndex::Meas),
+                MeasOrGateIndex::Gate(_) => Some(i),
+            })
+        });
+    }
+
     fn timestamps_valid(&self) -> bool {
         self.timestamps.valid()
     }
@@ -6921,6 +9749,18 @@ impl VersionedMetaroot for InnerMetaroot3_2 {
         self.byteord
     }
 
+    fn mode(&self) -> Mode {
+        Mode::List
+    }
+
+    fn acquisition_interval(&self, tolerance: TimeDelta) -> TemporalTentative {
+        self.datetimes.reconcile(&self.timestamps, tolerance)
+    }
+
+    fn as_subset(&self) -> Option<(&SubsetData, Option<Endian>)> {
+        None
+    }
+
     fn as_unstainedcenters(&self) -> Option<&UnstainedCenters> {
         self.unstained.unstainedcenters.as_ref_opt()
     }
@@ -6954,6 +9794,21 @@ impl VersionedMetaroot for InnerMetaroot3_2 {
         None
     }
 
+    fn reorder_region_indices(&mut self, order: &[usize]) {
+        self.applied_gates.mut_or_unset(|ag| {
+            ag.regions
+                .map_indices(|i| PrefixedMeasIndex(reordered_index(order, i.0)));
+            Ok(())
+        });
+    }
+
+    fn remove_region_index(&mut self, removed: MeasIndex) {
+        self.applied_gates.mut_or_unset(|ag| {
+            ag.regions
+                .try_map_indices(|i| shifted_index(removed, i.0).map(PrefixedMeasIndex))
+        });
+    }
+
     fn timestamps_valid(&self) -> bool {
         self.timestamps.valid()
     }
@@ -7236,43 +10091,380 @@ impl Optical3_1 {
         let specific = InnerOptical3_1::new(scale);
         Optical::new_common(width, range, specific)
     }
-}
+}
+
+impl Optical3_2 {
+    pub fn new(width: Width, range: Range, scale: Scale) -> Self {
+        let specific = InnerOptical3_2::new(scale);
+        Optical::new_common(width, range, specific)
+    }
+}
+
+impl<X> AsMut<CommonMeasurement> for Optical<X> {
+    fn as_mut(&mut self) -> &mut CommonMeasurement {
+        &mut self.common
+    }
+}
+
+impl<X> AsMut<CommonMeasurement> for Temporal<X> {
+    fn as_mut(&mut self) -> &mut CommonMeasurement {
+        &mut self.common
+    }
+}
+
+impl<X> AsRef<CommonMeasurement> for Optical<X> {
+    fn as_ref(&self) -> &CommonMeasurement {
+        &self.common
+    }
+}
+
+impl<X> AsRef<CommonMeasurement> for Temporal<X> {
+    fn as_ref(&self) -> &CommonMeasurement {
+        &self.common
+    }
+}
+
+enum_from_disp!(
+    pub SetFloatError,
+    [Nan, NanFloatOrInt],
+    [Length, KeyLengthError]
+);
+
+/// Make min/max/mean keywords for each column in a dataframe.
+fn column_stats_keywords(df: &FCSDataFrame, prefix: &str) -> Vec<(String, String)> {
+    df.iter_columns()
+        .enumerate()
+        .filter_map(|(i, c)| ColumnStats::from_column(c, &[]).map(|s| (i + 1, s)))
+        .flat_map(|(n, s)| {
+            [("MIN", s.min), ("MAX", s.max), ("MEAN", s.mean)]
+                .map(|(k, v)| (format!("{prefix}P{n}_{k}"), v.to_string()))
+        })
+        .collect()
+}
+
+/// Return true if key was made by [`column_stats_keywords`] with 'prefix'.
+fn is_column_stats_key(prefix: &str, key: &str) -> bool {
+    key.strip_prefix(prefix)
+        .and_then(|k| k.strip_prefix('P'))
+        .and_then(|k| k.split_once('_'))
+        .is_some_and(|(n, stat)| {
+            !n.is_empty()
+                && n.bytes().all(|b| b.is_ascii_digit())
+                && ["MIN", "MAX", "MEAN"].contains(&stat)
+        })
+}
+
+/// Error when column stats prefix cannot be used in a non-standard key
+pub struct ColumnStatsPrefixError {
+    prefix: String,
+    delim: u8,
+}
+
+impl ColumnStatsPrefixError {
+    fn check(prefix: &str, delim: u8) -> Result<(), Self> {
+        if prefix.parse::<NonStdKey>().is_err() || prefix.bytes().any(|b| b == delim) {
+            Err(Self {
+                prefix: prefix.to_string(),
+                delim,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for ColumnStatsPrefixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "column stats prefix '{}' must not start with '$' or contain \
+             the delimiter '{}'",
+            self.prefix,
+            char::from(self.delim)
+        )
+    }
+}
+
+/// Set $PAR to the number of contiguous $PnB if these disagree.
+///
+/// Return a warning if $PAR was replaced.
+fn infer_par(kws: &mut StdKeywords) -> Option<InferredParWarning> {
+    let n = (0..)
+        .take_while(|i: &usize| kws.contains_key(&Width::std((*i).into())))
+        .count();
+    let original = kws.get(&Par::std()).cloned();
+    let current = original
+        .as_ref()
+        .and_then(|p| p.trim().parse::<usize>().ok());
+    if n == 0 || current == Some(n) {
+        return None;
+    }
+    kws.insert(Par::std(), n.to_string());
+    Some(InferredParWarning {
+        original,
+        inferred: n,
+    })
+}
+
+pub enum VersionLimitError {
+    IndexDigits(MeasIndex),
+    AsciiWidth(MeasIndex, Width),
+    TooManyEvents { events: usize, max_events: usize },
+}
+
+impl fmt::Display for VersionLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::IndexDigits(i) => write!(
+                f,
+                "measurement {i} exceeds {MAX_PAR_2_0} measurements supported by most 2.0 readers"
+            ),
+            Self::AsciiWidth(i, w) => write!(
+                f,
+                "measurement {i} has width {w} which exceeds 20 chars allowed for ASCII"
+            ),
+            Self::TooManyEvents { events, max_events } => write!(
+                f,
+                "{events} events will not fit into DATA, max is {max_events} \
+                 since offsets must be stored in HEADER"
+            ),
+        }
+    }
+}
+
+/// Non-standard key recording the number of events kept by subsampling.
+///
+/// Value will be like "K/N" where K is the events kept and N is the original
+/// number of events.
+pub const SUBSAMPLE_KEY: &str = "FIREFLOW_SUBSAMPLE";
+
+/// Non-standard key recording how events were subsampled.
+pub const SUBSAMPLE_METHOD_KEY: &str = "FIREFLOW_SUBSAMPLE_METHOD";
+
+/// Non-standard key recording the number of events from each concatenated
+/// dataset, separated by commas.
+pub const CONCAT_EVENTS_KEY: &str = "FIREFLOW_CONCAT_EVENTS";
+
+/// Prefix for non-standard keys recording $FIL from each concatenated dataset.
+///
+/// Keys will be like "FIREFLOW_CONCAT_FILE<n>" where 'n' is the position of
+/// the dataset in [`CONCAT_EVENTS_KEY`] starting at 1. Each file gets its own
+/// key since $FIL may contain any character. Datasets without $FIL will have
+/// no key.
+pub const CONCAT_FILE_PREFIX: &str = "FIREFLOW_CONCAT_FILE";
+
+/// Return true if key was made with [`CONCAT_FILE_PREFIX`].
+fn is_concat_file_key(key: &str) -> bool {
+    key.strip_prefix(CONCAT_FILE_PREFIX)
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Keywords which must match for each measurement when concatenating
+struct ConcatParams {
+    width: Width,
+    scale: Option<Scale>,
+    datatype: AlphaNumType,
+    timestep: Option<Timestep>,
+}
+
+/// Non-standard key recording which events were kept by splitting.
+///
+/// Value will be like "A-B/N" for events A through B (starting at 1) of N
+/// original events, or "K/N" where K is the events selected by a mask.
+pub const SPLIT_KEY: &str = "FIREFLOW_SPLIT";
+
+/// Number of events to keep when subsampling.
+#[derive(Clone, Copy)]
+pub enum SubsampleSize {
+    /// Keep this many events (or all if there are fewer)
+    Count(usize),
+    /// Keep this fraction of events, which will be clamped to [0, 1]
+    Fraction(f64),
+}
+
+/// Order which event times must follow.
+#[derive(Clone, Copy, Default)]
+pub enum TimeOrder {
+    /// Do not check order
+    #[default]
+    Any,
+    /// Each time must be greater than or equal to the previous time
+    NonDecreasing,
+    /// Each time must be greater than the previous time
+    Increasing,
+}
+
+impl TimeOrder {
+    fn check(&self, xs: &[f64]) -> Result<(), TimeColumnError> {
+        let bad = match self {
+            Self::Any => None,
+            Self::NonDecreasing => xs.windows(2).position(|w| w[1] < w[0]),
+            Self::Increasing => xs.windows(2).position(|w| w[1] <= w[0]),
+        };
+        bad.map_or(Ok(()), |i| Err(TimeColumnError::Unordered(i + 1)))
+    }
+}
+
+/// How events should be chosen when subsampling.
+#[derive(Clone, Copy)]
+pub enum SubsampleMethod {
+    /// Pick events uniformly at random without replacement.
+    ///
+    /// The same seed will always produce the same events for a given dataset.
+    Random { seed: u64 },
+    /// Pick events at regular intervals starting with the first.
+    Systematic,
+    /// Pick the first events.
+    Head,
+}
+
+impl SubsampleSize {
+    pub(crate) fn nevents(&self, n: usize) -> usize {
+        match self {
+            Self::Count(k) => (*k).min(n),
+            Self::Fraction(f) => ((f.clamp(0.0, 1.0) * n as f64).round() as usize).min(n),
+        }
+    }
+}
+
+impl SubsampleMethod {
+    /// Return 'k' sorted row indices from 'n' rows.
+    pub(crate) fn select(&self, n: usize, k: usize) -> Vec<usize> {
+        match self {
+            Self::Head => (0..k).collect(),
+            Self::Systematic => (0..k).map(|i| i * n / k).collect(),
+            Self::Random { seed } => {
+                // Partial Fisher-Yates shuffle using splitmix64, which is good
+                // enough for picking events and avoids another dependency.
+                let mut state = *seed;
+                let mut next = || {
+                    state = state.wrapping_add(0x9E3779B97F4A7C15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+                    z ^ (z >> 31)
+                };
+                let mut xs: Vec<_> = (0..n).collect();
+                for i in 0..k {
+                    let j = i + (next() % ((n - i) as u64)) as usize;
+                    xs.swap(i, j);
+                }
+                xs.truncate(k);
+                xs.sort_unstable();
+                xs
+            }
+        }
+    }
+}
+
+impl fmt::Display for SubsampleMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Random { seed } => write!(f, "random (seed={seed})"),
+            Self::Systematic => write!(f, "systematic"),
+            Self::Head => write!(f, "head"),
+        }
+    }
+}
+
+/// Snapshot of a dataset used to detect modifications when writing.
+///
+/// This only stores hashes of TEXT and DATA. TEXT is compared using the
+/// keywords that would be written, excluding $LAST_MODIFIED, $LAST_MODIFIER,
+/// and $ORIGINALITY themselves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChangeTracker {
+    text: u64,
+    data: u64,
+}
+
+/// Parts of a dataset which differ from a [`ChangeTracker`] snapshot
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct DatasetChanges {
+    /// True if any keyword other than modification keywords changed
+    pub text: bool,
+
+    /// True if any value in DATA changed, including the number of events
+    pub data: bool,
+}
+
+const MODIFICATION_KEYS: [&str; 3] = ["$LAST_MODIFIED", "$LAST_MODIFIER", "$ORIGINALITY"];
+
+impl ChangeTracker {
+    /// Take a snapshot of a dataset, usually right after reading it.
+    pub fn new(core: &AnyCoreDataset) -> Self {
+        Self {
+            text: Self::hash_text(core),
+            data: Self::hash_data(core.as_data()),
+        }
+    }
 
-impl Optical3_2 {
-    pub fn new(width: Width, range: Range, scale: Scale) -> Self {
-        let specific = InnerOptical3_2::new(scale);
-        Optical::new_common(width, range, specific)
+    /// Return what changed in a dataset since the snapshot.
+    pub fn changes(&self, core: &AnyCoreDataset) -> DatasetChanges {
+        DatasetChanges {
+            text: Self::hash_text(core) != self.text,
+            data: Self::hash_data(core.as_data()) != self.data,
+        }
     }
-}
 
-impl<X> AsMut<CommonMeasurement> for Optical<X> {
-    fn as_mut(&mut self) -> &mut CommonMeasurement {
-        &mut self.common
+    fn hash_text(core: &AnyCoreDataset) -> u64 {
+        let mut kws: Vec<_> = core
+            .raw_keywords(None, None)
+            .into_iter()
+            .filter(|(k, _)| !MODIFICATION_KEYS.contains(&k.as_str()))
+            .collect();
+        kws.sort_unstable();
+        let mut h = DefaultHasher::new();
+        core.version().to_string().hash(&mut h);
+        kws.hash(&mut h);
+        h.finish()
+    }
+
+    fn hash_data(df: &FCSDataFrame) -> u64 {
+        let mut h = DefaultHasher::new();
+        df.nrows().hash(&mut h);
+        for c in df.iter_columns() {
+            for x in c.to_f64s() {
+                x.to_bits().hash(&mut h);
+            }
+        }
+        h.finish()
     }
 }
 
-impl<X> AsMut<CommonMeasurement> for Temporal<X> {
-    fn as_mut(&mut self) -> &mut CommonMeasurement {
-        &mut self.common
+impl DatasetChanges {
+    /// Return true if anything changed
+    pub fn any(&self) -> bool {
+        self.text || self.data
     }
 }
 
-impl<X> AsRef<CommonMeasurement> for Optical<X> {
-    fn as_ref(&self) -> &CommonMeasurement {
-        &self.common
-    }
+/// Result of checking if a dataset can be converted to another version.
+#[derive(Clone, Serialize)]
+pub struct VersionCompatibility {
+    /// Version to which conversion was attempted
+    pub version: Version,
+
+    /// Issues which would not prevent conversion
+    pub warnings: Vec<String>,
+
+    /// Issues which would prevent lossless conversion
+    pub errors: Vec<String>,
 }
 
-impl<X> AsRef<CommonMeasurement> for Temporal<X> {
-    fn as_ref(&self) -> &CommonMeasurement {
-        &self.common
+impl VersionCompatibility {
+    /// Return true if conversion would not lose any information.
+    pub fn is_lossless(&self) -> bool {
+        self.errors.is_empty()
     }
 }
 
 enum_from_disp!(
-    pub SetFloatError,
-    [Nan, NanFloatOrInt],
-    [Length, KeyLengthError]
+    /// A version conversion error from any version to any other
+    pub AnyConvertError,
+    [Infallible, ConvertError<Infallible>],
+    [Shortname, ConvertError<OptionalKwToIdentityError>],
+    [Limit, VersionLimitError]
 );
 
 pub struct ConvertError<E> {
@@ -7351,9 +10543,43 @@ enum_from_disp!(
     pub StdWriterError,
     [Layout, NewDataLayoutError],
     [Writer, ColumnWriterError],
-    [Overflow, Uint8DigitOverflow]
+    [Overflow, Uint8DigitOverflow],
+    [Target, TargetDatatypeError],
+    [Limit, VersionLimitError],
+    [StatsPrefix, ColumnStatsPrefixError]
 );
 
+pub enum TargetDatatypeError {
+    /// $PnDATATYPE was requested for a version other than 3.2
+    Overrides(Version),
+    /// $PnN does not match any measurement
+    Name(Shortname),
+    /// Float measurement has values which are not whole numbers
+    Lossy(MeasIndex),
+    /// New $PnR could not be derived
+    Range(NanFloatOrInt),
+    /// New layout does not match the number of measurements
+    Length(KeyLengthError),
+}
+
+impl fmt::Display for TargetDatatypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Overrides(v) => {
+                write!(f, "$PnDATATYPE is only allowed in FCS3.2, version is {v}")
+            }
+            Self::Name(n) => write!(f, "no measurement named '{n}' to convert"),
+            Self::Lossy(i) => write!(
+                f,
+                "measurement {i} has values which are not whole numbers \
+                 and cannot be converted to integers without loss"
+            ),
+            Self::Range(e) => write!(f, "could not set $PnR: {e}"),
+            Self::Length(e) => e.fmt(f),
+        }
+    }
+}
+
 pub enum ExistingLinkError {
     Trigger,
     UnstainedCenters,
@@ -7379,6 +10605,95 @@ enum_from_disp!(
     [Link, SpilloverLinkError]
 );
 
+pub enum CompensateError {
+    Singular,
+    UnknownNames(NonEmpty<Shortname>),
+    WrongSize { matrix: usize, par: usize },
+}
+
+impl fmt::Display for CompensateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Singular => write!(f, "compensation matrix is not invertible"),
+            Self::UnknownNames(ns) => write!(
+                f,
+                "spillover names do not match any $PnN: {}",
+                ns.iter().join(", ")
+            ),
+            Self::WrongSize { matrix, par } => write!(
+                f,
+                "compensation matrix has {matrix} rows/columns but $PAR is {par}"
+            ),
+        }
+    }
+}
+
+pub enum SubsetDecodeError {
+    MissingBits,
+    Bits(u32),
+    ByteOrd,
+    Length { expected: usize, actual: usize },
+}
+
+impl fmt::Display for SubsetDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::MissingBits => write!(f, "$CSVBITS is needed to read subsets"),
+            Self::Bits(x) => write!(f, "$CSVBITS must be between 1 and 64, got {x}"),
+            Self::ByteOrd => write!(
+                f,
+                "$BYTEORD must be big or little endian to read multi-byte subsets"
+            ),
+            Self::Length { expected, actual } => write!(
+                f,
+                "ANALYSIS must be {expected} bytes to hold subsets, got {actual}"
+            ),
+        }
+    }
+}
+
+pub enum GateEvalError {
+    GatedMeasurement(RegionIndex),
+    MissingMeasurement(RegionIndex, MeasIndex),
+    MissingRegion(RegionIndex),
+}
+
+impl fmt::Display for GateEvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::GatedMeasurement(r) => write!(
+                f,
+                "region {r} refers to a gated measurement, which is not in DATA"
+            ),
+            Self::MissingMeasurement(r, m) => {
+                write!(
+                    f,
+                    "region {r} refers to measurement {m}, which is not in DATA"
+                )
+            }
+            Self::MissingRegion(r) => {
+                write!(f, "region {r} is in $GATING but has no $RnI/$RnW")
+            }
+        }
+    }
+}
+
+pub enum TimeColumnError {
+    Missing,
+    UnknownName(Shortname),
+    Unordered(usize),
+}
+
+impl fmt::Display for TimeColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Missing => write!(f, "dataset has no time measurement"),
+            Self::UnknownName(n) => write!(f, "no measurement with $PnN '{n}'"),
+            Self::Unordered(i) => write!(f, "time at event {i} is out of order"),
+        }
+    }
+}
+
 pub struct SpilloverLinkError;
 
 impl fmt::Display for SpilloverLinkError {
@@ -7447,6 +10762,103 @@ impl fmt::Display for MeasDataMismatchError {
 
 pub struct MissingMeasurementNameError(Shortname);
 
+/// Error when datasets cannot be concatenated.
+///
+/// Indices refer to the position of the dataset being appended, starting at 0.
+pub enum ConcatError {
+    /// Dataset has a different version
+    Version(usize, Version),
+    /// Dataset does not have the same set of $PnN
+    Names(usize),
+    /// Measurement has a different $PnB, $PnE, datatype, or $TIMESTEP
+    Incompatible {
+        index: usize,
+        name: Shortname,
+        key: &'static str,
+    },
+}
+
+/// Error when events cannot be selected for splitting
+pub enum SplitError {
+    /// Range is reversed or extends past the number of events
+    Range(ops::Range<usize>, usize),
+    /// Mask length does not match the number of events
+    MaskLength(usize, usize),
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Range(r, n) => write!(
+                f,
+                "event range {}..{} is not valid for {n} events",
+                r.start, r.end
+            ),
+            Self::MaskLength(m, n) => {
+                write!(f, "event mask has length {m} but DATA has {n} events")
+            }
+        }
+    }
+}
+
+impl fmt::Display for ConcatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Version(i, v) => {
+                write!(f, "dataset {i} to append has different version ({v})")
+            }
+            Self::Names(i) => write!(f, "dataset {i} to append has different $PnN"),
+            Self::Incompatible { index, name, key } => write!(
+                f,
+                "measurement '{name}' in dataset {index} to append has different {key}"
+            ),
+        }
+    }
+}
+
+/// Error when a transform cannot be made for a measurement
+pub struct MeasTransformError {
+    pub name: Shortname,
+    pub error: TransformError,
+}
+
+enum_from_disp!(
+    pub RenameChannelError,
+    [Missing, MissingMeasurementNameError],
+    [Rename, RenameError]
+);
+
+enum_from_disp!(
+    pub ReorderChannelsError,
+    [Missing, MissingMeasurementNameError],
+    [Reorder, ReorderError]
+);
+
+enum_from_disp!(
+    pub TransformDataError,
+    [Missing, MissingMeasurementNameError],
+    [Params, MeasTransformError]
+);
+
+impl fmt::Display for MeasTransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not transform '{}': {}", self.name, self.error)
+    }
+}
+
+pub struct NonListModeError(pub Mode);
+
+impl fmt::Display for NonListModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "$MODE is '{}', which means DATA holds histogram counts rather \
+             than events and will be misread as list mode",
+            self.0
+        )
+    }
+}
+
 impl fmt::Display for MissingMeasurementNameError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "name {} does not exist in measurements", self.0)
@@ -7459,7 +10871,11 @@ enum_from_disp!(
     [Layout, NewDataLayoutError],
     [Data, NewDataReaderError],
     [Analysis, NewAnalysisReaderError],
-    [DataRead, ReadDataError]
+    [Selection, MissingMeasurementNameError],
+    [DataRead, ReadDataError],
+    [Crc, CrcMismatchError],
+    [DataHash, DataHashMismatchError],
+    [Mode, NonListModeError]
 );
 
 enum_from_disp!(
@@ -7467,16 +10883,52 @@ enum_from_disp!(
     [TEXT, LookupMeasWarning],
     [Layout, NewDataLayoutWarning],
     [Data, NewDataReaderWarning],
-    [Analysis, NewAnalysisReaderWarning]
+    [Analysis, NewAnalysisReaderWarning],
+    [ByteOrd, ByteOrdGuess],
+    [ReadData, ReadDataWarning],
+    [Crc, CrcMismatchError],
+    [DataHash, DataHashMismatchError],
+    [Mode, NonListModeError]
 );
 
 enum_from_disp!(
     pub LookupMeasWarning,
     [Parse, LookupKeysWarning],
     [Pattern, NonStdMeasRegexError],
-    [Pseudostandard, PseudostandardError]
+    [Pseudostandard, PseudostandardError],
+    [Typed, TypedKeywordError],
+    [Registered, RegisteredKeywordError],
+    [InferredPar, InferredParWarning]
 );
 
+impl Locate for LookupMeasWarning {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        match self {
+            Self::Parse(x) => x.locate(offsets),
+            Self::Typed(x) => x.locate(offsets),
+            _ => (),
+        }
+    }
+}
+
+impl Locate for StdDatasetFromRawError {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        match self {
+            Self::TEXT(x) => x.locate(offsets),
+            Self::Data(NewDataReaderError::ParseTot(x)) => x.locate(offsets),
+            _ => (),
+        }
+    }
+}
+
+impl Locate for StdDatasetFromRawWarning {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        if let Self::TEXT(x) = self {
+            x.locate(offsets);
+        }
+    }
+}
+
 pub struct RegionToMeasIndexError(GateIndex);
 
 impl fmt::Display for RegionToMeasIndexError {
@@ -7605,7 +11057,7 @@ impl fmt::Display for OpticalNonLinearError {
 
 enum_from_disp!(
     pub MetarootConvertError,
-    [NoCyt, NoCytError],
+    [NoCyt, ConvertNoCytError],
     [Byteord, EndianToByteOrdError],
     [Endian, SingleWidthError],
     [Mode, ModeNotListError],
@@ -7632,7 +11084,8 @@ enum_from_disp!(
     [Loss, AnyMetarootKeyLossError],
     [Optical, OpticalConvertWarning],
     [Temporal, TemporalConvertError],
-    [Comp2_0, Comp2_0TransferError]
+    [Comp2_0, Comp2_0TransferError],
+    [Limit, VersionLimitError]
 );
 
 enum_from_disp!(
@@ -7819,12 +11272,32 @@ where
 
 pub struct NoCytError;
 
+pub struct KeywordVersionError(&'static str, Version);
+
+impl fmt::Display for KeywordVersionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} is not supported in {}", self.0, self.1)
+    }
+}
+
 impl fmt::Display for NoCytError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "$CYT is missing")
     }
 }
 
+pub struct ConvertNoCytError;
+
+impl fmt::Display for ConvertNoCytError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "$CYT is required in 3.2 but is missing and cannot be \
+             derived, set $CYT before converting"
+        )
+    }
+}
+
 pub struct ModeNotListError;
 
 impl fmt::Display for ModeNotListError {
@@ -7832,3 +11305,705 @@ impl fmt::Display for ModeNotListError {
         write!(f, "$MODE is not L")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::fcs_read_std_dataset_from_bytes;
+    use crate::api::tests::{fcs_3_0_uint8, fcs_uint8};
+    use crate::config::DataReadConfig;
+    use crate::text::named_vec::ReorderError;
+    use crate::validated::pattern::TimePattern;
+
+    fn read(bytes: &[u8]) -> AnyCoreDataset {
+        match fcs_read_std_dataset_from_bytes(bytes, &DataReadConfig::default()) {
+            Ok(x) => x.resolve(|_| ()).0.dataset.standardized.core,
+            Err(e) => {
+                let (_, msg) = e.resolve(
+                    |_| (),
+                    |f| match f {
+                        Failure::Single(t) => t.to_string(),
+                        Failure::Many(t, es) => format!("{t}: {}", es.iter().join("; ")),
+                    },
+                );
+                panic!("{msg}")
+            }
+        }
+    }
+
+    fn write(core: &AnyCoreDataset, conf: &WriteConfig) -> Vec<u8> {
+        let mut h = BufWriter::new(vec![]);
+        core.h_write(&mut h, conf).ok().unwrap();
+        h.into_inner().ok().unwrap()
+    }
+
+    fn columns(core: &AnyCoreDataset) -> Vec<Vec<f64>> {
+        core.as_data().iter_columns().map(|c| c.to_f64s()).collect()
+    }
+
+    fn target(datatype: Option<AlphaNumType>, overrides: &[(&str, NumType)]) -> WriteConfig {
+        WriteConfig {
+            target_datatype: TargetDatatype {
+                datatype,
+                overrides: overrides
+                    .iter()
+                    .map(|(n, t)| (Shortname::new_unchecked(n), *t))
+                    .collect(),
+            },
+            ..WriteConfig::default()
+        }
+    }
+
+    fn two_measurements() -> AnyCoreDataset {
+        read(&fcs_3_0_uint8(&["FSC", "SSC"], &[1, 2, 3, 4, 5, 6]))
+    }
+
+    #[test]
+    fn test_target_datatype_int_to_float_round_trip() {
+        let core = two_measurements();
+        let expected = vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]];
+
+        let float = read(&write(&core, &target(Some(AlphaNumType::Single), &[])));
+        let float_kws = float.raw_keywords(None, None);
+        assert_eq!(float_kws.get("$DATATYPE").map(String::as_str), Some("F"));
+        assert_eq!(float_kws.get("$P1B").map(String::as_str), Some("32"));
+        assert_eq!(columns(&float), expected);
+
+        let int = read(&write(&float, &target(Some(AlphaNumType::Integer), &[])));
+        let int_kws = int.raw_keywords(None, None);
+        assert_eq!(int_kws.get("$DATATYPE").map(String::as_str), Some("I"));
+        assert_eq!(int_kws.get("$P1B").map(String::as_str), Some("8"));
+        assert_eq!(columns(&int), expected);
+    }
+
+    #[test]
+    fn test_target_datatype_ascii_round_trip() {
+        let core = two_measurements();
+        let expected = vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]];
+
+        let bytes = write(&core, &target(Some(AlphaNumType::Ascii), &[]));
+        let ascii = read(&bytes);
+        let ascii_kws = ascii.raw_keywords(None, None);
+        assert_eq!(ascii_kws.get("$DATATYPE").map(String::as_str), Some("A"));
+        let width: usize = ascii_kws["$P1B"].parse().unwrap();
+        assert!(width > 1);
+        assert_eq!(columns(&ascii), expected);
+
+        // fixed-width fields are left-padded with '0'
+        let begin: usize = str::from_utf8(&bytes[26..34])
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        let first = format!("{:0>width$}", 1);
+        assert_eq!(&bytes[begin..(begin + width)], first.as_bytes());
+    }
+
+    #[test]
+    fn test_convert_3_1() {
+        let bytes = fcs_uint8("FCS3.1", &["FSC", "SSC"], &[1, 2, 3, 4], &[]);
+        for (to, byteord) in [(Version::FCS3_0, "1"), (Version::FCS2_0, "1")] {
+            let new = read(&bytes)
+                .try_convert_version(to, false)
+                .ok()
+                .unwrap()
+                .terminate(())
+                .ok()
+                .unwrap()
+                .resolve(|_| ())
+                .0;
+            let converted = read(&write(&new, &WriteConfig::default()));
+            let kws = converted.raw_keywords(None, None);
+            assert!(to == converted.version());
+            assert_eq!(kws.get("$BYTEORD").map(String::as_str), Some(byteord));
+            assert_eq!(columns(&converted), vec![vec![1.0, 3.0], vec![2.0, 4.0]]);
+        }
+
+        let mut core = read(&bytes);
+        core.set_cyt(None).ok().unwrap();
+        let Err(e) = core.try_convert_version(Version::FCS3_2, true) else {
+            panic!("converted to 3.2 without $CYT")
+        };
+        let (_, msg) = e.terminate(()).resolve(
+            |_| (),
+            |f| match f {
+                Failure::Single(_) => String::new(),
+                Failure::Many(_, es) => es.iter().join("; "),
+            },
+        );
+        assert!(msg.contains("set $CYT"), "{msg}");
+    }
+
+    #[test]
+    fn test_column_stats_keywords() {
+        let stats = |prefix: &str| WriteConfig {
+            column_stats_prefix: Some(prefix.into()),
+            ..WriteConfig::default()
+        };
+        let bytes = fcs_uint8(
+            "FCS3.0",
+            &["FSC", "SSC"],
+            &[1, 2, 3, 4],
+            &[("X_P9_MAX", "7")],
+        );
+        let core = read(&bytes);
+
+        let kws = read(&write(&core, &stats("X_"))).raw_keywords(None, None);
+        assert_eq!(kws.get("X_P1_MIN").map(String::as_str), Some("1"));
+        assert_eq!(kws.get("X_P2_MAX").map(String::as_str), Some("4"));
+        // stale keywords for measurements which don't exist are dropped
+        assert!(!kws.contains_key("X_P9_MAX"));
+
+        let kws_other = read(&write(&core, &stats("Y_"))).raw_keywords(None, None);
+        assert_eq!(kws_other.get("X_P9_MAX").map(String::as_str), Some("7"));
+
+        for bad in ["$X_", "X\u{1e}"] {
+            let mut h = BufWriter::new(vec![]);
+            assert!(core.h_write(&mut h, &stats(bad)).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_concat() {
+        let first = read(&fcs_uint8(
+            "FCS3.0",
+            &["FSC", "SSC"],
+            &[1, 2, 3, 4],
+            &[("$FIL", "a,b.fcs")],
+        ));
+        let second = read(&fcs_3_0_uint8(&["SSC", "FSC"], &[6, 5]));
+        let merged = first.concat(std::slice::from_ref(&second)).ok().unwrap();
+        assert_eq!(
+            columns(&merged),
+            vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]]
+        );
+        let kws = merged.raw_keywords(None, None);
+        assert_eq!(kws.get(CONCAT_EVENTS_KEY).map(String::as_str), Some("2,1"));
+        let file1 = format!("{CONCAT_FILE_PREFIX}1");
+        let file2 = format!("{CONCAT_FILE_PREFIX}2");
+        assert_eq!(kws.get(&file1).map(String::as_str), Some("a,b.fcs"));
+        assert!(!kws.contains_key(&file2));
+
+        let mut float = second.clone();
+        if let AnyCoreDataset::FCS3_0(x) = &mut float {
+            x.metaroot.datatype = AlphaNumType::Single;
+        }
+        assert!(matches!(
+            first.concat(&[float]),
+            Err(ConcatError::Incompatible {
+                key: "$DATATYPE",
+                ..
+            })
+        ));
+
+        let mut conf = DataReadConfig::default();
+        conf.standard.time.pattern = Some(TimePattern::default());
+        let with_time = |timestep| {
+            let extra = [("$TIMESTEP", timestep)];
+            let bytes = fcs_uint8("FCS3.0", &["Time", "SSC"], &[1, 2], &extra);
+            let out = fcs_read_std_dataset_from_bytes(&bytes, &conf).ok().unwrap();
+            out.resolve(|_| ()).0.dataset.standardized.core
+        };
+        assert!(matches!(
+            with_time("0.1").concat(&[with_time("0.2")]),
+            Err(ConcatError::Incompatible {
+                key: "$TIMESTEP",
+                ..
+            })
+        ));
+        assert!(with_time("0.1").concat(&[with_time("0.1")]).is_ok());
+    }
+
+    #[test]
+    fn test_empty_data_round_trip() {
+        let is_u08 = |core: &AnyCoreDataset| {
+            core.as_data()
+                .iter_columns()
+                .all(|c| matches!(c, AnyFCSColumn::U08(_)))
+        };
+        let check = |core: &AnyCoreDataset| {
+            assert_eq!(core.as_data().nrows(), 0);
+            assert_eq!(core.as_data().ncols(), 2);
+            assert!(is_u08(core));
+        };
+
+        // $TOT=0 with one byte of DATA, which is shorter than one event
+        let bytes = fcs_3_0_uint8(&["FSC", "SSC"], &[0]);
+        let core = read(&bytes);
+        check(&core);
+        let written = write(&core, &WriteConfig::default());
+        assert!(written.windows(7).any(|w| w == b"$TOT\x1e0\x1e"));
+        check(&read(&written));
+
+        let text = crate::api::fcs_read_std_text_from_bytes(&bytes, &StdTextReadConfig::default())
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0
+            .standardized;
+        let empty = text.into_coredataset_empty();
+        check(&empty);
+        check(&read(&write(&empty, &WriteConfig::default())));
+    }
+
+    #[test]
+    fn test_compatibility() {
+        let find = |core: &AnyCoreDataset, v: Version| {
+            core.compatibility()
+                .into_iter()
+                .find(|c| c.version == v)
+                .unwrap()
+        };
+
+        let mut core = read(&fcs_uint8("FCS3.1", &["FSC", "SSC"], &[1, 2, 3, 4], &[]));
+        core.set_cyt(None).ok().unwrap();
+        let c3_2 = find(&core, Version::FCS3_2);
+        assert!(
+            c3_2.errors.iter().any(|e| e.contains("set $CYT")),
+            "{:?}",
+            c3_2.errors
+        );
+        assert!(find(&core, Version::FCS3_0).errors.is_empty());
+
+        let owned: Vec<_> = (1..=MAX_PAR_2_0 + 1).map(|i| format!("P{i}")).collect();
+        let names: Vec<_> = owned.iter().map(String::as_str).collect();
+        let wide = read(&fcs_3_0_uint8(&names, &vec![0; names.len()]));
+        let c2_0 = find(&wide, Version::FCS2_0);
+        assert_eq!(c2_0.warnings.len(), 1, "{:?}", c2_0.warnings);
+    }
+
+    #[test]
+    fn test_version_limit_events_boundary() {
+        let core = read(&fcs_uint8("FCS2.0", &["FSC"], &[1], &[]));
+        let AnyCoreDataset::FCS2_0(x) = &core else {
+            panic!("not 2.0")
+        };
+
+        // DATA begins where the writer puts it
+        let bytes = write(&core, &WriteConfig::default());
+        let header_begin: u64 = str::from_utf8(&bytes[26..34])
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        assert_eq!(x.data_begin(Tot(1), vec![], vec![], None), header_begin);
+
+        // each event is one byte, so DATA may have one event per byte between
+        // its beginning and the max HEADER offset (inclusive)
+        let begin = x.data_begin(Tot(99_999_000), vec![], vec![], None);
+        let max_events = (u64::from(MAX_HEADER_OFFSET) + 1 - begin) as usize;
+        let check = |n| {
+            x.check_version_limits_inner(Version::FCS2_0, Some(n), begin)
+                .into_parts()
+                .2
+        };
+        assert!(check(max_events).is_empty());
+        let es = check(max_events + 1);
+        assert!(matches!(
+            es.as_slice(),
+            [VersionLimitError::TooManyEvents { max_events: m, .. }] if *m == max_events
+        ));
+    }
+
+    #[test]
+    fn test_convert_index_digits_limit() {
+        let limit_warnings = |par: usize| {
+            let owned: Vec<_> = (1..=par).map(|i| format!("P{i}")).collect();
+            let names: Vec<_> = owned.iter().map(String::as_str).collect();
+            read(&fcs_3_0_uint8(&names, &vec![0; par]))
+                .try_convert_version(Version::FCS2_0, false)
+                .ok()
+                .unwrap()
+                .into_parts()
+                .1
+                .into_iter()
+                .filter(|w| matches!(w, MetarootConvertWarning::Limit(_)))
+                .count()
+        };
+        assert_eq!(limit_warnings(MAX_PAR_2_0), 0);
+        assert_eq!(limit_warnings(MAX_PAR_2_0 + 1), 1);
+    }
+
+    #[test]
+    fn test_target_datatype_overrides_3_2() {
+        let core = read(&fcs_3_0_uint8(&["FSC", "SSC", "FL1"], &[1, 2, 3, 4, 5, 6]))
+            .try_convert_version(Version::FCS3_2, true)
+            .ok()
+            .unwrap()
+            .terminate(())
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+        let conf = target(None, &[("SSC", NumType::Single)]);
+        let new = read(&write(&core, &conf));
+        let kws = new.raw_keywords(None, None);
+        assert_eq!(kws.get("$DATATYPE").map(String::as_str), Some("I"));
+        assert_eq!(kws.get("$P1DATATYPE"), None);
+        assert_eq!(kws.get("$P2DATATYPE").map(String::as_str), Some("F"));
+        assert_eq!(kws.get("$P3DATATYPE"), None);
+        assert_eq!(
+            columns(&new),
+            vec![vec![1.0, 4.0], vec![2.0, 5.0], vec![3.0, 6.0]]
+        );
+    }
+
+    fn write_with_data(core: &AnyCoreDataset, raw: &[u8]) -> Vec<u8> {
+        let mut h = BufWriter::new(vec![]);
+        core.h_write_with_data(&mut h, &WriteConfig::default(), Some(raw))
+            .ok()
+            .unwrap();
+        h.into_inner().ok().unwrap()
+    }
+
+    #[test]
+    fn test_write_with_data_modified() {
+        let mut core = two_measurements();
+        let raw = [1, 2, 3, 4, 5, 6];
+        assert_eq!(
+            columns(&read(&write_with_data(&core, &raw))),
+            vec![vec![1.0, 3.0, 5.0], vec![2.0, 4.0, 6.0]]
+        );
+        // DATA has the same length as before but different values, so the
+        // original bytes must not be used
+        let cols = vec![
+            U08Column::from(vec![9, 9, 9]).into(),
+            U08Column::from(vec![8, 8, 8]).into(),
+        ];
+        match &mut core {
+            AnyCoreDataset::FCS3_0(x) => x.set_data(cols).ok().unwrap(),
+            _ => panic!("expected 3.0 dataset"),
+        }
+        assert_eq!(
+            columns(&read(&write_with_data(&core, &raw))),
+            vec![vec![9.0, 9.0, 9.0], vec![8.0, 8.0, 8.0]]
+        );
+    }
+
+    #[test]
+    fn test_target_datatype_overrides_pre_3_2() {
+        let mut core = two_measurements();
+        let t = target(None, &[("SSC", NumType::Single)]).target_datatype;
+        let res = core.set_target_datatype(&t, false);
+        assert!(matches!(
+            res,
+            Err(TargetDatatypeError::Overrides(Version::FCS3_0))
+        ));
+    }
+
+    #[test]
+    fn test_target_datatype_lossy() {
+        let mut core = two_measurements();
+        let single = target(Some(AlphaNumType::Single), &[]).target_datatype;
+        let int = target(Some(AlphaNumType::Integer), &[]).target_datatype;
+        core.set_target_datatype(&single, true).ok().unwrap();
+        let cols = vec![
+            F32Column::from(vec![1.0, 3.0, 5.0]).into(),
+            F32Column::from(vec![2.0, 4.5, 6.0]).into(),
+        ];
+        match &mut core {
+            AnyCoreDataset::FCS3_0(x) => x.set_data(cols).ok().unwrap(),
+            _ => panic!("expected 3.0 dataset"),
+        }
+        let res = core.clone().set_target_datatype(&int, true);
+        assert!(matches!(res, Err(TargetDatatypeError::Lossy(i)) if i == MeasIndex::from(1)));
+        assert!(core.set_target_datatype(&int, false).is_ok());
+    }
+
+    #[test]
+    fn test_gate_membership() {
+        // events are (1,1), (5,5), (9,5), and (5,9)
+        let bytes = fcs_uint8(
+            "FCS3.2",
+            &["X", "Y"],
+            &[1, 1, 5, 5, 9, 5, 5, 9],
+            &[
+                ("$GATING", "R1 OR (R2 AND (NOT R3))"),
+                ("$R1I", "P1,P2"),
+                ("$R1W", "2,2;8,8"),
+                ("$R2I", "P1,P2"),
+                ("$R2W", "0,0;10,0;5,10"),
+                ("$R3I", "P1"),
+                ("$R3W", "4,6"),
+            ],
+        );
+        let res = read(&bytes).gate_membership().unwrap().ok().unwrap();
+        let masks: Vec<_> = res.regions.iter().map(|r| r.mask.clone()).collect();
+        // rectangle, polygon, and univariate region with inclusive bounds
+        assert_eq!(
+            masks,
+            vec![
+                vec![false, true, false, false],
+                vec![true, true, false, true],
+                vec![false, true, false, true],
+            ]
+        );
+        assert_eq!(res.mask, vec![true, true, false, false]);
+        assert_eq!(res.count, 2);
+    }
+
+    #[test]
+    fn test_gate_membership_none() {
+        assert!(two_measurements().gate_membership().is_none());
+    }
+
+    fn names(xs: &[&str]) -> Vec<Shortname> {
+        xs.iter().copied().map(Shortname::new_unchecked).collect()
+    }
+
+    fn linked_3_2() -> CoreDataset3_2 {
+        let bytes = fcs_uint8(
+            "FCS3.2",
+            &["FSC", "SSC", "FL1"],
+            &[1, 2, 3, 4, 5, 6],
+            &[
+                ("$TR", "FL1,5"),
+                ("$SPILLOVER", "2,FSC,FL1,1,0.1,0.2,1"),
+                ("$UNSTAINEDCENTERS", "2,SSC,FL1,1,2"),
+                ("$GATING", "R1 AND R2"),
+                ("$R1I", "P3"),
+                ("$R1W", "1,2"),
+                ("$R2I", "P1,P3"),
+                ("$R2W", "0,0;10,0;10,10"),
+            ],
+        );
+        match read(&bytes) {
+            AnyCoreDataset::FCS3_2(x) => *x,
+            _ => panic!("expected FCS3.2"),
+        }
+    }
+
+    #[test]
+    fn test_reorder_channels() {
+        let mut core = linked_3_2();
+        core.reorder_channels(&names(&["FL1", "FSC", "SSC"])[..])
+            .ok()
+            .unwrap();
+        let kws = AnyCoreDataset::from(core.clone()).raw_keywords(None, None);
+        let get = |k: &str| kws.get(k).map(String::as_str);
+        assert_eq!(get("$P1N"), Some("FL1"));
+        assert_eq!(get("$P2N"), Some("FSC"));
+        assert_eq!(get("$P3N"), Some("SSC"));
+        assert_eq!(get("$TR"), Some("FL1,5"));
+        assert_eq!(get("$R1I"), Some("P1"));
+        assert_eq!(get("$R2I"), Some("P2,P1"));
+        let cols: Vec<_> = core.data.iter_columns().map(|c| c.to_f64s()).collect();
+        assert_eq!(cols, vec![vec![3.0, 6.0], vec![1.0, 4.0], vec![2.0, 5.0]]);
+    }
+
+    #[test]
+    fn test_reorder_channels_reject() {
+        let mut core = linked_3_2();
+        let duplicated = core.reorder_channels(&names(&["FL1", "FSC", "FSC"])[..]);
+        assert!(matches!(
+            duplicated,
+            Err(ReorderChannelsError::Reorder(ReorderError::Permutation))
+        ));
+        let missing = core.reorder_channels(&names(&["FL1", "FSC", "FL2"])[..]);
+        assert!(matches!(missing, Err(ReorderChannelsError::Missing(_))));
+    }
+
+    #[test]
+    fn test_reorder_channels_default_names() {
+        let bytes = fcs_uint8("FCS3.0", &["", "P3", "FSC"], &[1, 2, 3], &[]);
+        let AnyCoreDataset::FCS3_0(mut core) = read(&bytes) else {
+            panic!("expected FCS3.0");
+        };
+        // the measurement without $PnN would be renamed to P3
+        let res = core.reorder_channels(&names(&["FSC", "P3", "P1"])[..]);
+        assert!(matches!(
+            res,
+            Err(ReorderChannelsError::Reorder(ReorderError::NonUnique))
+        ));
+        core.reorder_channels(&names(&["P1", "FSC", "P3"])[..])
+            .ok()
+            .unwrap();
+        // the measurement without $PnN is now the second and renamed to P2
+        core.reorder_channels(&names(&["FSC", "P1", "P3"])[..])
+            .ok()
+            .unwrap();
+        let kws = AnyCoreDataset::from(*core).raw_keywords(None, None);
+        assert_eq!(kws.get("$P1N").map(String::as_str), Some("FSC"));
+        assert_eq!(kws.get("$P2N"), None);
+        assert_eq!(kws.get("$P3N").map(String::as_str), Some("P3"));
+    }
+
+    #[test]
+    fn test_rename_channel() {
+        let mut core = linked_3_2();
+        let res = core.rename_channel(
+            &Shortname::new_unchecked("FL1"),
+            Shortname::new_unchecked("SSC"),
+        );
+        assert!(matches!(res, Err(RenameChannelError::Rename(_))));
+        core.rename_channel(
+            &Shortname::new_unchecked("FL1"),
+            Shortname::new_unchecked("FITC"),
+        )
+        .ok()
+        .unwrap();
+        let kws = AnyCoreDataset::from(core).raw_keywords(None, None);
+        let get = |k: &str| kws.get(k).map(String::as_str).unwrap_or_default();
+        assert_eq!(get("$P3N"), "FITC");
+        assert_eq!(get("$TR"), "FITC,5");
+        assert!(get("$SPILLOVER").starts_with("2,FSC,FITC,"));
+        // order of $UNSTAINEDCENTERS is not preserved
+        let centers: Vec<_> = get("$UNSTAINEDCENTERS").split(',').collect();
+        assert!(centers[1..3].contains(&"FITC") && !centers.contains(&"FL1"));
+        // regions refer to measurements by index so are unaffected
+        assert_eq!(get("$R1I"), "P3");
+    }
+
+    #[test]
+    fn test_add_remove_measurement() {
+        let mut core = AnyCoreDataset::from(linked_3_2());
+        let ssc = Shortname::new_unchecked("SSC");
+        assert_eq!(core.remove_measurement(&ssc), Some(MeasIndex::from(1)));
+        assert_eq!(core.remove_measurement(&ssc), None);
+        let kws = core.raw_keywords(None, None);
+        let get = |k: &str| kws.get(k).map(String::as_str).unwrap_or_default();
+        assert_eq!(get("$PAR"), "2");
+        assert_eq!(get("$P2N"), "FL1");
+        assert_eq!(get("$P3N"), "");
+        assert_eq!(get("$TR"), "FL1,5");
+        assert_eq!(get("$SPILLOVER"), "2,FSC,FL1,1,0.1,0.2,1");
+        assert_eq!(get("$UNSTAINEDCENTERS"), "1,FL1,2");
+        // regions after the removed measurement are renumbered
+        assert_eq!(get("$R1I"), "P2");
+        assert_eq!(get("$R2I"), "P1,P2");
+        assert_eq!(columns(&core), vec![vec![1.0, 4.0], vec![3.0, 6.0]]);
+
+        core.remove_measurement(&Shortname::new_unchecked("FL1"));
+        let col = AnyFCSColumn::from(FCSColumn::from(vec![7_u8, 8]));
+        core.add_measurement(
+            Shortname::new_unchecked("FL2"),
+            "8".parse().unwrap(),
+            "256".parse().ok().unwrap(),
+            col,
+        )
+        .ok()
+        .unwrap();
+        let added_kws = core.raw_keywords(None, None);
+        let added = |k: &str| added_kws.get(k).map(String::as_str);
+        assert_eq!(added("$PAR"), Some("2"));
+        assert_eq!(added("$P2N"), Some("FL2"));
+        assert_eq!(added("$P2E"), Some("0,0"));
+        // keywords which referred to the removed measurement are dropped
+        assert_eq!(added("$TR"), None);
+        assert_eq!(added("$SPILLOVER"), None);
+        assert_eq!(added("$UNSTAINEDCENTERS"), None);
+        assert_eq!(added("$GATING"), None);
+        assert_eq!(added("$R1I"), None);
+        assert_eq!(columns(&core), vec![vec![1.0, 4.0], vec![7.0, 8.0]]);
+
+        let dup = AnyFCSColumn::from(FCSColumn::from(vec![0_u8, 0]));
+        let res = core.add_measurement(
+            Shortname::new_unchecked("FSC"),
+            "8".parse().unwrap(),
+            "256".parse().ok().unwrap(),
+            dup,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_set_cyt_and_plate() {
+        let mut core = two_measurements();
+        core.set_cyt(Some(Cyt("Aurora".into()))).ok().unwrap();
+        assert_eq!(core.cyt(), Some("Aurora"));
+        core.set_cyt(None).ok().unwrap();
+        assert_eq!(core.cyt(), None);
+        let plate = PlateData {
+            plateid: Some(Plateid("P1".into())).into(),
+            ..PlateData::default()
+        };
+        assert!(core.set_plate(plate.clone()).is_err());
+
+        let mut new = AnyCoreDataset::from(linked_3_2());
+        assert!(new.set_cyt(None).is_err());
+        new.set_plate(plate).ok().unwrap();
+        let kws = new.raw_keywords(None, None);
+        assert_eq!(kws.get("$PLATEID").map(String::as_str), Some("P1"));
+    }
+
+    fn read_deprecated(disallow_deprecated: bool) -> (Vec<String>, Vec<String>) {
+        // $MODE is deprecated in 3.2 and $PLATEID is absent
+        let bytes = fcs_uint8("FCS3.2", &["FSC"], &[1, 2], &[]);
+        let conf = StdTextReadConfig {
+            disallow_deprecated,
+            ..StdTextReadConfig::default()
+        };
+        match crate::api::fcs_read_std_text_from_bytes(&bytes, &conf) {
+            Ok(t) => (
+                t.resolve(|ws| ws.iter().map(|w| w.to_string()).collect()).1,
+                vec![],
+            ),
+            Err(f) => f.resolve(
+                |ws| ws.iter().map(|w| w.to_string()).collect(),
+                |x| match x {
+                    Failure::Single(t) => vec![t.to_string()],
+                    Failure::Many(_, es) => es.iter().map(|e| e.to_string()).collect(),
+                },
+            ),
+        }
+    }
+
+    #[test]
+    fn test_deprecated_warning() {
+        let (ws, es) = read_deprecated(false);
+        assert!(es.is_empty());
+        assert_eq!(
+            ws.iter()
+                .filter(|w| w.starts_with("deprecated key"))
+                .collect::<Vec<_>>(),
+            vec!["deprecated key: $MODE"]
+        );
+    }
+
+    #[test]
+    fn test_deprecated_error() {
+        let (ws, es) = read_deprecated(true);
+        assert!(!ws.iter().any(|w| w.starts_with("deprecated key")));
+        assert_eq!(es, vec!["deprecated key: $MODE"]);
+    }
+
+    fn read_missing_name(cyt: bool) -> (Vec<String>, Vec<String>) {
+        // $P2N is required in 3.2; renaming $CYT keeps all offsets valid while
+        // making it missing
+        let bytes = fcs_uint8("FCS3.2", &["FSC", ""], &[1, 2], &[("$LOST", "many")]);
+        let text = String::from_utf8(bytes).unwrap();
+        let s = if cyt {
+            text
+        } else {
+            text.replace("$CYT/", "$XYZ/")
+        };
+        let conf = StdTextReadConfig {
+            allow_pseudostandard: true,
+            ..StdTextReadConfig::default()
+        };
+        match crate::api::fcs_read_std_text_from_bytes(s.as_bytes(), &conf) {
+            Ok(_) => panic!("$P2N should be missing"),
+            Err(f) => f.resolve(
+                |ws| ws.iter().map(|w| w.to_string()).collect(),
+                |x| match x {
+                    Failure::Single(t) => vec![t.to_string()],
+                    Failure::Many(_, es) => es.iter().map(|e| e.to_string()).collect(),
+                },
+            ),
+        }
+    }
+
+    #[test]
+    fn test_measurement_and_metaroot_errors() {
+        // metaroot succeeds, so its warnings are absorbed into the failure
+        let (ws, es) = read_missing_name(true);
+        assert_eq!(es, vec!["missing required key: $P2N"]);
+        assert!(ws.iter().any(|w| w.contains("$LOST")));
+        // metaroot fails, so both sets of errors are reported
+        let (_, es_both) = read_missing_name(false);
+        assert_eq!(
+            es_both,
+            vec!["missing required key: $P2N", "missing required key: $CYT"]
+        );
+    }
+}
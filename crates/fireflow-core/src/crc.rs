@@ -0,0 +1,177 @@
+//! The CRC which may follow the last segment of a dataset.
+//!
+//! Starting in 3.0, a dataset may be followed by an 8-byte CRC-16-CCITT
+//! computed over everything from the start of HEADER to the end of the last
+//! segment. This is written as an ASCII decimal number. The standard allows
+//! this to be all zeros (or missing entirely) if it was not computed.
+
+use std::fmt;
+use std::io;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::str;
+
+/// Number of bytes taken by the CRC after the last segment
+pub const CRC_LEN: usize = 8;
+
+/// Running CRC-16-CCITT (polynomial 0x1021, initial value 0xFFFF)
+#[derive(Clone, Copy)]
+pub struct Crc16(u16);
+
+impl Default for Crc16 {
+    fn default() -> Self {
+        Self(0xFFFF)
+    }
+}
+
+impl Crc16 {
+    pub fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u16::from(*b) << 8;
+            for _ in 0..8 {
+                self.0 = if self.0 & 0x8000 == 0 {
+                    self.0 << 1
+                } else {
+                    (self.0 << 1) ^ 0x1021
+                };
+            }
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Return the CRC as it would be written after the last segment.
+    pub fn to_bytes(self) -> [u8; CRC_LEN] {
+        let mut buf = [0; CRC_LEN];
+        buf.copy_from_slice(format!("{:0>1$}", self.0, CRC_LEN).as_bytes());
+        buf
+    }
+}
+
+/// Writer which may compute a CRC over everything written through it.
+pub(crate) struct CrcWriter<W> {
+    inner: W,
+    pub(crate) crc: Option<Crc16>,
+}
+
+impl<W> CrcWriter<W> {
+    pub(crate) fn new(inner: W, compute: bool) -> Self {
+        Self {
+            inner,
+            crc: compute.then(Crc16::default),
+        }
+    }
+}
+
+impl<W: Write> Write for CrcWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(crc) = self.crc.as_mut() {
+            crc.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Check the CRC immediately following the byte at 'end'.
+///
+/// Return None if the CRC is missing, is all zeros, or matches the CRC
+/// computed from the start of the dataset through 'end'.
+pub(crate) fn h_check_crc<R: Read + Seek>(
+    h: &mut BufReader<R>,
+    end: u64,
+) -> io::Result<Option<CrcMismatchError>> {
+    let mut stored = [0; CRC_LEN];
+    h.seek(SeekFrom::Start(end + 1))?;
+    match h.read_exact(&mut stored) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        x => x?,
+    }
+    if stored.iter().all(|x| matches!(x, b'0' | b' ' | 0)) {
+        return Ok(None);
+    }
+    let mut crc = Crc16::default();
+    let mut buf = [0; 8192];
+    let mut remaining = end + 1;
+    h.seek(SeekFrom::Start(0))?;
+    while remaining > 0 {
+        let n = remaining.min(buf.len() as u64) as usize;
+        h.read_exact(&mut buf[..n])?;
+        crc.update(&buf[..n]);
+        remaining -= n as u64;
+    }
+    let matches = str::from_utf8(&stored)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .is_some_and(|x| x == u32::from(crc.value()));
+    Ok((!matches).then(|| CrcMismatchError {
+        stored: String::from_utf8_lossy(&stored).into_owned(),
+        computed: crc.value(),
+    }))
+}
+
+/// Recompute the CRC immediately following the byte at 'end' in 'buf'.
+///
+/// 'buf' must hold the dataset from the start of HEADER. Nothing will be
+/// changed if the CRC is missing or all zeros, since this means it was never
+/// computed.
+pub(crate) fn rewrite_crc(buf: &mut [u8], end: usize) {
+    let Some(stored) = buf.get(end + 1..end + 1 + CRC_LEN) else {
+        return;
+    };
+    if stored.iter().all(|x| matches!(x, b'0' | b' ' | 0)) {
+        return;
+    }
+    let mut crc = Crc16::default();
+    crc.update(&buf[..=end]);
+    buf[end + 1..end + 1 + CRC_LEN].copy_from_slice(&crc.to_bytes());
+}
+
+pub struct CrcMismatchError {
+    stored: String,
+    computed: u16,
+}
+
+impl fmt::Display for CrcMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "CRC after last segment is '{}' but computed CRC is {}",
+            self.stored, self.computed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_crc16() {
+        // standard check value for CRC-16/CCITT-FALSE
+        let mut crc = Crc16::default();
+        crc.update(b"123456789");
+        assert_eq!(crc.value(), 0x29B1);
+        assert_eq!(&crc.to_bytes(), b"00010673");
+    }
+
+    #[test]
+    fn test_check_crc() {
+        let mut crc = Crc16::default();
+        crc.update(b"abc");
+        let good = [&b"abc"[..], &crc.to_bytes()].concat();
+        let bad = [&b"abc"[..], b"00000001"].concat();
+        let blank = [&b"abc"[..], b"00000000"].concat();
+        let check = |xs: Vec<u8>| h_check_crc(&mut BufReader::new(Cursor::new(xs)), 2).unwrap();
+        assert!(check(good).is_none());
+        assert!(check(bad).is_some());
+        assert!(check(blank).is_none());
+        assert!(check(b"abc".to_vec()).is_none());
+    }
+}
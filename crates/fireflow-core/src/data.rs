@@ -51,11 +51,14 @@
 //! combinations, and all the more reason why this doesn't need to be
 //! version-specific.
 
-use crate::config::{ReaderConfig, SharedConfig, WriteConfig};
+use crate::config::{MixedAsciiWidths, ReaderConfig, SharedConfig, WriteConfig};
 use crate::core::*;
 use crate::error::*;
+use crate::header::Version;
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one, newtype_disp, newtype_from};
+use crate::scratch::ParserScratch;
 use crate::segment::*;
+use crate::source::read_up_to;
 use crate::text::byteord::*;
 use crate::text::float_or_int::*;
 use crate::text::index::IndexFromOne;
@@ -71,6 +74,7 @@ use std::convert::Infallible;
 use std::fmt;
 use std::io;
 use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::iter;
 use std::num::ParseIntError;
 use std::str;
 use std::str::FromStr;
@@ -278,6 +282,9 @@ pub trait VersionedDataLayout: Sized {
 
     fn ncols(&self) -> usize;
 
+    /// Return the bitmask for each column, or None if not an integer.
+    fn bitmasks(&self) -> Vec<Option<u64>>;
+
     fn into_data_reader(
         self,
         kws: &mut StdKeywords,
@@ -325,6 +332,60 @@ pub trait VersionedDataLayout: Sized {
         df: &'a FCSDataFrame,
         conf: &WriteConfig,
     ) -> MultiResult<DataWriter<'a>, ColumnWriterError>;
+
+    fn into_reader_with_tot(
+        self,
+        tot: Tot,
+        seg: AnyDataSegment,
+        conf: &ReaderConfig,
+    ) -> Tentative<DataReader, NewDataReaderWarning, NewDataReaderError>;
+
+    /// Return a dataframe with no events and one column per measurement.
+    ///
+    /// Each column will have the type produced by reading DATA with this
+    /// layout. Return None if a reader cannot be made for this layout.
+    fn empty_dataframe(self) -> Option<FCSDataFrame> {
+        let conf = ReaderConfig::default();
+        let (reader, _, es) = self
+            .into_reader_with_tot(Tot(0), AnyDataSegment::default(), &conf)
+            .into_parts();
+        if !es.is_empty() {
+            return None;
+        }
+        let mut h = BufReader::new(io::Cursor::new(vec![]));
+        reader
+            .h_read(&mut h, &mut ParserScratch::default())
+            .ok()
+            .map(|(df, _)| df)
+    }
+
+    /// Return true if 'bytes' hold exactly the values in 'df' with this layout.
+    ///
+    /// This is used to check if DATA from a file may be copied verbatim rather
+    /// than serialized from the dataframe.
+    fn matches_data(self, bytes: &[u8], df: &FCSDataFrame) -> bool {
+        let Ok(seg) = AnyDataSegment::try_new_with_len(0, bytes.len() as u64);
+        let conf = ReaderConfig::default();
+        let (reader, _, es) = self
+            .into_reader_with_tot(Tot(df.nrows()), seg, &conf)
+            .into_parts();
+        if !es.is_empty() {
+            return false;
+        }
+        let mut h = BufReader::new(io::Cursor::new(bytes));
+        reader
+            .h_read(&mut h, &mut ParserScratch::default())
+            .is_ok_and(|(other, _)| {
+                other.nrows() == df.nrows()
+                    && other.ncols() == df.ncols()
+                    && other.iter_columns().zip(df.iter_columns()).all(|(x, y)| {
+                        let bits = |c: &AnyFCSColumn| -> Vec<_> {
+                            c.to_f64s().into_iter().map(f64::to_bits).collect()
+                        };
+                        bits(x) == bits(y)
+                    })
+            })
+    }
 }
 
 impl AnyEndianUintType {
@@ -352,6 +413,17 @@ impl AnyEndianUintType {
                 .errors_into()
             })
     }
+
+    #[allow(clippy::useless_conversion)]
+    fn bitmask(&self) -> u64 {
+        match_many_to_one!(
+            self,
+            Self,
+            [Uint08, Uint16, Uint24, Uint32, Uint40, Uint48, Uint56, Uint64],
+            x,
+            { u64::from(x.bitmask) }
+        )
+    }
 }
 
 macro_rules! uint_to_mixed {
@@ -664,7 +736,7 @@ impl<X> AsciiColumnWriter<'_, X> {
             // if string less than allocated chars, pad left side with zero before
             // writing number
             for _ in 0..(w - s.len()) {
-                h.write_all(&[30])?;
+                h.write_all(b"0")?;
             }
             h.write_all(s.as_bytes())
         }
@@ -687,6 +759,20 @@ impl<X> DelimColumnWriter<'_, X> {
 pub struct DataReader {
     pub column_reader: ColumnReader,
     pub seg: AnyDataSegment,
+    pub selection: DataSelection,
+}
+
+/// Columns and rows to keep when reading DATA
+#[derive(Clone, Default)]
+pub struct DataSelection {
+    /// Mask with one entry per column, or all columns if None
+    pub columns: Option<Vec<bool>>,
+
+    /// Rows to keep, or all rows if None
+    pub rows: Option<std::ops::Range<usize>>,
+
+    /// Subsample to take from the rows above, or all rows if None
+    pub sample: Option<(SubsampleSize, SubsampleMethod)>,
 }
 
 /// Instructions to read one column in the DATA segment.
@@ -713,10 +799,27 @@ pub struct DelimAsciiReader(DelimAsciiReaderInner);
 pub struct DelimAsciiReaderInner {
     pub columns: NonEmpty<Vec<u64>>,
     pub nbytes: usize,
+    /// If true, parse values with [`ascii_to_uint_lenient`]
+    pub lenient: bool,
 }
 
 pub struct AlphaNumReader {
+    /// Column readers, which are empty until DATA is read
     pub columns: NonEmpty<AlphaNumColumnReader>,
+
+    /// Number of events in DATA
+    pub nrows: usize,
+
+    /// Number of bytes in DATA.
+    ///
+    /// This may be less than the number of events times the event width if
+    /// the last event is incomplete (ie ENDDATA is off by one), in which case
+    /// the missing byte will be read from just after DATA, or as zero if the
+    /// file ends there.
+    pub nbytes: u64,
+
+    /// If true, parse ASCII values with [`ascii_to_uint_lenient`]
+    pub lenient_ascii: bool,
 }
 
 pub enum AlphaNumColumnReader {
@@ -743,6 +846,8 @@ pub struct AsciiColumnReader {
 pub struct UintColumnReader<B, S> {
     pub column: Vec<B>,
     pub uint_type: UintType<B, S>,
+    /// Number of values which exceeded the bitmask and were truncated
+    pub overrange: usize,
 }
 
 type OrderedUintColumnReader<B, const LEN: usize> = UintColumnReader<B, SizedByteOrd<LEN>>;
@@ -759,23 +864,86 @@ pub enum AnyUintColumnReader {
 }
 
 impl DataReader {
-    pub(crate) fn h_read<R>(self, h: &mut BufReader<R>) -> IOResult<FCSDataFrame, ReadDataError>
+    /// Read DATA and return any integer columns with values over their bitmask.
+    ///
+    /// Such values will have been truncated to the bitmask. Also warn if the
+    /// file ended before the last event was complete.
+    pub(crate) fn h_read<R>(
+        self,
+        h: &mut BufReader<R>,
+        scratch: &mut ParserScratch,
+    ) -> IOResult<(FCSDataFrame, Vec<ReadDataWarning>), ReadDataError>
     where
         R: Read + Seek,
     {
-        // TODO it seems a bit odd that we would have an empty segment this
-        // late in the process
+        // An empty segment is valid if there are no events (ie TEXT-only
+        // files), in which case the readers will read nothing but still return
+        // one (empty) column per measurement.
         if let Some(begin) = self.seg.inner.try_coords().map(|(x, _)| x) {
             h.seek(SeekFrom::Start(begin))?;
-            match self.column_reader {
-                ColumnReader::DelimitedAscii(p) => p.h_read(h).map_err(|e| e.inner_into()),
-                ColumnReader::DelimitedAsciiNoRows(p) => p.h_read(h).map_err(|e| e.inner_into()),
-                ColumnReader::AlphaNum(p) => p.h_read(h).map_err(|e| e.inner_into()),
-                ColumnReader::Empty => Ok(FCSDataFrame::default()),
+        }
+        let sel = &self.selection;
+        match self.column_reader {
+            ColumnReader::DelimitedAscii(p) => p
+                .h_read(h)
+                .map(|df| (sel.apply(df), vec![]))
+                .map_err(|e| e.inner_into()),
+            ColumnReader::DelimitedAsciiNoRows(p) => p
+                .h_read(h)
+                .map(|df| (sel.apply(df), vec![]))
+                .map_err(|e| e.inner_into()),
+            ColumnReader::AlphaNum(p) => p.h_read(h, sel, scratch).map_err(|e| e.inner_into()),
+            ColumnReader::Empty => Ok((FCSDataFrame::default(), vec![])),
+        }
+    }
+
+    /// Only read the given columns and rows.
+    pub(crate) fn with_selection(self, selection: DataSelection) -> Self {
+        Self { selection, ..self }
+    }
+}
+
+impl DataSelection {
+    fn keep_column(&self, i: usize) -> bool {
+        self.columns
+            .as_ref()
+            .is_none_or(|xs| xs.get(i).copied().unwrap_or(true))
+    }
+
+    /// Return row range truncated to the number of rows.
+    fn row_range(&self, nrows: usize) -> std::ops::Range<usize> {
+        self.rows.as_ref().map_or(0..nrows, |r| {
+            let end = r.end.min(nrows);
+            r.start.min(end)..end
+        })
+    }
+
+    /// Return sorted indices to sample from a row range of a given length.
+    ///
+    /// Indices are relative to the start of the range. Return None if all
+    /// rows should be kept.
+    fn sampled_rows(&self, n: usize) -> Option<Vec<usize>> {
+        self.sample
+            .as_ref()
+            .map(|(size, method)| method.select(n, size.nevents(n)))
+    }
+
+    /// Subset a dataframe which was read in full.
+    fn apply(&self, mut df: FCSDataFrame) -> FCSDataFrame {
+        if self.rows.is_some() || self.sample.is_some() {
+            let range = self.row_range(df.nrows());
+            let rows: Vec<_> = match self.sampled_rows(range.len()) {
+                Some(xs) => xs.into_iter().map(|i| range.start + i).collect(),
+                None => range.collect(),
+            };
+            df = df.select_rows(&rows[..]);
+        }
+        for i in (0..df.ncols()).rev() {
+            if !self.keep_column(i) {
+                df.drop_in_place(i);
             }
-        } else {
-            Ok(FCSDataFrame::default())
         }
+        df
     }
 }
 
@@ -784,12 +952,13 @@ impl ColumnReader {
         DataReader {
             column_reader: self,
             seg,
+            selection: DataSelection::default(),
         }
     }
 }
 
 impl FloatReader {
-    fn h_read<R: Read>(&mut self, h: &mut BufReader<R>, r: usize) -> io::Result<()> {
+    fn h_read<R: Read>(&mut self, h: &mut R, r: usize) -> io::Result<()> {
         match self {
             Self::F32(t) => t.h_read(h, r),
             Self::F64(t) => t.h_read(h, r),
@@ -802,13 +971,6 @@ impl FloatReader {
             Self::F64(x) => F64Column::from(x.column).into(),
         }
     }
-
-    fn len(&self) -> usize {
-        match self {
-            Self::F32(x) => x.column.len(),
-            Self::F64(x) => x.column.len(),
-        }
-    }
 }
 
 impl DelimAsciiReader {
@@ -816,53 +978,25 @@ impl DelimAsciiReader {
         // FCS 2.0 files have an optional $TOT field, which complicates this a
         // bit. If in this case we have $TOT so the columns have been
         // initialized to the number of rows.
-        let mut buf = Vec::new();
-        let mut last_was_delim = false;
         let mut data = self.0.columns;
         let nrows = data.head.len();
         let ncols = data.len();
-        let mut row = 0;
-        let mut col = 0;
-        // Delimiters are tab, newline, carriage return, space, or comma. Any
-        // consecutive delimiter counts as one, and delimiters can be mixed.
-        for b in h.bytes().take(self.0.nbytes) {
-            let byte = b?;
-            // exit if we encounter more rows than expected.
-            if row == nrows {
-                let e = ReadDelimAsciiError::RowsExceeded(RowsExceededError(nrows));
-                return Err(ImpureError::Pure(e));
-            }
-            if is_ascii_delim(byte) {
-                if !last_was_delim {
-                    last_was_delim = true;
-                    data[col][row] = ascii_to_uint(&buf)
-                        .map_err(ReadDelimAsciiError::Parse)
-                        .map_err(ImpureError::Pure)?;
-                    buf.clear();
-                    if col == ncols - 1 {
-                        col = 0;
-                        row += 1;
-                    } else {
-                        col += 1;
-                    }
-                }
-            } else {
-                buf.push(byte);
-                last_was_delim = false;
-            }
-        }
-        if !(col == 0 && row == nrows) {
-            let e = DelimIncompleteError { col, row, nrows };
+        let n = h_read_delim_ascii_values(h, self.0.nbytes, |i, buf| {
+            let x = parse_ascii(buf, self.0.lenient).map_err(ReadDelimAsciiError::Parse)?;
+            // exit if we encounter more rows than expected
+            data.get_mut(i % ncols)
+                .and_then(|c| c.get_mut(i / ncols))
+                .map(|y| *y = x)
+                .ok_or(ReadDelimAsciiError::RowsExceeded(RowsExceededError(nrows)))
+        })?;
+        if n != nrows * ncols {
+            let e = DelimIncompleteError {
+                col: n % ncols,
+                row: n / ncols,
+                nrows,
+            };
             return Err(ImpureError::Pure(ReadDelimAsciiError::Incomplete(e)));
         }
-        // The spec isn't clear if the last value should be a delim or
-        // not, so flush the buffer if it has anything in it since we
-        // only try to parse if we hit a delim above.
-        if !buf.is_empty() {
-            data[col][row] = ascii_to_uint(&buf)
-                .map_err(ReadDelimAsciiError::Parse)
-                .map_err(ImpureError::Pure)?;
-        }
         let cs: Vec<_> = data
             .into_iter()
             .map(FCSColumn::from)
@@ -879,55 +1013,62 @@ fn is_ascii_delim(x: u8) -> bool {
     x == 9 || x == 10 || x == 13 || x == 32 || x == 44
 }
 
+/// Read all values from delimited ASCII DATA.
+///
+/// Delimiters are tab, newline, carriage return, space, or comma. Any
+/// consecutive delimiter counts as one, and delimiters can be mixed. Leading
+/// and trailing delimiters are ignored, and the last value need not be
+/// followed by a delimiter since the spec isn't clear on this.
+///
+/// Each value will be passed to 'f' along with its position in DATA (starting
+/// at 0). Return the total number of values.
+fn h_read_delim_ascii_values<R, F, E>(
+    h: &mut BufReader<R>,
+    nbytes: usize,
+    mut f: F,
+) -> IOResult<usize, E>
+where
+    R: Read,
+    F: FnMut(usize, &[u8]) -> Result<(), E>,
+{
+    let mut buf = Vec::new();
+    let mut n = 0;
+    for b in h.bytes().take(nbytes) {
+        let byte = b?;
+        if !is_ascii_delim(byte) {
+            buf.push(byte);
+        } else if !buf.is_empty() {
+            f(n, &buf).map_err(ImpureError::Pure)?;
+            buf.clear();
+            n += 1;
+        }
+    }
+    if !buf.is_empty() {
+        f(n, &buf).map_err(ImpureError::Pure)?;
+        n += 1;
+    }
+    Ok(n)
+}
+
 impl DelimAsciiReaderNoRows {
     fn h_read<R: Read>(
         self,
         h: &mut BufReader<R>,
     ) -> IOResult<FCSDataFrame, ReadDelimAsciiNoRowsError> {
-        let mut buf = Vec::new();
         let mut data = self.0.columns;
         let ncols = data.len();
-        let mut col = 0;
-        let mut last_was_delim = false;
-        let go = |_data: &mut NonEmpty<Vec<u64>>, _col, _buf: &[u8]| {
-            ascii_to_uint(_buf)
-                .map_err(ReadDelimAsciiNoRowsError::Parse)
-                .map_err(ImpureError::Pure)
-                .map(|x| _data[_col].push(x))
-        };
-        // Delimiters are tab, newline, carriage return, space, or comma. Any
-        // consecutive delimiter counts as one, and delimiters can be mixed.
         // If we don't know the number of rows, the only choice is to push onto
         // the column vectors one at a time. This leads to the possibility that
         // the vectors may not be the same length in the end, in which case,
         // scream loudly and bail.
-        for b in h.bytes().take(self.0.nbytes) {
-            let byte = b?;
-            if is_ascii_delim(byte) {
-                if !last_was_delim {
-                    last_was_delim = true;
-                    buf.clear();
-                    go(&mut data, col, &buf)?;
-                    if col == ncols - 1 {
-                        col = 0;
-                    } else {
-                        col += 1;
-                    }
-                }
-            } else {
-                buf.push(byte);
-                last_was_delim = false;
-            }
-        }
-        if data.iter().map(|c| c.len()).unique().count() > 1 {
+        let n = h_read_delim_ascii_values(h, self.0.nbytes, |i, buf| {
+            let x = parse_ascii(buf, self.0.lenient).map_err(ReadDelimAsciiNoRowsError::Parse)?;
+            data[i % ncols].push(x);
+            Ok(())
+        })?;
+        if n % ncols != 0 {
             return Err(ImpureError::Pure(ReadDelimAsciiNoRowsError::Unequal));
         }
-        // The spec isn't clear if the last value should be a delim or
-        // not, so flush the buffer if it has anything in it since we
-        // only try to parse if we hit a delim above.
-        if !buf.is_empty() {
-            go(&mut data, col, &buf)?;
-        }
         let cs: Vec<_> = data
             .into_iter()
             .map(FCSColumn::from)
@@ -940,28 +1081,91 @@ impl DelimAsciiReaderNoRows {
 }
 
 impl AlphaNumReader {
-    fn h_read<R: Read>(mut self, h: &mut BufReader<R>) -> IOResult<FCSDataFrame, AsciiToUintError> {
-        let mut buf: Vec<u8> = vec![];
-        let nrows = self.columns.head.len();
-        for r in 0..nrows {
-            for c in self.columns.iter_mut() {
-                match c {
-                    AlphaNumColumnReader::Float(f) => f.h_read(h, r)?,
-                    AlphaNumColumnReader::Uint(u) => u.h_read(h, r)?,
-                    AlphaNumColumnReader::Ascii(d) => {
-                        buf.clear();
-                        h.take(u8::from(d.width).into()).read_to_end(&mut buf)?;
-                        d.column[r] = ascii_to_uint(&buf).map_err(ImpureError::Pure)?;
-                    }
+    fn h_read<R: Read + Seek>(
+        self,
+        h: &mut BufReader<R>,
+        sel: &DataSelection,
+        scratch: &mut ParserScratch,
+    ) -> IOResult<(FCSDataFrame, Vec<ReadDataWarning>), AsciiCellError> {
+        let rows = sel.row_range(self.nrows);
+        let sampled = sel.sampled_rows(rows.len());
+        let nkeep = sampled.as_ref().map_or(rows.len(), Vec::len);
+        let event_width: usize = self.columns.iter().map(|c| c.nbytes()).sum();
+        // Drop unselected columns and record the number of bytes to skip
+        // before each remaining column and at the end of each event. Only
+        // allocate the remaining columns, and only for the selected rows.
+        let mut columns = vec![];
+        let mut gap = 0;
+        for (i, mut c) in self.columns.into_iter().enumerate() {
+            if sel.keep_column(i) {
+                c.allocate(nkeep, scratch);
+                columns.push((gap, i, c));
+                gap = 0;
+            } else {
+                gap += c.nbytes();
+            }
+        }
+        h.seek_relative(rows.start as i64 * event_width as i64)?;
+        // Read whole events in large blocks and decode each value from slices
+        // of the block. This is much faster than reading each value from the
+        // handle one at a time. If subsampling, read each run of consecutive
+        // events this way and skip the events in between.
+        let runs = sampled.map_or_else(
+            || iter::once(0..rows.len()).collect(),
+            |xs| consecutive_runs(&xs),
+        );
+        let nbytes = self.nbytes;
+        let block_events = (DATA_BLOCK_BYTES / event_width.max(1)).max(1);
+        let block = &mut scratch.block;
+        let mut r = 0;
+        let mut pos = 0;
+        let mut missing = 0;
+        for run in runs {
+            if event_width == 0 {
+                break;
+            }
+            h.seek_relative((run.start - pos) as i64 * event_width as i64)?;
+            let mut e = run.start;
+            while e < run.end {
+                let n = block_events.min(run.end - e);
+                block.resize(n * event_width, 0);
+                // If the last event is incomplete (ie ENDDATA is one byte too
+                // small), read its last byte from just after DATA. Only pad
+                // with zeros if the file ends before this.
+                let offset = (rows.start + e) as u64 * event_width as u64;
+                let avail = nbytes.saturating_sub(offset).min(block.len() as u64) as usize;
+                h.read_exact(&mut block[..avail])?;
+                let nread = avail + read_up_to(h, &mut block[avail..])?;
+                block[nread..].fill(0);
+                missing += block.len() - nread;
+                for event in block.chunks_exact(event_width) {
+                    decode_event(&mut columns, event, r, rows.start + e, self.lenient_ascii)?;
+                    r += 1;
+                    e += 1;
                 }
             }
+            pos = run.end;
         }
-        let cs: Vec<_> = self
-            .columns
-            .into_iter()
-            .map(|c| c.into_fcs_column())
-            .collect();
-        Ok(FCSDataFrame::try_new(cs).unwrap())
+        let mut ws = vec![];
+        if missing > 0 {
+            ws.push(TruncatedDataWarning { missing }.into());
+        }
+        let mut cs = vec![];
+        for (_, i, c) in columns {
+            if let AlphaNumColumnReader::Uint(u) = &c {
+                let (count, bitmask) = u.overrange();
+                if count > 0 {
+                    let w = OverRangeWarning {
+                        index: i.into(),
+                        count,
+                        bitmask,
+                    };
+                    ws.push(w.into());
+                }
+            }
+            cs.push(c.into_fcs_column());
+        }
+        Ok((FCSDataFrame::try_new(cs).unwrap(), ws))
     }
 
     fn check_tot(
@@ -969,7 +1173,7 @@ impl AlphaNumReader {
         tot: Tot,
         allow_mismatch: bool,
     ) -> Tentative<(), TotEventMismatch, TotEventMismatch> {
-        let total_events = self.columns.head.len();
+        let total_events = self.nrows;
         if tot.0 != total_events {
             let i = TotEventMismatch { tot, total_events };
             Tentative::new_either((), vec![i], !allow_mismatch)
@@ -1009,6 +1213,10 @@ impl FixedLayout<AnyEndianUintType> {
             .map(Tentative::mconcat)
             .def_map_value(FixedLayout::from_vec)
     }
+
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        self.columns.iter().map(|c| Some(c.bitmask())).collect()
+    }
 }
 
 trait IntMath: Sized
@@ -1035,7 +1243,7 @@ trait NumProps<const DTLEN: usize>: Sized + Copy + Default {
 }
 
 trait OrderedFromBytes<const DTLEN: usize, const OLEN: usize>: NumProps<DTLEN> {
-    fn h_read_from_ordered<R: Read>(h: &mut BufReader<R>, order: &[u8; OLEN]) -> io::Result<Self> {
+    fn h_read_from_ordered<R: Read>(h: &mut R, order: &[u8; OLEN]) -> io::Result<Self> {
         let mut tmp = [0; OLEN];
         let mut buf = [0; DTLEN];
         h.read_exact(&mut tmp)?;
@@ -1198,7 +1406,7 @@ where
             .def_map_value(FixedLayout::from_vec)
     }
 
-    fn h_read_int_endian<R: Read>(h: &mut BufReader<R>, endian: Endian) -> io::Result<Self> {
+    fn h_read_int_endian<R: Read>(h: &mut R, endian: Endian) -> io::Result<Self> {
         // This will read data that is not a power-of-two bytes long. Start by
         // reading n bytes into a vector, which can take a varying size. Then
         // copy this into the power of 2 buffer and reset all the unused cells
@@ -1221,10 +1429,7 @@ where
         })
     }
 
-    fn h_read_int<R: Read>(
-        h: &mut BufReader<R>,
-        byteord: &SizedByteOrd<INTLEN>,
-    ) -> io::Result<Self> {
+    fn h_read_int<R: Read>(h: &mut R, byteord: &SizedByteOrd<INTLEN>) -> io::Result<Self> {
         match byteord {
             SizedByteOrd::Endian(e) => Self::h_read_int_endian(h, *e),
             SizedByteOrd::Order(order) => Self::h_read_from_ordered(h, order),
@@ -1353,10 +1558,7 @@ where
             .map(FixedLayout::from_vec)
     }
 
-    fn h_read_float<R: Read>(
-        h: &mut BufReader<R>,
-        byteord: &SizedByteOrd<LEN>,
-    ) -> io::Result<Self> {
+    fn h_read_float<R: Read>(h: &mut R, byteord: &SizedByteOrd<LEN>) -> io::Result<Self> {
         match byteord {
             SizedByteOrd::Endian(e) => {
                 let mut buf = [0; LEN];
@@ -1472,11 +1674,38 @@ impl AlphaNumColumnReader {
         }
     }
 
-    fn len(&self) -> usize {
+    /// Allocate 'n' values, reusing a column from the scratch if possible.
+    fn allocate(&mut self, n: usize, scratch: &mut ParserScratch) {
+        match self {
+            Self::Ascii(x) => x.column = scratch.take_column(n),
+            Self::Float(FloatReader::F32(x)) => x.column = scratch.take_column(n),
+            Self::Float(FloatReader::F64(x)) => x.column = scratch.take_column(n),
+            Self::Uint(x) => match_many_to_one!(
+                x,
+                AnyUintColumnReader,
+                [Uint08, Uint16, Uint24, Uint32, Uint40, Uint48, Uint56, Uint64],
+                y,
+                { y.column = scratch.take_column(n) }
+            ),
+        }
+    }
+
+    /// Number of bytes this column occupies in each event
+    fn nbytes(&self) -> usize {
         match self {
-            Self::Ascii(x) => x.column.len(),
-            Self::Float(x) => x.len(),
-            Self::Uint(x) => x.len(),
+            Self::Ascii(x) => u8::from(x.width).into(),
+            Self::Float(FloatReader::F32(_)) => 4,
+            Self::Float(FloatReader::F64(_)) => 8,
+            Self::Uint(x) => match x {
+                AnyUintColumnReader::Uint08(_) => 1,
+                AnyUintColumnReader::Uint16(_) => 2,
+                AnyUintColumnReader::Uint24(_) => 3,
+                AnyUintColumnReader::Uint32(_) => 4,
+                AnyUintColumnReader::Uint40(_) => 5,
+                AnyUintColumnReader::Uint48(_) => 6,
+                AnyUintColumnReader::Uint56(_) => 7,
+                AnyUintColumnReader::Uint64(_) => 8,
+            },
         }
     }
 }
@@ -1495,19 +1724,21 @@ impl AnyUintColumnReader {
         }
     }
 
-    fn len(&self) -> usize {
+    /// Return number of truncated values and the bitmask used to truncate them
+    #[allow(clippy::useless_conversion)]
+    fn overrange(&self) -> (usize, u64) {
         match_many_to_one!(
             self,
             Self,
             [Uint08, Uint16, Uint24, Uint32, Uint40, Uint48, Uint56, Uint64],
             x,
-            { x.column.len() }
+            { (x.overrange, u64::from(x.uint_type.bitmask)) }
         )
     }
 }
 
 impl AnyUintColumnReader {
-    fn h_read<R: Read>(&mut self, h: &mut BufReader<R>, r: usize) -> io::Result<()> {
+    fn h_read<R: Read>(&mut self, h: &mut R, r: usize) -> io::Result<()> {
         match_many_to_one!(
             self,
             AnyUintColumnReader,
@@ -1546,12 +1777,45 @@ impl MixedType {
     }
 }
 
+/// Parse an ASCII integer.
 fn ascii_to_uint(buf: &[u8]) -> Result<u64, AsciiToUintError> {
-    if buf.is_ascii() {
-        let s = unsafe { str::from_utf8_unchecked(buf) };
-        s.parse().map_err(AsciiToUintError::from)
+    ascii_str(buf)?.parse().map_err(AsciiToUintError::from)
+}
+
+/// Parse an ASCII integer leniently.
+///
+/// Fixed-width values are commonly padded with spaces or zeros, so surrounding
+/// whitespace will be trimmed. A leading '+' is allowed. Decimals without an
+/// exponent (eg "12.5") will be rounded to the nearest integer since ASCII
+/// values are always treated as integers.
+fn ascii_to_uint_lenient(buf: &[u8]) -> Result<u64, AsciiToUintError> {
+    let s = ascii_str(buf)?.trim();
+    match s.split_once('.') {
+        None => s.parse().map_err(AsciiToUintError::from),
+        Some((int, frac)) => {
+            if !frac.bytes().all(|b| b.is_ascii_digit()) || (int.is_empty() && frac.is_empty()) {
+                return Err(AsciiDecimalError(s.to_string()).into());
+            }
+            let x: u64 = if int.is_empty() { 0 } else { int.parse()? };
+            let round_up = frac.as_bytes().first().is_some_and(|b| *b >= b'5');
+            x.checked_add(u64::from(round_up))
+                .ok_or_else(|| AsciiDecimalError(s.to_string()).into())
+        }
+    }
+}
+
+fn ascii_str(buf: &[u8]) -> Result<&str, NotAsciiError> {
+    str::from_utf8(buf)
+        .ok()
+        .filter(|s| s.is_ascii())
+        .ok_or_else(|| NotAsciiError(buf.to_vec()))
+}
+
+fn parse_ascii(buf: &[u8], lenient: bool) -> Result<u64, AsciiToUintError> {
+    if lenient {
+        ascii_to_uint_lenient(buf)
     } else {
-        Err(NotAsciiError(buf.to_vec()).into())
+        ascii_to_uint(buf)
     }
 }
 
@@ -1577,7 +1841,12 @@ impl<C> FixedLayout<C> {
 }
 
 impl DelimitedLayout {
-    fn into_col_reader_maybe_rows(self, nbytes: usize, kw_tot: Option<Tot>) -> ColumnReader {
+    fn into_col_reader_maybe_rows(
+        self,
+        nbytes: usize,
+        kw_tot: Option<Tot>,
+        lenient: bool,
+    ) -> ColumnReader {
         if self.ncols == 0 {
             ColumnReader::Empty
         } else {
@@ -1587,25 +1856,30 @@ impl DelimitedLayout {
                     ColumnReader::DelimitedAscii(DelimAsciiReader(DelimAsciiReaderInner {
                         columns: NonEmpty::collect(repeat_n(vec![0; tot.0], self.ncols)).unwrap(),
                         nbytes,
+                        lenient,
                     }))
                 }
                 None => ColumnReader::DelimitedAsciiNoRows(DelimAsciiReaderNoRows(
                     DelimAsciiReaderInner {
                         columns: NonEmpty::collect(repeat_n(vec![], self.ncols)).unwrap(),
                         nbytes,
+                        lenient,
                     },
                 )),
             }
         }
     }
 
-    fn into_col_reader(self, nbytes: usize, tot: Tot) -> ColumnReader {
+    fn into_col_reader(self, nbytes: usize, tot: Tot, lenient: bool) -> ColumnReader {
         if self.ncols == 0 {
             ColumnReader::Empty
         } else {
+            // treat single-byte DATA (ie begin == end) as empty if no events
+            let data_bytes = if tot.0 == 0 && nbytes <= 1 { 0 } else { nbytes };
             ColumnReader::DelimitedAscii(DelimAsciiReader(DelimAsciiReaderInner {
                 columns: NonEmpty::collect(repeat_n(vec![0; tot.0], self.ncols)).unwrap(),
-                nbytes,
+                nbytes: data_bytes,
+                lenient,
             }))
         }
     }
@@ -1631,19 +1905,24 @@ impl<C> FixedLayout<C> {
     where
         C: IsFixedReader + IsFixed,
     {
-        let n = seg.inner.len() as usize;
-        let w = self.event_width();
-        let total_events = n / w;
-        let remainder = n % w;
-        let columns = self.columns.map(|c| c.into_col_reader(total_events));
-        let r = AlphaNumReader { columns };
-        if remainder > 0 {
-            let i = UnevenEventWidth {
-                event_width: w,
-                nbytes: n,
-                remainder,
-            };
-            Tentative::new_either(r, vec![i], !conf.allow_uneven_event_width)
+        // Keep byte math in u64 since DATA may be larger than 4GB
+        let n = seg.inner.len();
+        let w = self.event_width() as u64;
+        let i = UnevenEventWidth::new(w, n, conf.fix_uneven_event_width);
+        let total_events = match i.as_ref().and_then(|x| x.fix) {
+            Some(UnevenEventWidthCause::EndUnderByOne) => n / w + 1,
+            _ => n / w,
+        } as usize;
+        let columns = self.columns.map(|c| c.into_col_reader());
+        let r = AlphaNumReader {
+            columns,
+            nrows: total_events,
+            nbytes: n,
+            lenient_ascii: conf.lenient_ascii,
+        };
+        if let Some(x) = i {
+            let is_error = x.fix.is_none() && !conf.allow_uneven_event_width;
+            Tentative::new_either(r, vec![x], is_error)
         } else {
             Tentative::new1(r)
         }
@@ -1660,7 +1939,14 @@ impl<C> FixedLayout<C> {
         W: From<TotEventMismatch> + From<UnevenEventWidth>,
         E: From<TotEventMismatch> + From<UnevenEventWidth>,
     {
-        self.into_col_reader_inner(seg, conf)
+        // Some TEXT-only files point DATA at a single byte (ie begin == end)
+        // rather than using 0,0. Treat these as empty if there are no events.
+        let data_seg = if tot.0 == 0 && seg.inner.len() < self.event_width() as u64 {
+            AnyDataSegment::default()
+        } else {
+            seg
+        };
+        self.into_col_reader_inner(data_seg, conf)
             .inner_into()
             .and_tentatively(|reader| {
                 reader
@@ -1704,7 +1990,7 @@ pub trait IsFixed {
 }
 
 pub trait IsFixedReader {
-    fn into_col_reader(self, nrows: usize) -> AlphaNumColumnReader;
+    fn into_col_reader(self) -> AlphaNumColumnReader;
 }
 
 pub trait IsFixedWriter {
@@ -1727,10 +2013,11 @@ where
     T: Default,
     AlphaNumColumnReader: From<OrderedUintColumnReader<T, LEN>>,
 {
-    fn into_col_reader(self, nrows: usize) -> AlphaNumColumnReader {
+    fn into_col_reader(self) -> AlphaNumColumnReader {
         UintColumnReader {
-            column: vec![T::default(); nrows],
+            column: vec![],
             uint_type: self,
+            overrange: 0,
         }
         .into()
     }
@@ -1787,13 +2074,13 @@ impl IsFixed for AnyEndianUintType {
 }
 
 impl IsFixedReader for AnyEndianUintType {
-    fn into_col_reader(self, nrows: usize) -> AlphaNumColumnReader {
+    fn into_col_reader(self) -> AlphaNumColumnReader {
         match_many_to_one!(
             self,
             AnyEndianUintType,
             [Uint08, Uint16, Uint24, Uint32, Uint40, Uint48, Uint56, Uint64],
             x,
-            { OrderedUintType::from(x).into_col_reader(nrows) }
+            { OrderedUintType::from(x).into_col_reader() }
         )
     }
 }
@@ -1939,9 +2226,9 @@ where
     T: Default,
     AlphaNumColumnReader: From<FloatColumnReader<T, LEN>>,
 {
-    fn into_col_reader(self, nrows: usize) -> AlphaNumColumnReader {
+    fn into_col_reader(self) -> AlphaNumColumnReader {
         FloatColumnReader {
-            column: vec![T::default(); nrows],
+            column: vec![],
             byte_layout: self.byte_layout,
         }
         .into()
@@ -1977,24 +2264,24 @@ where
 }
 
 impl<T, const INTLEN: usize> OrderedUintColumnReader<T, INTLEN> {
-    fn h_read<R: Read, const DTLEN: usize>(
-        &mut self,
-        h: &mut BufReader<R>,
-        row: usize,
-    ) -> io::Result<()>
+    fn h_read<R: Read, const DTLEN: usize>(&mut self, h: &mut R, row: usize) -> io::Result<()>
     where
         T: IntFromBytes<DTLEN, INTLEN>,
         <T as FromStr>::Err: fmt::Display,
         T: Ord,
     {
         let x = T::h_read_int(h, &self.uint_type.byte_layout)?;
-        self.column[row] = x.min(self.uint_type.bitmask);
+        let bitmask = self.uint_type.bitmask;
+        if x > bitmask {
+            self.overrange += 1;
+        }
+        self.column[row] = x.min(bitmask);
         Ok(())
     }
 }
 
 impl<T, const LEN: usize> FloatColumnReader<T, LEN> {
-    fn h_read<R: Read>(&mut self, h: &mut BufReader<R>, row: usize) -> io::Result<()>
+    fn h_read<R: Read>(&mut self, h: &mut R, row: usize) -> io::Result<()>
     where
         T: FloatFromBytes<LEN>,
         <T as FromStr>::Err: fmt::Display,
@@ -2023,9 +2310,9 @@ impl IsFixed for AsciiType {
 }
 
 impl IsFixedReader for AsciiType {
-    fn into_col_reader(self, nrows: usize) -> AlphaNumColumnReader {
+    fn into_col_reader(self) -> AlphaNumColumnReader {
         AlphaNumColumnReader::Ascii(AsciiColumnReader {
-            column: vec![0; nrows],
+            column: vec![],
             width: self.chars,
         })
     }
@@ -2076,12 +2363,12 @@ impl IsFixed for MixedType {
 }
 
 impl IsFixedReader for MixedType {
-    fn into_col_reader(self, nrows: usize) -> AlphaNumColumnReader {
+    fn into_col_reader(self) -> AlphaNumColumnReader {
         match self {
-            Self::Ascii(a) => a.into_col_reader(nrows),
-            Self::Integer(i) => i.into_col_reader(nrows),
-            Self::Float(f) => OrderedFloatType::from(f).into_col_reader(nrows),
-            Self::Double(d) => OrderedFloatType::from(d).into_col_reader(nrows),
+            Self::Ascii(a) => a.into_col_reader(),
+            Self::Integer(i) => i.into_col_reader(),
+            Self::Float(f) => OrderedFloatType::from(f).into_col_reader(),
+            Self::Double(d) => OrderedFloatType::from(d).into_col_reader(),
         }
     }
 }
@@ -2167,6 +2454,22 @@ impl AnyOrderedUintLayout {
         )
     }
 
+    #[allow(clippy::useless_conversion)]
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        match_many_to_one!(
+            self,
+            Self,
+            [Uint08, Uint16, Uint24, Uint32, Uint40, Uint48, Uint56, Uint64],
+            l,
+            {
+                l.columns
+                    .iter()
+                    .map(|c| Some(u64::from(c.bitmask)))
+                    .collect()
+            }
+        )
+    }
+
     fn into_col_reader_inner(
         self,
         seg: AnyDataSegment,
@@ -2239,28 +2542,67 @@ impl AnyOrderedUintLayout {
 impl AsciiLayout {
     pub(crate) fn try_new<D>(
         cs: Vec<ColumnLayoutData<D>>,
-    ) -> MultiResult<Option<Self>, NewAsciiLayoutError> {
+        mixed: MixedAsciiWidths,
+    ) -> DeferredResult<Option<Self>, MixedAsciiWidthWarning, NewAsciiLayoutError> {
         let ncols = cs.len();
-        if cs.iter().all(|c| c.width == Width::Variable) {
-            Ok(Some(AsciiLayout::Delimited(DelimitedLayout { ncols })))
-        } else {
-            cs.into_iter()
-                .enumerate()
-                .map(|(i, c)| {
-                    c.width
-                        .try_into()
-                        .map(|chars| AsciiType { chars })
-                        .map_err(|error| {
-                            ColumnError {
-                                error,
-                                index: i.into(),
-                            }
-                            .into()
-                        })
-                })
-                .gather()
-                .map(|columns| FixedLayout::from_vec(columns).map(AsciiLayout::Fixed))
+        let variable: Vec<IndexFromOne> = cs
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.width == Width::Variable)
+            .map(|(i, _)| i.into())
+            .collect();
+        if variable.len() == ncols {
+            return Ok(Tentative::new1(Some(AsciiLayout::Delimited(
+                DelimitedLayout { ncols },
+            ))));
         }
+        let warning = (!variable.is_empty() && mixed != MixedAsciiWidths::Error)
+            .then_some(MixedAsciiWidthWarning { variable, mixed });
+        if mixed == MixedAsciiWidths::Delimited && warning.is_some() {
+            let d = AsciiLayout::Delimited(DelimitedLayout { ncols });
+            return Ok(Tentative::new(
+                Some(d),
+                warning.into_iter().collect(),
+                vec![],
+            ));
+        }
+        let mut res = Self::try_new_fixed(cs, mixed).mult_to_deferred();
+        if let Some(w) = warning {
+            res.def_push_warning(w);
+        }
+        res
+    }
+
+    fn try_new_fixed<D>(
+        cs: Vec<ColumnLayoutData<D>>,
+        mixed: MixedAsciiWidths,
+    ) -> MultiResult<Option<Self>, NewAsciiLayoutError> {
+        cs.into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let width = match c.width {
+                    Width::Variable if mixed == MixedAsciiWidths::FromRange => {
+                        let max = match c.range.0 {
+                            FloatOrInt::Int(x) => x.saturating_sub(1),
+                            FloatOrInt::Float(x) => (x.ceil() as u64).saturating_sub(1),
+                        };
+                        Chars::from_max(max).into()
+                    }
+                    w => w,
+                };
+                width
+                    .try_into()
+                    .map(|chars| AsciiType { chars })
+                    .map_err(|error| {
+                        ColumnError {
+                            error,
+                            index: i.into(),
+                        }
+                        .into()
+                    })
+            })
+            .gather()
+            .map(|columns| FixedLayout::from_vec(columns).map(AsciiLayout::Fixed))
     }
 
     fn ncols(&self) -> usize {
@@ -2323,7 +2665,7 @@ impl AsciiLayout {
         let nbytes = seg.inner.len() as usize;
         match self {
             AsciiLayout::Delimited(dl) => {
-                Tentative::new1(dl.into_col_reader_maybe_rows(nbytes, kw_tot))
+                Tentative::new1(dl.into_col_reader_maybe_rows(nbytes, kw_tot, conf.lenient_ascii))
             }
             AsciiLayout::Fixed(fl) => fl
                 .into_col_reader_inner(seg, conf)
@@ -2345,7 +2687,9 @@ impl AsciiLayout {
     {
         let nbytes = seg.inner.len() as usize;
         match self {
-            AsciiLayout::Delimited(dl) => Tentative::new1(dl.into_col_reader(nbytes, tot)),
+            AsciiLayout::Delimited(dl) => {
+                Tentative::new1(dl.into_col_reader(nbytes, tot, conf.lenient_ascii))
+            }
             AsciiLayout::Fixed(fl) => fl.into_col_reader(seg, tot, conf),
         }
     }
@@ -2448,6 +2792,10 @@ impl VersionedDataLayout for DataLayout2_0 {
         self.0.ncols()
     }
 
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        self.0.bitmasks()
+    }
+
     fn as_writer_inner<'a>(
         &self,
         df: &'a FCSDataFrame,
@@ -2488,6 +2836,15 @@ impl VersionedDataLayout for DataLayout2_0 {
         Ok(out)
     }
 
+    fn into_reader_with_tot(
+        self,
+        tot: Tot,
+        seg: AnyDataSegment,
+        conf: &ReaderConfig,
+    ) -> Tentative<DataReader, NewDataReaderWarning, NewDataReaderError> {
+        self.into_reader(Some(tot), seg, conf)
+    }
+
     fn as_analysis_reader(
         _: &mut StdKeywords,
         seg: HeaderAnalysisSegment,
@@ -2530,6 +2887,10 @@ impl VersionedDataLayout for DataLayout3_0 {
         self.0.ncols()
     }
 
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        self.0.bitmasks()
+    }
+
     fn as_writer_inner<'a>(
         &self,
         df: &'a FCSDataFrame,
@@ -2558,6 +2919,15 @@ impl VersionedDataLayout for DataLayout3_0 {
             .def_and_tentatively(|(tot, any_seg)| self.into_reader(tot, any_seg, conf))
     }
 
+    fn into_reader_with_tot(
+        self,
+        tot: Tot,
+        seg: AnyDataSegment,
+        conf: &ReaderConfig,
+    ) -> Tentative<DataReader, NewDataReaderWarning, NewDataReaderError> {
+        self.into_reader(tot, seg, conf)
+    }
+
     fn as_analysis_reader(
         kws: &mut StdKeywords,
         seg: HeaderAnalysisSegment,
@@ -2586,9 +2956,9 @@ impl VersionedDataLayout for DataLayout3_1 {
         conf: &SharedConfig,
     ) -> DeferredResult<Self, NewDataLayoutWarning, NewDataLayoutError> {
         match datatype {
-            AlphaNumType::Ascii => AsciiLayout::try_new(columns)
-                .map(|x| x.map_or(Self::Empty, Self::Ascii))
-                .mult_to_deferred(),
+            AlphaNumType::Ascii => AsciiLayout::try_new(columns, conf.mixed_ascii_widths)
+                .def_map_value(|x| x.map_or(Self::Empty, Self::Ascii))
+                .def_inner_into(),
             AlphaNumType::Integer => {
                 FixedLayout::try_new(columns, endian, conf.disallow_bitmask_truncation)
                     .def_map_value(|x| x.map_or(Self::Empty, Self::Integer))
@@ -2623,6 +2993,13 @@ impl VersionedDataLayout for DataLayout3_1 {
         }
     }
 
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        match self {
+            Self::Integer(i) => i.bitmasks(),
+            _ => vec![None; self.ncols()],
+        }
+    }
+
     fn as_writer_inner<'a>(
         &self,
         df: &'a FCSDataFrame,
@@ -2660,6 +3037,15 @@ impl VersionedDataLayout for DataLayout3_1 {
             .def_and_tentatively(|(tot, any_seg)| self.into_reader(tot, any_seg, conf))
     }
 
+    fn into_reader_with_tot(
+        self,
+        tot: Tot,
+        seg: AnyDataSegment,
+        conf: &ReaderConfig,
+    ) -> Tentative<DataReader, NewDataReaderWarning, NewDataReaderError> {
+        self.into_reader(tot, seg, conf)
+    }
+
     fn as_analysis_reader(
         kws: &mut StdKeywords,
         seg: HeaderAnalysisSegment,
@@ -2698,9 +3084,9 @@ impl VersionedDataLayout for DataLayout3_2 {
         let unique_dt: Vec<_> = dt_columns.iter().map(|c| c.datatype).unique().collect();
         match unique_dt[..] {
             [dt] => match dt {
-                AlphaNumType::Ascii => AsciiLayout::try_new(dt_columns)
-                    .map(|x| x.map_or(Self::Empty, Self::Ascii))
-                    .mult_to_deferred(),
+                AlphaNumType::Ascii => AsciiLayout::try_new(dt_columns, conf.mixed_ascii_widths)
+                    .def_map_value(|x| x.map_or(Self::Empty, Self::Ascii))
+                    .def_inner_into(),
                 AlphaNumType::Integer => {
                     FixedLayout::try_new(dt_columns, endian, conf.disallow_bitmask_truncation)
                         .def_map_value(|x| x.map_or(Self::Empty, Self::Integer))
@@ -2775,6 +3161,21 @@ impl VersionedDataLayout for DataLayout3_2 {
         }
     }
 
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        match self {
+            Self::Integer(i) => i.bitmasks(),
+            Self::Mixed(m) => m
+                .columns
+                .iter()
+                .map(|c| match c {
+                    MixedType::Integer(i) => Some(i.bitmask()),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![None; self.ncols()],
+        }
+    }
+
     fn as_writer_inner<'a>(
         &self,
         df: &'a FCSDataFrame,
@@ -2815,6 +3216,15 @@ impl VersionedDataLayout for DataLayout3_2 {
             .def_and_tentatively(|(tot, any_seg)| self.into_reader(tot, any_seg, conf))
     }
 
+    fn into_reader_with_tot(
+        self,
+        tot: Tot,
+        seg: AnyDataSegment,
+        conf: &ReaderConfig,
+    ) -> Tentative<DataReader, NewDataReaderWarning, NewDataReaderError> {
+        self.into_reader(tot, seg, conf)
+    }
+
     fn as_analysis_reader(
         kws: &mut StdKeywords,
         seg: HeaderAnalysisSegment,
@@ -3012,11 +3422,22 @@ impl OrderedDataLayout {
         conf: &SharedConfig,
     ) -> DeferredResult<Self, NewDataLayoutWarning, NewDataLayoutError> {
         match datatype {
-            AlphaNumType::Ascii => AsciiLayout::try_new(columns)
-                .map(|x| x.map_or(Self::Empty, Self::Ascii))
-                .mult_to_deferred(),
+            AlphaNumType::Ascii => AsciiLayout::try_new(columns, conf.mixed_ascii_widths)
+                .def_map_value(|x| x.map_or(Self::Empty, Self::Ascii))
+                .def_inner_into(),
             AlphaNumType::Integer => {
-                AnyOrderedUintLayout::try_new(columns, &byteord, conf.disallow_bitmask_truncation)
+                let padded = if conf.pad_integer_widths {
+                    columns
+                        .into_iter()
+                        .map(|c| ColumnLayoutData {
+                            width: c.width.pad_to_bytes(),
+                            ..c
+                        })
+                        .collect()
+                } else {
+                    columns
+                };
+                AnyOrderedUintLayout::try_new(padded, &byteord, conf.disallow_bitmask_truncation)
                     .def_map_value(|x| x.map_or(Self::Empty, Self::Integer))
                     .def_inner_into()
             }
@@ -3046,6 +3467,13 @@ impl OrderedDataLayout {
         }
     }
 
+    fn bitmasks(&self) -> Vec<Option<u64>> {
+        match self {
+            Self::Integer(i) => i.bitmasks(),
+            _ => vec![None; self.ncols()],
+        }
+    }
+
     fn as_writer_inner<'a>(
         &self,
         df: &'a FCSDataFrame,
@@ -3141,36 +3569,386 @@ fn kws_get_columns_3_2(
         .map(Tentative::mconcat)
 }
 
+/// DATA, ANALYSIS and OTHER along with the segments actually read
+pub(crate) type DataAndAnalysis = (
+    FCSDataFrame,
+    Analysis,
+    Others,
+    AnyDataSegment,
+    AnyAnalysisSegment,
+    Vec<ReadDataWarning>,
+);
+
 pub(crate) fn h_read_data_and_analysis<R: Read + Seek>(
     h: &mut BufReader<R>,
     data_reader: DataReader,
     analysis_reader: AnalysisReader,
     others_reader: OthersReader,
-) -> IOResult<
-    (
-        FCSDataFrame,
-        Analysis,
-        Others,
-        AnyDataSegment,
-        AnyAnalysisSegment,
-    ),
-    ReadDataError,
-> {
+    scratch: &mut ParserScratch,
+) -> IOResult<DataAndAnalysis, ReadDataError> {
     let dseg = data_reader.seg;
-    let data = data_reader.h_read(h)?;
+    let (data, ws) = data_reader.h_read(h, scratch)?;
     let analysis = analysis_reader.h_read(h)?;
     let others = others_reader.h_read(h)?;
-    Ok((data, analysis, others, dseg, analysis_reader.seg))
+    Ok((data, analysis, others, dseg, analysis_reader.seg, ws))
+}
+
+/// Number of events to sample when guessing $BYTEORD
+const BYTEORD_GUESS_EVENTS: u64 = 1000;
+
+/// Approximate number of bytes to read at once from fixed-width DATA
+const DATA_BLOCK_BYTES: usize = 1 << 20;
+
+/// Decode one event into row 'r' of each column.
+///
+/// Each column is paired with the number of bytes to skip before it and its
+/// index in the original event.
+fn decode_event(
+    columns: &mut [(usize, usize, AlphaNumColumnReader)],
+    event: &[u8],
+    r: usize,
+    event_index: usize,
+    lenient_ascii: bool,
+) -> IOResult<(), AsciiCellError> {
+    let mut cur = event;
+    for (skip, i, c) in columns.iter_mut() {
+        cur = &cur[*skip..];
+        match c {
+            AlphaNumColumnReader::Float(f) => f.h_read(&mut cur, r)?,
+            AlphaNumColumnReader::Uint(u) => u.h_read(&mut cur, r)?,
+            AlphaNumColumnReader::Ascii(d) => {
+                let (x, rest) = cur.split_at(usize::from(u8::from(d.width)));
+                cur = rest;
+                d.column[r] = parse_ascii(x, lenient_ascii).map_err(|error| {
+                    ImpureError::Pure(AsciiCellError {
+                        event: event_index,
+                        index: (*i).into(),
+                        error,
+                    })
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Group sorted indices into ranges of consecutive indices.
+fn consecutive_runs(xs: &[usize]) -> Vec<std::ops::Range<usize>> {
+    let mut runs: Vec<std::ops::Range<usize>> = vec![];
+    for &x in xs {
+        match runs.last_mut() {
+            Some(r) if r.end == x => r.end += 1,
+            _ => runs.push(x..x + 1),
+        }
+    }
+    runs
+}
+
+/// Guess $BYTEORD by reading a sample of events in DATA.
+///
+/// Only applies if $BYTEORD is missing or cannot be parsed. Each value in the
+/// first few events will be read as both big and little endian, and the
+/// endianness for which the most values are plausible given $PnR will be
+/// chosen. Integers are plausible if they do not exceed $PnR, and floats are
+/// plausible if they are finite and neither absurdly large nor vanishingly
+/// small relative to $PnR.
+///
+/// Return None if $BYTEORD is valid, if the layout is not numeric with
+/// byte-aligned widths, or if neither endianness is more plausible.
+pub(crate) fn h_guess_byteord<R: Read + Seek>(
+    h: &mut BufReader<R>,
+    version: Version,
+    kws: &StdKeywords,
+    seg: HeaderDataSegment,
+) -> io::Result<Option<ByteOrdGuess>> {
+    let original = kws.get(&ByteOrd::std()).cloned();
+    let is_valid = original.as_ref().is_some_and(|s| match version {
+        Version::FCS2_0 | Version::FCS3_0 => s.parse::<ByteOrd>().is_ok(),
+        Version::FCS3_1 | Version::FCS3_2 => s.parse::<Endian>().is_ok(),
+    });
+    if is_valid {
+        return Ok(None);
+    }
+    let Some((datatype, columns)) = AlphaNumType::get_metaroot_req(kws)
+        .ok()
+        .zip(kws_get_columns(kws).ok())
+    else {
+        return Ok(None);
+    };
+    let Some(widths) = columns
+        .iter()
+        .map(|c| {
+            let b = Bytes::try_from(c.width).ok()?;
+            let n = usize::from(u8::from(b));
+            let r = f64::try_from(c.range.0).ok()?;
+            let ok = match datatype {
+                AlphaNumType::Ascii => false,
+                AlphaNumType::Integer => n > 0,
+                AlphaNumType::Single => n == 4,
+                AlphaNumType::Double => n == 8,
+            };
+            ok.then_some((n, r))
+        })
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Ok(None);
+    };
+    let event_width: usize = widths.iter().map(|(n, _)| n).sum();
+    let Some((begin, end)) = kws_data_bounds(kws, seg) else {
+        return Ok(None);
+    };
+    if event_width == 0 || end <= begin {
+        return Ok(None);
+    }
+    let nbytes = (end - begin).min(event_width as u64 * BYTEORD_GUESS_EVENTS);
+    let mut buf = vec![];
+    h.seek(SeekFrom::Start(begin))?;
+    h.take(nbytes).read_to_end(&mut buf)?;
+
+    let mut little = 0;
+    let mut big = 0;
+    let mut total = 0;
+    for event in buf.chunks_exact(event_width) {
+        let mut i = 0;
+        for (n, r) in widths.iter().copied() {
+            let xs = &event[i..i + n];
+            i += n;
+            total += 1;
+            if is_plausible(datatype, xs.iter().copied(), r) {
+                little += 1;
+            }
+            if is_plausible(datatype, xs.iter().rev().copied(), r) {
+                big += 1;
+            }
+        }
+    }
+    let endian = match little.cmp(&big) {
+        std::cmp::Ordering::Greater => Endian::Little,
+        std::cmp::Ordering::Less => Endian::Big,
+        std::cmp::Ordering::Equal => return Ok(None),
+    };
+    // 3.1+ only allows 4-byte endian strings; older versions need one entry
+    // per byte, which we take from the first measurement
+    let value = match version {
+        Version::FCS3_1 | Version::FCS3_2 => endian.to_string(),
+        Version::FCS2_0 | Version::FCS3_0 => {
+            let n = widths[0].0;
+            match endian {
+                Endian::Little => (1..=n).join(","),
+                Endian::Big => (1..=n).rev().join(","),
+            }
+        }
+    };
+    Ok(Some(ByteOrdGuess {
+        endian,
+        value,
+        original,
+        little,
+        big,
+        total,
+    }))
+}
+
+/// Return the first byte of DATA and the byte after its last byte.
+///
+/// Use HEADER if it has DATA offsets, and fall back to $BEGIN/ENDDATA
+/// otherwise (ie for large files).
+fn kws_data_bounds(kws: &StdKeywords, seg: HeaderDataSegment) -> Option<(u64, u64)> {
+    let begin = seg
+        .inner
+        .try_coords()
+        .map(|(b, _)| u64::from(b))
+        .or_else(|| {
+            kws.get(&Begindata::std())
+                .and_then(|x| x.trim().parse().ok())
+        });
+    let end = seg.inner.try_next_byte().or_else(|| {
+        kws.get(&Enddata::std())
+            .and_then(|x| x.trim().parse::<u64>().ok())
+            .map(|e| e + 1)
+    });
+    begin.zip(end)
+}
+
+/// Histogram counts from DATA when $MODE is "C" or "U" (2.0-3.1).
+///
+/// In uncorrelated mode ("U"), DATA holds one histogram per measurement, one
+/// after the other. Measurement n has $PnR bins, and each count is an
+/// unsigned integer $PnB bits wide. 'counts' will have one vector per
+/// measurement, each with $PnR counts.
+///
+/// In correlated mode ("C"), DATA holds one multivariate histogram with a bin
+/// for each combination of measurement values, where measurement n has $PnR
+/// bins along its axis. 'counts' will have one flattened vector in row-major
+/// order (ie the bin for the last measurement varies fastest), so its length
+/// will be the product of all $PnR. Each count is $PnB bits wide, which must
+/// be the same for all measurements.
+///
+/// In both cases, counts use $BYTEORD, and $DATATYPE is ignored.
+#[derive(Clone)]
+pub struct HistogramData {
+    /// Value of $MODE
+    pub mode: Mode,
+
+    /// Number of bins for each measurement (ie $PnR)
+    pub bins: Vec<u64>,
+
+    /// Counts for each histogram as described above
+    pub counts: Vec<Vec<u64>>,
+}
+
+/// Read one unsigned count from a slice using $BYTEORD
+type UintReader = Box<dyn Fn(&[u8]) -> Option<u64>>;
+
+impl HistogramData {
+    pub(crate) fn h_read<R: Read + Seek>(
+        h: &mut BufReader<R>,
+        version: Version,
+        kws: &StdKeywords,
+        seg: HeaderDataSegment,
+    ) -> IOResult<Self, HistogramError> {
+        use crate::error::ImpureError::Pure;
+        let mode = kws
+            .get(&Mode::std())
+            .and_then(|x| x.parse::<Mode>().ok())
+            .ok_or(Pure(HistogramError::Mode))?;
+        if mode == Mode::List {
+            return Err(Pure(HistogramError::ListMode));
+        }
+        let columns = kws_get_columns(kws).map_err(|es| Pure(HistogramError::Columns(es.head)))?;
+        let mut bins = vec![];
+        let mut widths = vec![];
+        for (i, c) in columns.iter().enumerate() {
+            let n = Bytes::try_from(c.width)
+                .ok()
+                .map(|b| usize::from(u8::from(b)))
+                .filter(|n| (1..=8).contains(n));
+            let r = f64::try_from(c.range.0)
+                .ok()
+                .filter(|r| *r >= 1.0)
+                .map(|r| r as u64);
+            match n.zip(r) {
+                Some((w, b)) => {
+                    widths.push(w);
+                    bins.push(b);
+                }
+                None => return Err(Pure(HistogramError::Column(i.into()))),
+            }
+        }
+        let byteord = kws
+            .get(&ByteOrd::std())
+            .ok_or(Pure(HistogramError::ByteOrd))?;
+        let read_uint: UintReader = match version {
+            Version::FCS2_0 | Version::FCS3_0 => {
+                let b = byteord
+                    .parse::<ByteOrd>()
+                    .map_err(|_| Pure(HistogramError::ByteOrd))?;
+                Box::new(move |xs| b.read_uint(xs))
+            }
+            Version::FCS3_1 | Version::FCS3_2 => {
+                let e = byteord
+                    .parse::<Endian>()
+                    .map_err(|_| Pure(HistogramError::ByteOrd))?;
+                Box::new(move |xs| Some(e.read_uint(xs)))
+            }
+        };
+        // each histogram is a number of counts and the width of each count
+        let shapes: Vec<(u64, usize)> = match mode {
+            Mode::Uncorrelated => bins.iter().copied().zip(widths.iter().copied()).collect(),
+            _ => {
+                let w = widths.first().copied().unwrap_or(1);
+                if widths.iter().any(|x| *x != w) {
+                    return Err(Pure(HistogramError::UnequalWidths));
+                }
+                let total = bins
+                    .iter()
+                    .try_fold(1_u64, |acc, b| acc.checked_mul(*b))
+                    .ok_or(Pure(HistogramError::TooLarge))?;
+                vec![(total, w)]
+            }
+        };
+        let expected = shapes
+            .iter()
+            .try_fold(0_u64, |acc, (n, w)| {
+                acc.checked_add(n.checked_mul(*w as u64)?)
+            })
+            .ok_or(Pure(HistogramError::TooLarge))?;
+        let (begin, end) = kws_data_bounds(kws, seg).unwrap_or((0, 0));
+        let actual = end.saturating_sub(begin);
+        if expected != actual {
+            return Err(Pure(HistogramError::Length { expected, actual }));
+        }
+        h.seek(SeekFrom::Start(begin))?;
+        let mut counts = vec![];
+        let mut buf = [0_u8; 8];
+        for (n, w) in shapes {
+            let mut xs = Vec::with_capacity(n as usize);
+            for _ in 0..n {
+                h.read_exact(&mut buf[..w])?;
+                xs.push(read_uint(&buf[..w]).ok_or(Pure(HistogramError::ByteOrd))?);
+            }
+            counts.push(xs);
+        }
+        Ok(Self { mode, bins, counts })
+    }
+}
+
+/// Return true if a value (given as little endian bytes) is within $PnR.
+fn is_plausible(datatype: AlphaNumType, xs: impl Iterator<Item = u8>, range: f64) -> bool {
+    let ys: Vec<u8> = xs.collect();
+    let check_float = |x: f64| {
+        let limit = range.max(1.0) * 10.0;
+        x.is_finite() && (x == 0.0 || (1e-20..=limit).contains(&x.abs()))
+    };
+    match datatype {
+        AlphaNumType::Integer => {
+            let x = ys
+                .iter()
+                .rev()
+                .fold(0_u64, |acc, b| (acc << 8) | u64::from(*b));
+            (x as f64) <= range
+        }
+        AlphaNumType::Single => ys[..]
+            .try_into()
+            .is_ok_and(|b| check_float(f64::from(f32::from_le_bytes(b)))),
+        AlphaNumType::Double => ys[..]
+            .try_into()
+            .is_ok_and(|b| check_float(f64::from_le_bytes(b))),
+        AlphaNumType::Ascii => false,
+    }
 }
 
 enum_from_disp!(
     pub AsciiToUintError,
     [NotAscii, NotAsciiError],
-    [Int, ParseIntError]
+    [Int, ParseIntError],
+    [Decimal, AsciiDecimalError]
 );
 
 pub struct NotAsciiError(Vec<u8>);
 
+pub struct AsciiDecimalError(String);
+
+pub enum HistogramError {
+    Mode,
+    ListMode,
+    Columns(RawParsedError),
+    Column(IndexFromOne),
+    ByteOrd,
+    UnequalWidths,
+    TooLarge,
+    Length { expected: u64, actual: u64 },
+}
+
+/// Error when parsing one value in fixed-width ASCII DATA
+pub struct AsciiCellError {
+    /// Index of the event (starting at 0)
+    event: usize,
+    /// Index of the measurement
+    index: IndexFromOne,
+    error: AsciiToUintError,
+}
+
 enum_from_disp!(
     pub NewDataLayoutError,
     [Ascii,        NewAsciiLayoutError],
@@ -3184,9 +3962,16 @@ enum_from_disp!(
 enum_from_disp!(
     pub NewDataLayoutWarning,
     [FixedInt,     ColumnError<BitmaskError>],
-    [VariableInt,  UintColumnWarning]
+    [VariableInt,  UintColumnWarning],
+    [MixedAscii,   MixedAsciiWidthWarning]
 );
 
+/// Warning when only some $PnB are '*' in an ASCII layout
+pub struct MixedAsciiWidthWarning {
+    variable: Vec<IndexFromOne>,
+    mixed: MixedAsciiWidths,
+}
+
 pub struct NewAsciiLayoutError(ColumnError<WidthToCharsError>);
 
 newtype_from!(NewAsciiLayoutError, ColumnError<WidthToCharsError>);
@@ -3316,9 +4101,73 @@ pub struct TotEventMismatch {
 }
 
 pub struct UnevenEventWidth {
-    event_width: usize,
-    nbytes: usize,
-    remainder: usize,
+    event_width: u64,
+    nbytes: u64,
+    remainder: u64,
+    causes: Vec<UnevenEventWidthCause>,
+    fix: Option<UnevenEventWidthCause>,
+}
+
+/// A $BYTEORD which was inferred from DATA since the original was unusable
+pub struct ByteOrdGuess {
+    pub endian: Endian,
+    /// The value which will be used for $BYTEORD
+    pub value: String,
+    /// The original value of $BYTEORD, if any
+    pub original: Option<String>,
+    /// Number of sampled values plausible when read as little endian
+    pub little: usize,
+    /// Number of sampled values plausible when read as big endian
+    pub big: usize,
+    /// Total number of sampled values
+    pub total: usize,
+}
+
+/// Integer values in DATA which exceeded the bitmask derived from $PnR.
+///
+/// These values are truncated to the bitmask when read.
+pub struct OverRangeWarning {
+    /// Index of the measurement
+    pub index: IndexFromOne,
+    /// Number of events which exceeded the bitmask
+    pub count: usize,
+    /// The bitmask used for truncation
+    pub bitmask: u64,
+}
+
+enum_from_disp!(
+    pub ReadDataWarning,
+    [OverRange, OverRangeWarning],
+    [Truncated, TruncatedDataWarning]
+);
+
+impl ReadDataWarning {
+    /// Return true if this is an integer exceeding its bitmask.
+    pub(crate) fn is_overrange(&self) -> bool {
+        matches!(self, Self::OverRange(_))
+    }
+}
+
+/// The file ended before the last event was complete.
+///
+/// This may happen if ENDDATA is one byte too small and DATA is at the end of
+/// the file. The missing bytes are read as zeros.
+pub struct TruncatedDataWarning {
+    /// Number of bytes which were missing
+    pub missing: usize,
+}
+
+/// A possible explanation for DATA not being evenly divided by event width.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UnevenEventWidthCause {
+    /// ENDDATA points one byte past the last event
+    EndOverByOne,
+    /// ENDDATA points one byte before the end of the last event
+    EndUnderByOne,
+    /// DATA ends with a CRLF, which is 2 bytes
+    CrlfTrailer,
+    /// DATA includes the CRC at the end of the file, which is 8 bytes
+    CrcTrailer,
 }
 
 pub struct ColumnWriterError(ColumnError<AnyLossError>);
@@ -3388,7 +4237,7 @@ enum_from_disp!(
     pub ReadDataError,
     [Delim, ReadDelimAsciiError],
     [DelimNoRows, ReadDelimAsciiNoRowsError],
-    [AlphaNum, AsciiToUintError]
+    [AlphaNum, AsciiCellError]
 );
 
 enum_from_disp!(
@@ -3488,6 +4337,47 @@ impl fmt::Display for ReadDelimAsciiNoRowsError {
     }
 }
 
+impl fmt::Display for HistogramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Mode => write!(f, "$MODE is missing or invalid"),
+            Self::ListMode => write!(f, "$MODE is 'L', DATA does not hold histograms"),
+            Self::Columns(e) => write!(f, "{e}"),
+            Self::Column(i) => write!(
+                f,
+                "$PnB for measurement {i} must be 8-64 whole bytes \
+                 and $PnR must be at least 1 to read histograms"
+            ),
+            Self::ByteOrd => write!(f, "$BYTEORD is missing, invalid, or does not match $PnB"),
+            Self::UnequalWidths => {
+                write!(f, "all $PnB must be equal to read a correlated histogram")
+            }
+            Self::TooLarge => write!(f, "histogram is too large to read"),
+            Self::Length { expected, actual } => write!(
+                f,
+                "histogram should be {expected} bytes according to $PnB \
+                 and $PnR but DATA is {actual} bytes"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AsciiDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not parse '{}' as a decimal number", self.0)
+    }
+}
+
+impl fmt::Display for AsciiCellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "error in ASCII value for event {} and measurement {}: {}",
+            self.event, self.index, self.error
+        )
+    }
+}
+
 impl fmt::Display for NotAsciiError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
@@ -3528,6 +4418,121 @@ impl fmt::Display for TotEventMismatch {
     }
 }
 
+impl UnevenEventWidth {
+    /// Analyze DATA length relative to event width.
+    ///
+    /// Return None if event width evenly divides DATA. Otherwise, list all
+    /// explanations which could account for the remainder in order of
+    /// preference, and select the first as a fix if desired.
+    ///
+    /// Explanations which drop bytes are preferred over those that add bytes
+    /// since the former will never read outside the DATA segment.
+    fn new(event_width: u64, nbytes: u64, fix: bool) -> Option<Self> {
+        let remainder = nbytes % event_width;
+        if remainder == 0 {
+            return None;
+        }
+        let causes = UnevenEventWidthCause::from_remainder(event_width, remainder);
+        let selected = causes.first().copied().filter(|_| fix);
+        Some(Self {
+            event_width,
+            nbytes,
+            remainder,
+            causes,
+            fix: selected,
+        })
+    }
+}
+
+impl UnevenEventWidthCause {
+    fn from_remainder(event_width: u64, remainder: u64) -> Vec<Self> {
+        let mut xs = vec![];
+        if remainder == 1 {
+            xs.push(Self::EndOverByOne);
+        }
+        if remainder == 2 {
+            xs.push(Self::CrlfTrailer);
+        }
+        if remainder == 8 {
+            xs.push(Self::CrcTrailer);
+        }
+        if remainder + 1 == event_width {
+            xs.push(Self::EndUnderByOne);
+        }
+        xs
+    }
+}
+
+impl fmt::Display for UnevenEventWidthCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let s = match self {
+            Self::EndOverByOne => "ENDDATA is one byte too large (or DATA ends with LF)",
+            Self::EndUnderByOne => "ENDDATA is one byte too small",
+            Self::CrlfTrailer => "DATA ends with CRLF",
+            Self::CrcTrailer => "DATA includes 8-byte CRC",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for TruncatedDataWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "file ended {} byte(s) before the end of the last event, \
+             which were read as 0",
+            self.missing
+        )
+    }
+}
+
+impl fmt::Display for OverRangeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} event(s) in measurement {} exceeded $PnR and were \
+             truncated to {}",
+            self.count, self.index, self.bitmask
+        )
+    }
+}
+
+impl fmt::Display for ByteOrdGuess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let e = match self.endian {
+            Endian::Big => "big",
+            Endian::Little => "little",
+        };
+        match &self.original {
+            Some(x) => write!(f, "$BYTEORD '{x}' is invalid")?,
+            None => write!(f, "$BYTEORD is missing")?,
+        }
+        write!(
+            f,
+            "; guessed {e} endian ({}) since {} of {} sampled values \
+             were within $PnR (vs {} for the other endianness)",
+            self.value,
+            self.little.max(self.big),
+            self.total,
+            self.little.min(self.big),
+        )
+    }
+}
+
+impl fmt::Display for MixedAsciiWidthWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let how = match self.mixed {
+            MixedAsciiWidths::Delimited => "reading all measurements as delimited",
+            _ => "using width from $PnR",
+        };
+        write!(
+            f,
+            "$PnB is '*' for some but not all ASCII measurements ({}), {how}",
+            self.variable.iter().join(", ")
+        )
+    }
+}
+
 impl fmt::Display for UnevenEventWidth {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
@@ -3536,6 +4541,410 @@ impl fmt::Display for UnevenEventWidth {
              divide DATA segment which is {} bytes long \
              (remainder of {})",
             self.event_width, self.nbytes, self.remainder,
-        )
+        )?;
+        if self.causes.is_empty() {
+            write!(f, "; no likely explanation found")?;
+        } else {
+            write!(f, "; possible causes: {}", self.causes.iter().join(", "))?;
+        }
+        if let Some(x) = self.fix {
+            write!(f, "; assuming {x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const DELIMS: [u8; 5] = [9, 10, 13, 32, 44];
+
+    // xorshift so the "random" inputs are reproducible
+    fn next(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn delims(state: &mut u64, min: u64) -> Vec<u8> {
+        let n = min + next(state) % 3;
+        (0..n).map(|_| DELIMS[(next(state) % 5) as usize]).collect()
+    }
+
+    fn random_data(seed: u64) -> (Vec<u64>, Vec<u8>) {
+        let mut state = seed + 1;
+        let n = (next(&mut state) % 50) as usize;
+        let xs: Vec<_> = (0..n).map(|_| next(&mut state) % 100_000).collect();
+        let mut bytes = delims(&mut state, 0);
+        for x in xs.iter() {
+            bytes.extend(x.to_string().as_bytes());
+            bytes.extend(delims(&mut state, 1));
+        }
+        // sometimes omit the final delimiter
+        if next(&mut state).is_multiple_of(2) {
+            while bytes.last().is_some_and(|b| is_ascii_delim(*b)) {
+                bytes.pop();
+            }
+        }
+        (xs, bytes)
+    }
+
+    #[test]
+    fn test_ascii_to_uint_padded() {
+        let parse = |x: &str| ascii_to_uint_lenient(x.as_bytes()).ok();
+        assert_eq!(parse("   42"), Some(42));
+        assert_eq!(parse("00042"), Some(42));
+        assert_eq!(parse("42   "), Some(42));
+        assert_eq!(parse(" +42 "), Some(42));
+        assert_eq!(parse("42.4"), Some(42));
+        assert_eq!(parse("42.5"), Some(43));
+        assert_eq!(parse("42."), Some(42));
+        assert_eq!(parse("   "), None);
+        assert_eq!(parse("4.2e1"), None);
+        assert_eq!(parse("-42"), None);
+        assert_eq!(parse("18446744073709551614.5"), Some(u64::MAX));
+        assert_eq!(parse("18446744073709551615.5"), None);
+        assert_eq!(parse("4\u{e9}"), None);
+    }
+
+    #[test]
+    fn test_ascii_to_uint_strict() {
+        let parse = |x: &str| ascii_to_uint(x.as_bytes()).ok();
+        assert_eq!(parse("42"), Some(42));
+        assert_eq!(parse("00042"), Some(42));
+        assert_eq!(parse("+42"), Some(42));
+        assert_eq!(parse("   42"), None);
+        assert_eq!(parse("42   "), None);
+        assert_eq!(parse("42.5"), None);
+        assert_eq!(parse("18446744073709551615"), Some(u64::MAX));
+        assert_eq!(parse("4\u{e9}"), None);
+        assert!(ascii_to_uint(&[b'4', 0xff]).is_err());
+    }
+
+    #[test]
+    fn test_delim_ascii_random_delims() {
+        for seed in 0..500 {
+            let (xs, bytes) = random_data(seed);
+            let mut h = BufReader::new(Cursor::new(bytes.clone()));
+            let mut ys = vec![];
+            let n = h_read_delim_ascii_values(&mut h, bytes.len(), |_, buf| {
+                ascii_to_uint(buf).map(|x| ys.push(x))
+            })
+            .map_err(|_| ())
+            .unwrap();
+            assert_eq!(n, xs.len(), "seed {seed}");
+            assert_eq!(ys, xs, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_delim_ascii_rows_exceeded() {
+        let bytes = b"1,2,3,4,5,".to_vec();
+        let reader = DelimAsciiReader(DelimAsciiReaderInner {
+            columns: NonEmpty::from((vec![0; 2], vec![vec![0; 2]])),
+            nbytes: bytes.len(),
+            lenient: false,
+        });
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let res = reader.h_read(&mut h);
+        assert!(matches!(
+            res,
+            Err(ImpureError::Pure(ReadDelimAsciiError::RowsExceeded(_)))
+        ));
+    }
+
+    #[test]
+    fn test_delim_ascii_no_rows_trailing() {
+        let bytes = b" 1, 2\r\n3,4\r\n".to_vec();
+        let reader = DelimAsciiReaderNoRows(DelimAsciiReaderInner {
+            columns: NonEmpty::from((vec![], vec![vec![]])),
+            nbytes: bytes.len(),
+            lenient: false,
+        });
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let df = reader.h_read(&mut h).map_err(|_| ()).unwrap();
+        assert_eq!(df.nrows(), 2);
+    }
+
+    #[test]
+    fn test_alphanum_selection() {
+        let bytes = b"010203111213212223313233".to_vec();
+        let width = "2"
+            .parse::<Width>()
+            .ok()
+            .and_then(|w| Chars::try_from(w).ok())
+            .unwrap();
+        let col = || {
+            AlphaNumColumnReader::Ascii(AsciiColumnReader {
+                column: vec![],
+                width,
+            })
+        };
+        let reader = AlphaNumReader {
+            columns: NonEmpty::from((col(), vec![col(), col()])),
+            nrows: 4,
+            nbytes: bytes.len() as u64,
+            lenient_ascii: false,
+        };
+        let sel = DataSelection {
+            columns: Some(vec![false, true, false]),
+            rows: Some(1..3),
+            sample: None,
+        };
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let mut scratch = ParserScratch::default();
+        let (df, _) = reader
+            .h_read(&mut h, &sel, &mut scratch)
+            .map_err(|_| ())
+            .unwrap();
+        let cs: Vec<_> = df.iter_columns().collect();
+        assert_eq!(cs.len(), 1);
+        assert_eq!(df.nrows(), 2);
+        assert_eq!(cs[0].pos_to_string(0), "12");
+        assert_eq!(cs[0].pos_to_string(1), "22");
+    }
+
+    #[test]
+    fn test_alphanum_subsample() {
+        let bytes = b"0001020304050607".to_vec();
+        let width = "2"
+            .parse::<Width>()
+            .ok()
+            .and_then(|w| Chars::try_from(w).ok())
+            .unwrap();
+        let reader = AlphaNumReader {
+            columns: NonEmpty::new(AlphaNumColumnReader::Ascii(AsciiColumnReader {
+                column: vec![],
+                width,
+            })),
+            nrows: 8,
+            nbytes: bytes.len() as u64,
+            lenient_ascii: false,
+        };
+        let sel = DataSelection {
+            columns: None,
+            rows: Some(1..8),
+            sample: Some((SubsampleSize::Count(3), SubsampleMethod::Systematic)),
+        };
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let mut scratch = ParserScratch::default();
+        let (df, _) = reader
+            .h_read(&mut h, &sel, &mut scratch)
+            .map_err(|_| ())
+            .unwrap();
+        let c = df.iter_columns().next().unwrap();
+        assert_eq!(df.nrows(), 3);
+        assert_eq!(c.pos_to_string(0), "1");
+        assert_eq!(c.pos_to_string(1), "3");
+        assert_eq!(c.pos_to_string(2), "5");
+        scratch.recycle(df);
+        assert_eq!(scratch.ncolumns(), 1);
+    }
+
+    #[test]
+    fn test_end_under_by_one() {
+        // four 8-bit measurements with ENDDATA one byte short of 2 events
+        let read = |bytes: Vec<u8>| {
+            let columns = (0..4)
+                .map(|_| ColumnLayoutData {
+                    width: Width::from(Some(8)),
+                    range: Range::try_from(256.0).ok().unwrap(),
+                    datatype: (),
+                })
+                .collect();
+            let byteord = ByteOrd::try_from(vec![1]).ok().unwrap();
+            let layout = OrderedDataLayout::try_new(
+                AlphaNumType::Integer,
+                byteord,
+                columns,
+                &SharedConfig::default(),
+            )
+            .ok()
+            .unwrap()
+            .terminate(())
+            .ok()
+            .unwrap()
+            .resolve(|_| ())
+            .0;
+            let seg = AnyDataSegment::try_new(0_u64, 6_u64, OffsetCorrection::new(0, 0))
+                .ok()
+                .unwrap();
+            let conf = ReaderConfig {
+                fix_uneven_event_width: true,
+                ..ReaderConfig::default()
+            };
+            let OrderedDataLayout::Integer(fl) = layout else {
+                panic!("expected integer layout")
+            };
+            let (reader, _) = fl
+                .into_col_reader_inner(seg, &conf)
+                .terminate(())
+                .ok()
+                .unwrap()
+                .resolve(|_| ());
+            let mut h = BufReader::new(Cursor::new(bytes));
+            reader
+                .h_read(
+                    &mut h,
+                    &DataSelection::default(),
+                    &mut ParserScratch::default(),
+                )
+                .map_err(|_| ())
+                .unwrap()
+        };
+
+        // the last byte is just after ENDDATA
+        let (after_df, after_ws) = read(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let after_cs: Vec<_> = after_df.iter_columns().collect();
+        assert_eq!(after_df.nrows(), 2);
+        assert_eq!(after_cs[2].pos_to_string(1), "7");
+        assert_eq!(after_cs[3].pos_to_string(1), "8");
+        assert!(after_ws.is_empty());
+
+        // the file ends at ENDDATA
+        let (end_df, end_ws) = read(vec![1, 2, 3, 4, 5, 6, 7]);
+        let end_cs: Vec<_> = end_df.iter_columns().collect();
+        assert_eq!(end_cs[3].pos_to_string(1), "0");
+        assert!(matches!(
+            end_ws[..],
+            [ReadDataWarning::Truncated(TruncatedDataWarning {
+                missing: 1
+            })]
+        ));
+    }
+
+    #[test]
+    fn test_consecutive_runs() {
+        assert_eq!(consecutive_runs(&[]), Vec::<std::ops::Range<usize>>::new());
+        assert_eq!(consecutive_runs(&[0, 1, 2]), vec![0..3]);
+        assert_eq!(consecutive_runs(&[1, 2, 5, 7, 8]), vec![1..3, 5..6, 7..9]);
+    }
+
+    #[test]
+    fn test_uint_overrange() {
+        let bytes = vec![1, 200, 7, 255];
+        let col = AlphaNumColumnReader::Uint(AnyUintColumnReader::Uint08(UintColumnReader {
+            column: vec![],
+            uint_type: UintType {
+                bitmask: 99,
+                byte_layout: SizedByteOrd::Endian(Endian::Little),
+            },
+            overrange: 0,
+        }));
+        let reader = AlphaNumReader {
+            columns: NonEmpty::new(col),
+            nrows: 4,
+            nbytes: bytes.len() as u64,
+            lenient_ascii: false,
+        };
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let (df, ws) = reader
+            .h_read(
+                &mut h,
+                &DataSelection::default(),
+                &mut ParserScratch::default(),
+            )
+            .map_err(|_| ())
+            .unwrap();
+        let cs: Vec<_> = df.iter_columns().collect();
+        assert_eq!(cs[0].pos_to_string(1), "99");
+        let [ReadDataWarning::OverRange(w)] = &ws[..] else {
+            panic!("expected one overrange warning")
+        };
+        assert_eq!(w.count, 2);
+        assert_eq!(w.bitmask, 99);
+    }
+
+    #[test]
+    fn test_pad_integer_widths() {
+        let columns = || {
+            vec![ColumnLayoutData {
+                width: Width::from(Some(12)),
+                range: Range::try_from(4096.0).ok().unwrap(),
+                datatype: (),
+            }]
+        };
+        let byteord = || ByteOrd::try_from(vec![1, 2]).ok().unwrap();
+        let mut conf = SharedConfig::default();
+        let unpadded =
+            OrderedDataLayout::try_new(AlphaNumType::Integer, byteord(), columns(), &conf);
+        assert!(unpadded.is_err());
+        conf.pad_integer_widths = true;
+        let padded = OrderedDataLayout::try_new(AlphaNumType::Integer, byteord(), columns(), &conf);
+        assert!(matches!(
+            padded.map(|x| x.into_parts().0),
+            Ok(OrderedDataLayout::Integer(AnyOrderedUintLayout::Uint16(_)))
+        ));
+    }
+
+    #[test]
+    fn test_uncorrelated_histogram() {
+        let kws: StdKeywords = [
+            ("$MODE", "U"),
+            ("$PAR", "2"),
+            ("$P1B", "8"),
+            ("$P1R", "2"),
+            ("$P2B", "16"),
+            ("$P2R", "3"),
+            ("$BYTEORD", "1,2"),
+            ("$BEGINDATA", "0"),
+            ("$ENDDATA", "7"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.parse().unwrap(), v.to_string()))
+        .collect();
+        let bytes = vec![5, 6, 1, 0, 2, 0, 3, 0];
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let seg = HeaderDataSegment::default();
+        let Ok(hist) = HistogramData::h_read(&mut h, Version::FCS3_0, &kws, seg) else {
+            panic!("could not read histogram");
+        };
+        assert_eq!(hist.bins, vec![2, 3]);
+        assert_eq!(hist.counts, vec![vec![5, 6], vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_uneven_event_width_over_4gb() {
+        // ~5 GiB of 12-byte events with CRLF at the end
+        let nevents: u64 = 450_000_000;
+        let x = UnevenEventWidth::new(12, 12 * nevents + 2, true).unwrap();
+        assert_eq!(x.remainder, 2);
+        assert_eq!(x.fix, Some(UnevenEventWidthCause::CrlfTrailer));
+        assert!(UnevenEventWidth::new(12, 12 * nevents, true).is_none());
+    }
+
+    #[test]
+    fn test_mixed_ascii_widths() {
+        let col = |width, r| ColumnLayoutData {
+            width,
+            range: Range::from(r),
+            datatype: (),
+        };
+        let go = |m| {
+            let cs = vec![
+                col(Width::Variable, 1024),
+                col(Chars::from_max(99).into(), 100),
+            ];
+            AsciiLayout::try_new(cs, m).ok().map(|t| t.into_parts())
+        };
+        assert!(go(MixedAsciiWidths::Error).is_none());
+        match go(MixedAsciiWidths::Delimited) {
+            Some((Some(AsciiLayout::Delimited(d)), ws, _)) => {
+                assert_eq!(d.ncols, 2);
+                assert_eq!(ws.len(), 1);
+            }
+            _ => panic!("expected delimited layout"),
+        }
+        match go(MixedAsciiWidths::FromRange) {
+            Some((Some(AsciiLayout::Fixed(l)), ws, _)) => {
+                let chars: Vec<_> = l.columns.iter().map(|c| c.chars).collect();
+                assert!(chars == vec![Chars::from_max(1023), Chars::from_max(99)]);
+                assert_eq!(ws.len(), 1);
+            }
+            _ => panic!("expected fixed layout"),
+        }
     }
 }
@@ -0,0 +1,133 @@
+//! Hash of DATA which may be stored in non-standard keywords.
+//!
+//! When writing, a SHA-256 digest of DATA may be stored in TEXT along with the
+//! version of this library and the time of writing (see
+//! [`WriteConfig::data_hash`]). When reading, this digest may be compared to
+//! DATA to check that it was not altered or corrupted since it was written.
+//! Unlike the CRC, this only covers DATA and therefore survives changes to
+//! TEXT.
+//!
+//! [`WriteConfig::data_hash`]: crate::config::WriteConfig::data_hash
+
+use chrono::{SecondsFormat, Utc};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+/// Non-standard key for the hex-encoded SHA-256 digest of DATA
+pub const DATA_HASH_KEY: &str = "FIREFLOW_DATA_SHA256";
+
+/// Non-standard key for the library version which wrote the file
+pub const WRITER_VERSION_KEY: &str = "FIREFLOW_WRITER";
+
+/// Non-standard key for the UTC time the file was written (RFC 3339)
+pub const WRITTEN_KEY: &str = "FIREFLOW_WRITTEN";
+
+/// Return keywords with the digest of DATA, library version, and current time.
+pub(crate) fn provenance_keywords(data: &[u8]) -> Vec<(String, String)> {
+    [
+        (DATA_HASH_KEY, format!("{:x}", Sha256::digest(data))),
+        (
+            WRITER_VERSION_KEY,
+            format!("fireflow {}", env!("CARGO_PKG_VERSION")),
+        ),
+        (
+            WRITTEN_KEY,
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        ),
+    ]
+    .map(|(k, v)| (k.to_string(), v))
+    .into()
+}
+
+/// Check the stored digest against DATA between 'begin' and 'end' inclusive.
+///
+/// If 'begin' and 'end' are None, DATA is empty. Return None if the digests
+/// match (ignoring case).
+pub(crate) fn h_check_data_hash<R: Read + Seek>(
+    h: &mut BufReader<R>,
+    coords: Option<(u64, u64)>,
+    stored: &str,
+) -> io::Result<Option<DataHashMismatchError>> {
+    let mut sha = Sha256::new();
+    if let Some((begin, end)) = coords {
+        let mut buf = [0; 8192];
+        let mut remaining = end + 1 - begin;
+        h.seek(SeekFrom::Start(begin))?;
+        while remaining > 0 {
+            let n = remaining.min(buf.len() as u64) as usize;
+            h.read_exact(&mut buf[..n])?;
+            sha.update(&buf[..n]);
+            remaining -= n as u64;
+        }
+    }
+    let computed = format!("{:x}", sha.finalize());
+    Ok(
+        (!computed.eq_ignore_ascii_case(stored.trim())).then(|| DataHashMismatchError {
+            stored: stored.to_string(),
+            computed,
+        }),
+    )
+}
+
+pub struct DataHashMismatchError {
+    stored: String,
+    computed: String,
+}
+
+impl fmt::Display for DataHashMismatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{DATA_HASH_KEY} is '{}' but SHA-256 of DATA is '{}'",
+            self.stored, self.computed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sha(xs: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(xs))
+    }
+
+    // vectors from NIST FIPS 180-2 examples
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            sha(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha(&[b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn test_check_data_hash() {
+        let bytes = b"HEADERdata bytes".to_vec();
+        let digest = sha(b"data bytes");
+        let mut h = BufReader::new(Cursor::new(bytes));
+        let c = Some((6, 15));
+        assert!(h_check_data_hash(&mut h, c, &digest).unwrap().is_none());
+        assert!(h_check_data_hash(&mut h, c, &sha(b"other"))
+            .unwrap()
+            .is_some());
+        assert!(h_check_data_hash(&mut h, None, &sha(b""))
+            .unwrap()
+            .is_none());
+    }
+}
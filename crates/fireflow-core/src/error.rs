@@ -141,6 +141,21 @@ where
 
 impl<I: Iterator<Item = Result<T, E>>, T, E> ErrorIter<T, E> for I {}
 
+fn promote_warnings<F, W, E>(xs: Vec<W>, f: F) -> (Vec<W>, Vec<E>)
+where
+    F: Fn(W) -> Result<W, E>,
+{
+    let mut ws = vec![];
+    let mut es = vec![];
+    for x in xs {
+        match f(x) {
+            Ok(w) => ws.push(w),
+            Err(e) => es.push(e),
+        }
+    }
+    (ws, es)
+}
+
 impl<V, W> Terminal<V, W> {
     pub fn new(value: V) -> Self {
         Self {
@@ -412,6 +427,16 @@ impl<V, W, E> Tentative<V, W, E> {
         self.errors.extend(xs)
     }
 
+    /// Turn warnings into errors if 'f' returns Err, otherwise keep them.
+    pub fn promote_warnings<F>(&mut self, f: F)
+    where
+        F: Fn(W) -> Result<W, E>,
+    {
+        let (ws, es) = promote_warnings(std::mem::take(&mut self.warnings), f);
+        self.warnings = ws;
+        self.errors.extend(es);
+    }
+
     pub fn map<F, X>(self, f: F) -> Tentative<X, W, E>
     where
         F: FnOnce(V) -> X,
@@ -595,6 +620,23 @@ impl<V, W, E> Tentative<V, W, E> {
         }
     }
 
+    /// Like [`Tentative::terminate`] but also fail if there are any warnings
+    /// and 'warnings_are_errors' is true.
+    pub fn terminate_strict<T>(
+        self,
+        reason: T,
+        warnings_are_errors: bool,
+    ) -> TerminalResult<V, W, E, T> {
+        if warnings_are_errors && self.errors.is_empty() && !self.warnings.is_empty() {
+            Err(TerminalFailure {
+                warnings: self.warnings,
+                failure: Failure::Single(reason),
+            })
+        } else {
+            self.terminate(reason)
+        }
+    }
+
     pub fn zip<A>(self, a: Tentative<A, W, E>) -> Tentative<(V, A), W, E> {
         self.zip_with(a, |x, y| (x, y))
     }
@@ -660,12 +702,10 @@ impl<V, W, E> Tentative<V, W, E> {
         Tentative::new((), self.warnings, self.errors)
     }
 
-    #[cfg(test)]
     pub(crate) fn value(&self) -> &V {
         &self.value
     }
 
-    #[cfg(test)]
     pub(crate) fn errors(&self) -> &[E] {
         &self.errors[..]
     }
@@ -674,6 +714,10 @@ impl<V, W, E> Tentative<V, W, E> {
     pub(crate) fn warnings(&self) -> &[W] {
         &self.warnings[..]
     }
+
+    pub(crate) fn into_parts(self) -> (V, Vec<W>, Vec<E>) {
+        (self.value, self.warnings, self.errors)
+    }
 }
 
 impl<V, W, E> Tentative<Option<V>, W, E> {
@@ -719,6 +763,16 @@ impl<P, W, E> DeferredFailure<P, W, E> {
         }
     }
 
+    /// Turn warnings into errors if 'f' returns Err, otherwise keep them.
+    pub fn promote_warnings<F>(&mut self, f: F)
+    where
+        F: Fn(W) -> Result<W, E>,
+    {
+        let (ws, es) = promote_warnings(std::mem::take(&mut self.warnings), f);
+        self.warnings = ws;
+        self.errors.extend(es);
+    }
+
     pub fn map_passthru<F, X>(self, f: F) -> DeferredFailure<X, W, E>
     where
         F: FnOnce(P) -> X,
@@ -778,6 +832,13 @@ impl<P, W, E> DeferredFailure<P, W, E> {
         DeferredFailure::new(self.warnings, self.errors, ())
     }
 
+    /// Add all warnings and errors from a tentative, discarding its value.
+    pub fn absorb<V>(mut self, other: Tentative<V, W, E>) -> Self {
+        self.warnings.extend(other.warnings);
+        self.errors.extend(other.errors);
+        self
+    }
+
     pub fn zip<P1>(self, a: DeferredFailure<P1, W, E>) -> DeferredFailure<(P, P1), W, E> {
         self.zip_with(a, |x, y| (x, y))
     }
@@ -1101,6 +1162,10 @@ pub trait PassthruExt: Sized {
 
     fn def_push_warning(&mut self, w: Self::W);
 
+    fn def_promote_warnings<F>(&mut self, f: F)
+    where
+        F: Fn(Self::W) -> Result<Self::W, Self::E>;
+
     fn def_push_error_or_warning<X>(&mut self, x: X, is_error: bool)
     where
         X: Into<Self::W>,
@@ -1185,6 +1250,16 @@ impl<V, P, W, E> PassthruExt for PassthruResult<V, P, W, E> {
             Err(f) => f.push_warning(w),
         }
     }
+
+    fn def_promote_warnings<F>(&mut self, f: F)
+    where
+        F: Fn(Self::W) -> Result<Self::W, Self::E>,
+    {
+        match self {
+            Ok(tnt) => tnt.promote_warnings(f),
+            Err(g) => g.promote_warnings(f),
+        }
+    }
 }
 
 pub trait DeferredExt: Sized {
@@ -1214,6 +1289,12 @@ pub trait DeferredExt: Sized {
 
     fn def_terminate<T>(self, reason: T) -> TerminalResult<Self::V, Self::W, Self::E, T>;
 
+    fn def_terminate_strict<T>(
+        self,
+        reason: T,
+        warnings_are_errors: bool,
+    ) -> TerminalResult<Self::V, Self::W, Self::E, T>;
+
     fn def_unfail(self) -> Tentative<Option<Self::V>, Self::W, Self::E>;
 }
 
@@ -1262,6 +1343,17 @@ impl<V, W, E> DeferredExt for DeferredResult<V, W, E> {
         }
     }
 
+    fn def_terminate_strict<T>(
+        self,
+        reason: T,
+        warnings_are_errors: bool,
+    ) -> TerminalResult<Self::V, Self::W, Self::E, T> {
+        match self {
+            Ok(t) => t.terminate_strict(reason, warnings_are_errors),
+            Err(e) => Err(e.terminate(reason)),
+        }
+    }
+
     fn def_unfail(self) -> Tentative<Option<Self::V>, Self::W, Self::E> {
         self.map_or_else(|fail| fail.unfail_with(None), |tnt| tnt.map(Some))
     }
@@ -1316,3 +1408,17 @@ impl<E> From<io::Error> for ImpureError<E> {
         ImpureError::IO(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absorb() {
+        let f = DeferredFailure::<(), &str, &str>::new(vec!["w1"], NonEmpty::new("e1"), ());
+        let t = Tentative::new(1, vec!["w2"], vec!["e2", "e3"]);
+        let g = f.absorb(t);
+        assert_eq!(g.warnings, vec!["w1", "w2"]);
+        assert_eq!(g.errors, NonEmpty::from(("e1", vec!["e2", "e3"])));
+    }
+}
@@ -0,0 +1,162 @@
+//! Convert datasets to arrow record batches (and optionally parquet).
+//!
+//! Writing parquet requires the "parquet" feature.
+
+use crate::core::AnyCoreDataset;
+
+use polars_arrow::array::Array;
+use polars_arrow::datatypes::{ArrowSchema, Field, Metadata};
+use polars_arrow::record_batch::RecordBatchT;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "parquet")]
+use polars_parquet::write::{
+    transverse, CompressionOptions, Encoding, FileWriter, KeyValue, RowGroupIterator,
+    StatisticsOptions, Version, WriteOptions,
+};
+#[cfg(feature = "parquet")]
+use std::io::Write;
+
+/// DATA as an arrow record batch alongside all keywords from TEXT.
+///
+/// Each column will be named after its $PnN and will have the same type
+/// as in the dataset (ie integers will not be converted to floats).
+pub struct FCSRecordBatch {
+    pub batch: RecordBatchT<Box<dyn Array>>,
+
+    /// All keywords from TEXT which can be derived from the dataset.
+    ///
+    /// This will not include offsets or $TOT, which depend on how the dataset
+    /// is written.
+    pub metadata: Metadata,
+}
+
+impl FCSRecordBatch {
+    pub fn from_dataset(core: &AnyCoreDataset) -> Self {
+        let arrays: Vec<_> = core
+            .as_data()
+            .iter_columns()
+            .map(|c| c.as_array())
+            .collect();
+        let schema: ArrowSchema = core
+            .shortnames()
+            .into_iter()
+            .zip(arrays.iter())
+            .map(|(n, a)| {
+                let f = Field::new(n.as_ref().into(), a.dtype().clone(), false);
+                (f.name.clone(), f)
+            })
+            .collect();
+        let metadata = core
+            .raw_keywords(None, None)
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect();
+        let nrows = core.as_data().nrows();
+        Self {
+            batch: RecordBatchT::new(nrows, Arc::new(schema), arrays),
+            metadata,
+        }
+    }
+
+    /// Write record batch to a handle as parquet.
+    ///
+    /// Keywords will be stored as key/value metadata in the file footer.
+    /// Return the number of bytes written.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet<W: Write>(&self, h: W) -> Result<u64, ParquetError> {
+        let schema = (*self.batch.schema()).clone();
+        let options = WriteOptions {
+            statistics: StatisticsOptions::full(),
+            compression: CompressionOptions::Uncompressed,
+            version: Version::V2,
+            data_page_size: None,
+        };
+        let encodings = schema
+            .iter_values()
+            .map(|f| transverse(&f.dtype, |_| Encoding::Plain))
+            .collect();
+        let groups = RowGroupIterator::try_new(
+            [Ok(self.batch.clone())].into_iter(),
+            &schema,
+            options,
+            encodings,
+        )
+        .map_err(ParquetError::new)?;
+        let mut writer = FileWriter::try_new(h, schema, options).map_err(ParquetError::new)?;
+        for g in groups {
+            writer
+                .write(g.map_err(ParquetError::new)?)
+                .map_err(ParquetError::new)?;
+        }
+        let kvs = self
+            .metadata
+            .iter()
+            .map(|(k, v)| KeyValue {
+                key: k.to_string(),
+                value: Some(v.to_string()),
+            })
+            .collect();
+        writer.end(Some(kvs)).map_err(ParquetError::new)
+    }
+}
+
+pub struct ParquetError(String);
+
+impl ParquetError {
+    #[cfg(feature = "parquet")]
+    fn new<E: fmt::Display>(e: E) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl fmt::Display for ParquetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "error when writing parquet: {}", self.0)
+    }
+}
+
+#[cfg(all(test, feature = "parquet"))]
+mod tests {
+    use super::*;
+    use crate::api::fcs_read_std_dataset_from_bytes;
+    use crate::api::tests::minimal_fcs_3_0;
+    use crate::config::DataReadConfig;
+    use polars_parquet::read::{infer_schema, read_metadata};
+    use std::fs;
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let bytes = minimal_fcs_3_0(&[1, 2, 3]);
+        let (out, _) = fcs_read_std_dataset_from_bytes(&bytes, &DataReadConfig::default())
+            .ok()
+            .unwrap()
+            .resolve(|_| ());
+        let rb = FCSRecordBatch::from_dataset(&out.dataset.standardized.core);
+
+        let dir = std::env::temp_dir().join(format!("fireflow-parquet-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("minimal.parquet");
+        let n = rb
+            .write_parquet(fs::File::create(&p).unwrap())
+            .ok()
+            .unwrap();
+        let mut f = fs::File::open(&p).unwrap();
+        let len = f.metadata().unwrap().len();
+        let meta = read_metadata(&mut f).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(len, n);
+        assert_eq!(3, meta.num_rows);
+        let schema = infer_schema(&meta).unwrap();
+        let names: Vec<_> = schema
+            .iter_values()
+            .map(|field| field.name.to_string())
+            .collect();
+        assert_eq!(vec!["FSC".to_string()], names);
+        let kvs = meta.key_value_metadata().as_ref().unwrap();
+        let pnn = kvs.iter().find(|kv| kv.key == "$P1N").unwrap();
+        assert_eq!(Some("FSC".to_string()), pnn.value);
+    }
+}
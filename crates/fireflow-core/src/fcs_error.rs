@@ -0,0 +1,403 @@
+//! A flat summary of all errors which may arise when reading an FCS file.
+//!
+//! The errors returned by the readers in [`crate::api`] are deeply nested to
+//! reflect where they occur, which makes them tedious to match. [`FcsError`]
+//! collapses these into a few broad categories, each with structured fields
+//! (keyword, measurement index) where these are known. The original message
+//! is always preserved.
+
+use crate::api::*;
+use crate::core::StdDatasetFromRawError;
+use crate::data::*;
+use crate::error::ImpureError;
+use crate::header::HeaderError;
+use crate::segment::*;
+use crate::text::parser::*;
+
+use serde::Serialize;
+use std::fmt;
+
+/// Any error from reading an FCS file.
+#[derive(Clone, Serialize)]
+pub enum FcsError {
+    /// HEADER could not be read or is invalid.
+    Header { message: String },
+
+    /// TEXT could not be split into key/value pairs.
+    Text { message: String },
+
+    /// A keyword is missing or has an invalid value.
+    ///
+    /// 'key' will be None if the error involves more than one keyword.
+    /// 'index' is the measurement index (starting at 1) if 'key' is a
+    /// measurement keyword. 'offset' is the position of 'key' in the file
+    /// if known.
+    Keyword {
+        key: Option<String>,
+        index: Option<usize>,
+        offset: Option<u64>,
+        message: String,
+    },
+
+    /// A segment offset is missing, invalid, or disagrees with HEADER.
+    ///
+    /// 'key' is the offending keyword if the offset came from TEXT, and
+    /// 'offset' is the position of 'key' in the file if known. 'segment' is
+    /// the segment whose offsets are invalid (ie "DATA") if known.
+    Offset {
+        key: Option<String>,
+        segment: Option<String>,
+        offset: Option<u64>,
+        message: String,
+    },
+
+    /// Keywords describing the layout of DATA are inconsistent.
+    DataLayout { message: String },
+
+    /// DATA could not be read with the given layout.
+    Data { message: String },
+
+    /// The file could not be read.
+    IO { message: String },
+}
+
+impl FcsError {
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Header { message } => message,
+            Self::Text { message } => message,
+            Self::Keyword { message, .. } => message,
+            Self::Offset { message, .. } => message,
+            Self::DataLayout { message } => message,
+            Self::Data { message } => message,
+            Self::IO { message } => message,
+        }
+    }
+
+    /// The keyword associated with this error, if any.
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Self::Keyword { key, .. } => key.as_deref(),
+            Self::Offset { key, .. } => key.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The position of the keyword associated with this error, if known.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            Self::Keyword { offset, .. } => *offset,
+            Self::Offset { offset, .. } => *offset,
+            _ => None,
+        }
+    }
+
+    /// The segment associated with this error, if known.
+    pub fn segment(&self) -> Option<&str> {
+        match self {
+            Self::Offset { segment, .. } => segment.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn header<E: fmt::Display>(e: E) -> Self {
+        Self::Header {
+            message: e.to_string(),
+        }
+    }
+
+    fn text<E: fmt::Display>(e: E) -> Self {
+        Self::Text {
+            message: e.to_string(),
+        }
+    }
+
+    fn keyword<E: fmt::Display>(e: E, key: Option<String>, offset: Option<u64>) -> Self {
+        Self::Keyword {
+            index: key.as_deref().and_then(meas_index),
+            key,
+            offset,
+            message: e.to_string(),
+        }
+    }
+
+    fn req_keyword<E: fmt::Display>(e: &ReqKeyError<E>) -> Self {
+        let (key, offset) = match e {
+            ReqKeyError::Parse(p) => (p.key.to_string(), p.offset),
+            ReqKeyError::Missing(k) => (k.to_string(), None),
+        };
+        Self::keyword(e, Some(key), offset)
+    }
+
+    fn segment_offset<E: fmt::Display>(e: E, key: Option<String>, segment: Option<&str>) -> Self {
+        Self::Offset {
+            key,
+            segment: segment.map(String::from),
+            offset: None,
+            message: e.to_string(),
+        }
+    }
+
+    fn req_segment(e: &ReqSegmentError, segment: &str) -> Self {
+        let (key, offset) = match e {
+            ReqSegmentError::Key(ReqKeyError::Parse(p)) => (Some(p.key.to_string()), p.offset),
+            ReqSegmentError::Key(ReqKeyError::Missing(k)) => (Some(k.to_string()), None),
+            ReqSegmentError::Segment(_) => (None, None),
+        };
+        Self::Offset {
+            key,
+            segment: Some(segment.into()),
+            offset,
+            message: e.to_string(),
+        }
+    }
+
+    fn req_segment_with_default<I>(e: &ReqSegmentWithDefaultError<I>) -> Self
+    where
+        I: HasRegion,
+    {
+        match e {
+            ReqSegmentWithDefaultError::Req(x) => Self::req_segment(x, I::REGION),
+            ReqSegmentWithDefaultError::Mismatch(_) => {
+                Self::segment_offset(e, None, Some(I::REGION))
+            }
+        }
+    }
+
+    fn data_layout<E: fmt::Display>(e: E) -> Self {
+        Self::DataLayout {
+            message: e.to_string(),
+        }
+    }
+
+    fn data<E: fmt::Display>(e: E) -> Self {
+        Self::Data {
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Return the measurement index if key looks like $PnX.
+pub(crate) fn meas_index(key: &str) -> Option<usize> {
+    let rest = key.strip_prefix("$P")?;
+    let n = rest.chars().take_while(char::is_ascii_digit).count();
+    if n < rest.len() {
+        rest[..n].parse().ok()
+    } else {
+        None
+    }
+}
+
+impl fmt::Display for FcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(self.message())
+    }
+}
+
+impl<E> From<ImpureError<E>> for FcsError
+where
+    FcsError: From<E>,
+{
+    fn from(value: ImpureError<E>) -> Self {
+        match value {
+            ImpureError::IO(e) => Self::IO {
+                message: e.to_string(),
+            },
+            ImpureError::Pure(e) => e.into(),
+        }
+    }
+}
+
+impl From<HeaderError> for FcsError {
+    fn from(value: HeaderError) -> Self {
+        Self::header(value)
+    }
+}
+
+impl From<ParseRawTEXTError> for FcsError {
+    fn from(value: ParseRawTEXTError) -> Self {
+        match value {
+            ParseRawTEXTError::Header(e) => Self::header(e),
+            ParseRawTEXTError::Nextdata(e) => Self::req_keyword(&e),
+            ParseRawTEXTError::SuppOffsets(STextSegmentError::ReqSegment(e)) => {
+                Self::req_segment(&e, SupplementalTextSegmentId::REGION)
+            }
+            ParseRawTEXTError::SuppOffsets(e) => {
+                Self::segment_offset(e, None, Some(SupplementalTextSegmentId::REGION))
+            }
+            e => Self::text(e),
+        }
+    }
+}
+
+impl From<HeaderOrRawError> for FcsError {
+    fn from(value: HeaderOrRawError) -> Self {
+        match value {
+            HeaderOrRawError::Header(e) => e.into(),
+            HeaderOrRawError::RawTEXT(e) => e.into(),
+        }
+    }
+}
+
+impl From<LookupKeysError> for FcsError {
+    fn from(value: LookupKeysError) -> Self {
+        match value {
+            LookupKeysError::Parse(e) => Self::req_keyword(&*e),
+            e => Self::keyword(e, None, None),
+        }
+    }
+}
+
+impl From<NewDataReaderError> for FcsError {
+    fn from(value: NewDataReaderError) -> Self {
+        match value {
+            NewDataReaderError::ParseTot(e) => Self::req_keyword(&e),
+            NewDataReaderError::ParseSeg(e) => Self::req_segment_with_default(&e),
+            NewDataReaderError::Mismatch(e) => {
+                Self::segment_offset(e, None, Some(DataSegmentId::REGION))
+            }
+            e => Self::data_layout(e),
+        }
+    }
+}
+
+impl From<NewAnalysisReaderError> for FcsError {
+    fn from(value: NewAnalysisReaderError) -> Self {
+        match value {
+            NewAnalysisReaderError::ParseSeg(e) => Self::req_segment_with_default(&e),
+            NewAnalysisReaderError::Mismatch(e) => {
+                Self::segment_offset(e, None, Some(AnalysisSegmentId::REGION))
+            }
+        }
+    }
+}
+
+impl From<RawToReaderError> for FcsError {
+    fn from(value: RawToReaderError) -> Self {
+        match value {
+            RawToReaderError::Layout(e) => Self::data_layout(e),
+            RawToReaderError::Reader(e) => e.into(),
+        }
+    }
+}
+
+impl From<StdDatasetFromRawError> for FcsError {
+    fn from(value: StdDatasetFromRawError) -> Self {
+        match value {
+            StdDatasetFromRawError::TEXT(e) => e.into(),
+            StdDatasetFromRawError::Layout(e) => Self::data_layout(e),
+            StdDatasetFromRawError::Data(e) => e.into(),
+            StdDatasetFromRawError::Analysis(e) => e.into(),
+            StdDatasetFromRawError::Selection(e) => Self::data(e),
+            StdDatasetFromRawError::DataRead(e) => Self::data(e),
+            StdDatasetFromRawError::Crc(e) => Self::data(e),
+            StdDatasetFromRawError::DataHash(e) => Self::data(e),
+            StdDatasetFromRawError::Mode(e) => Self::data(e),
+        }
+    }
+}
+
+impl From<DatasetWithKwsError> for FcsError {
+    fn from(value: DatasetWithKwsError) -> Self {
+        match value {
+            DatasetWithKwsError::DataReader(e) => e.into(),
+            DatasetWithKwsError::AnalysisReader(e) => e.into(),
+            DatasetWithKwsError::Read(e) => Self::data(e),
+        }
+    }
+}
+
+impl From<StdTEXTError> for FcsError {
+    fn from(value: StdTEXTError) -> Self {
+        match value {
+            StdTEXTError::Raw(e) => e.into(),
+            StdTEXTError::Std(e) => e.into(),
+        }
+    }
+}
+
+impl From<StdDatasetError> for FcsError {
+    fn from(value: StdDatasetError) -> Self {
+        match value {
+            StdDatasetError::Raw(e) => (*e).into(),
+            StdDatasetError::Std(e) => e.into(),
+            StdDatasetError::Nextdata(e) => Self::segment_offset(e, Some("$NEXTDATA".into()), None),
+        }
+    }
+}
+
+impl From<RawDatasetError> for FcsError {
+    fn from(value: RawDatasetError) -> Self {
+        match value {
+            RawDatasetError::Raw(e) => (*e).into(),
+            RawDatasetError::Std(e) => e.into(),
+            RawDatasetError::Read(e) => e.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::tests::minimal_fcs_3_0;
+    use crate::config::{DataReadConfig, Profile};
+    use crate::error::Failure;
+
+    /// Read with a strict profile so these do not depend on the default.
+    fn read_errors(bytes: &[u8]) -> Vec<FcsError> {
+        let conf = DataReadConfig::from_profile(Profile::Strict);
+        let res = fcs_read_std_dataset_from_bytes(bytes, &conf);
+        let (_, es) = res.err().unwrap().resolve(
+            |_| (),
+            |f| match f {
+                Failure::Single(_) => vec![],
+                Failure::Many(_, es) => es.into_iter().map(FcsError::from).collect(),
+            },
+        );
+        es
+    }
+
+    /// Replace the first occurrence of 'from' with 'to' of the same length.
+    fn replace(bytes: &mut [u8], from: &str, to: &str) {
+        let i = bytes
+            .windows(from.len())
+            .position(|w| w == from.as_bytes())
+            .unwrap();
+        bytes[i..i + to.len()].copy_from_slice(to.as_bytes());
+    }
+
+    #[test]
+    fn test_offset_key() {
+        let mut bytes = minimal_fcs_3_0(&[1, 2, 3]);
+        replace(&mut bytes, "$BEGINDATA/2", "$BEGINDATA/x");
+        let es = read_errors(&bytes);
+        let e = es.iter().find(|e| e.key() == Some("$BEGINDATA")).unwrap();
+        assert!(matches!(e, FcsError::Offset { .. }));
+        assert_eq!(e.segment(), Some("DATA"));
+    }
+
+    #[test]
+    fn test_offset_mismatch() {
+        let mut bytes = minimal_fcs_3_0(&[1, 2, 3]);
+        // move the start of DATA in HEADER back by one
+        let begin: usize = std::str::from_utf8(&bytes[26..34])
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+        bytes[26..34].copy_from_slice(format!("{:>8}", begin - 1).as_bytes());
+        let es = read_errors(&bytes);
+        let e = es.iter().find(|e| e.segment().is_some()).unwrap();
+        assert_eq!(e.segment(), Some("DATA"));
+        assert_eq!(e.key(), None);
+    }
+
+    #[test]
+    fn test_meas_index() {
+        assert_eq!(Some(12), meas_index("$P12N"));
+        assert_eq!(None, meas_index("$PLATEID"));
+        assert_eq!(None, meas_index("$PAR"));
+        assert_eq!(None, meas_index("$P1"));
+        assert_eq!(None, meas_index("$TOT"));
+    }
+}
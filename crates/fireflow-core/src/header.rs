@@ -1,5 +1,6 @@
-use crate::config::HeaderConfig;
+use crate::config::{HeaderConfig, KeywordOrder};
 use crate::error::*;
+use crate::limits::*;
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
 use crate::segment::*;
 use crate::text::keywords::*;
@@ -9,18 +10,13 @@ use crate::validated::standard::*;
 
 use nonempty::NonEmpty;
 use serde::Serialize;
+use std::cmp::Ordering;
 use std::fmt;
 use std::io;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::iter::repeat;
 use std::str;
 
-/// The length of the HEADER.
-///
-/// This should always be the same. This also assumes that there are no OTHER
-/// segments (which for now are not supported).
-pub const HEADER_LEN: u8 = 58;
-
 /// All FCS versions this library supports.
 ///
 /// This appears as the first 6 bytes of any valid FCS file.
@@ -48,7 +44,7 @@ impl HeaderSegments {
         version: Version,
     ) -> io::Result<()> {
         for s in [
-            version.to_string(),
+            format!("{version}{}", " ".repeat(HEADER_SPACE_LEN)),
             self.text.header_string(),
             self.data.header_string(),
             self.analysis.header_string(),
@@ -131,7 +127,7 @@ impl HeaderSegments {
 
     /// Return number of bytes required to encode HEADER
     fn nbytes(&self) -> u64 {
-        u64::from(HEADER_LEN) + (self.other.len() as u64) * 16
+        u64::from(HEADER_LEN) + (self.other.len() * HEADER_OFFSET_WIDTH * 2) as u64
     }
 
     fn as_generics(&self) -> impl Iterator<Item = GenericSegment> {
@@ -234,7 +230,7 @@ fn h_read_required_header<R: Read>(
 }
 
 fn h_read_spaces<R: Read>(h: &mut BufReader<R>) -> Result<(), ImpureError<HeaderError>> {
-    let mut buf = [0_u8; 4];
+    let mut buf = [0_u8; HEADER_SPACE_LEN];
     h.read_exact(&mut buf)?;
     if buf.iter().all(|x| *x == 32) {
         Ok(())
@@ -283,7 +279,7 @@ fn h_read_other_segments<R: Read>(
 
 impl Version {
     fn h_read<R: Read>(h: &mut BufReader<R>) -> Result<Self, ImpureError<VersionError>> {
-        let mut buf = [0; 6];
+        let mut buf = [0; VERSION_LEN];
         h.read_exact(&mut buf)?;
         if buf.is_ascii() {
             let s = unsafe { str::from_utf8_unchecked(&buf) };
@@ -410,12 +406,8 @@ pub(crate) fn make_data_offset_keywords_2_0(
         .map_or(Ok(text_begin), |x| x.try_into())?;
     let analysis_seg = HeaderAnalysisSegment::try_new_with_len(analysis_begin, analysis_len)?;
 
-    let nextdata = Nextdata(Uint20Char(
-        analysis_seg
-            .inner
-            .try_next_byte()
-            .unwrap_or(u64::from(analysis_begin)),
-    ));
+    // only one dataset is ever written, so it is always the last
+    let nextdata = Nextdata(Uint20Char(0));
 
     let header = HeaderSegments {
         text: text_seg,
@@ -497,12 +489,8 @@ pub(crate) fn make_data_offset_keywords_3_0(
     let h_analysis_seg = analysis_seg.as_header();
     let h_data_seg = data_seg.as_header();
 
-    let nextdata = Nextdata(Uint20Char(
-        analysis_seg
-            .inner
-            .try_next_byte()
-            .unwrap_or(u64::from(analysis_begin)),
-    ));
+    // only one dataset is ever written, so it is always the last
+    let nextdata = Nextdata(Uint20Char(0));
 
     // NOTE in 3.2 *DATA and *SDATA are technically optional, but it is much
     // easier just to include them in the "required" stuff regardless.
@@ -535,6 +523,66 @@ pub(crate) fn make_data_offset_keywords_3_0(
     })
 }
 
+/// Combine metadata and measurement keywords in the given order.
+pub(crate) fn order_keywords(
+    mut meta: Vec<(String, String)>,
+    mut meas: Vec<(String, String)>,
+    order: KeywordOrder,
+) -> Vec<(String, String)> {
+    match order {
+        KeywordOrder::Unsorted => {
+            meta.extend(meas);
+            meta
+        }
+        KeywordOrder::Natural => {
+            meta.extend(meas);
+            meta.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+            meta
+        }
+        KeywordOrder::Grouped => {
+            // measurement keywords are grouped by index under natural ordering
+            // since the index is the first number in each key
+            meta.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+            meas.sort_by(|a, b| natural_cmp(&a.0, &b.0));
+            meta.extend(meas);
+            meta
+        }
+    }
+}
+
+/// Compare two strings such that runs of digits are compared numerically.
+///
+/// Non-digit characters are compared case-insensitively. Ties are broken by
+/// comparing the original strings so the ordering is total.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunks(s: &str) -> Vec<(bool, &str)> {
+        s.as_bytes()
+            .chunk_by(|x, y| x.is_ascii_digit() == y.is_ascii_digit())
+            .scan(0, |i, c| {
+                let j = *i + c.len();
+                let chunk = (c[0].is_ascii_digit(), &s[*i..j]);
+                *i = j;
+                Some(chunk)
+            })
+            .collect()
+    }
+    let xs = chunks(a);
+    let ys = chunks(b);
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let o = match (x, y) {
+            ((true, dx), (true, dy)) => {
+                let (nx, ny) = (dx.trim_start_matches('0'), dy.trim_start_matches('0'));
+                nx.len().cmp(&ny.len()).then_with(|| nx.cmp(ny))
+            }
+            ((_, sx), (_, sy)) => sx.to_ascii_uppercase().cmp(&sy.to_ascii_uppercase()),
+        };
+        if o != Ordering::Equal {
+            return o;
+        }
+    }
+    xs.len().cmp(&ys.len()).then_with(|| a.cmp(b))
+}
+
 fn raw_keywords_length(ks: &[(String, String)]) -> u64 {
     ks.iter().map(|(k, v)| k.len() + v.len() + 2).sum::<usize>() as u64
 }
@@ -548,7 +596,7 @@ fn other_segments(other_lens: Vec<u64>) -> (Vec<OtherSegment>, u64, u64) {
         os.push(seg);
     }
     let total_length = os.iter().map(|s| s.inner.len()).sum();
-    let header_length = (os.len() as u64) * 16;
+    let header_length = (os.len() * HEADER_OFFSET_WIDTH * 2) as u64;
     (os, header_length, total_length)
 }
 
@@ -560,9 +608,6 @@ fn other_segments(other_lens: Vec<u64>) -> (Vec<OtherSegment>, u64, u64) {
 /// much easier.
 pub(crate) const OFFSET_VAL_LEN: u64 = 20;
 
-/// The maximum value that may be stored in a HEADER offset.
-pub(crate) const MAX_HEADER_OFFSET: u32 = 99_999_999;
-
 /// Number of bytes consumed by $NEXTDATA keyword + value + delimiters
 fn nextdata_len() -> u64 {
     Nextdata::len() + OFFSET_VAL_LEN + 2
@@ -1,13 +1,74 @@
+//! Read and write FCS files.
+//!
+//! The main entry points are the following, which read successively more of
+//! an FCS file:
+//!
+//! - [`read_fcs_header`]: HEADER only
+//! - [`read_fcs_raw_text`]: HEADER and TEXT as raw key/value pairs
+//! - [`read_fcs_text`]: HEADER and standardized TEXT
+//! - [`read_metadata_only`]: HEADER, standardized TEXT, and raw keywords
+//! - [`read_fcs_file`]: HEADER, standardized TEXT, DATA, and ANALYSIS
+//! - [`read_fcs_data`]: DATA and ANALYSIS using keywords already read
+//! - [`read_all_datasets`]: all datasets in a file by following $NEXTDATA
+//! - [`validate`]: HEADER and TEXT, checked for conformance without reading DATA
+//!
+//! [`read_fcs_from_bytes`] and [`read_fcs_text_from_bytes`] do the same as
+//! their file-based counterparts on an in-memory FCS file, and
+//! [`write_fcs_to_bytes`] writes one, which is useful where there is no
+//! filesystem (ie wasm32).
+//!
+//! Files compressed with gzip or zstd are decompressed when opened if the
+//! `gzip` or `zstd` features are enabled (see [`source`]).
+//!
+//! With the `tokio` feature, [`read_fcs_file_async`] and
+//! [`read_fcs_reader_async`] read a dataset from an asynchronous stream, and
+//! [`read_fcs_text_reader_async`] reads only its HEADER and TEXT.
+//!
+//! When reading many files in a row, buffers may be reused between reads with
+//! [`scratch::ParserScratch`].
+//!
+//! Each takes a path and a configuration from [`config`]; the defaults of the
+//! latter will only accept fully-compliant files. All but [`validate`] return
+//! a result that must be resolved to obtain warnings and errors (see
+//! [`error`]); [`validate`] instead returns a report with all issues found.
+
 #![warn(clippy::shadow_reuse)]
 #![warn(clippy::shadow_unrelated)]
 
 pub mod api;
+pub mod builder;
 pub mod config;
 pub mod core;
+pub mod crc;
 pub mod data;
+pub mod datahash;
 pub mod error;
+pub mod export;
+pub mod fcs_error;
 pub mod header;
+pub mod limits;
 mod macros;
+pub mod report;
+pub mod scratch;
 pub mod segment;
+pub mod source;
+pub mod stats;
 pub mod text;
+pub mod transform;
 pub mod validated;
+
+pub use api::{
+    fcs_read_all_std_datasets as read_all_datasets, fcs_read_header as read_fcs_header,
+    fcs_read_metadata as read_metadata_only, fcs_read_raw_text as read_fcs_raw_text,
+    fcs_read_std_dataset as read_fcs_file, fcs_read_std_dataset_from_bytes as read_fcs_from_bytes,
+    fcs_read_std_dataset_with_keywords as read_fcs_data, fcs_read_std_text as read_fcs_text,
+    fcs_read_std_text_from_bytes as read_fcs_text_from_bytes, fcs_validate as validate,
+    fcs_write_dataset_to_bytes as write_fcs_to_bytes, FCSResult,
+};
+#[cfg(feature = "tokio")]
+pub use api::{
+    fcs_read_std_dataset_async as read_fcs_file_async,
+    fcs_read_std_dataset_from_async_reader as read_fcs_reader_async,
+    fcs_read_std_text_from_async_reader as read_fcs_text_reader_async,
+};
+pub use fcs_error::FcsError;
@@ -0,0 +1,51 @@
+//! Limits and thresholds derived from the FCS standards.
+//!
+//! A few (noted below) are not in any standard but rather reflect what common
+//! readers will accept.
+//!
+//! These are used when both reading and writing files, and are exposed here
+//! so that downstream code may check values before handing them off.
+
+use std::ops::RangeInclusive;
+
+/// Length of the version string at the start of HEADER (ie "FCS3.1")
+pub const VERSION_LEN: usize = 6;
+
+/// Number of spaces between the version and the first offset in HEADER
+pub const HEADER_SPACE_LEN: usize = 4;
+
+/// Number of bytes for each offset in HEADER
+pub const HEADER_OFFSET_WIDTH: usize = 8;
+
+/// The length of the HEADER.
+///
+/// This does not include any OTHER segment offsets which may follow the
+/// required offsets.
+pub const HEADER_LEN: u8 = 58;
+
+/// The maximum value that may be stored in a HEADER offset.
+pub const MAX_HEADER_OFFSET: u32 = 99_999_999;
+
+/// Maximum number of measurements in 2.0 that most readers will accept.
+///
+/// This is a convention of common readers and not part of the 2.0 standard.
+pub const MAX_PAR_2_0: usize = 99;
+
+/// Prefix for standard keys ('$')
+pub const STD_PREFIX: u8 = b'$';
+
+/// Characters which may be used as a delimiter in TEXT
+pub const DELIM_RANGE: RangeInclusive<u8> = 1..=126;
+
+/// Characters which may be used in a key in TEXT (ie printable ASCII)
+pub const KEY_CHAR_RANGE: RangeInclusive<u8> = 32..=126;
+
+/// Return true if character may be used as a delimiter.
+pub fn is_valid_delim(x: u8) -> bool {
+    DELIM_RANGE.contains(&x)
+}
+
+/// Return true if all characters may be used in a key.
+pub fn is_valid_key(xs: &[u8]) -> bool {
+    xs.iter().all(|x| KEY_CHAR_RANGE.contains(x))
+}
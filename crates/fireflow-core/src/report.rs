@@ -0,0 +1,280 @@
+//! A flat, machine-readable report of all issues from reading an FCS file.
+//!
+//! Readers in [`crate::api`] return warnings and errors as deeply nested types
+//! which are only meant to be displayed. [`ParseReport`] collects these into a
+//! list of [`ReportEntry`], each with a severity, the part of the file it
+//! concerns, and the keyword and measurement index where these are known. This
+//! serializes to JSON.
+
+use crate::api::*;
+use crate::core::{LookupMeasWarning, StdDatasetFromRawWarning};
+use crate::error::*;
+use crate::fcs_error::{meas_index, FcsError};
+use crate::text::parser::*;
+
+use serde::Serialize;
+use std::fmt;
+
+/// How serious an issue is.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Severity {
+    /// Issue which prevented the file from being read
+    Error,
+
+    /// Issue which did not prevent the file from being read
+    Warning,
+
+    /// Warning about a keyword or value which is deprecated
+    Deprecation,
+}
+
+/// Part of the file in which an issue arose.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum IssueSource {
+    Header,
+    Text,
+    Data,
+}
+
+/// One issue from reading an FCS file.
+#[derive(Clone, Serialize)]
+pub struct ReportEntry {
+    pub severity: Severity,
+
+    /// Part of the file involved, or None if unknown (ie IO errors)
+    pub source: Option<IssueSource>,
+
+    /// Keyword involved if only one
+    pub key: Option<String>,
+
+    /// Measurement index (starting at 1) if 'key' is a measurement keyword
+    pub index: Option<usize>,
+
+    /// Position of 'key' in the file if known
+    pub offset: Option<u64>,
+
+    pub message: String,
+}
+
+/// All issues from reading an FCS file.
+#[derive(Clone, Default, Serialize)]
+pub struct ParseReport {
+    /// Reason reading failed, if it did
+    pub failure: Option<String>,
+
+    pub entries: Vec<ReportEntry>,
+}
+
+/// A warning which may be added to a [`ParseReport`].
+pub trait ReportWarning {
+    fn report_entry(&self) -> ReportEntry;
+}
+
+impl ReportEntry {
+    fn warning<W: fmt::Display>(w: W, source: IssueSource) -> Self {
+        Self {
+            severity: Severity::Warning,
+            source: Some(source),
+            key: None,
+            index: None,
+            offset: None,
+            message: w.to_string(),
+        }
+    }
+
+    fn with_key(self, key: String, offset: Option<u64>) -> Self {
+        Self {
+            index: meas_index(&key),
+            key: Some(key),
+            offset,
+            ..self
+        }
+    }
+
+    fn deprecated(self) -> Self {
+        Self {
+            severity: Severity::Deprecation,
+            ..self
+        }
+    }
+}
+
+impl ParseReport {
+    /// Collect all warnings and errors from the result of a reader.
+    ///
+    /// Return the value if reading succeeded along with the report.
+    pub fn from_terminal<V, W, E, T>(res: TerminalResult<V, W, E, T>) -> (Option<V>, Self)
+    where
+        W: ReportWarning,
+        FcsError: From<E>,
+        T: fmt::Display,
+    {
+        let mut report = Self::default();
+        let value = match res {
+            Ok(t) => {
+                let (v, ws) = t.resolve(|ws| ws);
+                report.push_warnings(ws);
+                Some(v)
+            }
+            Err(f) => {
+                let (ws, failure) = f.resolve(|ws| ws, |e| e);
+                report.push_warnings(ws);
+                let t = match failure {
+                    Failure::Single(t) => t,
+                    Failure::Many(t, es) => {
+                        report
+                            .entries
+                            .extend((*es).into_iter().map(FcsError::from).map(Into::into));
+                        t
+                    }
+                };
+                report.failure = Some(t.to_string());
+                None
+            }
+        };
+        (value, report)
+    }
+
+    fn push_warnings<W: ReportWarning>(&mut self, ws: Vec<W>) {
+        self.entries
+            .extend(ws.iter().map(ReportWarning::report_entry));
+    }
+
+    /// Return number of entries with the given severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.severity == severity)
+            .count()
+    }
+}
+
+impl From<FcsError> for ReportEntry {
+    fn from(value: FcsError) -> Self {
+        let (source, key, index, offset) = match &value {
+            FcsError::Header { .. } => (Some(IssueSource::Header), None, None, None),
+            FcsError::Text { .. } => (Some(IssueSource::Text), None, None, None),
+            FcsError::Keyword {
+                key, index, offset, ..
+            } => (Some(IssueSource::Text), key.clone(), *index, *offset),
+            FcsError::Offset { key, offset, .. } => {
+                let s = if key.is_some() {
+                    IssueSource::Text
+                } else {
+                    IssueSource::Header
+                };
+                (Some(s), key.clone(), None, *offset)
+            }
+            FcsError::DataLayout { .. } => (Some(IssueSource::Text), None, None, None),
+            FcsError::Data { .. } => (Some(IssueSource::Data), None, None, None),
+            FcsError::IO { .. } => (None, None, None, None),
+        };
+        Self {
+            severity: Severity::Error,
+            source,
+            key,
+            index,
+            offset,
+            message: value.message().to_string(),
+        }
+    }
+}
+
+impl ReportWarning for ParseRawTEXTWarning {
+    fn report_entry(&self) -> ReportEntry {
+        let e = ReportEntry::warning(self, IssueSource::Text);
+        match self {
+            Self::Nextdata(p) => e.with_key(p.key.to_string(), p.offset),
+            _ => e,
+        }
+    }
+}
+
+impl ReportWarning for LookupMeasWarning {
+    fn report_entry(&self) -> ReportEntry {
+        let e = ReportEntry::warning(self, IssueSource::Text);
+        match self {
+            Self::Parse(LookupKeysWarning::Parse(p)) => e.with_key(p.key.to_string(), p.offset),
+            Self::Parse(LookupKeysWarning::Dep(DeprecatedError::Key(k))) => {
+                e.with_key(k.0.to_string(), None).deprecated()
+            }
+            Self::Parse(LookupKeysWarning::Dep(DeprecatedError::Value(v))) => {
+                let k = match v {
+                    DepValueWarning::DatatypeASCII => "$DATATYPE",
+                    DepValueWarning::ModeCorrelated | DepValueWarning::ModeUncorrelated => "$MODE",
+                };
+                e.with_key(k.into(), None).deprecated()
+            }
+            Self::InferredPar(_) => e.with_key("$PAR".into(), None),
+            _ => e,
+        }
+    }
+}
+
+impl ReportWarning for StdDatasetFromRawWarning {
+    fn report_entry(&self) -> ReportEntry {
+        match self {
+            Self::TEXT(e) => e.report_entry(),
+            Self::Layout(_) | Self::ByteOrd(_) => ReportEntry::warning(self, IssueSource::Text),
+            _ => ReportEntry::warning(self, IssueSource::Data),
+        }
+    }
+}
+
+impl ReportWarning for StdTEXTWarning {
+    fn report_entry(&self) -> ReportEntry {
+        match self {
+            Self::Raw(e) => e.report_entry(),
+            Self::Std(e) => e.report_entry(),
+        }
+    }
+}
+
+impl ReportWarning for StdDatasetWarning {
+    fn report_entry(&self) -> ReportEntry {
+        match self {
+            Self::Raw(e) => e.report_entry(),
+            Self::Std(e) => e.report_entry(),
+        }
+    }
+}
+
+impl ReportWarning for RawDatasetWarning {
+    fn report_entry(&self) -> ReportEntry {
+        match self {
+            Self::Raw(e) => e.report_entry(),
+            Self::Std(e) => e.report_entry(),
+            Self::Read(_) => ReportEntry::warning(self, IssueSource::Data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_entry() {
+        let e = ReportEntry::from(FcsError::Keyword {
+            key: Some("$P3E".into()),
+            index: Some(3),
+            offset: Some(120),
+            message: "bad".into(),
+        });
+        assert!(e.severity == Severity::Error);
+        assert!(e.source == Some(IssueSource::Text));
+        assert_eq!(e.index, Some(3));
+        assert_eq!(e.offset, Some(120));
+    }
+
+    #[test]
+    fn test_deprecated_entry() {
+        let w = LookupMeasWarning::Parse(LookupKeysWarning::Dep(DeprecatedError::Value(
+            DepValueWarning::ModeCorrelated,
+        )));
+        let e = w.report_entry();
+        assert!(e.severity == Severity::Deprecation);
+        assert_eq!(e.key.as_deref(), Some("$MODE"));
+        assert_eq!(e.index, None);
+    }
+}
@@ -0,0 +1,106 @@
+//! Buffers which may be reused when reading many FCS files.
+//!
+//! Reading a dataset allocates a buffer for TEXT, a buffer for blocks of DATA,
+//! and one vector per column. When reading many files in a row, these can be
+//! kept in a [`ParserScratch`] and passed to
+//! [`fcs_read_std_dataset_with_scratch`] to avoid allocating them anew for
+//! each file. Columns are only reused if they are given back with
+//! [`ParserScratch::recycle`] once the previous dataset is no longer needed.
+//!
+//! Only columns from fixed-width DATA (ie not delimited ASCII) are taken from
+//! the scratch.
+//!
+//! [`fcs_read_std_dataset_with_scratch`]: crate::api::fcs_read_std_dataset_with_scratch
+
+use crate::validated::dataframe::{AnyFCSColumn, FCSDataFrame};
+
+/// Buffers to be reused across reads.
+#[derive(Default)]
+pub struct ParserScratch {
+    /// Buffer for primary and supplemental TEXT
+    pub(crate) text: Vec<u8>,
+
+    /// Buffer for blocks of events in fixed-width DATA
+    pub(crate) block: Vec<u8>,
+
+    u8: Vec<Vec<u8>>,
+    u16: Vec<Vec<u16>>,
+    u32: Vec<Vec<u32>>,
+    u64: Vec<Vec<u64>>,
+    f32: Vec<Vec<f32>>,
+    f64: Vec<Vec<f64>>,
+}
+
+impl ParserScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the columns of a dataframe to be used by the next read.
+    ///
+    /// Columns which are still shared (ie by a clone of the dataframe) cannot
+    /// be reused and will be dropped.
+    pub fn recycle(&mut self, df: FCSDataFrame) {
+        for c in df.into_columns() {
+            match c {
+                AnyFCSColumn::U08(x) => push_buffer(&mut self.u8, x.0),
+                AnyFCSColumn::U16(x) => push_buffer(&mut self.u16, x.0),
+                AnyFCSColumn::U32(x) => push_buffer(&mut self.u32, x.0),
+                AnyFCSColumn::U64(x) => push_buffer(&mut self.u64, x.0),
+                AnyFCSColumn::F32(x) => push_buffer(&mut self.f32, x.0),
+                AnyFCSColumn::F64(x) => push_buffer(&mut self.f64, x.0),
+            }
+        }
+    }
+
+    /// Return the number of columns which may be reused.
+    pub fn ncolumns(&self) -> usize {
+        self.u8.len()
+            + self.u16.len()
+            + self.u32.len()
+            + self.u64.len()
+            + self.f32.len()
+            + self.f64.len()
+    }
+
+    /// Drop all buffers and free their memory.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Return a column with 'n' values, reusing a recycled column if possible.
+    pub(crate) fn take_column<T: ScratchColumn>(&mut self, n: usize) -> Vec<T> {
+        let mut xs = T::pool(self).pop().unwrap_or_default();
+        xs.clear();
+        xs.resize(n, T::default());
+        xs
+    }
+}
+
+fn push_buffer<T>(pool: &mut Vec<Vec<T>>, b: polars_arrow::buffer::Buffer<T>) {
+    if let Some(xs) = b.into_mut().right() {
+        pool.push(xs);
+    }
+}
+
+/// A column type which may be kept in [`ParserScratch`].
+pub(crate) trait ScratchColumn: Sized + Clone + Default {
+    fn pool(s: &mut ParserScratch) -> &mut Vec<Vec<Self>>;
+}
+
+macro_rules! impl_scratch_column {
+    ($t:ident) => {
+        impl ScratchColumn for $t {
+            fn pool(s: &mut ParserScratch) -> &mut Vec<Vec<Self>> {
+                &mut s.$t
+            }
+        }
+    };
+}
+
+impl_scratch_column!(u8);
+impl_scratch_column!(u16);
+impl_scratch_column!(u32);
+impl_scratch_column!(u64);
+impl_scratch_column!(f32);
+impl_scratch_column!(f64);
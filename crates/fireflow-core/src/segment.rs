@@ -1,5 +1,6 @@
 use crate::config::HeaderConfig;
 use crate::error::*;
+use crate::limits::HEADER_OFFSET_WIDTH;
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
 use crate::text::keywords::*;
 use crate::text::parser::*;
@@ -69,7 +70,7 @@ pub struct SegmentFromHeader;
 pub struct SegmentFromTEXT;
 
 /// Denotes a segment came from either TEXT or HEADER
-#[derive(Clone, Copy)]
+#[derive(Default, Clone, Copy)]
 pub struct SegmentFromAnywhere;
 
 /// Denotes the segment pertains to primary TEXT
@@ -414,6 +415,10 @@ impl HasSource for SegmentFromTEXT {
     const SRC: &'static str = "TEXT";
 }
 
+impl HasSource for SegmentFromAnywhere {
+    const SRC: &'static str = "HEADER/TEXT";
+}
+
 impl HasRegion for AnalysisSegmentId {
     const REGION: &'static str = "ANALYSIS";
 }
@@ -640,16 +645,16 @@ impl<I: Copy> HeaderSegment<I> {
     where
         I: HasRegion,
     {
-        let mut buf0 = [0_u8; 8];
-        let mut buf1 = [0_u8; 8];
+        let mut buf0 = [0_u8; HEADER_OFFSET_WIDTH];
+        let mut buf1 = [0_u8; HEADER_OFFSET_WIDTH];
         h.read_exact(&mut buf0).into_mult()?;
         h.read_exact(&mut buf1).into_mult()?;
         Self::parse(&buf0, &buf1, allow_blank, conf, corr).mult_map_errors(ImpureError::Pure)
     }
 
     pub(crate) fn parse(
-        bs0: &[u8; 8],
-        bs1: &[u8; 8],
+        bs0: &[u8; HEADER_OFFSET_WIDTH],
+        bs1: &[u8; HEADER_OFFSET_WIDTH],
         allow_blank: bool,
         conf: &HeaderConfig,
         corr: OffsetCorrection<I, SegmentFromHeader>,
@@ -686,7 +691,10 @@ impl<I: Copy> HeaderSegment<I> {
             .inner
             .try_coords()
             .unwrap_or((Uint8Digit::default(), Uint8Digit::default()));
-        format!("{:>8}{:>8}", b, e)
+        // NOTE format as strings first since Display for the offsets does not
+        // respect width
+        let w = HEADER_OFFSET_WIDTH;
+        format!("{:>w$}{:>w$}", b.to_string(), e.to_string())
     }
 
     pub(crate) fn unless(
@@ -0,0 +1,310 @@
+//! Opening FCS files which may be compressed.
+//!
+//! Offsets in HEADER and TEXT require random access, which compressed streams
+//! do not support. Compressed files are therefore decompressed entirely into
+//! memory when opened. Decompression requires the `gzip` or `zstd` features;
+//! otherwise such files will fail to open with an error saying so.
+//!
+//! With the `tokio` feature, [`SparseSource`] holds only those parts of an
+//! asynchronous stream which the parser has asked for, so that a dataset may
+//! be read without buffering the whole stream.
+
+#[cfg(feature = "tokio")]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression detected when opening a file
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+/// A readable and seekable FCS file, possibly decompressed into memory
+pub enum FcsSource {
+    File(fs::File),
+    Memory(io::Cursor<Vec<u8>>),
+}
+
+impl Compression {
+    /// Detect compression using the first bytes of a file
+    pub fn from_magic(xs: &[u8]) -> Option<Self> {
+        if xs.starts_with(&GZIP_MAGIC) {
+            Some(Self::Gzip)
+        } else if xs.starts_with(&ZSTD_MAGIC) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    fn decompress<R: Read>(self, r: R) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Self::Gzip => {
+                let mut buf = vec![];
+                flate2::read::MultiGzDecoder::new(r).read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+            #[cfg(feature = "zstd")]
+            Self::Zstd => {
+                let mut buf = vec![];
+                zstd::stream::read::Decoder::new(r)?.read_to_end(&mut buf)?;
+                Ok(buf)
+            }
+            #[allow(unreachable_patterns)]
+            _ => {
+                let _ = r;
+                Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("file is {self} compressed but '{self}' feature is not enabled"),
+                ))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Gzip => write!(f, "gzip"),
+            Self::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FcsSource {
+    /// Open a file, decompressing it if it starts with gzip or zstd magic.
+    pub fn open(p: &path::Path) -> io::Result<Self> {
+        let mut file = fs::File::options().read(true).open(p)?;
+        let mut magic = [0_u8; 4];
+        let n = read_up_to(&mut file, &mut magic)?;
+        file.seek(SeekFrom::Start(0))?;
+        if let Some(c) = Compression::from_magic(&magic[..n]) {
+            c.decompress(io::BufReader::new(file))
+                .map(|xs| Self::Memory(io::Cursor::new(xs)))
+        } else {
+            Ok(Self::File(file))
+        }
+    }
+
+    /// Return true if the file was decompressed
+    pub fn is_decompressed(&self) -> bool {
+        matches!(self, Self::Memory(_))
+    }
+}
+
+/// Read until 'buf' is full or EOF and return the number of bytes read.
+pub(crate) fn read_up_to<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match r.read(&mut buf[n..])? {
+            0 => break,
+            m => n += m,
+        }
+    }
+    Ok(n)
+}
+
+impl Read for FcsSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::File(x) => x.read(buf),
+            Self::Memory(x) => x.read(buf),
+        }
+    }
+}
+
+impl Seek for FcsSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::File(x) => x.seek(pos),
+            Self::Memory(x) => x.seek(pos),
+        }
+    }
+}
+
+/// A seekable stream of which only some byte ranges have been fetched.
+///
+/// Reading bytes which have not been fetched fails and records where the read
+/// started, after which [`SparseSource::fetch_missing`] will fetch them so the
+/// read can be retried. Reading past the end of the stream returns EOF as with
+/// a file.
+#[cfg(feature = "tokio")]
+pub(crate) struct SparseSource {
+    chunks: BTreeMap<u64, Vec<u8>>,
+    len: u64,
+    pos: u64,
+    missing: Option<(u64, u64)>,
+}
+
+/// Fetch at least this many bytes when a read misses.
+#[cfg(feature = "tokio")]
+const MIN_FETCH: u64 = 0x10000;
+
+#[cfg(feature = "tokio")]
+impl SparseSource {
+    /// Make a source for an asynchronous stream with nothing fetched.
+    pub(crate) async fn new<R>(h: &mut R) -> io::Result<Self>
+    where
+        R: tokio::io::AsyncSeek + Unpin,
+    {
+        use tokio::io::AsyncSeekExt;
+        let len = h.seek(SeekFrom::End(0)).await?;
+        Ok(Self {
+            chunks: BTreeMap::new(),
+            len,
+            pos: 0,
+            missing: None,
+        })
+    }
+
+    /// Fetch all bytes from 'begin' up to but not including 'end'.
+    ///
+    /// Bytes already fetched or past the end of the stream are skipped.
+    pub(crate) async fn fetch<R>(&mut self, h: &mut R, begin: u64, end: u64) -> io::Result<()>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+        for (b, e) in self.gaps(begin, end.min(self.len)) {
+            // ASSUME this fits in memory since it is part of an FCS file
+            let mut buf = vec![0; (e - b) as usize];
+            h.seek(SeekFrom::Start(b)).await?;
+            h.read_exact(&mut buf).await?;
+            self.chunks.insert(b, buf);
+        }
+        Ok(())
+    }
+
+    /// Fetch bytes for the last read which missed and rewind.
+    ///
+    /// Return false if no read missed since the last call.
+    pub(crate) async fn fetch_missing<R>(&mut self, h: &mut R) -> io::Result<bool>
+    where
+        R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin,
+    {
+        self.pos = 0;
+        if let Some((begin, n)) = self.missing.take() {
+            self.fetch(h, begin, begin.saturating_add(n.max(MIN_FETCH)))
+                .await?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Return total number of bytes fetched
+    #[cfg(test)]
+    fn fetched(&self) -> u64 {
+        self.chunks.values().map(|xs| xs.len() as u64).sum()
+    }
+
+    fn find(&self, pos: u64) -> Option<&[u8]> {
+        self.chunks
+            .range(..=pos)
+            .next_back()
+            .and_then(|(b, xs)| xs.get(usize::try_from(pos - b).ok()?..))
+            .filter(|xs| !xs.is_empty())
+    }
+
+    fn gaps(&self, begin: u64, end: u64) -> Vec<(u64, u64)> {
+        let mut acc = vec![];
+        let mut i = begin;
+        while i < end {
+            if let Some(xs) = self.find(i) {
+                i += xs.len() as u64;
+            } else {
+                let next = self.chunks.range(i..end).next().map_or(end, |(b, _)| *b);
+                acc.push((i, next));
+                i = next;
+            }
+        }
+        acc
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Read for SparseSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        if let Some(xs) = self.find(self.pos) {
+            let n = xs.len().min(buf.len());
+            buf[..n].copy_from_slice(&xs[..n]);
+            self.pos += n as u64;
+            Ok(n)
+        } else {
+            self.missing = Some((self.pos, buf.len() as u64));
+            Err(io::Error::other(format!(
+                "byte {} has not been fetched",
+                self.pos
+            )))
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Seek for SparseSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new = match pos {
+            SeekFrom::Start(x) => Some(x),
+            SeekFrom::End(x) => self.len.checked_add_signed(x),
+            SeekFrom::Current(x) => self.pos.checked_add_signed(x),
+        };
+        let p = new.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek to negative position")
+        })?;
+        self.pos = p;
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic() {
+        assert_eq!(
+            Some(Compression::Gzip),
+            Compression::from_magic(&[0x1f, 0x8b, 0x08, 0x00])
+        );
+        assert_eq!(
+            Some(Compression::Zstd),
+            Compression::from_magic(&[0x28, 0xb5, 0x2f, 0xfd])
+        );
+        assert_eq!(None, Compression::from_magic(b"FCS3"));
+        assert_eq!(None, Compression::from_magic(&[0x1f]));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_sparse_source() {
+        let bytes: Vec<u8> = (0..200_u8).collect();
+        let mut h = io::Cursor::new(bytes.clone());
+        let mut src = SparseSource::new(&mut h).await.unwrap();
+        src.fetch(&mut h, 10, 20).await.unwrap();
+        src.fetch(&mut h, 15, 30).await.unwrap();
+        assert_eq!(20, src.fetched());
+
+        let mut buf = [0_u8; 20];
+        src.seek(SeekFrom::Start(10)).unwrap();
+        src.read_exact(&mut buf).unwrap();
+        assert_eq!(&bytes[10..30], &buf[..]);
+        assert!(src.read(&mut buf).is_err());
+        assert!(src.fetch_missing(&mut h).await.unwrap());
+        assert!(!src.fetch_missing(&mut h).await.unwrap());
+        assert_eq!(190, src.fetched());
+
+        src.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(0, src.read(&mut buf).unwrap());
+    }
+}
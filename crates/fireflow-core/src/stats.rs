@@ -0,0 +1,431 @@
+//! Summary statistics and histograms for measurements.
+//!
+//! Statistics may be computed for a single column with
+//! [`ColumnStats::from_column`] or for all measurements in a dataset with
+//! [`AnyCoreDataset::summary`], which pairs each column with $PnN and $PnS.
+//! Likewise, histograms may be computed with [`Histogram::from_column`] or
+//! [`AnyCoreDataset::histograms`].
+//!
+//! [`AnyCoreDataset::range_audit`] compares $PnR of each integer measurement
+//! to the largest value its $PnB can hold and the largest value in DATA.
+//!
+//! [`AnyCoreDataset::summary`]: crate::core::AnyCoreDataset::summary
+//! [`AnyCoreDataset::histograms`]: crate::core::AnyCoreDataset::histograms
+//! [`AnyCoreDataset::range_audit`]: crate::core::AnyCoreDataset::range_audit
+
+use crate::text::byteord::Width;
+use crate::text::float_or_int::FloatOrInt;
+use crate::text::index::MeasIndex;
+use crate::text::keywords::{Longname, Range};
+use crate::transform::{Logicle, Transform};
+use crate::validated::dataframe::AnyFCSColumn;
+use crate::validated::shortname::Shortname;
+
+use serde::Serialize;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+
+/// Percentiles reported when none are otherwise given.
+pub const DEFAULT_PERCENTILES: [f64; 6] = [1.0, 5.0, 25.0, 75.0, 95.0, 99.0];
+
+/// Statistics for one column.
+///
+/// NaN values are ignored.
+#[derive(Clone, Serialize)]
+pub struct ColumnStats {
+    /// Number of values used (ie those which are not NaN)
+    pub count: usize,
+
+    pub min: f64,
+
+    pub max: f64,
+
+    pub mean: f64,
+
+    pub median: f64,
+
+    /// Sample standard deviation, which is 0 for a single value
+    pub stdev: f64,
+
+    /// Pairs of percentile (from 0 to 100) and value
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+/// Statistics for one measurement with its names
+#[derive(Clone, Serialize)]
+pub struct MeasurementSummary {
+    /// Position of the measurement
+    pub index: MeasIndex,
+
+    /// Value for $PnN, or a name made from the prefix if missing
+    pub shortname: Shortname,
+
+    /// Value for $PnS if given
+    pub longname: Option<Longname>,
+
+    /// Statistics for this measurement, or None if there are no values
+    pub stats: Option<ColumnStats>,
+}
+
+/// Counts of values in bins for one column
+#[derive(Clone, Serialize)]
+pub struct Histogram {
+    /// Edges of each bin in increasing order, with one more edge than bins.
+    ///
+    /// Each bin includes its lower edge, and the last bin also includes its
+    /// upper edge.
+    pub edges: Vec<f64>,
+
+    /// Number of values in each bin
+    pub counts: Vec<usize>,
+
+    /// Number of values outside all bins, including NaN
+    pub excluded: usize,
+}
+
+/// Histogram for one measurement with its names
+#[derive(Clone, Serialize)]
+pub struct MeasurementHistogram {
+    /// Position of the measurement
+    pub index: MeasIndex,
+
+    /// Value for $PnN, or a name made from the prefix if missing
+    pub shortname: Shortname,
+
+    /// Value for $PnS if given
+    pub longname: Option<Longname>,
+
+    pub histogram: Histogram,
+}
+
+/// Declared and representable range for one integer measurement
+#[derive(Clone, Serialize)]
+pub struct RangeAudit {
+    /// Position of the measurement
+    pub index: MeasIndex,
+
+    /// Value for $PnN, or a name made from the prefix if missing
+    pub shortname: Shortname,
+
+    /// Value for $PnB
+    pub width: Width,
+
+    /// Value for $PnR
+    pub range: Range,
+
+    /// Bitmask applied to this measurement when reading or writing, which is
+    /// the largest value it can hold
+    pub bitmask: u64,
+
+    /// Largest value in DATA, or None if there are no events
+    pub max: Option<u64>,
+
+    /// True if $PnR is larger than 'bitmask' + 1, in which case values near
+    /// $PnR cannot be stored and were likely truncated
+    pub truncated: bool,
+
+    /// True if 'max' equals 'bitmask', in which case some values may have
+    /// been clipped
+    pub saturated: bool,
+}
+
+/// Spacing of histogram bins.
+///
+/// Linear and log bins span the smallest and largest values to be counted.
+/// Logicle bins span the range of the logicle scale, which is fixed by its
+/// parameters.
+#[derive(Clone, Copy, Default)]
+pub enum BinScale {
+    /// Bins of equal width
+    #[default]
+    Linear,
+    /// Bins of equal width after log10; values which are not positive will
+    /// be excluded
+    Log,
+    /// Bins of equal width on a logicle scale
+    Logicle(Logicle),
+}
+
+/// Error when parsing a bin scale from a string
+pub struct BinScaleError(String);
+
+impl BinScale {
+    /// Return bin edges given the values to be counted.
+    fn edges(&self, xs: &[f64], bins: usize) -> Vec<f64> {
+        let (x0, x1) = match self {
+            Self::Logicle(l) => {
+                return (0..=bins)
+                    .map(|i| l.inverse(i as f64 / bins as f64))
+                    .collect()
+            }
+            Self::Linear => minmax(xs.iter().copied()),
+            Self::Log => minmax(xs.iter().filter(|x| **x > 0.0).map(|x| x.log10())),
+        };
+        let (lo, hi) = if x0 == x1 {
+            (x0 - 0.5, x1 + 0.5)
+        } else {
+            (x0, x1)
+        };
+        let step = (hi - lo) / bins as f64;
+        (0..=bins)
+            .map(|i| if i == bins { hi } else { lo + i as f64 * step })
+            .map(|x| {
+                if matches!(self, Self::Log) {
+                    10_f64.powf(x)
+                } else {
+                    x
+                }
+            })
+            .collect()
+    }
+}
+
+/// Return smallest and largest values, or (0, 1) if there are none.
+fn minmax<I: Iterator<Item = f64>>(xs: I) -> (f64, f64) {
+    xs.fold(None, |acc: Option<(f64, f64)>, x| {
+        Some(acc.map_or((x, x), |(lo, hi)| (lo.min(x), hi.max(x))))
+    })
+    .unwrap_or((0.0, 1.0))
+}
+
+impl Histogram {
+    /// Count values in a column.
+    ///
+    /// See [`BinScale`] for how bins are spaced.
+    pub fn from_column(c: &AnyFCSColumn, bins: NonZeroUsize, scale: &BinScale) -> Self {
+        Self::from_values(&c.to_f64s(), bins, scale)
+    }
+
+    /// Count values in a list.
+    ///
+    /// See [`Self::from_column`].
+    pub fn from_values(xs: &[f64], bins: NonZeroUsize, scale: &BinScale) -> Self {
+        let n = bins.get();
+        let valid: Vec<_> = xs.iter().copied().filter(|x| !x.is_nan()).collect();
+        let edges = scale.edges(&valid, n);
+        let mut counts = vec![0; n];
+        let mut excluded = xs.len() - valid.len();
+        for x in valid.iter() {
+            // Linear and log edges span all values by construction, but only
+            // compare with the outer edges for logicle since rounding may put
+            // the smallest or largest value just outside the others.
+            let outside = match scale {
+                BinScale::Linear => false,
+                BinScale::Log => *x <= 0.0,
+                BinScale::Logicle(_) => *x < edges[0] || *x > edges[n],
+            };
+            if outside {
+                excluded += 1;
+            } else {
+                let i = edges.partition_point(|e| e <= x).saturating_sub(1);
+                counts[i.min(n - 1)] += 1;
+            }
+        }
+        Self {
+            edges,
+            counts,
+            excluded,
+        }
+    }
+}
+
+impl FromStr for BinScale {
+    type Err = BinScaleError;
+
+    /// Parse "linear", "log", or "logicle".
+    ///
+    /// The latter may be followed by parameters like "logicle:T,W,M,A".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || BinScaleError(s.to_string());
+        match s.split_once(':') {
+            None => match s {
+                "linear" => Ok(Self::Linear),
+                "log" => Ok(Self::Log),
+                "logicle" => Ok(Self::Logicle(Logicle::default())),
+                _ => Err(err()),
+            },
+            Some(("logicle", ps)) => {
+                let xs: Vec<f64> = ps
+                    .split(',')
+                    .map(|x| x.trim().parse().map_err(|_| err()))
+                    .collect::<Result<_, _>>()?;
+                match xs[..] {
+                    [t, w, m, a] => Logicle::new(t, w, m, a)
+                        .map(Self::Logicle)
+                        .map_err(|_| err()),
+                    _ => Err(err()),
+                }
+            }
+            _ => Err(err()),
+        }
+    }
+}
+
+impl fmt::Display for BinScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Log => write!(f, "log"),
+            Self::Logicle(l) => write!(f, "logicle:{l}"),
+        }
+    }
+}
+
+impl fmt::Display for BinScaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "bin scale must be 'linear', 'log', 'logicle', or \
+             'logicle:T,W,M,A', got '{}'",
+            self.0
+        )
+    }
+}
+
+impl ColumnStats {
+    /// Compute statistics from a column.
+    ///
+    /// Percentiles will be clamped to [0, 100] and interpolated linearly
+    /// between values. Return None if the column has no values which aren't
+    /// NaN.
+    pub fn from_column(c: &AnyFCSColumn, percentiles: &[f64]) -> Option<Self> {
+        Self::from_values(c.to_f64s(), percentiles)
+    }
+
+    /// Compute statistics from a list of values.
+    ///
+    /// See [`Self::from_column`].
+    pub fn from_values(mut xs: Vec<f64>, percentiles: &[f64]) -> Option<Self> {
+        xs.retain(|x| !x.is_nan());
+        if xs.is_empty() {
+            return None;
+        }
+        xs.sort_unstable_by(f64::total_cmp);
+        let n = xs.len();
+        let mean = xs.iter().sum::<f64>() / n as f64;
+        let stdev = if n > 1 {
+            let ss: f64 = xs.iter().map(|x| (x - mean).powi(2)).sum();
+            (ss / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        Some(Self {
+            count: n,
+            min: xs[0],
+            max: xs[n - 1],
+            mean,
+            median: percentile(&xs, 50.0),
+            stdev,
+            percentiles: percentiles
+                .iter()
+                .map(|p| (*p, percentile(&xs, *p)))
+                .collect(),
+        })
+    }
+}
+
+impl RangeAudit {
+    /// Compare $PnR and the values in a column to the bitmask.
+    pub fn new(
+        index: MeasIndex,
+        shortname: Shortname,
+        width: Width,
+        range: Range,
+        bitmask: u64,
+        c: &AnyFCSColumn,
+    ) -> Self {
+        let truncated = match range.0 {
+            FloatOrInt::Int(x) => x.saturating_sub(1) > bitmask,
+            FloatOrInt::Float(x) => x - 1.0 > bitmask as f64,
+        };
+        let max = c.max_u64();
+        Self {
+            index,
+            shortname,
+            width,
+            range,
+            bitmask,
+            max,
+            truncated,
+            saturated: max == Some(bitmask),
+        }
+    }
+}
+
+/// Return a percentile from sorted non-empty values.
+fn percentile(xs: &[f64], p: f64) -> f64 {
+    let h = p.clamp(0.0, 100.0) / 100.0 * (xs.len() - 1) as f64;
+    let lo = h.floor() as usize;
+    let hi = h.ceil() as usize;
+    xs[lo] + (h - lo as f64) * (xs[hi] - xs[lo])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let xs = vec![4.0, 1.0, f64::NAN, 3.0, 2.0];
+        let s = ColumnStats::from_values(xs, &[0.0, 25.0, 100.0, 150.0]).unwrap();
+        assert_eq!(s.count, 4);
+        assert_eq!(s.min, 1.0);
+        assert_eq!(s.max, 4.0);
+        assert_eq!(s.mean, 2.5);
+        assert_eq!(s.median, 2.5);
+        assert!((s.stdev - (5.0_f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(
+            s.percentiles,
+            vec![(0.0, 1.0), (25.0, 1.75), (100.0, 4.0), (150.0, 4.0)]
+        );
+    }
+
+    #[test]
+    fn test_histogram() {
+        let bins = NonZeroUsize::new(4).unwrap();
+        let xs = [0.0, 1.0, 2.0, 2.5, 4.0, f64::NAN];
+        let h = Histogram::from_values(&xs, bins, &BinScale::Linear);
+        assert_eq!(h.edges, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(h.counts, vec![1, 1, 2, 1]);
+        assert_eq!(h.excluded, 1);
+        let ys = [-1.0, 1.0, 10.0, 100.0];
+        let bins2 = NonZeroUsize::new(2).unwrap();
+        let log = Histogram::from_values(&ys, bins2, &BinScale::Log);
+        assert_eq!(log.counts, vec![1, 2]);
+        assert_eq!(log.excluded, 1);
+    }
+
+    #[test]
+    fn test_bin_scale() {
+        assert!("logicle".parse::<BinScale>().is_ok());
+        assert!("logicle:10000,0.5,4,0".parse::<BinScale>().is_ok());
+        assert!("logicle:1,2".parse::<BinScale>().is_err());
+        assert!("logicle:10000,3,4,0".parse::<BinScale>().is_err());
+    }
+
+    #[test]
+    fn test_range_audit() {
+        let w = "32".parse::<Width>().ok().unwrap();
+        let i = MeasIndex::from(0);
+        let n: Shortname = "FL1".parse().ok().unwrap();
+        let c = AnyFCSColumn::U32(vec![1, 7, 3].into());
+        let big = Range::try_from(2.0_f64.powi(128)).ok().unwrap();
+        let wide = RangeAudit::new(i, n.clone(), w, big, u64::from(u32::MAX), &c);
+        assert!(wide.truncated);
+        assert!(!wide.saturated);
+        assert_eq!(wide.max, Some(7));
+        let narrow = RangeAudit::new(i, n, w, Range::from(8), 7, &c);
+        assert!(!narrow.truncated);
+        assert!(narrow.saturated);
+    }
+
+    #[test]
+    fn test_stats_empty() {
+        assert!(ColumnStats::from_values(vec![], &[]).is_none());
+        assert!(ColumnStats::from_values(vec![f64::NAN], &[]).is_none());
+        let s = ColumnStats::from_values(vec![7.0], &[]).unwrap();
+        assert_eq!(s.stdev, 0.0);
+        assert_eq!(s.median, 7.0);
+    }
+}
@@ -34,7 +34,8 @@ pub struct ByteOrd(Vec<u8>);
 ///
 /// The $PnB key actually stores bits. However, this library only supports
 /// widths that are multiples of 8 (ie bytes) for now. Therefore, this key
-/// actually stores the number of bytes indicated by $PnB.
+/// actually stores the number of bytes indicated by $PnB. Other widths may be
+/// padded to whole bytes (see [`Width::pad_to_bytes`]).
 ///
 /// This may also be '*' which means "delimited ASCII" which is only valid when
 /// $DATATYPE=A.
@@ -97,6 +98,15 @@ impl ByteOrd {
         ByteOrd((0..4).collect())
     }
 
+    /// Return big or little endian byte order with the given width.
+    pub fn from_endian(e: Endian, n: Bytes) -> Self {
+        let xs = 0..n.0;
+        match e {
+            Endian::Little => ByteOrd(xs.collect()),
+            Endian::Big => ByteOrd(xs.rev().collect()),
+        }
+    }
+
     // ASSUME this will always be 1-8 elements
     pub fn nbytes(&self) -> Bytes {
         Bytes(self.0.len() as u8)
@@ -130,6 +140,21 @@ impl ByteOrd {
         }
     }
 
+    /// Read an unsigned integer from bytes in this order.
+    ///
+    /// Return None if the number of bytes does not match this byte order,
+    /// unless it is big or little endian, which applies to any width.
+    pub fn read_uint(&self, xs: &[u8]) -> Option<u64> {
+        if let Some(e) = self.as_endian() {
+            Some(e.read_uint(xs))
+        } else if xs.len() == self.0.len() {
+            let f = |acc, (i, x): (&u8, &u8)| acc | (u64::from(*x) << (8 * u32::from(*i)));
+            Some(self.0.iter().zip(xs).fold(0, f))
+        } else {
+            None
+        }
+    }
+
     pub fn as_sized_endian<const LEN: usize>(
         &self,
     ) -> Result<SizedEndian<LEN>, ByteOrdToSizedEndianError> {
@@ -152,6 +177,15 @@ impl Endian {
         }
     }
 
+    /// Read an unsigned integer of up to 8 bytes.
+    pub fn read_uint(&self, xs: &[u8]) -> u64 {
+        let f = |acc, x: &u8| (acc << 8) | u64::from(*x);
+        match self {
+            Endian::Big => xs.iter().fold(0, f),
+            Endian::Little => xs.iter().rev().fold(0, f),
+        }
+    }
+
     pub fn as_bytord(&self, n: Bytes) -> ByteOrd {
         let it = 0..(u8::from(n));
         let xs = match self {
@@ -192,6 +226,20 @@ impl TryFrom<Width> for BitsOrChars {
     }
 }
 
+impl Chars {
+    /// Return the number of chars needed to write 'x' in decimal.
+    pub fn from_max(x: u64) -> Self {
+        Chars(x.checked_ilog10().map_or(1, |d| d as u8 + 1))
+    }
+}
+
+impl Bytes {
+    /// Return the number of bytes needed to store 'x'.
+    pub fn from_max(x: u64) -> Self {
+        Bytes((u64::BITS - x.leading_zeros()).div_ceil(8).max(1) as u8)
+    }
+}
+
 impl TryFrom<BitsOrChars> for Chars {
     type Error = CharsError;
     /// Return the number of chars represented by this if 20 or less.
@@ -285,6 +333,16 @@ impl Width {
         Width::Fixed(BitsOrChars(64))
     }
 
+    /// Round a fixed width up to the nearest multiple of 8 bits.
+    ///
+    /// Widths over 64 bits will be left unchanged.
+    pub fn pad_to_bytes(self) -> Self {
+        match self {
+            Width::Fixed(BitsOrChars(x)) if x < 64 => Width::Fixed(BitsOrChars(x.div_ceil(8) * 8)),
+            _ => self,
+        }
+    }
+
     /// Given a list of widths and a type, return the byte-width for a matrix.
     ///
     /// That is, only return Ok if the widths are all the same and they
@@ -302,7 +360,10 @@ impl Width {
         t: AlphaNumType,
     ) -> DeferredResult<Bytes, WidthToBytesError, SingleWidthError> {
         if let Some(ws) = NonEmpty::collect(widths.iter().copied()) {
-            let bs = ne_map_results(ws, Bytes::try_from).mult_to_deferred();
+            // ASSUME unique elements of a nonempty list are nonempty
+            let bs = ne_map_results(ws, Bytes::try_from)
+                .map(|xs| NonEmpty::collect(xs.into_iter().unique()).unwrap())
+                .mult_to_deferred();
 
             let go = |sizes: NonEmpty<_>, expected: usize| {
                 if sizes.tail.is_empty() {
@@ -381,7 +442,7 @@ impl FromStr for ByteOrd {
 
 impl fmt::Display for ByteOrd {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.0.iter().join(","))
+        write!(f, "{}", self.0.iter().map(|x| x + 1).join(","))
     }
 }
 
@@ -128,6 +128,18 @@ impl Compensation {
         }
     }
 
+    /// Reorder rows and columns of matrix.
+    ///
+    /// 'order' gives the current index of the measurement to put in each
+    /// position. Do nothing if its length does not match the matrix.
+    pub(crate) fn reorder(&mut self, order: &[usize]) -> Result<(), ClearOptional> {
+        if order.len() == self.matrix.ncols() {
+            let m = &self.matrix;
+            self.matrix = DMatrix::from_fn(m.nrows(), m.ncols(), |r, c| m[(order[r], order[c])]);
+        }
+        Ok(())
+    }
+
     pub fn matrix(&self) -> &DMatrix<f32> {
         &self.matrix
     }
@@ -220,6 +232,7 @@ pub(crate) fn lookup_dfc(
                 error: e,
                 key: k,
                 value: v.clone(),
+                offset: None,
             })
             .map(Some)
     })
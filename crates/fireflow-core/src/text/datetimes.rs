@@ -5,9 +5,11 @@ use crate::validated::standard::*;
 
 use super::optionalkw::*;
 use super::parser::*;
+use super::timestamps::{LabTimezone, Timestamps, ZonedTimeError};
 
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
 use serde::Serialize;
+use std::convert::Infallible;
 use std::fmt;
 use std::str::FromStr;
 
@@ -105,17 +107,92 @@ impl Datetimes {
         }
     }
 
-    pub(crate) fn lookup<E>(kws: &mut StdKeywords) -> LookupTentative<Self, E> {
+    pub(crate) fn lookup<E>(
+        kws: &mut StdKeywords,
+        zone: Option<LabTimezone>,
+    ) -> LookupTentative<Self, E> {
+        // Add offsets to any values which lack them so they can be parsed
+        // like any other optional key.
+        let ws: Vec<_> = [BeginDateTime::std(), EndDateTime::std()]
+            .into_iter()
+            .flat_map(|k| add_missing_offset(kws, k, zone))
+            .map(LookupKeysWarning::Offsetless)
+            .collect();
         let b = BeginDateTime::lookup_opt(kws, false);
         let e = EndDateTime::lookup_opt(kws, false);
-        b.zip(e).and_tentatively(|(begin, end)| {
+        let mut tnt = b.zip(e).and_tentatively(|(begin, end)| {
             Datetimes::try_new(begin, end)
                 .map(Tentative::new1)
                 .unwrap_or_else(|w| {
                     let ow = LookupKeysWarning::Relation(w.into());
                     Tentative::new(Datetimes::default(), vec![ow], vec![])
                 })
-        })
+        });
+        tnt.extend_warnings(ws);
+        tnt
+    }
+
+    /// Cross-check with $DATE/$BTIM/$ETIM and return the acquisition interval.
+    ///
+    /// Legacy timestamps have no time zone, so they will be interpreted in the
+    /// lab time zone if set, or in the offset of the corresponding datetime
+    /// otherwise. A warning will be issued if these disagree by more than
+    /// 'tolerance' or if $ETIM is before $BTIM (which is assumed to mean the
+    /// acquisition ran past midnight).
+    ///
+    /// The returned interval will use $BEGINDATETIME/$ENDDATETIME where
+    /// present and the legacy timestamps otherwise. The interval will be None
+    /// if there is no begin time or if it cannot be made absolute.
+    pub fn reconcile<X>(&self, ts: &Timestamps<X>, tolerance: TimeDelta) -> TemporalTentative
+    where
+        X: PartialOrd + Copy,
+        NaiveTime: From<X>,
+    {
+        let mut ws = vec![];
+        let begin_dt = self.begin_naive();
+        let end_dt = self.end_naive();
+        let (begin_leg, end_leg) = match ts.interval_naive() {
+            Some((b, e, rollover)) => {
+                if rollover {
+                    ws.push(TemporalWarning::Rollover);
+                }
+                (Some(b), e)
+            }
+            None => (None, None),
+        };
+        // Use the offset of either datetime when no lab time zone is given,
+        // since it likely reflects the lab time zone anyway.
+        let fallback = begin_dt.or(end_dt).map(|x| *x.offset());
+        let absolute = |x: NaiveDateTime| match ts.timezone() {
+            Some(z) => z.resolve(x).ok(),
+            None => fallback.and_then(|o| o.from_local_datetime(&x).single()),
+        };
+        let begin_abs = begin_leg.and_then(absolute);
+        let end_abs = end_leg.and_then(absolute);
+        if let (Some(datetime), Some(legacy)) = (begin_dt, begin_abs)
+            && (datetime - legacy).abs() > tolerance
+        {
+            ws.push(TemporalWarning::Begin(TemporalMismatch {
+                datetime,
+                legacy,
+            }));
+        }
+        if let (Some(datetime), Some(legacy)) = (end_dt, end_abs)
+            && (datetime - legacy).abs() > tolerance
+        {
+            ws.push(TemporalWarning::End(TemporalMismatch { datetime, legacy }));
+        }
+        let interval = begin_dt.or(begin_abs).map(|begin| {
+            let end = end_dt.or(end_abs);
+            if end.is_some_and(|e| e < begin) {
+                ws.push(TemporalWarning::Reversed);
+            }
+            AcquisitionInterval {
+                begin,
+                end: end.filter(|e| *e >= begin),
+            }
+        });
+        Tentative::new(interval, ws, vec![])
     }
 
     pub(crate) fn opt_keywords(&self) -> impl Iterator<Item = (String, String)> {
@@ -139,6 +216,105 @@ impl Datetimes {
     }
 }
 
+pub type TemporalTentative = Tentative<Option<AcquisitionInterval>, TemporalWarning, Infallible>;
+
+/// Time range over which a sample was acquired.
+#[derive(Clone, Copy, Serialize)]
+pub struct AcquisitionInterval {
+    pub begin: DateTime<FixedOffset>,
+
+    /// End of acquisition, if known
+    pub end: Option<DateTime<FixedOffset>>,
+}
+
+/// Issue found when cross-checking time-related keywords
+pub enum TemporalWarning {
+    /// $BEGINDATETIME disagrees with $DATE+$BTIM
+    Begin(TemporalMismatch),
+    /// $ENDDATETIME disagrees with $DATE+$ETIM
+    End(TemporalMismatch),
+    /// $ETIM is before $BTIM
+    Rollover,
+    /// End of acquisition is before its beginning
+    Reversed,
+}
+
+pub struct TemporalMismatch {
+    pub datetime: DateTime<FixedOffset>,
+    pub legacy: DateTime<FixedOffset>,
+}
+
+impl fmt::Display for TemporalWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Begin(x) => write!(
+                f,
+                "$BEGINDATETIME ({}) does not match $DATE and $BTIM ({})",
+                FCSDateTime(x.datetime),
+                FCSDateTime(x.legacy)
+            ),
+            Self::End(x) => write!(
+                f,
+                "$ENDDATETIME ({}) does not match $DATE and $ETIM ({})",
+                FCSDateTime(x.datetime),
+                FCSDateTime(x.legacy)
+            ),
+            Self::Rollover => write!(
+                f,
+                "$ETIM is before $BTIM, assuming acquisition ran past midnight"
+            ),
+            Self::Reversed => write!(f, "end of acquisition is before its beginning"),
+        }
+    }
+}
+
+/// Replace a datetime value without an offset with one that has an offset.
+///
+/// The value is interpreted in the given time zone, or UTC if not given (or if
+/// it cannot be interpreted in the given time zone). Return a warning if the
+/// value was changed.
+fn add_missing_offset(
+    kws: &mut StdKeywords,
+    key: StdKey,
+    zone: Option<LabTimezone>,
+) -> Option<OffsetlessDatetimeWarning> {
+    let value = kws.get_mut(&key)?;
+    let naive = NaiveDateTime::parse_from_str(value, NAIVE_FORMAT).ok()?;
+    let utc = naive.and_utc().fixed_offset();
+    let res = zone.map_or(Ok(utc), |z| z.resolve(naive));
+    let interpreted = FCSDateTime(*res.as_ref().unwrap_or(&utc));
+    *value = interpreted.to_string();
+    Some(OffsetlessDatetimeWarning {
+        key,
+        interpreted,
+        error: res.err(),
+    })
+}
+
+/// Format for datetimes without a UTC offset
+const NAIVE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Warning when a datetime has no UTC offset and needed to be interpreted
+pub struct OffsetlessDatetimeWarning {
+    key: StdKey,
+    interpreted: FCSDateTime,
+    error: Option<ZonedTimeError>,
+}
+
+impl fmt::Display for OffsetlessDatetimeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} has no UTC offset, interpreted as {}",
+            self.key, self.interpreted
+        )?;
+        if let Some(e) = &self.error {
+            write!(f, " since {e}")?;
+        }
+        Ok(())
+    }
+}
+
 pub struct ReversedDatetimes;
 
 type DatetimesResult<T> = Result<T, ReversedDatetimes>;
@@ -154,7 +330,6 @@ impl FromStr for FCSDateTime {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let formats = [
-            "%Y-%m-%dT%H:%M:%S%.f",
             "%Y-%m-%dT%H:%M:%S%.f%#z",
             "%Y-%m-%dT%H:%M:%S%.f%:z",
             "%Y-%m-%dT%H:%M:%S%.f%::z",
@@ -165,7 +340,12 @@ impl FromStr for FCSDateTime {
                 return Ok(FCSDateTime(t));
             }
         }
-        Err(FCSDateTimeError)
+        // Values without an offset are assumed to be in UTC. When reading
+        // TEXT, these will already have an offset added (see
+        // 'add_missing_offset') so this is only a fallback.
+        NaiveDateTime::parse_from_str(s, NAIVE_FORMAT)
+            .map(|t| FCSDateTime(t.and_utc().fixed_offset()))
+            .map_err(|_| FCSDateTimeError)
     }
 }
 
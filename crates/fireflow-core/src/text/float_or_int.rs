@@ -8,7 +8,7 @@ use std::str::FromStr;
 /// Technically this should only be an integer, but many versions also store
 /// floats which makes sense for cases where $DATATYPE/$PnDATATYPE indicates
 /// float or double.
-#[derive(Clone, Copy, Serialize)]
+#[derive(Clone, Copy, Serialize, PartialEq)]
 pub enum FloatOrInt {
     // this should never be NaN
     Float(f64),
@@ -0,0 +1,370 @@
+use crate::core::MeasurementRow;
+use crate::text::index::MeasIndex;
+use crate::text::keywords::{DetectorType, DetectorVoltage, Filter, Wavelength};
+use crate::text::ranged_float::NonNegFloat;
+use crate::validated::shortname::Shortname;
+use crate::validated::standard::*;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// A description of the lasers and emission filters installed on a cytometer.
+///
+/// This is meant to be loaded from an external source (ie JSON) and used to
+/// check that $PnL and $PnF for each measurement are physically possible on
+/// the instrument which supposedly generated the file. Conflicts usually
+/// indicate that these keywords were entered incorrectly.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct InstrumentHardware {
+    /// Excitation wavelengths (in nm) of all lasers on the instrument.
+    pub lasers: Vec<u32>,
+
+    /// All emission filters installed on the instrument.
+    ///
+    /// If empty, $PnF will only be checked against the lasers.
+    #[serde(default)]
+    pub filters: Vec<FilterBand>,
+
+    /// Maximum difference (in nm) allowed when comparing wavelengths.
+    ///
+    /// This applies both when matching $PnL to a laser and when matching
+    /// $PnF to an installed filter.
+    #[serde(default)]
+    pub tolerance: u32,
+}
+
+/// An emission filter as commonly written in $PnF.
+///
+/// $PnF is a free-form string, but most vendors use one of the following
+/// formats: "530/30" (bandpass with center and width), "670LP" (longpass), or
+/// "505SP" (shortpass).
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilterBand {
+    BandPass { center: u32, width: u32 },
+    LongPass(u32),
+    ShortPass(u32),
+}
+
+impl FilterBand {
+    /// Return the lowest and highest wavelength passed by this filter.
+    ///
+    /// Upper bound will be None for longpass filters.
+    pub fn bounds(&self) -> (u32, Option<u32>) {
+        match self {
+            Self::BandPass { center, width } => {
+                let half = width / 2;
+                (
+                    center.saturating_sub(half),
+                    Some(center.saturating_add(half)),
+                )
+            }
+            Self::LongPass(x) => (*x, None),
+            Self::ShortPass(x) => (0, Some(*x)),
+        }
+    }
+
+    fn approx_eq(&self, other: &Self, tolerance: u32) -> bool {
+        let near = |a: u32, b: u32| a.abs_diff(b) <= tolerance;
+        match (self, other) {
+            (
+                Self::BandPass {
+                    center: c0,
+                    width: w0,
+                },
+                Self::BandPass {
+                    center: c1,
+                    width: w1,
+                },
+            ) => near(*c0, *c1) && near(*w0, *w1),
+            (Self::LongPass(x), Self::LongPass(y)) => near(*x, *y),
+            (Self::ShortPass(x), Self::ShortPass(y)) => near(*x, *y),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for FilterBand {
+    type Err = FilterBandError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.trim().to_uppercase();
+        let t = upper.strip_suffix("NM").unwrap_or(&upper).trim_end();
+        let int = |x: &str| x.trim().parse::<u32>().map_err(FilterBandError::Int);
+        if let Some(x) = t.strip_suffix("LP") {
+            int(x).map(Self::LongPass)
+        } else if let Some(x) = t.strip_suffix("SP") {
+            int(x).map(Self::ShortPass)
+        } else {
+            match t.split("/").collect::<Vec<_>>()[..] {
+                [c, w] => Ok(Self::BandPass {
+                    center: int(c)?,
+                    width: int(w)?,
+                }),
+                _ => Err(FilterBandError::Format),
+            }
+        }
+    }
+}
+
+impl fmt::Display for FilterBand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::BandPass { center, width } => write!(f, "{center}/{width}"),
+            Self::LongPass(x) => write!(f, "{x}LP"),
+            Self::ShortPass(x) => write!(f, "{x}SP"),
+        }
+    }
+}
+
+pub enum FilterBandError {
+    Int(ParseIntError),
+    Format,
+}
+
+impl fmt::Display for FilterBandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Int(e) => e.fmt(f),
+            Self::Format => write!(f, "must be like 'N/W', 'NLP', or 'NSP'"),
+        }
+    }
+}
+
+impl InstrumentHardware {
+    /// Check $PnL and $PnF for one measurement against this instrument.
+    ///
+    /// $PnF values which cannot be interpreted as a filter are skipped since
+    /// this keyword is free-form and may contain anything.
+    pub(crate) fn check_measurement(
+        &self,
+        i: MeasIndex,
+        wavelengths: &[u32],
+        filter: Option<&Filter>,
+    ) -> Vec<HardwareConflictError> {
+        let t = self.tolerance;
+        let mut es = vec![];
+        for w in wavelengths {
+            if !self.lasers.iter().any(|l| l.abs_diff(*w) <= t) {
+                es.push(HardwareConflictError {
+                    index: i,
+                    kind: HardwareConflict::NoLaser(*w),
+                });
+            }
+        }
+        if let Some(band) = filter.and_then(|f| f.0.parse::<FilterBand>().ok()) {
+            if !self.filters.is_empty() && !self.filters.iter().any(|x| x.approx_eq(&band, t)) {
+                es.push(HardwareConflictError {
+                    index: i,
+                    kind: HardwareConflict::NoFilter(band),
+                });
+            }
+            // Emission can never be shorter than excitation. Scatter channels
+            // are fine since their filter will pass the laser wavelength.
+            if let (Some(upper), Some(lowest)) = (band.bounds().1, wavelengths.iter().min())
+                && upper.saturating_add(t) < *lowest
+            {
+                es.push(HardwareConflictError {
+                    index: i,
+                    kind: HardwareConflict::BelowExcitation(band, *lowest),
+                });
+            }
+        }
+        es
+    }
+}
+
+/// Instrument configuration as reconstructed from TEXT.
+///
+/// This groups measurements by laser using $PnL, such that each laser appears
+/// once regardless of how many measurements it excites. Measurements with more
+/// than one $PnL (ie co-excitation in 3.1+) will appear under each laser.
+#[derive(Clone, Default, Serialize)]
+pub struct OpticalConfig {
+    /// All lasers sorted by wavelength
+    pub lasers: Vec<LaserConfig>,
+
+    /// Optical measurements without $PnL (usually scatter)
+    pub unassigned: Vec<DetectorConfig>,
+}
+
+/// One laser and the detectors it excites
+#[derive(Clone, Serialize)]
+pub struct LaserConfig {
+    /// Excitation wavelength in nm ($PnL)
+    pub wavelength: u32,
+
+    /// All distinct values of $PnO (in mW) for this laser.
+    ///
+    /// This should have at most one value, more indicates that $PnO is
+    /// inconsistent among the measurements for this laser. If $PnO has one
+    /// power per laser, only the power at the same position as this laser in
+    /// $PnL is used.
+    pub powers: Vec<NonNegFloat>,
+
+    /// All measurements excited by this laser
+    pub detectors: Vec<DetectorConfig>,
+}
+
+/// One detector as described by its measurement
+#[derive(Clone, Serialize)]
+pub struct DetectorConfig {
+    /// Position of the measurement (ie "n" in $Pn* keywords)
+    pub index: MeasIndex,
+
+    /// Value for $PnN
+    pub shortname: Option<Shortname>,
+
+    /// Value for $PnF
+    pub filter: Option<Filter>,
+
+    /// Value for $PnF if it could be interpreted as a filter
+    pub band: Option<FilterBand>,
+
+    /// Value for $PnV
+    pub voltage: Option<DetectorVoltage>,
+
+    /// Value for $PnD
+    pub detector_type: Option<DetectorType>,
+}
+
+impl OpticalConfig {
+    /// Build configuration from a measurement table.
+    ///
+    /// The time measurement is ignored.
+    pub fn from_rows(rows: &[MeasurementRow]) -> Self {
+        let mut lasers = BTreeMap::new();
+        let mut unassigned = vec![];
+        for r in rows.iter().filter(|r| !r.is_temporal) {
+            let d = DetectorConfig {
+                index: r.index,
+                shortname: r.shortname.clone(),
+                filter: r.filter.clone(),
+                band: r.filter.as_ref().and_then(|f| f.0.parse().ok()),
+                voltage: r.detector_voltage,
+                detector_type: r.detector_type.clone(),
+            };
+            if r.wavelengths.is_empty() {
+                unassigned.push(d);
+                continue;
+            }
+            for (i, w) in r.wavelengths.iter().enumerate().unique_by(|(_, w)| **w) {
+                let l = lasers.entry(*w).or_insert_with(|| LaserConfig {
+                    wavelength: *w,
+                    powers: vec![],
+                    detectors: vec![],
+                });
+                if let Some(p) = r.power.as_ref().and_then(|x| x.for_laser(i))
+                    && !l.powers.contains(&p)
+                {
+                    l.powers.push(p);
+                }
+                l.detectors.push(d.clone());
+            }
+        }
+        Self {
+            lasers: lasers.into_values().collect(),
+            unassigned,
+        }
+    }
+}
+
+pub struct HardwareConflictError {
+    pub index: MeasIndex,
+    pub kind: HardwareConflict,
+}
+
+pub enum HardwareConflict {
+    /// $PnL does not match any laser
+    NoLaser(u32),
+    /// $PnF does not match any installed filter
+    NoFilter(FilterBand),
+    /// $PnF only passes light shorter than the excitation wavelength
+    BelowExcitation(FilterBand, u32),
+}
+
+impl fmt::Display for HardwareConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let j = self.index.into();
+        let l = Wavelength::std(j);
+        let p = Filter::std(j);
+        match &self.kind {
+            HardwareConflict::NoLaser(w) => {
+                write!(f, "{l} is {w} which does not match any laser on instrument")
+            }
+            HardwareConflict::NoFilter(b) => {
+                write!(
+                    f,
+                    "{p} is {b} which does not match any filter on instrument"
+                )
+            }
+            HardwareConflict::BelowExcitation(b, w) => write!(
+                f,
+                "{p} is {b} which only passes wavelengths below excitation at {w} ({l})"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for InstrumentHardware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "lasers: {}; filters: {}",
+            self.lasers.iter().join(","),
+            self.filters.iter().join(",")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_band() {
+        assert!(matches!(
+            "530/30".parse::<FilterBand>(),
+            Ok(FilterBand::BandPass {
+                center: 530,
+                width: 30
+            })
+        ));
+        assert!(matches!(
+            "670 LP".parse::<FilterBand>(),
+            Ok(FilterBand::LongPass(670))
+        ));
+        assert!(matches!(
+            "505sp".parse::<FilterBand>(),
+            Ok(FilterBand::ShortPass(505))
+        ));
+        assert!("FITC".parse::<FilterBand>().is_err());
+        let wide = FilterBand::BandPass {
+            center: u32::MAX - 1,
+            width: 10,
+        };
+        assert_eq!((u32::MAX - 6, Some(u32::MAX)), wide.bounds());
+    }
+
+    #[test]
+    fn test_check_measurement() {
+        let hw = InstrumentHardware {
+            lasers: vec![405, 488],
+            filters: vec![FilterBand::BandPass {
+                center: 530,
+                width: 30,
+            }],
+            tolerance: 2,
+        };
+        let i = MeasIndex::from(0);
+        let ok = Filter("530/30".into());
+        let bad = Filter("450/50".into());
+        assert!(hw.check_measurement(i, &[488], Some(&ok)).is_empty());
+        assert_eq!(hw.check_measurement(i, &[532], Some(&ok)).len(), 1);
+        // not installed and below 488 excitation
+        assert_eq!(hw.check_measurement(i, &[488], Some(&bad)).len(), 2);
+    }
+}
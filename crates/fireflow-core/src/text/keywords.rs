@@ -25,6 +25,7 @@ use serde::Serialize;
 use std::collections::HashSet;
 use std::convert::Infallible;
 use std::fmt;
+use std::iter::Peekable;
 use std::num::{ParseFloatError, ParseIntError};
 use std::str::FromStr;
 
@@ -117,7 +118,7 @@ impl fmt::Display for TriggerError {
 }
 
 /// The values used for the $MODE key (up to 3.1)
-#[derive(Clone, PartialEq, Eq, Serialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum Mode {
     List,
     Uncorrelated,
@@ -555,6 +556,77 @@ impl fmt::Display for WavelengthsError {
     }
 }
 
+/// The value for the $PnO key (all versions).
+///
+/// The standard says this is a single integer in mW, but some instruments
+/// write floats, or a comma-separated list with one power per laser in $PnL.
+/// Integers will be written back without a decimal point.
+#[derive(Clone, PartialEq)]
+pub struct Power(pub NonEmpty<NonNegFloat>);
+
+impl Serialize for Power {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.iter().collect::<Vec<_>>().serialize(serializer)
+    }
+}
+
+newtype_from!(Power, NonEmpty<NonNegFloat>);
+newtype_from_outer!(Power, NonEmpty<NonNegFloat>);
+
+impl From<NonNegFloat> for Power {
+    fn from(value: NonNegFloat) -> Self {
+        Self(NonEmpty::new(value))
+    }
+}
+
+impl fmt::Display for Power {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0.iter().join(","))
+    }
+}
+
+impl FromStr for Power {
+    type Err = PowerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ps = vec![];
+        for x in s.split(",") {
+            ps.push(x.trim().parse().map_err(PowerError::Float)?);
+        }
+        NonEmpty::from_vec(ps).ok_or(PowerError::Empty).map(Power)
+    }
+}
+
+impl Power {
+    /// Return the power of the laser at position 'i' in $PnL.
+    ///
+    /// A single power applies to all lasers.
+    pub fn for_laser(&self, i: usize) -> Option<NonNegFloat> {
+        if self.0.tail.is_empty() {
+            Some(self.0.head)
+        } else {
+            self.0.get(i).copied()
+        }
+    }
+}
+
+pub enum PowerError {
+    Float(RangedFloatError),
+    Empty,
+}
+
+impl fmt::Display for PowerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            PowerError::Float(e) => e.fmt(f),
+            PowerError::Empty => write!(f, "list must not be empty"),
+        }
+    }
+}
+
 /// A datetime as used in the $LAST_MODIFIED key (3.1+ only)
 ///
 /// Inner value is private to ensure it always gets parsed/printed using the
@@ -651,10 +723,10 @@ impl fmt::Display for OriginalityError {
 
 /// The value of the $UNICODE key (3.0 only)
 ///
-/// Formatted like 'codepage,[keys]'. This key is not actually used for anything
-/// in this library and is present to be complete. The original purpose was to
-/// indicate keywords which supported UTF-8, but these days it is hard to
-/// write a library that does NOT support UTF-8 ;)
+/// Formatted like 'codepage,[keys]'. The code page is a Windows code page
+/// identifier, and the keys are those whose values are encoded with it. When
+/// reading TEXT, values for these keys which cannot be otherwise decoded will
+/// be decoded using this code page if supported (see [`Unicode::decode`]).
 #[derive(Clone, Serialize, PartialEq)]
 pub struct Unicode {
     pub page: u32,
@@ -679,6 +751,77 @@ impl FromStr for Unicode {
     }
 }
 
+impl Unicode {
+    /// Return true if this code page can be decoded.
+    ///
+    /// Supported pages are UTF-8 (65001), US-ASCII (20127), ISO-8859-1 (28591),
+    /// and Windows-1252 (1252).
+    pub fn is_supported(&self) -> bool {
+        matches!(self.page, 65001 | 20127 | 28591 | 1252)
+    }
+
+    /// Decode bytes using this code page.
+    ///
+    /// Return None if the page is not supported or the bytes are invalid.
+    pub fn decode(&self, xs: &[u8]) -> Option<String> {
+        match self.page {
+            65001 => String::from_utf8(xs.to_vec()).ok(),
+            20127 => xs
+                .is_ascii()
+                .then(|| xs.iter().copied().map(char::from).collect()),
+            28591 => Some(xs.iter().copied().map(char::from).collect()),
+            1252 => Some(xs.iter().copied().map(windows_1252_to_char).collect()),
+            _ => None,
+        }
+    }
+
+    /// Return true if the given key is listed in this keyword.
+    pub fn has_key(&self, k: &[u8]) -> bool {
+        self.kws
+            .iter()
+            .any(|x| x.as_bytes().eq_ignore_ascii_case(k))
+    }
+}
+
+/// Convert a byte from Windows-1252 to a char.
+///
+/// This is the same as Latin-1 except for 0x80-0x9F, most of which are
+/// punctuation. Unassigned bytes in this range are mapped as in Latin-1.
+fn windows_1252_to_char(x: u8) -> char {
+    let c = match x {
+        0x80 => 0x20AC,
+        0x82 => 0x201A,
+        0x83 => 0x0192,
+        0x84 => 0x201E,
+        0x85 => 0x2026,
+        0x86 => 0x2020,
+        0x87 => 0x2021,
+        0x88 => 0x02C6,
+        0x89 => 0x2030,
+        0x8A => 0x0160,
+        0x8B => 0x2039,
+        0x8C => 0x0152,
+        0x8E => 0x017D,
+        0x91 => 0x2018,
+        0x92 => 0x2019,
+        0x93 => 0x201C,
+        0x94 => 0x201D,
+        0x95 => 0x2022,
+        0x96 => 0x2013,
+        0x97 => 0x2014,
+        0x98 => 0x02DC,
+        0x99 => 0x2122,
+        0x9A => 0x0161,
+        0x9B => 0x203A,
+        0x9C => 0x0153,
+        0x9E => 0x017E,
+        0x9F => 0x0178,
+        _ => u32::from(x),
+    };
+    // ASSUME all of the above are valid code points
+    char::from_u32(c).unwrap()
+}
+
 impl fmt::Display for Unicode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{},{}", self.page, self.kws.iter().join(","))
@@ -716,6 +859,12 @@ pub enum OpticalType {
 
 pub struct OpticalTypeError;
 
+/// Warning when $PnTYPE denotes categorical values but $PnE or $PnG is set
+pub struct OpticalTypeScaleWarning {
+    pub index: MeasIndex,
+    pub measurement_type: OpticalType,
+}
+
 impl FromStr for OpticalType {
     type Err = OpticalTypeError;
 
@@ -738,7 +887,7 @@ impl FromStr for OpticalType {
 impl fmt::Display for OpticalType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
-            OpticalType::ForwardScatter => write!(f, "Foward Scatter"),
+            OpticalType::ForwardScatter => write!(f, "Forward Scatter"),
             OpticalType::SideScatter => write!(f, "Side Scatter"),
             OpticalType::RawFluorescence => write!(f, "Raw Fluorescence"),
             OpticalType::UnmixedFluorescence => write!(f, "Unmixed Fluorescence"),
@@ -752,10 +901,18 @@ impl fmt::Display for OpticalType {
 }
 
 impl fmt::Display for OpticalTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "$PnTYPE shall only be 'Time' for the time measurement")
+    }
+}
+
+impl fmt::Display for OpticalTypeScaleWarning {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "$PnTYPE for time measurement shall not be 'Time' if given"
+            "$PnTYPE for measurement {} is '{}' but $PnE is not linear \
+             or $PnG is not 1",
+            self.index, self.measurement_type
         )
     }
 }
@@ -790,11 +947,15 @@ impl fmt::Display for TemporalTypeError {
 }
 
 /// The value of the $PnFEATURE key (3.2+)
-#[derive(Clone, Copy, Serialize, PartialEq)]
+#[derive(Clone, Serialize, PartialEq)]
 pub enum Feature {
     Area,
     Width,
     Height,
+    /// A non-standard feature such as 'Eccentricity' from imaging cytometers.
+    ///
+    /// These will only be read if allowed in the configuration.
+    Other(String),
 }
 
 impl FromStr for Feature {
@@ -816,6 +977,30 @@ impl fmt::Display for Feature {
             Feature::Area => write!(f, "Area"),
             Feature::Width => write!(f, "Width"),
             Feature::Height => write!(f, "Height"),
+            Feature::Other(s) => f.write_str(s),
+        }
+    }
+}
+
+impl Feature {
+    /// Look up $PnFEATURE, accepting the given non-standard values.
+    ///
+    /// Values which are not standard and not allowed will be dropped with a
+    /// warning as usual.
+    pub(crate) fn lookup_allowed<E>(
+        kws: &mut StdKeywords,
+        i: IndexFromOne,
+        allowed: &[String],
+    ) -> LookupTentative<OptionalKw<Self>, E> {
+        let k = Self::std(i);
+        if kws
+            .get(&k)
+            .is_some_and(|v| v.parse::<Self>().is_err() && allowed.contains(v))
+        {
+            let v = kws.remove(&k).unwrap();
+            Tentative::new1(Some(Feature::Other(v)).into())
+        } else {
+            Self::lookup_opt(kws, i, false)
         }
     }
 }
@@ -1107,94 +1292,66 @@ impl FromStr for Gating {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.is_ascii() {
-            let mut it = tokenize_gating(s);
-            match_tokens(&mut it, 0)
+            let mut it = tokenize_gating(s).peekable();
+            if it.peek().is_none() {
+                return Err(GatingError::Empty);
+            }
+            let g = match_expr(&mut it)?;
+            match it.next() {
+                None => Ok(g),
+                Some(GatingToken::RParen) => Err(GatingError::ExtraParen),
+                Some(_) => Err(GatingError::InvalidOpToken),
+            }
         } else {
             Err(GatingError::NonAscii)
         }
     }
 }
 
-fn match_tokens(
-    rest: &mut impl Iterator<Item = GatingToken>,
-    depth: u32,
-) -> Result<Gating, GatingError> {
-    if let Some(this) = rest.next() {
-        match this {
-            GatingToken::LParen => match_tokens_depth(rest, depth + 1),
-            GatingToken::Not => {
-                let inner = match_tokens_depth(rest, depth)?;
-                let new = Gating::Not(Box::new(inner));
-                match_tokens_acc(new, rest, depth)
+// Operators have equal precedence and are applied left to right, and NOT
+// applies to the region or parenthesized expression immediately after it.
+fn match_expr<I>(rest: &mut Peekable<I>) -> Result<Gating, GatingError>
+where
+    I: Iterator<Item = GatingToken>,
+{
+    let mut acc = match_atom(rest)?;
+    loop {
+        match rest.peek() {
+            Some(GatingToken::And) => {
+                rest.next();
+                acc = Gating::And(Box::new(acc), Box::new(match_atom(rest)?));
             }
-            GatingToken::Region(r) => {
-                let new = Gating::Region(r);
-                match_tokens_acc(new, rest, depth)
+            Some(GatingToken::Or) => {
+                rest.next();
+                acc = Gating::Or(Box::new(acc), Box::new(match_atom(rest)?));
             }
-            _ => Err(GatingError::InvalidExprToken),
+            _ => return Ok(acc),
         }
-    } else {
-        Err(GatingError::Empty)
     }
 }
 
-fn match_tokens_depth(
-    rest: &mut impl Iterator<Item = GatingToken>,
-    depth: u32,
-) -> Result<Gating, GatingError> {
-    if let Some(this) = rest.next() {
-        match this {
-            GatingToken::LParen => {
-                let inner = match_tokens_depth(rest, depth + 1)?;
-                match_tokens_acc(inner, rest, depth)
-            }
-            GatingToken::Not => {
-                let inner = match_tokens_depth(rest, depth)?;
-                Ok(Gating::Not(Box::new(inner)))
-            }
-            GatingToken::Region(r) => Ok(Gating::Region(r)),
-            _ => Err(GatingError::InvalidExprToken),
-        }
-    } else {
-        Err(GatingError::ExpectedExpr)
-    }
-}
-
-fn match_tokens_acc(
-    acc: Gating,
-    rest: &mut impl Iterator<Item = GatingToken>,
-    depth: u32,
-) -> Result<Gating, GatingError> {
-    if let Some(this) = rest.next() {
-        match this {
-            GatingToken::And => {
-                let right = match_tokens_depth(rest, depth)?;
-                let new = Gating::And(Box::new(acc), Box::new(right));
-                match_tokens_acc(new, rest, depth)
-            }
-            GatingToken::Or => {
-                let right = match_tokens_depth(rest, depth)?;
-                let new = Gating::Or(Box::new(acc), Box::new(right));
-                match_tokens_acc(new, rest, depth)
-            }
-            GatingToken::RParen => {
-                if depth > 0 {
-                    match_tokens_acc(acc, rest, depth - 1)
-                } else {
-                    Err(GatingError::ExtraParen)
-                }
+fn match_atom<I>(rest: &mut Peekable<I>) -> Result<Gating, GatingError>
+where
+    I: Iterator<Item = GatingToken>,
+{
+    match rest.next() {
+        Some(GatingToken::LParen) => {
+            let inner = match_expr(rest)?;
+            match rest.next() {
+                Some(GatingToken::RParen) => Ok(inner),
+                Some(_) => Err(GatingError::InvalidOpToken),
+                None => Err(GatingError::MissingParen),
             }
-            _ => Err(GatingError::InvalidOpToken),
         }
-    } else if depth == 0 {
-        Ok(acc)
-    } else {
-        Err(GatingError::MissingParen)
+        Some(GatingToken::Not) => match_atom(rest).map(|x| Gating::Not(Box::new(x))),
+        Some(GatingToken::Region(r)) => Ok(Gating::Region(r)),
+        Some(_) => Err(GatingError::InvalidExprToken),
+        None => Err(GatingError::ExpectedExpr),
     }
 }
 
 fn tokenize_gating(s: &str) -> impl Iterator<Item = GatingToken> {
-    s.split(['.', ' ']).filter(|x| x.is_empty()).flat_map(|x| {
+    s.split(['.', ' ']).filter(|x| !x.is_empty()).flat_map(|x| {
         x.split('(').flat_map(|y| {
             if y.is_empty() {
                 vec![GatingToken::LParen]
@@ -1270,13 +1427,41 @@ impl fmt::Display for GatingError {
 }
 
 /// The value of the $PnR key
-#[derive(Clone, Copy, Serialize)]
-pub struct Range(pub FloatOrInt);
+///
+/// The string from which this was parsed (if any) is kept so that it can be
+/// written back exactly as it was read (ie "1024.0" will not become "1024").
+/// It will only be used while it still represents the current value.
+#[derive(Clone, Serialize)]
+pub struct Range(pub FloatOrInt, #[serde(skip)] Option<String>);
+
+impl From<FloatOrInt> for Range {
+    fn from(value: FloatOrInt) -> Self {
+        Self(value, None)
+    }
+}
 
-newtype_from!(Range, FloatOrInt);
 newtype_from_outer!(Range, FloatOrInt);
-newtype_disp!(Range);
-newtype_fromstr!(Range, ParseFloatOrIntError);
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self
+            .1
+            .as_ref()
+            .filter(|s| s.parse::<FloatOrInt>().is_ok_and(|x| x == self.0))
+        {
+            Some(s) => f.write_str(s),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseFloatOrIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(|x| Self(x, Some(s.to_string())))
+    }
+}
 
 impl TryFrom<f64> for Range {
     type Error = NanFloatOrInt;
@@ -1705,7 +1890,7 @@ kw_req_meta!(ByteOrd, "BYTEORD"); // 3.1+
 // all versions
 kw_req_meas!(Width, "B");
 kw_opt_meas_string!(Filter, "F");
-kw_opt_meas_int!(Power, u32, "O");
+kw_opt_meas!(Power, "O");
 kw_opt_meas_string!(PercentEmitted, "P");
 kw_req_meas!(Range, "R");
 kw_opt_meas_string!(Longname, "S");
@@ -1754,7 +1939,7 @@ impl BiIndexedKey for Dfc {
 
 // 3.0/3.1 subsets
 kw_opt_meta_int!(CSMode, usize, "CSMODE");
-kw_opt_meta_int!(CSVBits, u32, "CSVBits");
+kw_opt_meta_int!(CSVBits, u32, "CSVBITS");
 
 impl IndexedKey for CSVFlag {
     const PREFIX: &'static str = "CSV";
@@ -1837,3 +2022,41 @@ opt_meta!(Beginanalysis);
 opt_meta!(Endanalysis);
 opt_meta!(Beginstext);
 opt_meta!(Endstext);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_gating(s: &str) -> Result<String, String> {
+        s.parse::<Gating>()
+            .map(|g| g.to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_gating() {
+        assert_eq!(parse_gating("R1"), Ok("R1".into()));
+        assert_eq!(parse_gating("R1.AND.R2"), Ok("(R1 AND R2)".into()));
+        assert_eq!(
+            parse_gating("R1 OR (R2 AND (NOT R3))"),
+            Ok("(R1 OR (R2 AND (NOT R3)))".into())
+        );
+        assert_eq!(
+            parse_gating("NOT R1 AND R2 OR R3"),
+            Ok("(((NOT R1) AND R2) OR R3)".into())
+        );
+        // output should be parsable as input
+        let s = parse_gating("(R1 OR R2) AND NOT (R3)").unwrap();
+        assert_eq!(parse_gating(&s), Ok(s));
+    }
+
+    #[test]
+    fn test_gating_invalid() {
+        let err = |s: &str| parse_gating(s).is_err();
+        assert!(err(""));
+        assert!(err("R1 AND"));
+        assert!(err("(R1 OR R2"));
+        assert!(err("R1 OR R2)"));
+        assert!(err("R1 R2"));
+        assert!(err("AND R1"));
+    }
+}
@@ -2,13 +2,17 @@ pub mod byteord;
 pub mod compensation;
 pub mod datetimes;
 pub mod float_or_int;
+pub mod hardware;
 pub mod index;
 pub mod keywords;
 pub mod named_vec;
 pub mod optionalkw;
 pub mod parser;
 pub mod ranged_float;
+pub mod registry;
 pub mod scale;
 pub mod spillover;
 pub mod timestamps;
+pub mod typed;
 pub mod unstainedcenters;
+pub mod vendor;
@@ -785,6 +785,57 @@ impl<K: MightHave, U, V> WrappedNamedVec<K, U, V> {
         }
     }
 
+    /// Reorder all elements including the center.
+    ///
+    /// 'order' gives the current index of the element to put in each
+    /// position, and must include every index exactly once. Elements without
+    /// a name will get a default name from their new index, so return error if
+    /// this would conflict with an existing name.
+    pub fn reorder(&mut self, order: &[MeasIndex]) -> Result<(), ReorderError> {
+        let n = self.len();
+        let is: Vec<usize> = order.iter().map(|i| (*i).into()).collect();
+        if is.len() != n || is.iter().any(|i| *i >= n) || !all_unique(is.iter()) {
+            return Err(ReorderError::Permutation);
+        }
+        {
+            let mut keys: Vec<_> = self
+                .iter()
+                .map(|(_, x)| Some(x.both(|l| K::wrap(&l.key), |r| K::as_ref(&r.key))))
+                .collect();
+            let new_keys = is.iter().map(|i| keys[*i].take().unwrap()).collect();
+            if !self.as_prefix().all_unique::<K>(new_keys) {
+                return Err(ReorderError::NonUnique);
+            }
+        }
+        let (xs, prefix): (Vec<Either<K, U, V>>, _) = match mem::replace(self, dummy()) {
+            NamedVec::Split(s, _) => (
+                s.left
+                    .into_iter()
+                    .map(|p| Element::NonCenter((p.key, p.value)))
+                    .chain([Element::Center((s.center.key, s.center.value))])
+                    .chain(
+                        s.right
+                            .into_iter()
+                            .map(|p| Element::NonCenter((p.key, p.value))),
+                    )
+                    .collect(),
+                s.prefix,
+            ),
+            NamedVec::Unsplit(u) => (
+                u.members
+                    .into_iter()
+                    .map(|p| Element::NonCenter((p.key, p.value)))
+                    .collect(),
+                u.prefix,
+            ),
+        };
+        let mut slots: Vec<_> = xs.into_iter().map(Some).collect();
+        // ASSUME these won't fail since we checked the permutation and names
+        let ys = is.iter().map(|i| slots[*i].take().unwrap()).collect();
+        *self = Self::try_new(ys, prefix).ok().unwrap();
+        Ok(())
+    }
+
     /// Rename center element.
     ///
     /// Return previous name if center exists.
@@ -1833,6 +1884,11 @@ pub enum SetKeysError {
     NonUnique,
 }
 
+pub enum ReorderError {
+    Permutation,
+    NonUnique,
+}
+
 #[derive(Debug)]
 pub enum SetCenterError {
     NoName,
@@ -1992,6 +2048,19 @@ impl fmt::Display for KeyLengthError {
     }
 }
 
+impl fmt::Display for ReorderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            ReorderError::Permutation => {
+                write!(f, "new order must include each measurement exactly once")
+            }
+            ReorderError::NonUnique => {
+                write!(f, "new order would give default names which are not unique")
+            }
+        }
+    }
+}
+
 impl fmt::Display for SetKeysError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
@@ -63,11 +63,11 @@ impl<V> OptionalKw<V> {
         match mem::replace(self, None.into()).0 {
             None => None,
             Some(mut x) => match f(&mut x) {
-                Ok(y) => Some(y),
-                Err(_) => {
-                    *self = None.into();
-                    None
+                Ok(y) => {
+                    *self = Some(x).into();
+                    Some(y)
                 }
+                Err(_) => None,
             },
         }
     }
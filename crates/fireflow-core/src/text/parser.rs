@@ -155,9 +155,10 @@ where
     where
         ParseOptKeyWarning: From<<Self as FromStr>::Err>,
     {
-        let mut x = process_opt(Self::remove_metaroot_opt(kws));
-        // TODO toggle
-        if dep {
+        let res = Self::remove_metaroot_opt(kws);
+        let present = is_present(&res);
+        let mut x = process_opt(res);
+        if dep && present {
             x.push_warning(DeprecatedError::Key(DepKeyWarning(Self::std())).into());
         }
         x
@@ -199,8 +200,10 @@ where
     where
         ParseOptKeyWarning: From<<Self as FromStr>::Err>,
     {
-        let mut x = process_opt(Self::remove_meas_opt(kws, i));
-        if dep {
+        let res = Self::remove_meas_opt(kws, i);
+        let present = is_present(&res);
+        let mut x = process_opt(res);
+        if dep && present {
             x.push_warning(DeprecatedError::Key(DepKeyWarning(Self::std(i))).into());
         }
         x
@@ -288,8 +291,9 @@ where
                 error,
                 key: k,
                 value: v.clone(),
+                offset: None,
             })
-            .map_err(ReqKeyError::Parse),
+            .map_err(ReqKeyError::from),
         None => Err(ReqKeyError::Missing(k)),
     }
 }
@@ -305,6 +309,7 @@ where
                 error,
                 key: k,
                 value: v.clone(),
+                offset: None,
             })
         })
         .transpose()
@@ -322,8 +327,9 @@ where
                 error,
                 key: k,
                 value: v,
+                offset: None,
             })
-            .map_err(ReqKeyError::Parse),
+            .map_err(ReqKeyError::from),
         None => Err(ReqKeyError::Missing(k)),
     }
 }
@@ -339,6 +345,7 @@ where
                 error,
                 key: k,
                 value: v,
+                offset: None,
             })
         })
         .transpose()
@@ -385,13 +392,19 @@ where
     V: FromStr,
     ParseOptKeyWarning: From<<V as FromStr>::Err>,
 {
+    let present = is_present(&res);
     let mut x = process_opt(res);
-    if dep {
+    if dep && present {
         x.push_warning(DeprecatedError::Key(DepKeyWarning(k)).into());
     }
     x
 }
 
+/// Return true if an optional key was found, whether or not it parsed.
+fn is_present<V, E>(res: &Result<OptionalKw<V>, E>) -> bool {
+    !matches!(res, Ok(OptionalKw(None)))
+}
+
 pub(crate) fn process_opt<V, E>(
     res: Result<OptionalKw<V>, ParseKeyError<<V as FromStr>::Err>>,
 ) -> Tentative<OptionalKw<V>, LookupKeysWarning, E>
@@ -424,8 +437,6 @@ pub(crate) type LookupTentative<V, E> = Tentative<V, LookupKeysWarning, E>;
 enum_from_disp!(
     pub LookupKeysError,
     [Parse, Box<ReqKeyError<ParseReqKeyError>>],
-    // TODO this currently does nothing, need to add a flag to toggle these to
-    // errors
     [Dep, DeprecatedError],
     [Misc, LookupMiscError],
     [Pseudostandard, PseudostandardError]
@@ -436,7 +447,10 @@ enum_from_disp!(
     [Parse, ParseKeyError<ParseOptKeyWarning>],
     [Relation, LookupRelationalWarning],
     [Linked, LinkedNameError],
-    [Dep, DeprecatedError]
+    [Dep, DeprecatedError],
+    [Offsetless, OffsetlessDatetimeWarning],
+    [ScaleOffset, ScaleOffsetWarning],
+    [ForcedLinear, ForcedLinearScaleWarning]
 );
 
 enum_from_disp!(
@@ -472,6 +486,7 @@ enum_from_disp!(
     [RangedFloat,        RangedFloatError],
     [Feature,            FeatureError],
     [Wavelengths,        WavelengthsError],
+    [Power,              PowerError],
     [Calibration3_1,     CalibrationError<CalibrationFormat3_1>],
     [Calibration3_2,     CalibrationError<CalibrationFormat3_2>],
     [Int,                ParseIntError],
@@ -527,7 +542,8 @@ enum_from_disp!(
     [CompShape, NewCompError],
     [GateRegion, MismatchedIndexAndWindowError],
     [GateRegionLink, GateRegionLinkError],
-    [GateMeasLink, GateMeasurementLinkError]
+    [GateMeasLink, GateMeasurementLinkError],
+    [OpticalTypeScale, OpticalTypeScaleWarning]
 );
 
 /// Error/warning triggered when encountering a key which is deprecated
@@ -587,6 +603,30 @@ impl fmt::Display for TemporalError {
 /// Error denoting that pseudostandard keyword was found.
 pub struct PseudostandardError(pub StdKey);
 
+/// Warning denoting that $PAR was replaced by the number of $PnB found.
+pub struct InferredParWarning {
+    /// The original value of $PAR, if any
+    pub original: Option<String>,
+    /// Number of contiguous $PnB keywords starting at 1
+    pub inferred: usize,
+}
+
+impl fmt::Display for InferredParWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let n = self.inferred;
+        match &self.original {
+            Some(p) => write!(
+                f,
+                "$PAR is '{p}' but found $PnB for {n} measurements, using {n}"
+            ),
+            None => write!(
+                f,
+                "$PAR is missing but found $PnB for {n} measurements, using {n}"
+            ),
+        }
+    }
+}
+
 impl fmt::Display for PseudostandardError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "pseudostandard keyword found: {}", self.0)
@@ -597,6 +637,9 @@ pub struct ParseKeyError<E> {
     pub error: E,
     pub key: StdKey,
     pub value: String,
+
+    /// Position of the key in the file, if known
+    pub offset: Option<u64>,
 }
 
 impl<E> ParseKeyError<E> {
@@ -608,6 +651,7 @@ impl<E> ParseKeyError<E> {
             error: self.error.into(),
             key: self.key,
             value: self.value,
+            offset: self.offset,
         }
     }
 
@@ -619,6 +663,7 @@ impl<E> ParseKeyError<E> {
             error,
             key: self.key,
             value: self.value,
+            offset: self.offset,
         })
     }
 }
@@ -628,6 +673,9 @@ where
     E: fmt::Display,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if let Some(o) = self.offset {
+            write!(f, "byte {o}: ")?;
+        }
         write!(
             f,
             "{} (key='{}', value='{}')",
@@ -637,17 +685,23 @@ where
 }
 
 pub enum ReqKeyError<E> {
-    Parse(ParseKeyError<E>),
+    Parse(Box<ParseKeyError<E>>),
     Missing(StdKey),
 }
 
+impl<E> From<ParseKeyError<E>> for ReqKeyError<E> {
+    fn from(value: ParseKeyError<E>) -> Self {
+        Self::Parse(Box::new(value))
+    }
+}
+
 impl<E> ReqKeyError<E> {
     pub fn inner_into<F>(self) -> ReqKeyError<F>
     where
         F: From<E>,
     {
         match self {
-            ReqKeyError::Parse(e) => ReqKeyError::Parse(e.inner_into()),
+            ReqKeyError::Parse(e) => e.inner_into().into(),
             ReqKeyError::Missing(e) => ReqKeyError::Missing(e),
         }
     }
@@ -657,7 +711,7 @@ impl<E> ReqKeyError<E> {
         F: FnOnce(E) -> Result<X, E>,
     {
         match self {
-            ReqKeyError::Parse(p) => p.with_error(f).map_err(ReqKeyError::Parse),
+            ReqKeyError::Parse(p) => p.with_error(f).map_err(ReqKeyError::from),
             ReqKeyError::Missing(m) => Err(ReqKeyError::Missing(m)),
         }
     }
@@ -674,3 +728,50 @@ where
         }
     }
 }
+
+/// An error or warning which may refer to a keyword in TEXT.
+///
+/// Implementing this allows the position of the offending keyword to be
+/// attached after it has been created, since keywords are looked up without
+/// knowledge of where they came from.
+pub trait Locate {
+    fn locate(&mut self, offsets: &KeywordOffsets);
+}
+
+impl<E> Locate for ParseKeyError<E> {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        self.offset = offsets.get(&self.key.to_string()).copied();
+    }
+}
+
+impl<E> Locate for ReqKeyError<E> {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        if let Self::Parse(x) = self {
+            x.locate(offsets);
+        }
+    }
+}
+
+impl Locate for LookupKeysError {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        if let Self::Parse(x) = self {
+            x.locate(offsets);
+        }
+    }
+}
+
+impl Locate for LookupKeysWarning {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        if let Self::Parse(x) = self {
+            x.locate(offsets);
+        }
+    }
+}
+
+impl<E: Locate> Locate for ImpureError<E> {
+    fn locate(&mut self, offsets: &KeywordOffsets) {
+        if let Self::Pure(x) = self {
+            x.locate(offsets);
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use crate::header::Version;
+use crate::text::byteord::Width;
+use crate::text::compensation::Compensation3_0;
+use crate::text::datetimes::{BeginDateTime, EndDateTime};
+use crate::text::keywords::*;
+use crate::text::spillover::Spillover;
+use crate::text::timestamps::FCSDate;
+use crate::text::unstainedcenters::UnstainedCenters;
+use crate::validated::shortname::Shortname;
+use crate::validated::standard::{StdKey, StdKeywords};
+
+use serde::Serialize;
+use std::fmt;
+use std::mem;
+use std::str::FromStr;
+
+/// Whether a keyword may appear in a given FCS version
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum KeyStatus {
+    /// Not part of this version
+    Absent,
+    /// May be omitted
+    Optional,
+    /// Must be present (possibly only under some conditions)
+    Required,
+    /// Allowed but marked for removal in a future version
+    Deprecated,
+}
+
+/// A keyword whose value has its own type.
+///
+/// Values are parsed with [`FromStr`] and written with [`fmt::Display`]. Any
+/// type implementing this may be added to a [`KeywordRegistry`] with
+/// [`KeywordRegistry::register_keyword`], which is how keywords for future
+/// versions or vendors should be declared.
+pub trait Keyword: FromStr + fmt::Display {
+    /// Name without leading '$'.
+    ///
+    /// Lowercase 'n' and 'm' stand for an index starting at 1, so 'PnB'
+    /// describes '$P1B', '$P2B', etc.
+    const NAME: &'static str;
+
+    /// Status for 2.0, 3.0, 3.1, and 3.2 respectively
+    const STATUS: [KeyStatus; 4];
+}
+
+/// Parse a value and write it back, or return a reason it could not be parsed
+type Normalize = fn(&str) -> Result<String, String>;
+
+/// Description of one standard or vendor keyword.
+///
+/// The name does not include the leading '$' for standard keywords. Lowercase
+/// 'n' and 'm' stand for an index starting at 1, so 'PnB' describes '$P1B',
+/// '$P2B', etc.
+#[derive(Clone, Serialize)]
+pub struct KeywordSpec {
+    /// Name of the keyword, with lowercase placeholders for indices
+    pub name: String,
+
+    /// Status for 2.0, 3.0, 3.1, and 3.2 respectively
+    pub status: [KeyStatus; 4],
+
+    /// Parser and writer for values if this keyword has a type
+    #[serde(skip)]
+    normalize: Option<Normalize>,
+}
+
+/// A collection of keyword descriptions which may be extended.
+///
+/// The default registry contains every standard keyword known to this library.
+/// New versions or vendor keywords may be added with [`KeywordRegistry::register`]
+/// without changing the lookup code for existing versions.
+#[derive(Clone, Serialize)]
+pub struct KeywordRegistry(Vec<KeywordSpec>);
+
+/// Error when a keyword name has characters that cannot be matched
+#[derive(Debug)]
+pub struct KeywordSpecError(String);
+
+/// Error when a keyword in a registry has a value which cannot be parsed
+pub struct RegisteredKeywordError {
+    pub key: StdKey,
+    pub reason: String,
+}
+
+use KeyStatus::{Absent as A, Deprecated as D, Optional as O, Required as R};
+
+macro_rules! standard_keywords {
+    ($($t:ty, $name:expr, $status:expr;)*) => {
+        $(
+            impl Keyword for $t {
+                const NAME: &'static str = $name;
+                const STATUS: [KeyStatus; 4] = $status;
+            }
+        )*
+
+        fn typed_standard_keywords() -> Vec<KeywordSpec> {
+            vec![$(KeywordSpec::from_keyword::<$t>(),)*]
+        }
+    };
+}
+
+// keywords which have the same type in all versions
+standard_keywords!(
+    // metaroot
+    Abrt, "ABRT", [O, O, O, O];
+    Beginanalysis, "BEGINANALYSIS", [A, R, R, O];
+    Begindata, "BEGINDATA", [A, R, R, R];
+    BeginDateTime, "BEGINDATETIME", [A, A, A, O];
+    Beginstext, "BEGINSTEXT", [A, R, R, O];
+    Carrierid, "CARRIERID", [A, A, A, O];
+    Carriertype, "CARRIERTYPE", [A, A, A, O];
+    Cells, "CELLS", [O, O, O, O];
+    Com, "COM", [O, O, O, O];
+    Compensation3_0, "COMP", [A, O, A, A];
+    CSMode, "CSMODE", [A, O, O, A];
+    CSVBits, "CSVBITS", [A, O, O, A];
+    Cyt, "CYT", [O, O, O, R];
+    Cytsn, "CYTSN", [A, O, O, O];
+    AlphaNumType, "DATATYPE", [R, R, R, R];
+    FCSDate, "DATE", [O, O, O, D];
+    Endanalysis, "ENDANALYSIS", [A, R, R, O];
+    Enddata, "ENDDATA", [A, R, R, R];
+    EndDateTime, "ENDDATETIME", [A, A, A, O];
+    Endstext, "ENDSTEXT", [A, R, R, O];
+    Exp, "EXP", [O, O, O, O];
+    Fil, "FIL", [O, O, O, O];
+    Flowrate, "FLOWRATE", [A, A, A, O];
+    Gate, "GATE", [O, O, O, D];
+    Gating, "GATING", [O, O, O, D];
+    Inst, "INST", [O, O, O, O];
+    ModifiedDateTime, "LAST_MODIFIED", [A, A, O, O];
+    LastModifier, "LAST_MODIFIER", [A, A, O, O];
+    Locationid, "LOCATIONID", [A, A, A, O];
+    Lost, "LOST", [O, O, O, O];
+    Nextdata, "NEXTDATA", [R, R, R, R];
+    Op, "OP", [O, O, O, O];
+    Originality, "ORIGINALITY", [A, A, O, O];
+    Par, "PAR", [R, R, R, R];
+    Plateid, "PLATEID", [A, A, O, O];
+    Platename, "PLATENAME", [A, A, O, O];
+    Proj, "PROJ", [O, O, O, O];
+    Smno, "SMNO", [O, O, O, O];
+    Spillover, "SPILLOVER", [A, A, O, O];
+    Src, "SRC", [O, O, O, O];
+    Sys, "SYS", [O, O, O, O];
+    Timestep, "TIMESTEP", [A, R, R, R];
+    Tot, "TOT", [O, R, R, R];
+    Trigger, "TR", [O, O, O, O];
+    Unicode, "UNICODE", [A, O, A, A];
+    UnstainedCenters, "UNSTAINEDCENTERS", [A, A, A, O];
+    UnstainedInfo, "UNSTAINEDINFO", [A, A, A, O];
+    Vol, "VOL", [A, A, O, O];
+    Wellid, "WELLID", [A, A, O, O];
+    // measurements
+    Analyte, "PnANALYTE", [A, A, A, O];
+    Width, "PnB", [R, R, R, R];
+    Display, "PnD", [A, A, O, O];
+    NumType, "PnDATATYPE", [A, A, A, O];
+    DetectorName, "PnDET", [A, A, A, O];
+    Filter, "PnF", [O, O, O, O];
+    Feature, "PnFEATURE", [A, A, A, O];
+    Gain, "PnG", [A, O, O, O];
+    Shortname, "PnN", [O, O, R, R];
+    Power, "PnO", [O, O, O, O];
+    PercentEmitted, "PnP", [O, O, O, O];
+    Range, "PnR", [R, R, R, R];
+    Longname, "PnS", [O, O, O, O];
+    DetectorType, "PnT", [O, O, O, O];
+    Tag, "PnTAG", [A, A, A, O];
+    DetectorVoltage, "PnV", [O, O, O, O];
+    // gates
+    GateScale, "GnE", [O, O, O, D];
+    GateFilter, "GnF", [O, O, O, D];
+    GateShortname, "GnN", [O, O, O, D];
+    GatePercentEmitted, "GnP", [O, O, O, D];
+    GateRange, "GnR", [O, O, O, D];
+    GateLongname, "GnS", [O, O, O, D];
+    GateDetectorType, "GnT", [O, O, O, D];
+    GateDetectorVoltage, "GnV", [O, O, O, D];
+);
+
+// keywords whose type depends on version or which are parsed together with
+// other keywords
+const UNTYPED_STANDARD_KEYWORDS: &[(&str, [KeyStatus; 4])] = &[
+    // metaroot
+    ("BTIM", [O, O, O, D]),
+    ("BYTEORD", [R, R, R, R]),
+    ("CSVnFLAG", [A, O, O, A]),
+    ("DFCmTOn", [O, O, A, A]),
+    ("ETIM", [O, O, O, D]),
+    ("MODE", [R, R, R, D]),
+    ("PKn", [O, O, O, A]),
+    ("PKNn", [O, O, O, A]),
+    // measurements
+    ("PnCALIBRATION", [A, A, O, O]),
+    ("PnE", [O, R, R, R]),
+    ("PnL", [O, O, O, O]),
+    ("PnTYPE", [A, A, A, O]),
+    // gates
+    ("RnI", [O, O, O, D]),
+    ("RnW", [O, O, O, D]),
+];
+
+fn version_index(v: Version) -> usize {
+    match v {
+        Version::FCS2_0 => 0,
+        Version::FCS3_0 => 1,
+        Version::FCS3_1 => 2,
+        Version::FCS3_2 => 3,
+    }
+}
+
+fn normalize_value<K>(s: &str) -> Result<String, String>
+where
+    K: Keyword,
+    <K as FromStr>::Err: fmt::Display,
+{
+    s.parse::<K>()
+        .map(|x| x.to_string())
+        .map_err(|e| e.to_string())
+}
+
+impl KeywordSpec {
+    /// Make a new keyword description without a type.
+    ///
+    /// Name must only have uppercase ASCII letters, digits, or '_' aside from
+    /// the 'n' and 'm' placeholders.
+    pub fn new(name: &str, status: [KeyStatus; 4]) -> Result<Self, KeywordSpecError> {
+        if !name.is_empty()
+            && name.chars().all(|c| {
+                c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_' || c == 'n' || c == 'm'
+            })
+        {
+            Ok(Self {
+                name: name.to_string(),
+                status,
+                normalize: None,
+            })
+        } else {
+            Err(KeywordSpecError(name.to_string()))
+        }
+    }
+
+    /// Make a new keyword description from a type.
+    ///
+    /// Values will be checked with the type's parser when reading.
+    pub fn from_keyword<K>() -> Self
+    where
+        K: Keyword,
+        <K as FromStr>::Err: fmt::Display,
+    {
+        Self {
+            name: K::NAME.to_string(),
+            status: K::STATUS,
+            normalize: Some(normalize_value::<K>),
+        }
+    }
+
+    /// Return status of this keyword for a given version
+    pub fn status(&self, v: Version) -> KeyStatus {
+        self.status[version_index(v)]
+    }
+
+    /// Return true if this keyword is indexed (ie has 'n' or 'm')
+    pub fn is_indexed(&self) -> bool {
+        self.name.contains(['n', 'm'])
+    }
+
+    /// Return true if key (without leading '$') matches this description.
+    ///
+    /// Placeholders must match integers greater than zero.
+    pub fn matches(&self, key: &str) -> bool {
+        match_template(self.name.as_bytes(), key.as_bytes())
+    }
+
+    /// Parse a value and write it back as it would appear in TEXT.
+    ///
+    /// Return None if this keyword has no type.
+    pub fn normalize(&self, value: &str) -> Option<Result<String, String>> {
+        self.normalize.map(|f| f(value))
+    }
+}
+
+/// Return true if key matches template.
+///
+/// Placeholders must match integers greater than zero.
+fn match_template(template: &[u8], key: &[u8]) -> bool {
+    match template.split_first() {
+        None => key.is_empty(),
+        Some((b'n' | b'm', rest)) => {
+            let ndigits = key.iter().take_while(|c| c.is_ascii_digit()).count();
+            // try each possible number of digits since the rest of the
+            // template may start with a digit
+            key.first().is_some_and(|c| *c != b'0')
+                && (1..=ndigits).any(|i| match_template(rest, &key[i..]))
+        }
+        Some((c, rest)) => key
+            .split_first()
+            .is_some_and(|(k, krest)| k == c && match_template(rest, krest)),
+    }
+}
+
+impl KeywordRegistry {
+    /// Return a registry with all known standard keywords
+    pub fn standard() -> Self {
+        let untyped = UNTYPED_STANDARD_KEYWORDS
+            .iter()
+            .map(|(name, status)| KeywordSpec {
+                name: name.to_string(),
+                status: *status,
+                normalize: None,
+            });
+        Self(
+            typed_standard_keywords()
+                .into_iter()
+                .chain(untyped)
+                .collect(),
+        )
+    }
+
+    /// Add a keyword description.
+    ///
+    /// If a description with the same name already exists, replace it and
+    /// return the old description.
+    pub fn register(&mut self, spec: KeywordSpec) -> Option<KeywordSpec> {
+        if let Some(x) = self.0.iter_mut().find(|x| x.name == spec.name) {
+            Some(mem::replace(x, spec))
+        } else {
+            self.0.push(spec);
+            None
+        }
+    }
+
+    /// Add a keyword description from a type.
+    ///
+    /// See [`KeywordRegistry::register`].
+    pub fn register_keyword<K>(&mut self) -> Option<KeywordSpec>
+    where
+        K: Keyword,
+        <K as FromStr>::Err: fmt::Display,
+    {
+        self.register(KeywordSpec::from_keyword::<K>())
+    }
+
+    /// Return the description matching a key (without leading '$').
+    ///
+    /// Non-indexed descriptions take precedence, followed by the order in
+    /// which descriptions were added.
+    pub fn find(&self, key: &str) -> Option<&KeywordSpec> {
+        self.0
+            .iter()
+            .find(|x| !x.is_indexed() && x.name == key)
+            .or_else(|| self.0.iter().find(|x| x.is_indexed() && x.matches(key)))
+    }
+
+    /// Return status of a key (without leading '$') for a given version.
+    ///
+    /// Unknown keys are [`KeyStatus::Absent`].
+    pub fn status(&self, key: &str, v: Version) -> KeyStatus {
+        self.find(key).map_or(KeyStatus::Absent, |x| x.status(v))
+    }
+
+    /// Return all descriptions with a given status for a version
+    pub fn with_status(&self, v: Version, s: KeyStatus) -> impl Iterator<Item = &KeywordSpec> {
+        self.0.iter().filter(move |x| x.status(v) == s)
+    }
+
+    /// Return all descriptions
+    pub fn iter(&self) -> impl Iterator<Item = &KeywordSpec> {
+        self.0.iter()
+    }
+}
+
+impl Default for KeywordRegistry {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+/// Remove and return all keywords which are in the registry.
+///
+/// This is meant to be applied to keywords left over after standardization,
+/// in which case any standard keyword returned here was never used. These are
+/// usually for a different version or have an index which does not correspond
+/// to a measurement. Keywords with a type are also checked, and those which
+/// cannot be parsed are returned as errors.
+pub(crate) fn remove_registered(
+    kws: &mut StdKeywords,
+    reg: &KeywordRegistry,
+) -> (StdKeywords, Vec<RegisteredKeywordError>) {
+    let (registered, rest): (StdKeywords, _) = mem::take(kws)
+        .into_iter()
+        .partition(|(k, _)| reg.find(k.as_ref()).is_some());
+    *kws = rest;
+    let errors = registered
+        .iter()
+        .flat_map(|(k, v)| {
+            reg.find(k.as_ref())
+                .and_then(|s| s.normalize(v))
+                .and_then(Result::err)
+                .map(|reason| RegisteredKeywordError {
+                    key: k.clone(),
+                    reason,
+                })
+        })
+        .collect();
+    (registered, errors)
+}
+
+impl fmt::Display for KeyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let s = match self {
+            Self::Absent => "absent",
+            Self::Optional => "optional",
+            Self::Required => "required",
+            Self::Deprecated => "deprecated",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl fmt::Display for KeywordSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "keyword name must only have uppercase letters, digits, '_', \
+             or 'n'/'m' as index placeholders, found '{}'",
+            self.0
+        )
+    }
+}
+
+impl fmt::Display for RegisteredKeywordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "could not parse {}: {}", self.key, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_template() {
+        let m = |t: &str, k: &str| match_template(t.as_bytes(), k.as_bytes());
+        assert!(m("PnB", "P1B"));
+        assert!(m("PnB", "P10B"));
+        assert!(!m("PnB", "P0B"));
+        assert!(!m("PnB", "PB"));
+        assert!(!m("PnB", "P1BB"));
+        assert!(m("DFCmTOn", "DFC1TO12"));
+        assert!(!m("DFCmTOn", "DFC1TO"));
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut reg = KeywordRegistry::standard();
+        assert_eq!(reg.status("PAR", Version::FCS2_0), KeyStatus::Required);
+        assert_eq!(reg.status("P3N", Version::FCS3_0), KeyStatus::Optional);
+        assert_eq!(reg.status("P3N", Version::FCS3_1), KeyStatus::Required);
+        assert_eq!(reg.status("P3DET", Version::FCS3_1), KeyStatus::Absent);
+        // 'PKNn' should not be confused with 'PKn'
+        assert!(reg.find("PKN1").is_some_and(|x| x.name == "PKNn"));
+        assert_eq!(reg.status("FOO", Version::FCS3_2), KeyStatus::Absent);
+        let spec = KeywordSpec::new("FOO", [A, A, A, O]).unwrap();
+        assert!(reg.register(spec).is_none());
+        assert_eq!(reg.status("FOO", Version::FCS3_2), KeyStatus::Optional);
+        assert!(KeywordSpec::new("foo", [A, A, A, O]).is_err());
+    }
+
+    struct Laser(u32);
+
+    impl FromStr for Laser {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Self)
+        }
+    }
+
+    impl fmt::Display for Laser {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl Keyword for Laser {
+        const NAME: &'static str = "LASERnPOWER";
+        const STATUS: [KeyStatus; 4] = [A, A, A, O];
+    }
+
+    #[test]
+    fn test_register_keyword() {
+        let mut reg = KeywordRegistry::standard();
+        reg.register_keyword::<Laser>();
+        let spec = reg.find("LASER2POWER").unwrap();
+        assert_eq!(spec.status(Version::FCS3_2), KeyStatus::Optional);
+        assert_eq!(spec.normalize("020"), Some(Ok("20".to_string())));
+        assert!(spec.normalize("x").is_some_and(|r| r.is_err()));
+        let par = reg.find("PAR").unwrap();
+        assert_eq!(par.normalize("3"), Some(Ok("3".to_string())));
+        assert_eq!(reg.find("P1E").unwrap().normalize("0,0"), None);
+    }
+
+    #[test]
+    fn test_remove_registered() {
+        let mut reg = KeywordRegistry::standard();
+        reg.register_keyword::<Laser>();
+        let mut kws: StdKeywords = [
+            ("$P5N", "FL5"),
+            ("$PLATEID", "7"),
+            ("$LASER1POWER", "high"),
+            ("$FOO", "bar"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.parse().unwrap(), v.to_string()))
+        .collect();
+        let (registered, errors) = remove_registered(&mut kws, &reg);
+        assert_eq!(registered.len(), 3);
+        assert_eq!(kws.len(), 1);
+        assert!(kws.contains_key("FOO"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].key.as_ref(), "LASER1POWER");
+    }
+}
@@ -7,6 +7,7 @@ use crate::validated::standard::*;
 
 use serde::Serialize;
 use std::fmt;
+use std::mem;
 use std::num::ParseFloatError;
 use std::str::FromStr;
 
@@ -43,6 +44,28 @@ impl Scale {
             })
             .map_err(|_| LogRangeError { decades, offset })
     }
+
+    /// Convert a raw channel value to a scaled value.
+    ///
+    /// For log scales, this is 10^(decades * x / range) * offset where range
+    /// is the value of $PnR. Linear values are returned unchanged.
+    pub fn apply(&self, x: f64, range: f64) -> f64 {
+        match self {
+            Scale::Linear => x,
+            Scale::Log(l) => {
+                let d = f64::from(f32::from(l.decades));
+                let o = f64::from(f32::from(l.offset));
+                10_f64.powf(d * x / range) * o
+            }
+        }
+    }
+}
+
+impl LogScale {
+    /// Return the number of decades (ie 'f1')
+    pub fn decades(&self) -> f32 {
+        self.decades.into()
+    }
 }
 
 impl FromStr for Scale {
@@ -72,13 +95,17 @@ impl Scale {
         try_fix: bool,
     ) -> LookupResult<Scale> {
         let res = Scale::remove_meas_req(kws, i.into());
+        let mut ws: Vec<LookupKeysWarning> = vec![];
         if try_fix {
             res.map_or_else(
                 |e| {
                     e.with_parse_error(|se| {
                         if let ScaleError::LogRange(le) = se {
                             le.try_fix_offset()
-                                .map(Scale::Log)
+                                .map(|x| {
+                                    ws.push(ScaleOffsetWarning(Scale::std(i.into())).into());
+                                    Scale::Log(x)
+                                })
                                 .map_err(ScaleError::LogRange)
                         } else {
                             Err(se)
@@ -93,6 +120,10 @@ impl Scale {
         .map_err(|e| e.inner_into())
         .map_err(Box::new)
         .into_deferred()
+        .map(|mut tnt| {
+            tnt.extend_warnings(ws);
+            tnt
+        })
     }
 
     pub(crate) fn lookup_fixed_opt<E>(
@@ -102,13 +133,17 @@ impl Scale {
         try_fix: bool,
     ) -> LookupTentative<OptionalKw<Scale>, E> {
         let res = Scale::remove_meas_opt(kws, i.into());
+        let mut ws: Vec<LookupKeysWarning> = vec![];
         let fix_res = if try_fix {
             res.map_or_else(
                 |e| {
                     e.with_error(|se| {
                         if let ScaleError::LogRange(le) = se {
                             le.try_fix_offset()
-                                .map(|x| Some(Scale::Log(x)).into())
+                                .map(|x| {
+                                    ws.push(ScaleOffsetWarning(Scale::std(i.into())).into());
+                                    Some(Scale::Log(x)).into()
+                                })
                                 .map_err(ScaleError::LogRange)
                         } else {
                             Err(se)
@@ -120,7 +155,23 @@ impl Scale {
         } else {
             res
         };
-        process_opt_dep(fix_res, Scale::std(i.into()), dep)
+        let mut tnt = process_opt_dep(fix_res, Scale::std(i.into()), dep);
+        tnt.extend_warnings(ws);
+        tnt
+    }
+
+    /// Replace $PnE with '0,0' if it is present and not already linear.
+    pub(crate) fn force_linear(
+        kws: &mut StdKeywords,
+        i: MeasIndex,
+    ) -> Option<ForcedLinearScaleWarning> {
+        let key = Scale::std(i.into());
+        let value = kws.get_mut(&key)?;
+        if value.parse::<Scale>().is_ok_and(|x| x == Scale::Linear) {
+            return None;
+        }
+        let old = mem::replace(value, Scale::Linear.to_string());
+        Some(ForcedLinearScaleWarning { key, old })
     }
 }
 
@@ -173,6 +224,35 @@ impl LogRangeError {
     }
 }
 
+/// Warning when a log scale with an offset of 0 was given an offset of 1
+pub struct ScaleOffsetWarning(pub StdKey);
+
+impl fmt::Display for ScaleOffsetWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} has log offset of 0, which was replaced with 1",
+            self.0
+        )
+    }
+}
+
+/// Warning when a scale was replaced with linear scale
+pub struct ForcedLinearScaleWarning {
+    key: StdKey,
+    old: String,
+}
+
+impl fmt::Display for ForcedLinearScaleWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} was '{}' but was forced to be linear",
+            self.key, self.old
+        )
+    }
+}
+
 impl fmt::Display for LogRangeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
@@ -21,6 +21,28 @@ pub struct Spillover {
 
     /// Numeric values in the spillover matrix in row-major order.
     matrix: DMatrix<f32>,
+
+    /// Number of decimal places to use when writing values.
+    ///
+    /// If None, use as many as needed to represent each value exactly.
+    #[serde(skip)]
+    precision: Option<usize>,
+}
+
+/// A spillover matrix in a form convenient for numeric libraries.
+#[derive(Clone, Serialize)]
+pub struct SpilloverMatrix {
+    /// Measurement names corresponding to each row and column.
+    pub names: Vec<String>,
+
+    /// Values in row-major order.
+    pub values: Vec<f64>,
+
+    /// Number of rows.
+    pub nrows: usize,
+
+    /// Number of columns.
+    pub ncols: usize,
 }
 
 impl Spillover {
@@ -43,28 +65,114 @@ impl Spillover {
             Ok(Self {
                 measurements,
                 matrix,
+                precision: None,
             })
         }
     }
 
+    /// Make a new spillover matrix from values in row-major order.
+    ///
+    /// Number of values must be the square of the number of measurements.
+    pub fn try_from_row_major(
+        measurements: Vec<Shortname>,
+        values: Vec<f32>,
+    ) -> Result<Self, SpilloverError> {
+        let n = measurements.len();
+        if values.len() != n * n {
+            Err(SpilloverError::NonSquare)
+        } else {
+            Self::try_new(measurements, DMatrix::from_row_iterator(n, n, values))
+        }
+    }
+
     pub fn measurements(&self) -> Vec<&Shortname> {
         self.measurements.iter().collect()
     }
 
+    /// Return names and values as plain vectors.
+    pub fn to_matrix(&self) -> SpilloverMatrix {
+        SpilloverMatrix {
+            names: self.measurements.iter().map(|m| m.to_string()).collect(),
+            // transpose since the underlying storage is column-major
+            values: self
+                .matrix
+                .transpose()
+                .iter()
+                .copied()
+                .map(f64::from)
+                .collect(),
+            nrows: self.matrix.nrows(),
+            ncols: self.matrix.ncols(),
+        }
+    }
+
     pub fn matrix(&self) -> &DMatrix<f32> {
         &self.matrix
     }
 
-    pub(crate) fn remove_by_name(&mut self, n: &Shortname) -> Result<bool, ClearOptional> {
-        if let Some(i) = self.measurements.iter().position(|m| m == n) {
+    /// Return the number of decimal places used when writing values.
+    pub fn precision(&self) -> Option<usize> {
+        self.precision
+    }
+
+    /// Set the number of decimal places used when writing values.
+    ///
+    /// If None, write each value with as many digits as needed to represent
+    /// it exactly.
+    pub fn set_precision(&mut self, precision: Option<usize>) {
+        self.precision = precision;
+    }
+
+    /// Return the spillover coefficient from one measurement into another.
+    ///
+    /// 'from' is the row and 'to' is the column.
+    pub fn get(&self, from: &Shortname, to: &Shortname) -> Option<f32> {
+        let i = self.position(from)?;
+        let j = self.position(to)?;
+        Some(self.matrix[(i, j)])
+    }
+
+    /// Set the spillover coefficient from one measurement into another.
+    ///
+    /// 'from' is the row and 'to' is the column.
+    pub fn set(&mut self, from: &Shortname, to: &Shortname, x: f32) -> Result<(), SpilloverError> {
+        let i = self.position(from).ok_or(SpilloverError::NotFound)?;
+        let j = self.position(to).ok_or(SpilloverError::NotFound)?;
+        self.matrix[(i, j)] = x;
+        Ok(())
+    }
+
+    /// Add a measurement to the end of the matrix.
+    ///
+    /// The new row and column will be zero except for 1.0 on the diagonal,
+    /// such that the new measurement has no spillover into or from others.
+    pub fn insert(&mut self, n: Shortname) -> Result<(), SpilloverError> {
+        if self.position(&n).is_some() {
+            return Err(SpilloverError::NonUnique);
+        }
+        let i = self.measurements.len();
+        let mut m = self.matrix.clone().insert_row(i, 0.0).insert_column(i, 0.0);
+        m[(i, i)] = 1.0;
+        self.matrix = m;
+        self.measurements.push(n);
+        Ok(())
+    }
+
+    /// Remove a measurement and its row and column from the matrix.
+    ///
+    /// Return true if the measurement was present. Return error if this would
+    /// make the matrix smaller than 2x2.
+    pub fn remove(&mut self, n: &Shortname) -> Result<bool, SpilloverError> {
+        if let Some(i) = self.position(n) {
             if self.measurements.len() < 3 {
-                Err(ClearOptional)
+                Err(SpilloverError::TooSmall)
             } else {
                 // TODO this looks expensive; it copies almost everything 3x;
                 // good thing these matrices aren't that big (usually). The
                 // alternative is to iterate over the matrix and populate a new
                 // one while skipping certain elements.
                 self.matrix = self.matrix.clone().remove_row(i).remove_column(i);
+                self.measurements.remove(i);
                 Ok(true)
             }
         } else {
@@ -72,6 +180,14 @@ impl Spillover {
         }
     }
 
+    fn position(&self, n: &Shortname) -> Option<usize> {
+        self.measurements.iter().position(|m| m == n)
+    }
+
+    pub(crate) fn remove_by_name(&mut self, n: &Shortname) -> Result<bool, ClearOptional> {
+        self.remove(n).map_err(|_| ClearOptional)
+    }
+
     pub(crate) fn table(&self, delim: &str) -> Vec<String> {
         let header0 = vec!["[-]"];
         let header = header0
@@ -95,8 +211,13 @@ impl fmt::Display for Spillover {
         let n = self.measurements.len();
         // DMatrix slices are column major, so transpose first to output
         // row-major
-        let xs = self.matrix.transpose().as_slice().iter().join(",");
-        write!(f, "{n},{xs}")
+        let t = self.matrix.transpose();
+        let xs = match self.precision {
+            Some(p) => t.as_slice().iter().map(|x| format!("{x:.p$}")).join(","),
+            None => t.as_slice().iter().join(","),
+        };
+        let ms = self.measurements.iter().join(",");
+        write!(f, "{n},{ms},{xs}")
     }
 }
 
@@ -142,6 +263,7 @@ pub enum SpilloverError {
     NameLen,
     NonUnique,
     TooSmall,
+    NotFound,
 }
 
 pub enum ParseSpilloverError {
@@ -171,6 +293,7 @@ impl fmt::Display for SpilloverError {
             SpilloverError::NonUnique => "Names are not unique",
             SpilloverError::NameLen => "Name length does not match matrix dimensions",
             SpilloverError::TooSmall => "Matrix is less than 2x2",
+            SpilloverError::NotFound => "Name is not in matrix",
         };
         write!(f, "{}", s)
     }
@@ -190,3 +313,39 @@ impl OptLinkedKey for Spillover {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_spillover() {
+        let s = "2,FL1-A,FL2-A,1,0.1,0.2,1";
+        let mut x = s.parse::<Spillover>().ok().unwrap();
+        assert_eq!(x.to_string(), s);
+        let a = Shortname::new_unchecked("FL1-A");
+        let b = Shortname::new_unchecked("FL2-A");
+        let c = Shortname::new_unchecked("FL3-A");
+        assert!(x.insert(c.clone()).is_ok());
+        assert!(x.insert(c.clone()).is_err());
+        assert!(x.set(&c, &a, 0.05).is_ok());
+        assert_eq!(x.get(&a, &b), Some(0.1));
+        assert_eq!(
+            x.to_string(),
+            "3,FL1-A,FL2-A,FL3-A,1,0.1,0,0.2,1,0,0.05,0,1"
+        );
+        assert_eq!(x.remove(&b).ok(), Some(true));
+        x.set_precision(Some(2));
+        assert_eq!(x.to_string(), "2,FL1-A,FL3-A,1.00,0.00,0.05,1.00");
+        assert!(x.remove(&a).is_err());
+    }
+
+    #[test]
+    fn test_spillover_to_matrix() {
+        let x = "2,FL1-A,FL2-A,1,0.5,0.25,1".parse::<Spillover>().ok().unwrap();
+        let m = x.to_matrix();
+        assert_eq!(m.names, ["FL1-A", "FL2-A"]);
+        assert_eq!(m.values, [1.0, 0.5, 0.25, 1.0]);
+        assert_eq!((m.nrows, m.ncols), (2, 2));
+    }
+}
@@ -5,7 +5,10 @@ use crate::validated::standard::*;
 use super::optionalkw::*;
 use super::parser::*;
 
-use chrono::{NaiveDate, NaiveTime, Timelike};
+use chrono::{
+    DateTime, FixedOffset, Local, MappedLocalTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta,
+    TimeZone, Timelike,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::Serialize;
@@ -27,6 +30,10 @@ pub struct Timestamps<X> {
 
     /// The value of the $DATE key
     date: Option<FCSDate>,
+
+    /// The time zone in which the above were recorded, if known
+    #[serde(skip)]
+    zone: Option<LabTimezone>,
 }
 
 impl<X> Default for Timestamps<X> {
@@ -35,10 +42,54 @@ impl<X> Default for Timestamps<X> {
             btim: None,
             etim: None,
             date: None,
+            zone: None,
+        }
+    }
+}
+
+/// The time zone of the lab in which an FCS file was acquired.
+///
+/// $BTIM, $ETIM, and $DATE do not carry any time zone information, so this
+/// must be supplied externally in order to make absolute timestamps.
+#[derive(Clone, Copy)]
+pub struct LabTimezone {
+    pub zone: LabZone,
+    pub ambiguous: AmbiguousTimePolicy,
+}
+
+impl LabTimezone {
+    /// Return a local datetime with the offset of this time zone.
+    pub fn resolve(&self, x: NaiveDateTime) -> ZonedTimeResult<DateTime<FixedOffset>> {
+        match self.zone {
+            LabZone::Local => resolve_local(&Local, x, self.ambiguous).map(|y| y.fixed_offset()),
+            LabZone::Fixed(o) => resolve_local(&o, x, self.ambiguous),
         }
     }
 }
 
+#[derive(Clone, Copy)]
+pub enum LabZone {
+    /// The time zone of the machine reading the file (including DST)
+    Local,
+    /// A fixed offset from UTC
+    Fixed(FixedOffset),
+}
+
+/// How to resolve times which occur twice due to DST transitions.
+///
+/// Times which do not exist (ie in the gap when clocks move forward) will
+/// always be an error.
+#[derive(Clone, Copy, Default)]
+pub enum AmbiguousTimePolicy {
+    /// Return an error
+    #[default]
+    Error,
+    /// Use the earlier of the two possible times
+    Earliest,
+    /// Use the later of the two possible times
+    Latest,
+}
+
 #[derive(Clone, Copy, Serialize)]
 pub struct Btim<T>(pub T);
 
@@ -99,6 +150,7 @@ where
             btim: btim.0,
             etim: etim.0,
             date: date.0,
+            zone: None,
         };
         if ret.valid() {
             Ok(ret)
@@ -142,9 +194,110 @@ where
             btim: self.btim.map(|x| Btim(f(x.0))),
             etim: self.etim.map(|x| Etim(f(x.0))),
             date: self.date,
+            zone: self.zone,
+        }
+    }
+
+    /// Return the lab time zone used to make absolute timestamps
+    pub fn timezone(&self) -> Option<LabTimezone> {
+        self.zone
+    }
+
+    /// Set the lab time zone used to make absolute timestamps
+    pub fn set_timezone(&mut self, zone: Option<LabTimezone>) {
+        self.zone = zone
+    }
+
+    /// Return $DATE+$BTIM in the given time zone.
+    ///
+    /// Return None if either $DATE or $BTIM is missing.
+    pub fn btim_in<Tz: TimeZone>(
+        &self,
+        tz: &Tz,
+        policy: AmbiguousTimePolicy,
+    ) -> ZonedTimeResult<Option<DateTime<Tz>>>
+    where
+        NaiveTime: From<X>,
+    {
+        self.datetime_naive(self.btim.map(|x| x.0))
+            .map(|x| resolve_local(tz, x, policy))
+            .transpose()
+    }
+
+    /// Return $DATE+$ETIM in the given time zone.
+    ///
+    /// Return None if either $DATE or $ETIM is missing.
+    pub fn etim_in<Tz: TimeZone>(
+        &self,
+        tz: &Tz,
+        policy: AmbiguousTimePolicy,
+    ) -> ZonedTimeResult<Option<DateTime<Tz>>>
+    where
+        NaiveTime: From<X>,
+    {
+        self.datetime_naive(self.etim.map(|x| x.0))
+            .map(|x| resolve_local(tz, x, policy))
+            .transpose()
+    }
+
+    /// Return $DATE+$BTIM in the lab time zone.
+    ///
+    /// Return None if the lab time zone is not set or if $DATE or $BTIM is
+    /// missing.
+    pub fn btim_zoned(&self) -> ZonedTimeResult<Option<DateTime<FixedOffset>>>
+    where
+        NaiveTime: From<X>,
+    {
+        self.zoned(self.btim.map(|x| x.0))
+    }
+
+    /// Return $DATE+$ETIM in the lab time zone.
+    ///
+    /// Return None if the lab time zone is not set or if $DATE or $ETIM is
+    /// missing.
+    pub fn etim_zoned(&self) -> ZonedTimeResult<Option<DateTime<FixedOffset>>>
+    where
+        NaiveTime: From<X>,
+    {
+        self.zoned(self.etim.map(|x| x.0))
+    }
+
+    fn zoned(&self, t: Option<X>) -> ZonedTimeResult<Option<DateTime<FixedOffset>>>
+    where
+        NaiveTime: From<X>,
+    {
+        if let (Some(z), Some(x)) = (self.zone, self.datetime_naive(t)) {
+            z.resolve(x).map(Some)
+        } else {
+            Ok(None)
         }
     }
 
+    /// Return $DATE+$BTIM and $DATE+$ETIM.
+    ///
+    /// If $ETIM is before $BTIM, assume the acquisition ran past midnight and
+    /// put $ETIM on the following day; the returned flag will be true in this
+    /// case. Return None if $DATE or $BTIM is missing.
+    pub fn interval_naive(&self) -> Option<(NaiveDateTime, Option<NaiveDateTime>, bool)>
+    where
+        NaiveTime: From<X>,
+    {
+        let begin = self.datetime_naive(self.btim.map(|x| x.0))?;
+        let end = self.datetime_naive(self.etim.map(|x| x.0));
+        let rollover = end.is_some_and(|e| e < begin);
+        let end_rolled = end.map(|e| if rollover { e + TimeDelta::days(1) } else { e });
+        Some((begin, end_rolled, rollover))
+    }
+
+    fn datetime_naive(&self, t: Option<X>) -> Option<NaiveDateTime>
+    where
+        NaiveTime: From<X>,
+    {
+        self.date
+            .zip(t)
+            .map(|(d, x)| d.0.and_time(NaiveTime::from(x)))
+    }
+
     pub fn valid(&self) -> bool {
         if self.date.is_some() {
             if let (Some(b), Some(e)) = (&self.btim, &self.etim) {
@@ -157,7 +310,11 @@ where
         }
     }
 
-    pub(crate) fn lookup<E>(kws: &mut StdKeywords, dep: bool) -> LookupTentative<Self, E>
+    pub(crate) fn lookup<E>(
+        kws: &mut StdKeywords,
+        dep: bool,
+        zone: Option<LabTimezone>,
+    ) -> LookupTentative<Self, E>
     where
         Btim<X>: OptMetarootKey,
         Etim<X>: OptMetarootKey,
@@ -169,7 +326,10 @@ where
         let d = FCSDate::lookup_opt(kws, dep);
         b.zip3(e, d).and_tentatively(|(btim, etim, date)| {
             Timestamps::new(btim, etim, date)
-                .map(Tentative::new1)
+                .map(|mut t| {
+                    t.zone = zone;
+                    Tentative::new1(t)
+                })
                 .unwrap_or_else(|w| {
                     let ow = LookupKeysWarning::Relation(w.into());
                     Tentative::new(Timestamps::default(), vec![ow], vec![])
@@ -192,6 +352,38 @@ where
     }
 }
 
+fn resolve_local<Tz: TimeZone>(
+    tz: &Tz,
+    x: NaiveDateTime,
+    policy: AmbiguousTimePolicy,
+) -> ZonedTimeResult<DateTime<Tz>> {
+    match tz.from_local_datetime(&x) {
+        MappedLocalTime::Single(y) => Ok(y),
+        MappedLocalTime::Ambiguous(earliest, latest) => match policy {
+            AmbiguousTimePolicy::Error => Err(ZonedTimeError::Ambiguous(x)),
+            AmbiguousTimePolicy::Earliest => Ok(earliest),
+            AmbiguousTimePolicy::Latest => Ok(latest),
+        },
+        MappedLocalTime::None => Err(ZonedTimeError::Nonexistent(x)),
+    }
+}
+
+pub enum ZonedTimeError {
+    Ambiguous(NaiveDateTime),
+    Nonexistent(NaiveDateTime),
+}
+
+pub type ZonedTimeResult<T> = Result<T, ZonedTimeError>;
+
+impl fmt::Display for ZonedTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Ambiguous(x) => write!(f, "{x} occurs twice in lab time zone"),
+            Self::Nonexistent(x) => write!(f, "{x} does not exist in lab time zone"),
+        }
+    }
+}
+
 pub struct ReversedTimestamps;
 
 type TimestampsResult<T> = Result<T, ReversedTimestamps>;
@@ -369,3 +561,132 @@ impl fmt::Display for FCSTime100Error {
         write!(f, "must be like 'hh:mm:ss[.cc]'")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zone with European DST rules for 2024 only (UTC+0, UTC+1 in summer).
+    #[derive(Clone, Copy, Debug)]
+    struct Summer2024;
+
+    impl Summer2024 {
+        fn dst(utc: &NaiveDateTime) -> bool {
+            let begin = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+            let end = NaiveDate::from_ymd_opt(2024, 10, 27).unwrap();
+            let one = NaiveTime::from_hms_opt(1, 0, 0).unwrap();
+            begin.and_time(one) <= *utc && *utc < end.and_time(one)
+        }
+    }
+
+    impl TimeZone for Summer2024 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Self
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> MappedLocalTime<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(
+            &self,
+            local: &NaiveDateTime,
+        ) -> MappedLocalTime<FixedOffset> {
+            // earliest first, so summer time (which maps to an earlier instant)
+            // goes before winter time
+            let xs: Vec<_> = [3600, 0]
+                .into_iter()
+                .map(|s| FixedOffset::east_opt(s).unwrap())
+                .filter(|o| {
+                    let utc = *local - TimeDelta::seconds(o.local_minus_utc().into());
+                    self.offset_from_utc_datetime(&utc) == *o
+                })
+                .collect();
+            match xs[..] {
+                [x] => MappedLocalTime::Single(x),
+                [x, y] => MappedLocalTime::Ambiguous(x, y),
+                _ => MappedLocalTime::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            FixedOffset::east_opt(if Self::dst(utc) { 3600 } else { 0 }).unwrap()
+        }
+    }
+
+    fn local(m: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, m, d)
+            .and_then(|x| x.and_hms_opt(h, mi, 0))
+            .unwrap()
+    }
+
+    fn offset(x: &ZonedTimeResult<DateTime<Summer2024>>) -> Option<i32> {
+        x.as_ref().ok().map(|y| y.offset().local_minus_utc())
+    }
+
+    #[test]
+    fn test_resolve_local_unambiguous() {
+        for policy in [
+            AmbiguousTimePolicy::Error,
+            AmbiguousTimePolicy::Earliest,
+            AmbiguousTimePolicy::Latest,
+        ] {
+            let winter = resolve_local(&Summer2024, local(1, 15, 12, 0), policy);
+            let summer = resolve_local(&Summer2024, local(7, 15, 12, 0), policy);
+            assert_eq!(Some(0), offset(&winter));
+            assert_eq!(Some(3600), offset(&summer));
+        }
+    }
+
+    #[test]
+    fn test_resolve_local_fall_back() {
+        // clocks go from 02:00 back to 01:00, so 01:30 happens twice
+        let x = local(10, 27, 1, 30);
+        assert!(matches!(
+            resolve_local(&Summer2024, x, AmbiguousTimePolicy::Error),
+            Err(ZonedTimeError::Ambiguous(y)) if y == x
+        ));
+        let earliest = resolve_local(&Summer2024, x, AmbiguousTimePolicy::Earliest);
+        let latest = resolve_local(&Summer2024, x, AmbiguousTimePolicy::Latest);
+        assert_eq!(Some(3600), offset(&earliest));
+        assert_eq!(Some(0), offset(&latest));
+        assert_eq!(
+            Some(local(10, 27, 0, 30)),
+            earliest.ok().map(|y| y.naive_utc())
+        );
+        assert_eq!(
+            Some(local(10, 27, 1, 30)),
+            latest.ok().map(|y| y.naive_utc())
+        );
+    }
+
+    #[test]
+    fn test_resolve_local_spring_forward() {
+        // clocks go from 01:00 to 02:00, so 01:30 never happens
+        let x = local(3, 31, 1, 30);
+        for policy in [
+            AmbiguousTimePolicy::Error,
+            AmbiguousTimePolicy::Earliest,
+            AmbiguousTimePolicy::Latest,
+        ] {
+            assert!(matches!(
+                resolve_local(&Summer2024, x, policy),
+                Err(ZonedTimeError::Nonexistent(y)) if y == x
+            ));
+        }
+        assert_eq!(
+            Some(3600),
+            offset(&resolve_local(
+                &Summer2024,
+                local(3, 31, 2, 0),
+                AmbiguousTimePolicy::Error
+            ))
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
+use crate::text::datetimes::{FCSDateTime, FCSDateTimeError};
+use crate::text::parser::ParseKeyError;
+use crate::validated::standard::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::num::{ParseFloatError, ParseIntError};
+use std::str::FromStr;
+
+/// Types to which pseudostandard keywords may be promoted.
+///
+/// Many vendors write their own keywords starting with '$' which are not part
+/// of any standard and are thus not understood by this library. These may be
+/// declared to have one of these types, in which case they will be parsed and
+/// returned alongside the standardized keywords rather than as plain strings.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeywordType {
+    /// A signed integer
+    Int,
+    /// A 64-bit float
+    Float,
+    /// A datetime formatted like $BEGINDATETIME
+    DateTime,
+    /// A matrix formatted like $SPILLOVER (ie 'n,[names],[values]')
+    Matrix,
+}
+
+/// Pseudostandard keys declared to have a specific type
+pub type KeywordTypes = HashMap<StdKey, KeywordType>;
+
+/// Pseudostandard keywords which were parsed according to 'KeywordTypes'
+pub type TypedKeywords = HashMap<StdKey, TypedValue>;
+
+/// A pseudostandard keyword value parsed to its declared type
+#[derive(Clone, Serialize)]
+pub enum TypedValue {
+    Int(i64),
+    Float(f64),
+    DateTime(FCSDateTime),
+    Matrix(TypedMatrix),
+}
+
+/// A square matrix with named rows and columns.
+///
+/// Values are stored row-major.
+#[derive(Clone, Serialize)]
+pub struct TypedMatrix {
+    pub names: Vec<String>,
+    pub values: Vec<Vec<f64>>,
+}
+
+impl KeywordType {
+    pub fn parse_value(&self, s: &str) -> Result<TypedValue, TypedValueError> {
+        match self {
+            Self::Int => s.parse().map(TypedValue::Int).map_err(TypedValueError::Int),
+            Self::Float => s
+                .parse()
+                .map(TypedValue::Float)
+                .map_err(TypedValueError::Float),
+            Self::DateTime => s
+                .parse()
+                .map(TypedValue::DateTime)
+                .map_err(TypedValueError::DateTime),
+            Self::Matrix => s
+                .parse()
+                .map(TypedValue::Matrix)
+                .map_err(TypedValueError::Matrix),
+        }
+    }
+}
+
+impl FromStr for TypedMatrix {
+    type Err = TypedMatrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut xs = s.split(",");
+        let n = xs
+            .next()
+            .and_then(|x| x.trim().parse::<usize>().ok())
+            .ok_or(TypedMatrixError::Size)?;
+        let names: Vec<_> = xs.by_ref().take(n).map(|x| x.to_string()).collect();
+        let flat = xs
+            .map(|x| x.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(TypedMatrixError::Value)?;
+        if names.len() != n || flat.len() != n * n {
+            return Err(TypedMatrixError::Length(n));
+        }
+        let values = flat.chunks(n).map(|r| r.to_vec()).collect();
+        Ok(Self { names, values })
+    }
+}
+
+impl FromStr for KeywordType {
+    type Err = KeywordTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "int" => Ok(Self::Int),
+            "float" => Ok(Self::Float),
+            "datetime" => Ok(Self::DateTime),
+            "matrix" => Ok(Self::Matrix),
+            _ => Err(KeywordTypeError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for KeywordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let x = match self {
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::DateTime => "datetime",
+            Self::Matrix => "matrix",
+        };
+        write!(f, "{x}")
+    }
+}
+
+/// Remove and parse all keywords with a declared type.
+///
+/// Keywords which fail to parse will be left in place and returned as errors.
+pub(crate) fn promote_keywords(
+    kws: &mut StdKeywords,
+    types: &KeywordTypes,
+) -> (TypedKeywords, Vec<TypedKeywordError>) {
+    let mut typed = HashMap::new();
+    let mut errors = vec![];
+    for (k, t) in types {
+        if let Some(v) = kws.get(k) {
+            match t.parse_value(v) {
+                Ok(x) => {
+                    kws.remove(k);
+                    typed.insert(k.clone(), x);
+                }
+                Err(error) => errors.push(TypedKeywordError {
+                    error,
+                    key: k.clone(),
+                    value: v.clone(),
+                    offset: None,
+                }),
+            }
+        }
+    }
+    (typed, errors)
+}
+
+pub struct KeywordTypeError(String);
+
+/// A pseudostandard keyword which could not be parsed to its declared type
+pub type TypedKeywordError = ParseKeyError<TypedValueError>;
+
+enum_from_disp!(
+    pub TypedValueError,
+    [Int, ParseIntError],
+    [Float, ParseFloatError],
+    [DateTime, FCSDateTimeError],
+    [Matrix, TypedMatrixError]
+);
+
+pub enum TypedMatrixError {
+    Size,
+    Value(ParseFloatError),
+    Length(usize),
+}
+
+impl fmt::Display for KeywordTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "keyword type must be one of 'int', 'float', 'datetime', \
+             or 'matrix', got '{}'",
+            self.0
+        )
+    }
+}
+
+impl fmt::Display for TypedMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Size => write!(f, "first element must be a positive integer"),
+            Self::Value(e) => write!(f, "matrix value could not be parsed: {e}"),
+            Self::Length(n) => write!(f, "expected {n} names and {} values", n * n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(k: &str) -> StdKey {
+        k.parse().ok().unwrap()
+    }
+
+    #[test]
+    fn test_promote_keywords() {
+        let mut kws: StdKeywords = [
+            ("$VENDORGAIN", "3"),
+            ("$VENDORVOLT", "1.5"),
+            ("$VENDORDATE", "2024-01-02T03:04:05+01:00"),
+            ("$VENDORMAT", "2,A,B,1,0,0,1"),
+            ("$VENDORTAG", "blue"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (key(k), v.to_string()))
+        .collect();
+        let types: KeywordTypes = [
+            ("$VENDORGAIN", KeywordType::Int),
+            ("$VENDORVOLT", KeywordType::Float),
+            ("$VENDORDATE", KeywordType::DateTime),
+            ("$VENDORMAT", KeywordType::Matrix),
+            ("$VENDORMISSING", KeywordType::Int),
+        ]
+        .into_iter()
+        .map(|(k, t)| (key(k), t))
+        .collect();
+        let (typed, errors) = promote_keywords(&mut kws, &types);
+        assert!(errors.is_empty());
+        assert_eq!(4, typed.len());
+        assert!(matches!(typed.get("VENDORGAIN"), Some(TypedValue::Int(3))));
+        assert!(matches!(
+            typed.get("VENDORVOLT"),
+            Some(TypedValue::Float(x)) if *x == 1.5
+        ));
+        assert!(matches!(
+            typed.get("VENDORDATE"),
+            Some(TypedValue::DateTime(x)) if x.to_string() == "2024-01-02T03:04:05+01:00"
+        ));
+        assert!(matches!(
+            typed.get("VENDORMAT"),
+            Some(TypedValue::Matrix(m))
+                if m.names == ["A", "B"] && m.values == [[1.0, 0.0], [0.0, 1.0]]
+        ));
+        // promoted keywords are removed, untyped ones are left alone
+        assert_eq!(1, kws.len());
+        assert!(kws.contains_key("VENDORTAG"));
+    }
+
+    #[test]
+    fn test_reject_keywords() {
+        let bad = [
+            ("$VENDORGAIN", "3.5", KeywordType::Int),
+            ("$VENDORVOLT", "high", KeywordType::Float),
+            ("$VENDORDATE", "yesterday", KeywordType::DateTime),
+            ("$VENDORMAT", "x,A,1", KeywordType::Matrix),
+            ("$VENDORBIG", "2,A,B,1,0", KeywordType::Matrix),
+            ("$VENDORNAN", "1,A,one", KeywordType::Matrix),
+        ];
+        let mut kws: StdKeywords = bad
+            .iter()
+            .map(|(k, v, _)| (key(k), v.to_string()))
+            .collect();
+        let types: KeywordTypes = bad.iter().map(|(k, _, t)| (key(k), *t)).collect();
+        let (typed, errors) = promote_keywords(&mut kws, &types);
+        assert!(typed.is_empty());
+        // keywords which failed to parse are left in place
+        assert_eq!(bad.len(), kws.len());
+        let error = |k: &str| {
+            errors
+                .iter()
+                .find(|e| e.key.as_ref() == k)
+                .map(|e| &e.error)
+        };
+        assert!(matches!(error("VENDORGAIN"), Some(TypedValueError::Int(_))));
+        assert!(matches!(
+            error("VENDORVOLT"),
+            Some(TypedValueError::Float(_))
+        ));
+        assert!(matches!(
+            error("VENDORDATE"),
+            Some(TypedValueError::DateTime(_))
+        ));
+        assert!(matches!(
+            error("VENDORMAT"),
+            Some(TypedValueError::Matrix(TypedMatrixError::Size))
+        ));
+        assert!(matches!(
+            error("VENDORBIG"),
+            Some(TypedValueError::Matrix(TypedMatrixError::Length(2)))
+        ));
+        assert!(matches!(
+            error("VENDORNAN"),
+            Some(TypedValueError::Matrix(TypedMatrixError::Value(_)))
+        ));
+        let e = errors.iter().find(|e| e.key.as_ref() == "VENDORGAIN");
+        assert_eq!(
+            Some("invalid digit found in string (key='$VENDORGAIN', value='3.5')"),
+            e.map(|x| x.to_string()).as_deref()
+        );
+    }
+}
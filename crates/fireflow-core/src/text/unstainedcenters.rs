@@ -74,7 +74,7 @@ impl FromStr for UnstainedCenters {
             let remainder = xs.by_ref().count();
             let total = values.len() + measurements.len() + remainder;
             let expected = 2 * n;
-            if total != expected {
+            if total == expected {
                 let fvalues: Vec<_> = values
                     .into_iter()
                     .filter_map(|x| x.parse::<f32>().ok())
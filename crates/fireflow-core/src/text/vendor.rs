@@ -0,0 +1,235 @@
+use crate::text::spillover::Spillover;
+use crate::validated::nonstandard::NonStdKeywords;
+
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Non-standard keys commonly used for the spillover matrix prior to 3.1.
+///
+/// These have the same format as $SPILLOVER.
+pub const LEGACY_SPILLOVER_KEYS: [&str; 2] = ["SPILL", "SPILLOVER"];
+
+/// An instrument vendor with predictable non-standard keywords.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Vendor {
+    BD,
+    Cytek,
+    Beckman,
+}
+
+/// How to choose a vendor when reading TEXT.
+#[derive(Clone, Copy, Default)]
+pub enum VendorProfile {
+    /// Do not look for vendor-specific keywords.
+    #[default]
+    Off,
+    /// Guess the vendor from $CYT.
+    Auto,
+    /// Always use the given vendor.
+    Fixed(Vendor),
+}
+
+/// Values parsed from vendor-specific non-standard keywords.
+///
+/// The keywords used to derive these values are not removed from the
+/// non-standard keywords, so they will be written as-is.
+#[derive(Clone, Serialize)]
+pub struct VendorKeywords {
+    /// The vendor whose keywords were used
+    pub vendor: Vendor,
+
+    /// Spillover matrix from non-standard keywords (usually "SPILL")
+    pub spillover: Option<Spillover>,
+
+    /// Laser settings, in order of index
+    pub lasers: Vec<Laser>,
+}
+
+/// Laser settings from "LASERnNAME", "LASERnDELAY", and "LASERnASF" (BD)
+#[derive(Clone, Serialize)]
+pub struct Laser {
+    /// The 'n' in the above keywords
+    pub index: usize,
+
+    /// Name of the laser, usually including its wavelength
+    pub name: Option<String>,
+
+    /// Delay of the laser relative to the trigger laser
+    pub delay: Option<f32>,
+
+    /// Area scaling factor
+    pub asf: Option<f32>,
+}
+
+impl Vendor {
+    /// Guess the vendor from the value of $CYT.
+    pub fn detect(cyt: &str) -> Option<Self> {
+        let c = cyt.to_lowercase();
+        let has = |xs: &[&str]| xs.iter().any(|x| c.contains(x));
+        if has(&["cytek", "aurora", "northern lights"]) {
+            Some(Self::Cytek)
+        } else if has(&[
+            "beckman", "coulter", "cytoflex", "gallios", "navios", "moflo",
+        ]) {
+            Some(Self::Beckman)
+        } else if has(&[
+            "bd ", "becton", "facs", "lsr", "accuri", "fortessa", "symphony", "canto",
+        ]) || c == "bd"
+        {
+            Some(Self::BD)
+        } else {
+            None
+        }
+    }
+
+    /// Non-standard keys which may hold a spillover matrix, in order of
+    /// preference.
+    pub fn spillover_keys(&self) -> &'static [&'static str] {
+        match self {
+            Self::BD | Self::Cytek => &LEGACY_SPILLOVER_KEYS,
+            Self::Beckman => &["SPILLOVER", "SPILL"],
+        }
+    }
+
+    fn has_lasers(&self) -> bool {
+        matches!(self, Self::BD)
+    }
+}
+
+impl VendorProfile {
+    /// Return the vendor to use given $CYT (if any).
+    pub fn vendor(&self, cyt: Option<&str>) -> Option<Vendor> {
+        match self {
+            Self::Off => None,
+            Self::Auto => cyt.and_then(Vendor::detect),
+            Self::Fixed(v) => Some(*v),
+        }
+    }
+}
+
+impl VendorKeywords {
+    /// Parse vendor-specific values from non-standard keywords.
+    ///
+    /// Return None if no vendor was chosen. Keywords which are missing or
+    /// cannot be parsed will be skipped.
+    pub fn from_keywords(
+        profile: VendorProfile,
+        cyt: Option<&str>,
+        kws: &NonStdKeywords,
+    ) -> Option<Self> {
+        let vendor = profile.vendor(cyt)?;
+        let spillover = find_spillover(kws, vendor.spillover_keys());
+        let lasers = if vendor.has_lasers() {
+            find_lasers(kws)
+        } else {
+            vec![]
+        };
+        Some(Self {
+            vendor,
+            spillover,
+            lasers,
+        })
+    }
+}
+
+/// Return the value for a non-standard key, ignoring case.
+fn get_nonstd<'a>(kws: &'a NonStdKeywords, k: &str) -> Option<&'a str> {
+    kws.iter()
+        .find(|(kk, _)| kk.as_ref().eq_ignore_ascii_case(k))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Return the first spillover matrix found under the given keys.
+pub(crate) fn find_spillover(kws: &NonStdKeywords, keys: &[&str]) -> Option<Spillover> {
+    keys.iter()
+        .filter_map(|k| get_nonstd(kws, k))
+        .find_map(|v| v.parse::<Spillover>().ok())
+}
+
+fn find_lasers(kws: &NonStdKeywords) -> Vec<Laser> {
+    let mut lasers = vec![];
+    // lasers should be numbered consecutively from 1, so stop at the first gap
+    for index in 1.. {
+        let get = |suffix| get_nonstd(kws, &format!("LASER{index}{suffix}"));
+        let name = get("NAME").map(String::from);
+        let delay = get("DELAY").and_then(|x| x.trim().parse().ok());
+        let asf = get("ASF").and_then(|x| x.trim().parse().ok());
+        if name.is_none() && delay.is_none() && asf.is_none() {
+            break;
+        }
+        lasers.push(Laser {
+            index,
+            name,
+            delay,
+            asf,
+        });
+    }
+    lasers
+}
+
+impl FromStr for VendorProfile {
+    type Err = VendorProfileError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" | "none" => Ok(Self::Off),
+            "auto" => Ok(Self::Auto),
+            "bd" => Ok(Self::Fixed(Vendor::BD)),
+            "cytek" => Ok(Self::Fixed(Vendor::Cytek)),
+            "beckman" => Ok(Self::Fixed(Vendor::Beckman)),
+            _ => Err(VendorProfileError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let s = match self {
+            Self::BD => "BD",
+            Self::Cytek => "Cytek",
+            Self::Beckman => "Beckman",
+        };
+        f.write_str(s)
+    }
+}
+
+pub struct VendorProfileError(String);
+
+impl fmt::Display for VendorProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "vendor profile must be one of 'off', 'auto', 'bd', 'cytek', \
+             or 'beckman', got '{}'",
+            self.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validated::nonstandard::NonStdKey;
+
+    #[test]
+    fn test_bd_keywords() {
+        let kws: NonStdKeywords = [
+            ("SPILL", "2,FL1-A,FL2-A,1,0.1,0.2,1"),
+            ("LASER1NAME", "Blue"),
+            ("LASER1DELAY", "0.0"),
+            ("LASER2NAME", "Red"),
+            ("LASER2ASF", "1.12"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (NonStdKey::from_unchecked(k), v.to_string()))
+        .collect();
+        let v =
+            VendorKeywords::from_keywords(VendorProfile::Auto, Some("LSRFortessa"), &kws).unwrap();
+        assert!(v.vendor == Vendor::BD);
+        assert!(v.spillover.is_some());
+        assert_eq!(v.lasers.len(), 2);
+        assert_eq!(v.lasers[1].asf, Some(1.12));
+        assert!(VendorKeywords::from_keywords(VendorProfile::Auto, None, &kws).is_none());
+    }
+}
@@ -0,0 +1,418 @@
+//! Logicle and arcsinh transforms for display and gating.
+//!
+//! Both are linear-like near zero and logarithmic for larger values, which
+//! suits compensated data that may be negative. Each is described by the
+//! following parameters, as defined in Gating-ML 2.0:
+//!
+//! - T: the largest data value on the scale ("top")
+//! - M: the width of the whole scale in decades
+//! - A: additional negative decades
+//! - W: the width of the linear region in decades (logicle only)
+//!
+//! Transforms map data to [0, 1] where T maps to 1. Values beyond this range
+//! will be transformed but lie outside [0, 1].
+//!
+//! Parameters may be derived from a measurement's keywords using
+//! [`TransformSpec`]; see [`AnyCoreDataset::transformed_data`].
+//!
+//! [`AnyCoreDataset::transformed_data`]: crate::core::AnyCoreDataset::transformed_data
+
+use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
+use crate::validated::dataframe::AnyFCSColumn;
+
+use std::f64::consts::LN_10;
+use std::fmt;
+
+/// Default for M, which is appropriate for 18-bit data
+pub const DEFAULT_DECADES: f64 = 4.5;
+
+/// Default for W
+pub const DEFAULT_LINEAR_WIDTH: f64 = 0.5;
+
+/// A monotonic function applied to each value in a column.
+pub trait Transform {
+    /// Transform one value.
+    fn apply(&self, x: f64) -> f64;
+
+    /// Undo the transform for one value.
+    fn inverse(&self, y: f64) -> f64;
+
+    /// Transform every value in a column, returning a 64-bit float column.
+    fn apply_column(&self, c: &AnyFCSColumn) -> AnyFCSColumn {
+        c.map_f64(|x| self.apply(x))
+    }
+}
+
+/// Logicle transform (Parks et al., 2006; Moore and Parks, 2012)
+#[derive(Clone, Copy)]
+pub struct Logicle {
+    t: f64,
+    w: f64,
+    m: f64,
+    a: f64,
+    // Coefficients of the biexponential function, see 'new' for details
+    coef: LogicleCoef,
+}
+
+#[derive(Clone, Copy)]
+struct LogicleCoef {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    f: f64,
+    x1: f64,
+}
+
+/// Arcsinh transform as defined in Gating-ML 2.0
+#[derive(Clone, Copy)]
+pub struct Arcsinh {
+    t: f64,
+    m: f64,
+    a: f64,
+}
+
+/// Any supported transform
+#[derive(Clone, Copy)]
+pub enum AnyTransform {
+    Logicle(Logicle),
+    Arcsinh(Arcsinh),
+}
+
+/// A transform whose parameters may be filled in from a measurement.
+///
+/// If None, T will be the largest value of the measurement after applying
+/// $PnE, $PnG, and $PnCALIBRATION to $PnR. Likewise M will be the decades in
+/// $PnE if log-scaled, otherwise [`DEFAULT_DECADES`].
+#[derive(Clone, Copy)]
+pub enum TransformSpec {
+    Logicle {
+        top: Option<f64>,
+        width: f64,
+        decades: Option<f64>,
+        extra: f64,
+    },
+    Arcsinh {
+        top: Option<f64>,
+        decades: Option<f64>,
+        extra: f64,
+    },
+}
+
+/// Error when logicle parameters are out of range
+pub struct LogicleError;
+
+/// Error when arcsinh parameters are out of range
+pub struct ArcsinhError;
+
+/// Error when T is neither given nor derivable from a measurement
+pub struct MissingTopError;
+
+enum_from_disp!(
+    pub TransformError,
+    [Logicle, LogicleError],
+    [Arcsinh, ArcsinhError],
+    [Top, MissingTopError]
+);
+
+impl Logicle {
+    /// Make new logicle transform with parameters T, W, M, and A.
+    ///
+    /// These must satisfy T > 0, M > 0, 0 <= W <= M/2, and -W <= A <= M - 2W.
+    pub fn new(t: f64, w: f64, m: f64, a: f64) -> Result<Self, LogicleError> {
+        if !(t > 0.0 && m > 0.0 && (0.0..=m / 2.0).contains(&w) && (-w..=m - 2.0 * w).contains(&a))
+        {
+            return Err(LogicleError);
+        }
+        // See Moore and Parks (2012) for the derivation of these coefficients
+        let wn = w / (m + a);
+        let x2 = a / (m + a);
+        let x1 = x2 + wn;
+        let x0 = x2 + 2.0 * wn;
+        let b = (m + a) * LN_10;
+        let d = logicle_solve_d(b, wn);
+        let ca = (x0 * (b + d)).exp();
+        let fa = (b * x1).exp() - ca / (d * x1).exp();
+        let coef_a = t / ((b.exp() - fa) - ca / d.exp());
+        let coef = LogicleCoef {
+            a: coef_a,
+            b,
+            c: ca * coef_a,
+            d,
+            f: fa * coef_a,
+            x1,
+        };
+        Ok(Self { t, w, m, a, coef })
+    }
+
+    /// The biexponential function and its derivative for y >= x1
+    fn biex(&self, y: f64) -> (f64, f64) {
+        let k = &self.coef;
+        let p = k.a * (k.b * y).exp();
+        let q = k.c * (-k.d * y).exp();
+        (p - q - k.f, k.b * p + k.d * q)
+    }
+}
+
+impl Transform for Logicle {
+    fn apply(&self, x: f64) -> f64 {
+        let x1 = self.coef.x1;
+        if x.is_nan() {
+            return x;
+        }
+        if x == 0.0 {
+            return x1;
+        }
+        // The biexponential is antisymmetric about x1 and increasing, so
+        // solve for |x| above x1 and reflect if needed. Use Newton's method
+        // but fall back to bisection if it leaves the bracket.
+        let target = x.abs();
+        let mut lo = x1;
+        let mut hi = 1.0_f64.max(x1);
+        while self.biex(hi).0 < target && hi.is_finite() {
+            hi += 1.0;
+        }
+        let mut y = (lo + hi) / 2.0;
+        for _ in 0..100 {
+            let (s, ds) = self.biex(y);
+            let e = s - target;
+            if e < 0.0 {
+                lo = y;
+            } else {
+                hi = y;
+            }
+            let next = y - e / ds;
+            let z = if next > lo && next < hi {
+                next
+            } else {
+                (lo + hi) / 2.0
+            };
+            if (z - y).abs() <= f64::EPSILON * z.abs().max(1.0) {
+                y = z;
+                break;
+            }
+            y = z;
+        }
+        if x < 0.0 {
+            2.0 * x1 - y
+        } else {
+            y
+        }
+    }
+
+    fn inverse(&self, y: f64) -> f64 {
+        let x1 = self.coef.x1;
+        if y < x1 {
+            -self.biex(2.0 * x1 - y).0
+        } else {
+            self.biex(y).0
+        }
+    }
+}
+
+impl Default for Logicle {
+    fn default() -> Self {
+        // ASSUME these are valid
+        Self::new(262144.0, DEFAULT_LINEAR_WIDTH, DEFAULT_DECADES, 0.0)
+            .ok()
+            .unwrap()
+    }
+}
+
+/// Solve 2 * (ln(d) - ln(b)) + w * (b + d) = 0 for d.
+fn logicle_solve_d(b: f64, w: f64) -> f64 {
+    if w == 0.0 {
+        return b;
+    }
+    // the function is increasing in d and changes sign in (0, b], so bisect
+    let f = |d: f64| 2.0 * (d.ln() - b.ln()) + w * (b + d);
+    let (mut lo, mut hi) = (0.0, b);
+    for _ in 0..100 {
+        let mid = (lo + hi) / 2.0;
+        if f(mid) < 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+impl Arcsinh {
+    /// Make new arcsinh transform with parameters T, M, and A.
+    ///
+    /// These must satisfy T > 0, M > 0, and 0 <= A <= M.
+    pub fn new(t: f64, m: f64, a: f64) -> Result<Self, ArcsinhError> {
+        if t > 0.0 && m > 0.0 && (0.0..=m).contains(&a) {
+            Ok(Self { t, m, a })
+        } else {
+            Err(ArcsinhError)
+        }
+    }
+}
+
+impl Transform for Arcsinh {
+    fn apply(&self, x: f64) -> f64 {
+        ((x * (self.m * LN_10).sinh() / self.t).asinh() + self.a * LN_10)
+            / ((self.m + self.a) * LN_10)
+    }
+
+    fn inverse(&self, y: f64) -> f64 {
+        (y * (self.m + self.a) * LN_10 - self.a * LN_10).sinh() * self.t / (self.m * LN_10).sinh()
+    }
+}
+
+impl Transform for AnyTransform {
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Self::Logicle(t) => t.apply(x),
+            Self::Arcsinh(t) => t.apply(x),
+        }
+    }
+
+    fn inverse(&self, y: f64) -> f64 {
+        match self {
+            Self::Logicle(t) => t.inverse(y),
+            Self::Arcsinh(t) => t.inverse(y),
+        }
+    }
+}
+
+impl TransformSpec {
+    /// Logicle with default W and A and parameters derived from keywords
+    pub fn logicle() -> Self {
+        Self::Logicle {
+            top: None,
+            width: DEFAULT_LINEAR_WIDTH,
+            decades: None,
+            extra: 0.0,
+        }
+    }
+
+    /// Arcsinh with default A and parameters derived from keywords
+    pub fn arcsinh() -> Self {
+        Self::Arcsinh {
+            top: None,
+            decades: None,
+            extra: 0.0,
+        }
+    }
+
+    /// Make a transform using T and M from a measurement where not given.
+    ///
+    /// 'top' should be None if it cannot be derived from the measurement (ie
+    /// for time) and 'decades' should be None if the measurement is not
+    /// log-scaled.
+    pub fn resolve(
+        &self,
+        top: Option<f64>,
+        decades: Option<f64>,
+    ) -> Result<AnyTransform, TransformError> {
+        let m = |d: &Option<f64>| d.or(decades).unwrap_or(DEFAULT_DECADES);
+        match self {
+            Self::Logicle {
+                top: t,
+                width,
+                decades: d,
+                extra,
+            } => {
+                let tt = t.or(top).ok_or(MissingTopError)?;
+                Logicle::new(tt, *width, m(d), *extra)
+                    .map(AnyTransform::Logicle)
+                    .map_err(TransformError::Logicle)
+            }
+            Self::Arcsinh {
+                top: t,
+                decades: d,
+                extra,
+            } => {
+                let tt = t.or(top).ok_or(MissingTopError)?;
+                Arcsinh::new(tt, m(d), *extra)
+                    .map(AnyTransform::Arcsinh)
+                    .map_err(TransformError::Arcsinh)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Logicle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{},{},{},{}", self.t, self.w, self.m, self.a)
+    }
+}
+
+impl fmt::Display for LogicleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "logicle parameters must satisfy T > 0, M > 0, \
+             0 <= W <= M/2, and -W <= A <= M - 2W"
+        )
+    }
+}
+
+impl fmt::Display for MissingTopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "T must be given since it cannot be derived from $PnR")
+    }
+}
+
+impl fmt::Display for ArcsinhError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "arcsinh parameters must satisfy T > 0, M > 0, and 0 <= A <= M"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: Transform>(t: &T, xs: &[f64]) {
+        for x in xs {
+            let y = t.apply(*x);
+            assert!(
+                (t.inverse(y) - x).abs() <= 1e-6 * x.abs().max(1.0),
+                "{x} -> {y} -> {}",
+                t.inverse(y)
+            );
+        }
+    }
+
+    #[test]
+    fn test_logicle() {
+        let l = Logicle::default();
+        assert!((l.inverse(1.0) - 262144.0).abs() < 1e-6);
+        assert!((l.apply(262144.0) - 1.0).abs() < 1e-9);
+        // zero should be at the end of the linear region (W / M)
+        assert!((l.apply(0.0) - 0.5 / 4.5).abs() < 1e-12);
+        assert!(l.inverse(0.0) < 0.0);
+        roundtrip(&l, &[-1000.0, -1.0, 0.5, 10.0, 1234.5, 262144.0, 1e6]);
+        assert!(Logicle::new(262144.0, 3.0, 4.5, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_arcsinh() {
+        let a = Arcsinh::new(262144.0, 4.5, 0.0).ok().unwrap();
+        assert!((a.apply(262144.0) - 1.0).abs() < 1e-9);
+        assert_eq!(a.apply(0.0), 0.0);
+        roundtrip(&a, &[-1000.0, -1.0, 0.5, 10.0, 1234.5, 262144.0]);
+        assert!(Arcsinh::new(262144.0, 4.5, 5.0).is_err());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let t = TransformSpec::logicle().resolve(Some(1024.0), Some(3.0));
+        assert!(t.is_ok_and(|x| (x.apply(1024.0) - 1.0).abs() < 1e-9));
+        assert!(TransformSpec::arcsinh().resolve(None, None).is_err());
+        let bad = TransformSpec::Logicle {
+            top: None,
+            width: 4.0,
+            decades: None,
+            extra: 0.0,
+        };
+        assert!(bad.resolve(Some(1024.0), None).is_err());
+    }
+}
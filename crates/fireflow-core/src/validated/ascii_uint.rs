@@ -1,6 +1,6 @@
 //! Types used for constructing offsets in HEADER and TEXT
 
-use crate::header::MAX_HEADER_OFFSET;
+use crate::limits::MAX_HEADER_OFFSET;
 use crate::macros::{
     enum_from, enum_from_disp, match_many_to_one, newtype_disp, newtype_from, newtype_from_outer,
     newtype_fromstr,
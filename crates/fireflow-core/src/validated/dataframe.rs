@@ -2,9 +2,11 @@ use crate::data::ColumnWriter;
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
 use crate::text::named_vec::BoundaryIndexError;
 
+use nalgebra::DMatrix;
 use polars_arrow::array::{Array, PrimitiveArray};
 use polars_arrow::buffer::Buffer;
 use polars_arrow::datatypes::ArrowDataType;
+use serde::{Serialize, Serializer};
 use std::any::type_name;
 use std::fmt;
 use std::iter;
@@ -91,6 +93,117 @@ impl AnyFCSColumn {
         }
     }
 
+    /// Return the largest value in this column as an integer.
+    ///
+    /// Floats are rounded toward zero and saturate at 0 and [`u64::MAX`].
+    /// Return None if column is empty.
+    pub fn max_u64(&self) -> Option<u64> {
+        match self {
+            Self::U08(xs) => xs.0.iter().map(|x| u64::from(*x)).max(),
+            Self::U16(xs) => xs.0.iter().map(|x| u64::from(*x)).max(),
+            Self::U32(xs) => xs.0.iter().map(|x| u64::from(*x)).max(),
+            Self::U64(xs) => xs.0.iter().copied().max(),
+            Self::F32(xs) => xs.0.iter().map(|x| *x as u64).max(),
+            Self::F64(xs) => xs.0.iter().map(|x| *x as u64).max(),
+        }
+    }
+
+    /// Return true if all values are whole numbers which fit in a u64.
+    ///
+    /// Always true for integer columns.
+    pub fn is_whole(&self) -> bool {
+        let go = |x: f64| x >= 0.0 && x < u64::MAX as f64 && x.fract() == 0.0;
+        match self {
+            Self::F32(xs) => xs.0.iter().all(|x| go(f64::from(*x))),
+            Self::F64(xs) => xs.0.iter().all(|x| go(*x)),
+            _ => true,
+        }
+    }
+
+    /// Return true for each value which is greater than 'x'.
+    pub fn above_threshold(&self, x: u32) -> Vec<bool> {
+        let y = u64::from(x);
+        let z = f64::from(x);
+        match self {
+            Self::U08(xs) => xs.0.iter().map(|v| u64::from(*v) > y).collect(),
+            Self::U16(xs) => xs.0.iter().map(|v| u64::from(*v) > y).collect(),
+            Self::U32(xs) => xs.0.iter().map(|v| u64::from(*v) > y).collect(),
+            Self::U64(xs) => xs.0.iter().map(|v| *v > y).collect(),
+            Self::F32(xs) => xs.0.iter().map(|v| f64::from(*v) > z).collect(),
+            Self::F64(xs) => xs.0.iter().map(|v| *v > z).collect(),
+        }
+    }
+
+    /// Return all values in this column converted to floats.
+    pub fn to_f64s(&self) -> Vec<f64> {
+        match self {
+            Self::U08(xs) => u8::iter_converted::<f64>(xs).map(|x| x.new).collect(),
+            Self::U16(xs) => u16::iter_converted::<f64>(xs).map(|x| x.new).collect(),
+            Self::U32(xs) => u32::iter_converted::<f64>(xs).map(|x| x.new).collect(),
+            Self::U64(xs) => u64::iter_converted::<f64>(xs).map(|x| x.new).collect(),
+            Self::F32(xs) => f32::iter_converted::<f64>(xs).map(|x| x.new).collect(),
+            Self::F64(xs) => f64::iter_converted::<f64>(xs).map(|x| x.new).collect(),
+        }
+    }
+
+    /// Return a new column with each value converted to a float and mapped.
+    pub(crate) fn map_f64<F: Fn(f64) -> f64>(&self, f: F) -> Self {
+        let xs: Vec<_> = self.to_f64s().into_iter().map(f).collect();
+        F64Column::from(xs).into()
+    }
+
+    /// Return new column with the rows of other columns appended.
+    ///
+    /// If the columns have different types, all will be converted to 64-bit
+    /// floats.
+    pub(crate) fn concat(&self, others: &[&Self]) -> Self {
+        fn go<T: Copy>(x: &FCSColumn<T>, ys: &[&FCSColumn<T>]) -> FCSColumn<T> {
+            let n = x.0.len() + ys.iter().map(|y| y.0.len()).sum::<usize>();
+            let mut xs = Vec::with_capacity(n);
+            xs.extend(x.0.iter().copied());
+            for y in ys {
+                xs.extend(y.0.iter().copied());
+            }
+            FCSColumn::from(xs)
+        }
+        macro_rules! same {
+            ($x:expr, $var:ident) => {
+                others
+                    .iter()
+                    .map(|o| match o {
+                        Self::$var(y) => Some(y),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .map(|ys| go($x, &ys[..]).into())
+            };
+        }
+        let same_type = match self {
+            Self::U08(x) => same!(x, U08),
+            Self::U16(x) => same!(x, U16),
+            Self::U32(x) => same!(x, U32),
+            Self::U64(x) => same!(x, U64),
+            Self::F32(x) => same!(x, F32),
+            Self::F64(x) => same!(x, F64),
+        };
+        same_type.unwrap_or_else(|| {
+            let mut xs = self.to_f64s();
+            for o in others {
+                xs.extend(o.to_f64s());
+            }
+            F64Column::from(xs).into()
+        })
+    }
+
+    /// Return new column with only the rows at the given indices.
+    ///
+    /// Will panic if any index is out of bounds.
+    pub(crate) fn select_rows(&self, rows: &[usize]) -> Self {
+        match_many_to_one!(self, AnyFCSColumn, [U08, U16, U32, U64, F32, F64], x, {
+            FCSColumn::from(rows.iter().map(|i| x.0[*i]).collect::<Vec<_>>()).into()
+        })
+    }
+
     pub fn as_array(&self) -> Box<dyn Array> {
         match self.clone() {
             Self::U08(xs) => Box::new(PrimitiveArray::new(ArrowDataType::UInt8, xs.0, None)),
@@ -103,6 +216,117 @@ impl AnyFCSColumn {
     }
 }
 
+impl Serialize for AnyFCSColumn {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match_many_to_one!(self, AnyFCSColumn, [U08, U16, U32, U64, F32, F64], x, {
+            serializer.collect_seq(x.0.iter())
+        })
+    }
+}
+
+/// A column type defined outside the native FCS types.
+///
+/// DATA can only store unsigned integers and floats, so any other column type
+/// (booleans, class labels, etc) must encode itself as a native column when
+/// added to a dataset and decode itself from a native column when read back.
+/// Implement this to use such types with [`FCSDataFrame`] without needing to
+/// extend [`AnyFCSColumn`].
+pub trait CustomColumn: Sized {
+    /// Encode this column as a native column for storage in DATA.
+    fn to_fcs_column(&self) -> AnyFCSColumn;
+
+    /// Decode this column from a native column in DATA.
+    fn from_fcs_column(c: &AnyFCSColumn) -> Result<Self, DecodeColumnError>;
+}
+
+/// A column of flags, stored as 0 or 1 in an 8-bit column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoolColumn(pub Vec<bool>);
+
+/// A column of class codes, as for measurements where $PnTYPE=Classification.
+///
+/// Codes are stored as 32-bit unsigned integers. Labels for each code are not
+/// stored in DATA and must be supplied separately.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassColumn(pub Vec<u32>);
+
+impl ClassColumn {
+    /// Return the label for each code, or None if a code has no label.
+    pub fn labeled<'a, S: AsRef<str>>(&self, labels: &'a [S]) -> Vec<Option<&'a str>> {
+        self.0
+            .iter()
+            .map(|c| labels.get(*c as usize).map(|l| l.as_ref()))
+            .collect()
+    }
+}
+
+impl CustomColumn for BoolColumn {
+    fn to_fcs_column(&self) -> AnyFCSColumn {
+        U08Column::from(self.0.iter().map(|x| u8::from(*x)).collect::<Vec<_>>()).into()
+    }
+
+    fn from_fcs_column(c: &AnyFCSColumn) -> Result<Self, DecodeColumnError> {
+        c.to_f64s()
+            .into_iter()
+            .enumerate()
+            .map(|(row, x)| {
+                if x == 0.0 {
+                    Ok(false)
+                } else if x == 1.0 {
+                    Ok(true)
+                } else {
+                    Err(DecodeColumnError { row, kind: "bool" })
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+impl CustomColumn for ClassColumn {
+    fn to_fcs_column(&self) -> AnyFCSColumn {
+        U32Column::from(self.0.clone()).into()
+    }
+
+    fn from_fcs_column(c: &AnyFCSColumn) -> Result<Self, DecodeColumnError> {
+        c.to_f64s()
+            .into_iter()
+            .enumerate()
+            .map(|(row, x)| {
+                if x.fract() == 0.0 && (0.0..=f64::from(u32::MAX)).contains(&x) {
+                    Ok(x as u32)
+                } else {
+                    Err(DecodeColumnError {
+                        row,
+                        kind: "class code",
+                    })
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+/// Error when a native column cannot be decoded into a custom column.
+#[derive(Debug)]
+pub struct DecodeColumnError {
+    pub row: usize,
+    pub kind: &'static str,
+}
+
+impl fmt::Display for DecodeColumnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "value in row {} cannot be decoded as {}",
+            self.row, self.kind
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct NewDataframeError;
 
@@ -151,6 +375,10 @@ impl FCSDataFrame {
         self.nrows = 0;
     }
 
+    pub(crate) fn into_columns(self) -> Vec<AnyFCSColumn> {
+        self.columns
+    }
+
     pub fn iter_columns(&self) -> Iter<'_, AnyFCSColumn> {
         self.columns.iter()
     }
@@ -175,6 +403,80 @@ impl FCSDataFrame {
         self.ncols() == 0
     }
 
+    /// Return new dataframe with the rows of other dataframes appended.
+    ///
+    /// Each of 'others' is paired with one entry per column in this dataframe
+    /// giving the index of its column to append to it. Will panic if any index
+    /// is out of bounds.
+    pub(crate) fn concat_rows(&self, others: &[(&Self, Vec<usize>)]) -> Self {
+        if self.is_empty() {
+            return Self::default();
+        }
+        Self {
+            columns: self
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(k, c)| {
+                    let ys: Vec<_> = others
+                        .iter()
+                        .map(|(o, order)| &o.columns[order[k]])
+                        .collect();
+                    c.concat(&ys[..])
+                })
+                .collect(),
+            nrows: self.nrows() + others.iter().map(|(o, _)| o.nrows()).sum::<usize>(),
+        }
+    }
+
+    /// Return new dataframe with columns at the given indices.
+    ///
+    /// Will panic if any index is out of bounds.
+    pub(crate) fn select_columns(&self, cols: &[usize]) -> Self {
+        Self {
+            columns: cols.iter().map(|i| self.columns[*i].clone()).collect(),
+            nrows: self.nrows,
+        }
+    }
+
+    /// Return new dataframe with only the rows at the given indices.
+    ///
+    /// Will panic if any index is out of bounds.
+    pub(crate) fn select_rows(&self, rows: &[usize]) -> Self {
+        if self.is_empty() {
+            return Self::default();
+        }
+        Self {
+            columns: self.columns.iter().map(|c| c.select_rows(rows)).collect(),
+            nrows: rows.len(),
+        }
+    }
+
+    /// Return new dataframe with a compensation matrix applied to some columns.
+    ///
+    /// 'm' must be the inverse of a spillover matrix whose rows and columns
+    /// correspond to the columns at 'indices'. Each event (as a row vector)
+    /// will be multiplied by 'm'. Compensated columns will be converted to
+    /// 64-bit floats and all others will be returned unchanged.
+    ///
+    /// Will panic if any index is out of bounds or 'm' is not the same size
+    /// as 'indices'.
+    pub(crate) fn compensate(&self, indices: &[usize], m: &DMatrix<f64>) -> Self {
+        let n = self.nrows();
+        // DMatrix is column-major so columns can be concatenated directly
+        let xs: Vec<_> = indices
+            .iter()
+            .flat_map(|i| self.columns[*i].to_f64s())
+            .collect();
+        let ys = DMatrix::from_vec(n, indices.len(), xs) * m;
+        let mut columns = self.columns.clone();
+        for (j, i) in indices.iter().enumerate() {
+            let y: Vec<_> = ys.column(j).iter().copied().collect();
+            columns[*i] = F64Column::from(y).into();
+        }
+        Self { columns, nrows: n }
+    }
+
     pub(crate) fn drop_in_place(&mut self, i: usize) -> Option<AnyFCSColumn> {
         if i > self.columns.len() {
             None
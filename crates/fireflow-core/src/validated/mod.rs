@@ -4,6 +4,7 @@ pub mod datepattern;
 pub mod nonstandard;
 pub mod other_width;
 pub mod pattern;
+pub mod repair;
 pub mod shortname;
 pub mod standard;
 pub mod textdelim;
@@ -1,7 +1,7 @@
 use crate::macros::{newtype_asref, newtype_disp};
 use crate::text::index::IndexFromOne;
 
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fmt;
@@ -21,8 +21,17 @@ pub type NonStdKeywords = HashMap<NonStdKey, String>;
 /// This will have exactly one '%n' and not start with a '$'. The
 /// '%n' will be replaced by the measurement index which will be used
 /// to match keywords.
+///
+/// By default this is a regular expression. If `prefix` is true, it is
+/// instead a literal which must match the start of a keyword (ie 'P%nDISPLAY'
+/// will match 'P7DISPLAY' and 'P7DISPLAYMODE'). A trailing '%n' will not
+/// match a longer index, so 'P%n' matches 'P1X' but not 'P10X'.
 #[derive(Clone)]
-pub struct NonStdMeasPattern(String);
+pub struct NonStdMeasPattern {
+    pattern: String,
+    prefix: bool,
+    case_insensitive: bool,
+}
 
 impl FromStr for NonStdKey {
     type Err = NonStdKeyError;
@@ -66,22 +75,56 @@ impl FromStr for NonStdMeasPattern {
     type Err = NonStdMeasPatternError;
 
     fn from_str(s: &str) -> Result<Self, NonStdMeasPatternError> {
+        Self::new(s, false, false)
+    }
+}
+
+impl NonStdMeasPattern {
+    pub fn new(
+        s: &str,
+        prefix: bool,
+        case_insensitive: bool,
+    ) -> Result<Self, NonStdMeasPatternError> {
         if s.starts_with("$") || s.match_indices("%n").count() != 1 {
             Err(NonStdMeasPatternError(s.to_string()))
         } else {
-            Ok(NonStdMeasPattern(s.to_string()))
+            Ok(NonStdMeasPattern {
+                pattern: s.to_string(),
+                prefix,
+                case_insensitive,
+            })
         }
     }
-}
 
-impl NonStdMeasPattern {
+    pub fn is_prefix(&self) -> bool {
+        self.prefix
+    }
+
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
     // pub fn with_par(&self, par: Par) -> MultiResult<Vec<NonStdMeasRegex, NonStdMeasRegexError>> {
     //     (0..par.0).map(|n| self.from_index(n.into())).gather()
     // }
 
     pub fn from_index(&self, n: IndexFromOne) -> Result<NonStdMeasRegex, NonStdMeasRegexError> {
-        let pattern = self.0.replace("%n", n.to_string().as_str());
-        Regex::new(pattern.as_str())
+        let i = n.to_string();
+        let pattern = if self.prefix {
+            // ASSUME this will always split since we checked for '%n'
+            let (before, after) = self.pattern.split_once("%n").unwrap_or_default();
+            let rest = if after.is_empty() {
+                "([^0-9]|$)".to_string()
+            } else {
+                regex::escape(after)
+            };
+            format!("^{}{i}{rest}", regex::escape(before))
+        } else {
+            self.pattern.replace("%n", i.as_str())
+        };
+        RegexBuilder::new(pattern.as_str())
+            .case_insensitive(self.case_insensitive)
+            .build()
             .map_err(|_| NonStdMeasRegexError { pattern, index: n })
             .map(NonStdMeasRegex)
     }
@@ -141,8 +184,18 @@ impl fmt::Display for NonStdMeasRegexError {
     }
 }
 
+impl fmt::Display for NonStdMeasPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+impl AsRef<str> for NonStdMeasPattern {
+    fn as_ref(&self) -> &str {
+        self.pattern.as_str()
+    }
+}
+
 newtype_disp!(NonStdKey);
-newtype_disp!(NonStdMeasPattern);
 
 newtype_asref!(NonStdKey, str);
-newtype_asref!(NonStdMeasPattern, str);
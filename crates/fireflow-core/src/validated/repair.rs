@@ -0,0 +1,241 @@
+use crate::validated::standard::{KeywordOffsets, ValidKeywords};
+
+use serde::Serialize;
+use std::fmt;
+use std::mem;
+
+/// Rules to repair keywords in TEXT before they are standardized.
+///
+/// These are applied in order: renames, then rewrites, then insertions. Keys
+/// are given as they would appear in TEXT, so standard keys must start with
+/// '$'. Rules with keys that are not valid will be ignored.
+#[derive(Clone, Default)]
+pub struct KeywordRepairs {
+    /// Keys to rename as (from, to), such as ("$DATATYPE ", "$DATATYPE")
+    ///
+    /// Any value under 'to' will be overwritten.
+    pub rename: Vec<(String, String)>,
+
+    /// Values to rewrite as (key, from, to).
+    ///
+    /// If 'from' is None, the value will be replaced regardless of what it is.
+    /// Otherwise it will only be replaced if it matches 'from' exactly.
+    pub rewrite: Vec<(String, Option<String>, String)>,
+
+    /// Keywords to insert if not already present as (key, value)
+    pub insert: Vec<(String, String)>,
+}
+
+/// Rules to remove or hash keywords which may identify a patient or sample.
+///
+/// These are applied after any repairs. Keys are given as they would appear
+/// in TEXT. The default will do nothing; use [`Anonymizer::identifying`] for a
+/// reasonable set of keys to remove.
+#[derive(Clone, Default)]
+pub struct Anonymizer {
+    /// Keys to remove
+    pub remove: Vec<String>,
+
+    /// Keys whose values will be replaced with a hash.
+    ///
+    /// This allows files from the same source to be linked without revealing
+    /// the original value. The hash is not cryptographic, so 'salt' should be
+    /// set to a secret value if the original values are easy to guess.
+    pub hash: Vec<String>,
+
+    /// String to prepend to each value before hashing
+    pub salt: String,
+
+    /// If true, remove all non-standard keywords
+    pub remove_nonstandard: bool,
+}
+
+/// A change made to TEXT by a repair or anonymization rule
+#[derive(Clone, Serialize)]
+pub enum KeywordModification {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Rewritten {
+        key: String,
+        old: String,
+        new: String,
+    },
+    Inserted {
+        key: String,
+        value: String,
+    },
+    // values are not stored for these since they may be sensitive
+    Removed {
+        key: String,
+    },
+    Hashed {
+        key: String,
+    },
+}
+
+impl KeywordRepairs {
+    pub fn is_empty(&self) -> bool {
+        self.rename.is_empty() && self.rewrite.is_empty() && self.insert.is_empty()
+    }
+
+    /// Apply all rules to keywords and return each change that was made.
+    pub(crate) fn apply(&self, kws: &mut ValidKeywords) -> Vec<KeywordModification> {
+        let mut mods = vec![];
+        for (from, to) in self.rename.iter() {
+            if let Ok(Some(v)) = kws.delete(from) {
+                if kws.set(to, v.clone()).is_ok() {
+                    mods.push(KeywordModification::Renamed {
+                        from: from.clone(),
+                        to: to.clone(),
+                    });
+                } else {
+                    // put it back if new key is invalid
+                    let _ = kws.set(from, v);
+                }
+            }
+        }
+        for (key, from, to) in self.rewrite.iter() {
+            if let Ok(Some(old)) = kws.get(key).map(|x| x.cloned())
+                && from.as_ref().is_none_or(|f| f == &old)
+                && &old != to
+            {
+                let _ = kws.set(key, to.clone());
+                mods.push(KeywordModification::Rewritten {
+                    key: key.clone(),
+                    old,
+                    new: to.clone(),
+                });
+            }
+        }
+        for (key, value) in self.insert.iter() {
+            if let Ok(None) = kws.get(key) {
+                let _ = kws.set(key, value.clone());
+                mods.push(KeywordModification::Inserted {
+                    key: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        mods
+    }
+}
+
+impl Anonymizer {
+    /// Remove keys which commonly identify a patient, sample, or operator.
+    ///
+    /// This covers $FIL, $OP, $SRC, $SMNO, $CELLS, $COM, $EXP, $INST, $PROJ,
+    /// and the plate, well, carrier, and location IDs.
+    pub fn identifying() -> Self {
+        let remove = [
+            "$FIL",
+            "$OP",
+            "$SRC",
+            "$SMNO",
+            "$CELLS",
+            "$COM",
+            "$EXP",
+            "$INST",
+            "$PROJ",
+            "$PLATEID",
+            "$PLATENAME",
+            "$WELLID",
+            "$CARRIERID",
+            "$CARRIERTYPE",
+            "$LOCATIONID",
+        ];
+        Self {
+            remove: remove.map(String::from).to_vec(),
+            ..Self::default()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remove.is_empty() && self.hash.is_empty() && !self.remove_nonstandard
+    }
+
+    /// Apply all rules to keywords and return each change that was made.
+    pub(crate) fn apply(&self, kws: &mut ValidKeywords) -> Vec<KeywordModification> {
+        let mut mods = vec![];
+        if self.remove_nonstandard {
+            for k in mem::take(&mut kws.nonstd).into_keys() {
+                mods.push(KeywordModification::Removed { key: k.to_string() });
+            }
+        }
+        for key in self.remove.iter() {
+            if let Ok(Some(_)) = kws.delete(key) {
+                mods.push(KeywordModification::Removed { key: key.clone() });
+            }
+        }
+        for key in self.hash.iter() {
+            if let Ok(Some(old)) = kws.get(key).map(|x| x.cloned()) {
+                let _ = kws.set(key, hash_value(&self.salt, &old));
+                mods.push(KeywordModification::Hashed { key: key.clone() });
+            }
+        }
+        mods
+    }
+}
+
+/// Return 64-bit FNV-1a hash of salt and value as hex.
+fn hash_value(salt: &str, value: &str) -> String {
+    let h = salt
+        .bytes()
+        .chain(value.bytes())
+        .fold(0xcbf29ce484222325_u64, |acc, b| {
+            (acc ^ u64::from(b)).wrapping_mul(0x100000001b3)
+        });
+    format!("{h:016x}")
+}
+
+/// Update key offsets to reflect renamed and removed keys.
+pub(crate) fn rename_offsets(mods: &[KeywordModification], offsets: &mut KeywordOffsets) {
+    // standard keys are stored uppercase, non-standard keys as-is
+    let norm = |k: &String| {
+        if k.starts_with('$') {
+            k.to_ascii_uppercase()
+        } else {
+            k.clone()
+        }
+    };
+    for m in mods {
+        match m {
+            KeywordModification::Renamed { from, to } => {
+                if let Some(o) = offsets.remove(&norm(from)) {
+                    offsets.insert(norm(to), o);
+                }
+            }
+            KeywordModification::Removed { key } => {
+                offsets.remove(&norm(key));
+            }
+            _ => (),
+        }
+    }
+}
+
+impl fmt::Display for KeywordModification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Renamed { from, to } => write!(f, "renamed key '{from}' to '{to}'"),
+            Self::Rewritten { key, old, new } => {
+                write!(f, "rewrote value for '{key}' from '{old}' to '{new}'")
+            }
+            Self::Inserted { key, value } => write!(f, "inserted '{key}' with value '{value}'"),
+            Self::Removed { key } => write!(f, "removed '{key}'"),
+            Self::Hashed { key } => write!(f, "replaced value for '{key}' with hash"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_value() {
+        assert_eq!("cbf29ce484222325", hash_value("", ""));
+        assert_eq!(hash_value("s", "x"), hash_value("", "sx"));
+        assert_ne!(hash_value("", "x"), hash_value("", "y"));
+    }
+}
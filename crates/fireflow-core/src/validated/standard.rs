@@ -1,15 +1,19 @@
-use crate::config::RawTextReadConfig;
+use crate::config::{RawTextReadConfig, TextEncoding};
 use crate::error::*;
+use crate::limits::{is_valid_key, STD_PREFIX};
 use crate::macros::{enum_from, enum_from_disp, match_many_to_one};
 use crate::text::index::IndexFromOne;
 use crate::validated::nonstandard::*;
 
 use serde::Serialize;
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::fmt;
+use std::hash::Hash;
+use std::mem;
 use std::str;
+use std::str::FromStr;
 
 /// Represents a standard key.
 ///
@@ -25,7 +29,7 @@ use std::str;
 pub struct StdKey(String);
 
 /// A collection dump for parsed keywords of varying quality
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct ParsedKeywords {
     /// Standard keywords (with '$')
     pub std: StdKeywords,
@@ -38,6 +42,15 @@ pub struct ParsedKeywords {
 
     /// Keywords that are not valid UTF-8 strings
     pub byte_pairs: BytesPairs,
+
+    /// Number of non-standard keywords that were not stored
+    pub nonstd_skipped: usize,
+
+    /// Keys whose values had binary contamination and were converted lossily
+    pub contaminated: Vec<String>,
+
+    /// Byte offset of each standard and non-standard key
+    pub offsets: KeywordOffsets,
 }
 
 /// 'ParsedKeywords' without the bad stuff
@@ -165,6 +178,12 @@ pub type StdKeywords = HashMap<StdKey, String>;
 pub type NonAsciiPairs = Vec<(String, String)>;
 pub type BytesPairs = Vec<(Vec<u8>, Vec<u8>)>;
 
+/// Byte offset of the first character of each key in TEXT.
+///
+/// Keys are stored as they appear when converted to strings, so standard keys
+/// will have a '$' and be uppercase.
+pub type KeywordOffsets = HashMap<String, u64>;
+
 impl AsRef<str> for StdKey {
     fn as_ref(&self) -> &str {
         self.0.as_ref()
@@ -177,6 +196,95 @@ impl Borrow<str> for StdKey {
     }
 }
 
+impl FromStr for StdKey {
+    type Err = StdKeyError;
+
+    /// Parse a standard key from a string.
+    ///
+    /// The string must start with '$' and be printable ASCII. Lowercase letters
+    /// will be converted to uppercase as is done when reading TEXT.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let k = s.as_bytes();
+        if k.len() > 1 && k[0] == STD_PREFIX && is_valid_key(&k[1..]) {
+            Ok(StdKey(s[1..].to_ascii_uppercase()))
+        } else {
+            Err(StdKeyError(s.to_string()))
+        }
+    }
+}
+
+impl ValidKeywords {
+    /// Set a keyword given a raw key string.
+    ///
+    /// The key will be treated as standard if it starts with '$' and
+    /// non-standard otherwise. Return the previous value if present. Values
+    /// may not be empty since these cannot be written to TEXT; use
+    /// [`ValidKeywords::delete`] instead.
+    pub fn set(&mut self, k: &str, v: String) -> Result<Option<String>, KeywordSetError> {
+        if v.is_empty() {
+            return Err(EmptyValueError(k.to_string()).into());
+        }
+        if k.starts_with('$') {
+            let kk = k.parse::<StdKey>()?;
+            Ok(self.std.insert(kk, v))
+        } else {
+            Ok(self.nonstd.insert(NonStdKey::from_unchecked(k), v))
+        }
+    }
+
+    /// Remove a keyword given a raw key string.
+    ///
+    /// Return the removed value if present.
+    pub fn delete(&mut self, k: &str) -> Result<Option<String>, StdKeyError> {
+        if k.starts_with('$') {
+            k.parse::<StdKey>().map(|kk| self.std.remove(&kk))
+        } else {
+            Ok(self.nonstd.remove(&NonStdKey::from_unchecked(k)))
+        }
+    }
+
+    /// Serialize all keywords to a TEXT segment using the given delimiter.
+    ///
+    /// Keys will be sorted, standard keys first. Delimiters in keys or values
+    /// will be escaped by doubling them. Keywords with empty values will be
+    /// skipped, since these would be read as an escaped delimiter.
+    pub fn to_text_bytes(&self, delim: u8) -> Vec<u8> {
+        let d = char::from(delim).to_string();
+        let dd = d.repeat(2);
+        let mut std: Vec<_> = self
+            .std
+            .iter()
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        let mut nonstd: Vec<_> = self
+            .nonstd
+            .iter()
+            .filter(|(_, v)| !v.is_empty())
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        std.sort();
+        nonstd.sort();
+        let mut out = d.clone();
+        for (k, v) in std.into_iter().chain(nonstd) {
+            out.push_str(&k.replace(&d, &dd));
+            out.push_str(&d);
+            out.push_str(&v.replace(&d, &dd));
+            out.push_str(&d);
+        }
+        out.into_bytes()
+    }
+
+    /// Get a keyword value given a raw key string.
+    pub fn get(&self, k: &str) -> Result<Option<&String>, StdKeyError> {
+        if k.starts_with('$') {
+            k.parse::<StdKey>().map(|kk| self.std.get(&kk))
+        } else {
+            Ok(self.nonstd.get(&NonStdKey::from_unchecked(k)))
+        }
+    }
+}
+
 impl fmt::Display for StdKey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "${}", self.0)
@@ -188,18 +296,32 @@ impl ParsedKeywords {
         &mut self,
         k: &[u8],
         v: &[u8],
+        pos: usize,
         conf: &RawTextReadConfig,
     ) -> Result<(), Leveled<KeywordInsertError>> {
-        // ASSUME key and value are never blank since we checked both prior to
-        // calling this. The FCS standards do not allow either to be blank.
+        // ASSUME key is never blank since we checked prior to calling this.
+        // The value may only be blank if we are keeping empty values; the FCS
+        // standards do not allow either to be blank.
         let n = k.len();
-        match str::from_utf8(v) {
-            Ok(vv) => {
+        // Values with binary contamination may be converted lossily if
+        // desired, in which case note the key so the user knows which values
+        // were altered.
+        let res = match decode_bytes(v, conf.text_encoding) {
+            Some(x) if !(conf.lossy_values && has_control_chars(v)) => Some(x),
+            _ if conf.lossy_values => {
+                self.contaminated
+                    .push(String::from_utf8_lossy(k).into_owned());
+                Some(Cow::Owned(lossy_value(v)))
+            }
+            _ => None,
+        };
+        match res {
+            Some(vv) => {
                 // Trim whitespace from value if desired. Warn (or half) if this
                 // results in a blank.
                 let value = if conf.trim_value_whitespace {
                     let trimmed = vv.trim();
-                    if trimmed.is_empty() {
+                    if trimmed.is_empty() && !conf.keep_empty_values {
                         let w = BlankValueError(k.to_vec());
                         return Err(Leveled::new(w.into(), !conf.allow_empty));
                     } else {
@@ -208,55 +330,71 @@ impl ParsedKeywords {
                 } else {
                     vv.to_string()
                 };
-                if n > 1 && k[0] == STD_PREFIX && is_printable_ascii(&k[1..]) {
+                if n > 1 && k[0] == STD_PREFIX && is_valid_key(&k[1..]) {
                     // Standard key: starts with '$', check remaining chars are
                     // ASCII and convert lowercase to uppercase
                     let xs = k[1..].iter().copied().map(ascii_to_upper).collect();
                     let kk = StdKey(unsafe { String::from_utf8_unchecked(xs) });
                     match self.std.entry(kk) {
-                        Entry::Occupied(e) => {
+                        Entry::Occupied(mut e) => {
                             let w = StdPresent {
                                 key: e.key().clone(),
-                                value,
+                                first: e.get().clone(),
+                                second: value.clone(),
                             };
-                            Err(Leveled::new(w.into(), !conf.allow_nonunique))
+                            if conf.nonunique_last_wins {
+                                self.offsets.insert(e.key().to_string(), pos as u64);
+                                e.insert(value);
+                            }
+                            Err(Leveled::new(Box::new(w).into(), !conf.allow_nonunique))
                         }
                         Entry::Vacant(e) => {
+                            self.offsets.insert(e.key().to_string(), pos as u64);
                             e.insert(value);
                             Ok(())
                         }
                     }
-                } else if n > 0 && is_printable_ascii(k) {
+                } else if n > 0 && is_valid_key(k) {
                     // Non-standard key: does not start with '$' but is still
                     // ASCII
+                    if conf.skip_nonstandard {
+                        self.nonstd_skipped += 1;
+                        return Ok(());
+                    }
                     let kk = NonStdKey::into_unchecked(unsafe {
                         String::from_utf8_unchecked(k.to_vec())
                     });
                     match self.nonstd.entry(kk) {
-                        Entry::Occupied(e) => {
+                        Entry::Occupied(mut e) => {
                             let w = NonStdPresent {
                                 key: e.key().clone(),
-                                value,
+                                first: e.get().clone(),
+                                second: value.clone(),
                             };
-                            Err(Leveled::new(w.into(), !conf.allow_nonunique))
+                            if conf.nonunique_last_wins {
+                                self.offsets.insert(e.key().to_string(), pos as u64);
+                                e.insert(value);
+                            }
+                            Err(Leveled::new(Box::new(w).into(), !conf.allow_nonunique))
                         }
                         Entry::Vacant(e) => {
+                            self.offsets.insert(e.key().to_string(), pos as u64);
                             e.insert(value);
                             Ok(())
                         }
                     }
-                } else if let Ok(kk) = String::from_utf8(k.to_vec()) {
+                } else if let Some(kk) = decode_bytes(k, conf.text_encoding) {
                     // Non-ascii key: these are technically not allowed but save
-                    // them anyways in case the user cares. If key isn't UTF-8
-                    // then give up.
-                    self.non_ascii.push((kk, value));
+                    // them anyways in case the user cares. If key can't be
+                    // decoded then give up.
+                    self.non_ascii.push((kk.into_owned(), value));
                     Ok(())
                 } else {
                     self.byte_pairs.push((k.to_vec(), value.into()));
                     Ok(())
                 }
             }
-            _ => {
+            None => {
                 self.byte_pairs.push((k.to_vec(), v.to_vec()));
                 Ok(())
             }
@@ -264,35 +402,206 @@ impl ParsedKeywords {
     }
 }
 
+impl ParsedKeywords {
+    /// Decode keywords with values that could not be decoded when parsing.
+    ///
+    /// The function should return the decoded value given a key and value, or
+    /// None if the pair should remain undecoded. Decoded pairs will be removed
+    /// from 'byte_pairs' and inserted as standard or non-standard keywords
+    /// (without offsets) unless the key is already present.
+    pub(crate) fn decode_byte_pairs<F>(&mut self, f: F)
+    where
+        F: Fn(&[u8], &[u8]) -> Option<String>,
+    {
+        let mut rest = vec![];
+        for (k, v) in mem::take(&mut self.byte_pairs) {
+            let key = str::from_utf8(&k).ok();
+            let inserted = match (key, f(&k, &v)) {
+                (Some(kk), Some(vv)) => {
+                    if let Ok(sk) = kk.parse::<StdKey>() {
+                        insert_vacant(&mut self.std, sk, vv)
+                    } else if is_valid_key(&k) && !k.starts_with(&[STD_PREFIX]) {
+                        insert_vacant(&mut self.nonstd, NonStdKey::from_unchecked(kk), vv)
+                    } else {
+                        false
+                    }
+                }
+                _ => false,
+            };
+            if !inserted {
+                rest.push((k, v));
+            }
+        }
+        self.byte_pairs = rest;
+    }
+}
+
+fn insert_vacant<K: Eq + Hash>(kws: &mut HashMap<K, String>, k: K, v: String) -> bool {
+    match kws.entry(k) {
+        Entry::Occupied(_) => false,
+        Entry::Vacant(e) => {
+            e.insert(v);
+            true
+        }
+    }
+}
+
+impl ParsedKeywords {
+    /// Merge keywords from supplemental TEXT into those from primary TEXT.
+    ///
+    /// Keys present in both with the same value are merged silently. Keys with
+    /// different values are returned as conflicts, and the value which is kept
+    /// depends on 'stext_takes_precedence'.
+    pub(crate) fn merge_supp(
+        &mut self,
+        supp: Self,
+        conf: &RawTextReadConfig,
+    ) -> Vec<Leveled<KeywordInsertError>> {
+        let std = supp
+            .std
+            .into_iter()
+            .flat_map(|(k, v)| merge_supp_value(&mut self.std, k, v, conf));
+        let nonstd = supp
+            .nonstd
+            .into_iter()
+            .flat_map(|(k, v)| merge_supp_value(&mut self.nonstd, k, v, conf));
+        let conflicts = std.collect::<Vec<_>>().into_iter().chain(nonstd).collect();
+        self.non_ascii.extend(supp.non_ascii);
+        self.byte_pairs.extend(supp.byte_pairs);
+        self.nonstd_skipped += supp.nonstd_skipped;
+        self.contaminated.extend(supp.contaminated);
+        for (k, o) in supp.offsets {
+            if conf.stext_takes_precedence {
+                self.offsets.insert(k, o);
+            } else {
+                self.offsets.entry(k).or_insert(o);
+            }
+        }
+        conflicts
+    }
+
+    /// Add a constant to all key offsets.
+    ///
+    /// Use this to make offsets relative to the start of the file rather than
+    /// the start of the buffer that was parsed.
+    pub(crate) fn shift_offsets(&mut self, base: u64) {
+        for o in self.offsets.values_mut() {
+            *o += base;
+        }
+    }
+}
+
+fn merge_supp_value<K>(
+    kws: &mut HashMap<K, String>,
+    k: K,
+    v: String,
+    conf: &RawTextReadConfig,
+) -> Option<Leveled<KeywordInsertError>>
+where
+    K: Eq + Hash + fmt::Display,
+{
+    match kws.entry(k) {
+        Entry::Vacant(e) => {
+            e.insert(v);
+            None
+        }
+        Entry::Occupied(mut e) => {
+            if e.get() == &v {
+                None
+            } else {
+                let w = SuppConflict {
+                    key: e.key().to_string(),
+                    primary: e.get().clone(),
+                    supp: v.clone(),
+                };
+                if conf.stext_takes_precedence {
+                    e.insert(v);
+                }
+                Some(Leveled::new(Box::new(w).into(), !conf.allow_nonunique))
+            }
+        }
+    }
+}
+
 enum_from_disp!(
     #[derive(Debug)]
     pub KeywordInsertError,
-    [StdPresent, StdPresent],
-    [NonStdPresent, NonStdPresent],
+    [StdPresent, Box<StdPresent>],
+    [NonStdPresent, Box<NonStdPresent>],
+    [SuppConflict, Box<SuppConflict>],
     [Blank, BlankValueError]
 );
 
 #[derive(Debug)]
 pub struct BlankValueError(pub Vec<u8>);
 
+#[derive(Debug)]
+pub struct StdKeyError(String);
+
+enum_from_disp!(
+    #[derive(Debug)]
+    pub KeywordSetError,
+    [Key, StdKeyError],
+    [Empty, EmptyValueError]
+);
+
+#[derive(Debug)]
+pub struct EmptyValueError(String);
+
 #[derive(Debug)]
 pub struct StdPresent {
     key: StdKey,
-    value: String,
+    first: String,
+    second: String,
 }
 
 #[derive(Debug)]
 pub struct NonStdPresent {
     key: NonStdKey,
-    value: String,
+    first: String,
+    second: String,
+}
+
+#[derive(Debug)]
+pub struct SuppConflict {
+    key: String,
+    primary: String,
+    supp: String,
 }
 
 impl fmt::Display for StdPresent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "std key '{}' already present, has value '{}'",
-            self.key, self.value
+            "std key '{}' present more than once, \
+             has values '{}' and '{}'",
+            self.key, self.first, self.second
+        )
+    }
+}
+
+impl fmt::Display for StdKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "standard key must start with '$' and be printable ASCII, found '{}'",
+            self.0
+        )
+    }
+}
+
+impl fmt::Display for EmptyValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "value for key '{}' cannot be empty", self.0)
+    }
+}
+
+impl fmt::Display for SuppConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "key '{}' has value '{}' in TEXT but '{}' in supplemental TEXT",
+            self.key, self.primary, self.supp
         )
     }
 }
@@ -301,14 +610,38 @@ impl fmt::Display for NonStdPresent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
             f,
-            "non-std key '{}' already present, has value '{}'",
-            self.key, self.value
+            "non-std key '{}' present more than once, \
+             has values '{}' and '{}'",
+            self.key, self.first, self.second
         )
     }
 }
 
-fn is_printable_ascii(xs: &[u8]) -> bool {
-    xs.iter().all(|x| 32 <= *x && *x <= 126)
+/// Decode bytes from TEXT using the given encoding.
+///
+/// Return None if the bytes are not valid for the encoding.
+fn decode_bytes(xs: &[u8], enc: TextEncoding) -> Option<Cow<'_, str>> {
+    let utf8 = || str::from_utf8(xs).ok().map(Cow::Borrowed);
+    let latin1 = || Cow::Owned(xs.iter().copied().map(char::from).collect::<String>());
+    match enc {
+        TextEncoding::Utf8 => utf8(),
+        TextEncoding::Latin1 => Some(latin1()),
+        TextEncoding::Utf8OrLatin1 => utf8().or_else(|| Some(latin1())),
+    }
+}
+
+fn has_control_chars(xs: &[u8]) -> bool {
+    xs.iter()
+        .any(|x| (*x < 32 && !matches!(x, 9 | 10 | 13)) || *x == 127)
+}
+
+/// Convert bytes to a string, dropping control characters and replacing
+/// invalid UTF-8.
+fn lossy_value(xs: &[u8]) -> String {
+    String::from_utf8_lossy(xs)
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r'))
+        .collect()
 }
 
 fn ascii_to_upper(x: u8) -> u8 {
@@ -318,5 +651,3 @@ fn ascii_to_upper(x: u8) -> u8 {
         x
     }
 }
-
-const STD_PREFIX: u8 = 36; // '$'
@@ -1,3 +1,5 @@
+use crate::limits::{is_valid_delim, DELIM_RANGE};
+
 use std::fmt;
 
 /// The delimiter used when writing TEXT
@@ -12,10 +14,10 @@ impl Default for TEXTDelim {
 
 impl TEXTDelim {
     pub fn new(x: u8) -> Result<TEXTDelim, TEXTDelimError> {
-        if (1..=126).contains(&x) {
-            Err(TEXTDelimError(x))
-        } else {
+        if is_valid_delim(x) {
             Ok(TEXTDelim(x))
+        } else {
+            Err(TEXTDelimError(x))
         }
     }
 
@@ -28,6 +30,31 @@ pub struct TEXTDelimError(u8);
 
 impl fmt::Display for TEXTDelimError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        write!(f, "delimiter should be char b/t 1 and 126, got {}", self.0)
+        write!(
+            f,
+            "delimiter should be char b/t {} and {}, got {}",
+            DELIM_RANGE.start(),
+            DELIM_RANGE.end(),
+            self.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_accepts_valid_delims() {
+        for x in [1, b'/', b'|', 126] {
+            assert_eq!(TEXTDelim::new(x).ok().map(|d| d.inner()), Some(x));
+        }
+    }
+
+    #[test]
+    fn test_new_rejects_invalid_delims() {
+        for x in [0, 127, 255] {
+            assert_eq!(TEXTDelim::new(x).err().map(|e| e.0), Some(x));
+        }
     }
 }
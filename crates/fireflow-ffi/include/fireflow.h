@@ -0,0 +1,56 @@
+#ifndef FIREFLOW_H
+#define FIREFLOW_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* An opened FCS dataset */
+typedef struct FireflowFile FireflowFile;
+
+/* Summary of an opened dataset; new fields will only be appended */
+typedef struct FireflowSummary {
+    uint32_t version_major;
+    uint32_t version_minor;
+    size_t channels;
+    size_t events;
+} FireflowSummary;
+
+/* Last error on this thread or NULL; owned by the library */
+const char *fireflow_last_error(void);
+
+/* Open a file with default settings; NULL on failure */
+FireflowFile *fireflow_open(const char *path);
+
+/* Release a file from fireflow_open */
+void fireflow_free(FireflowFile *file);
+
+/* Release a string returned by fireflow_get_keyword */
+void fireflow_free_string(char *s);
+
+/* Fill out with a summary; 0 on success, -1 on failure */
+int fireflow_summary(const FireflowFile *file, FireflowSummary *out);
+
+/* Value of a keyword (include '$' for standard keys) or NULL */
+char *fireflow_get_keyword(const FireflowFile *file, const char *key);
+
+/* Number of channels (ie $PAR) */
+size_t fireflow_get_channel_count(const FireflowFile *file);
+
+/* Number of events */
+size_t fireflow_get_event_count(const FireflowFile *file);
+
+/* Copy channel (from 0) to out; number of values written or -1 on failure */
+int64_t fireflow_copy_channel_data_f64(const FireflowFile *file,
+                                       size_t channel,
+                                       double *out,
+                                       size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FIREFLOW_H */
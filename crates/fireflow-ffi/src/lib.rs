@@ -0,0 +1,441 @@
+//! C interface for reading FCS files.
+//!
+//! A file is opened with [`fireflow_open`] which returns an opaque handle that
+//! must be released with [`fireflow_free`]. Strings returned by this library
+//! must be released with [`fireflow_free_string`]. Functions which can fail
+//! return NULL or a negative number, after which [`fireflow_last_error`] will
+//! describe the problem. Panics are caught at the boundary and reported the
+//! same way.
+//!
+//! See `include/fireflow.h` for the corresponding declarations.
+
+use fireflow_core::api::fcs_read_std_dataset;
+use fireflow_core::config::DataReadConfig;
+use fireflow_core::core::AnyCoreDataset;
+use fireflow_core::error::Failure;
+use fireflow_core::header::Version;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString, c_char, c_int};
+use std::fmt;
+use std::panic;
+use std::path;
+use std::ptr;
+
+/// An opened FCS dataset (opaque to C)
+pub struct FireflowFile {
+    core: AnyCoreDataset,
+    keywords: HashMap<String, String>,
+}
+
+/// Summary of an opened dataset.
+///
+/// The layout of this struct is stable; new fields will only be appended.
+#[repr(C)]
+pub struct FireflowSummary {
+    /// Major version (ie 3 for FCS3.1)
+    pub version_major: u32,
+
+    /// Minor version (ie 1 for FCS3.1)
+    pub version_minor: u32,
+
+    /// Number of measurements (ie $PAR)
+    pub channels: usize,
+
+    /// Number of events in DATA
+    pub events: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error<E: fmt::Display>(e: E) {
+    // remove NULs so the message can always be converted
+    let msg = e.to_string().replace('\0', "");
+    let c = CString::new(msg).unwrap_or_default();
+    LAST_ERROR.with(|x| *x.borrow_mut() = Some(c));
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|x| *x.borrow_mut() = None);
+}
+
+/// Run 'f', returning 'on_panic' rather than unwinding into C.
+fn catch<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or_else(|e| {
+        let msg = e
+            .downcast_ref::<&str>()
+            .map(|x| (*x).to_string())
+            .or_else(|| e.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown".into());
+        set_error(format!("internal error: {msg}"));
+        on_panic
+    })
+}
+
+fn format_failure<E: fmt::Display, T: fmt::Display>(f: Failure<E, T>) -> String {
+    match f {
+        Failure::Single(t) => t.to_string(),
+        Failure::Many(t, es) => {
+            let mut s = t.to_string();
+            for e in *es {
+                s.push_str("; ");
+                s.push_str(&e.to_string());
+            }
+            s
+        }
+    }
+}
+
+/// Return the last error on this thread, or NULL if none.
+///
+/// The returned string is owned by this library and is valid until the next
+/// call into this library on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn fireflow_last_error() -> *const c_char {
+    catch(ptr::null(), || {
+        LAST_ERROR.with(|x| x.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+    })
+}
+
+/// Open and read an FCS file using default settings.
+///
+/// Return NULL on failure. Warnings are ignored.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_open(path: *const c_char) -> *mut FireflowFile {
+    catch(ptr::null_mut(), || {
+        clear_error();
+        if path.is_null() {
+            set_error("path is NULL");
+            return ptr::null_mut();
+        }
+        // SAFETY: caller guarantees this is a valid C string
+        let p = match unsafe { CStr::from_ptr(path) }.to_str() {
+            Ok(s) => path::PathBuf::from(s),
+            Err(e) => {
+                set_error(e);
+                return ptr::null_mut();
+            }
+        };
+        let conf = DataReadConfig::default();
+        match fcs_read_std_dataset(&p, &conf) {
+            Ok(t) => {
+                let (out, _) = t.resolve(|_| ());
+                let dataset = out.dataset;
+                let core = dataset.standardized.core;
+                let mut keywords = core.raw_keywords(None, None);
+                keywords.extend(
+                    dataset
+                        .pseudostandard
+                        .into_iter()
+                        .map(|(k, v)| (k.to_string(), v)),
+                );
+                Box::into_raw(Box::new(FireflowFile { core, keywords }))
+            }
+            Err(f) => {
+                let ((), msg) = f.resolve(|_| (), format_failure);
+                set_error(msg);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Release a file opened with [`fireflow_open`].
+///
+/// # Safety
+///
+/// `file` must be NULL or a pointer returned by [`fireflow_open`] which has
+/// not already been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_free(file: *mut FireflowFile) {
+    catch((), || {
+        if !file.is_null() {
+            // SAFETY: caller guarantees this came from Box::into_raw
+            drop(unsafe { Box::from_raw(file) });
+        }
+    })
+}
+
+/// Release a string returned by this library.
+///
+/// # Safety
+///
+/// `s` must be NULL or a pointer returned by [`fireflow_get_keyword`] which
+/// has not already been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_free_string(s: *mut c_char) {
+    catch((), || {
+        if !s.is_null() {
+            // SAFETY: caller guarantees this came from CString::into_raw
+            drop(unsafe { CString::from_raw(s) });
+        }
+    })
+}
+
+/// Fill `out` with a summary of an opened file.
+///
+/// Return 0 on success and -1 on failure.
+///
+/// # Safety
+///
+/// `file` must be a valid handle and `out` must point to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_summary(
+    file: *const FireflowFile,
+    out: *mut FireflowSummary,
+) -> c_int {
+    catch(-1, || {
+        clear_error();
+        // SAFETY: caller guarantees this is a valid handle or NULL
+        let Some(f) = (unsafe { file.as_ref() }) else {
+            set_error("file is NULL");
+            return -1;
+        };
+        if out.is_null() {
+            set_error("output is NULL");
+            return -1;
+        }
+        let (version_major, version_minor) = match f.core.version() {
+            Version::FCS2_0 => (2, 0),
+            Version::FCS3_0 => (3, 0),
+            Version::FCS3_1 => (3, 1),
+            Version::FCS3_2 => (3, 2),
+        };
+        let s = FireflowSummary {
+            version_major,
+            version_minor,
+            channels: f.core.par().0,
+            events: f.core.nrows(),
+        };
+        // SAFETY: checked for NULL above, caller guarantees it is writable
+        unsafe { out.write(s) };
+        0
+    })
+}
+
+/// Return the value of a keyword, or NULL if not found.
+///
+/// Standard keywords must include the leading '$'. The returned string must
+/// be released with [`fireflow_free_string`].
+///
+/// # Safety
+///
+/// `file` must be a valid handle and `key` must be a valid NUL-terminated
+/// string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_get_keyword(
+    file: *const FireflowFile,
+    key: *const c_char,
+) -> *mut c_char {
+    catch(ptr::null_mut(), || {
+        clear_error();
+        // SAFETY: caller guarantees this is a valid handle or NULL
+        let Some(f) = (unsafe { file.as_ref() }) else {
+            set_error("file is NULL");
+            return ptr::null_mut();
+        };
+        if key.is_null() {
+            set_error("key is NULL");
+            return ptr::null_mut();
+        }
+        // SAFETY: caller guarantees this is a valid C string
+        let k = unsafe { CStr::from_ptr(key) }.to_string_lossy();
+        match f.keywords.get(k.as_ref()) {
+            Some(v) => CString::new(v.replace('\0', "")).map_or(ptr::null_mut(), CString::into_raw),
+            None => {
+                set_error(format!("keyword '{k}' not found"));
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Return the number of channels (ie $PAR), or 0 if `file` is NULL.
+///
+/// # Safety
+///
+/// `file` must be NULL or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_get_channel_count(file: *const FireflowFile) -> usize {
+    catch(0, || {
+        // SAFETY: caller guarantees this is a valid handle or NULL
+        unsafe { file.as_ref() }.map_or(0, |f| f.core.par().0)
+    })
+}
+
+/// Return the number of events, or 0 if `file` is NULL.
+///
+/// # Safety
+///
+/// `file` must be NULL or a valid handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_get_event_count(file: *const FireflowFile) -> usize {
+    catch(0, || {
+        // SAFETY: caller guarantees this is a valid handle or NULL
+        unsafe { file.as_ref() }.map_or(0, |f| f.core.nrows())
+    })
+}
+
+/// Copy one channel (indexed from 0) into `out` as doubles.
+///
+/// `len` must be at least the number of events. Return the number of values
+/// written or -1 on failure.
+///
+/// # Safety
+///
+/// `file` must be a valid handle and `out` must point to at least `len`
+/// writable doubles.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fireflow_copy_channel_data_f64(
+    file: *const FireflowFile,
+    channel: usize,
+    out: *mut f64,
+    len: usize,
+) -> i64 {
+    catch(-1, || {
+        clear_error();
+        // SAFETY: caller guarantees this is a valid handle or NULL
+        let Some(f) = (unsafe { file.as_ref() }) else {
+            set_error("file is NULL");
+            return -1;
+        };
+        if out.is_null() {
+            set_error("output is NULL");
+            return -1;
+        }
+        let Some(c) = f.core.as_data().iter_columns().nth(channel) else {
+            set_error(format!("channel {channel} does not exist"));
+            return -1;
+        };
+        let xs = c.to_f64s();
+        if xs.len() > len {
+            set_error(format!(
+                "output has length {len} but channel has {} events",
+                xs.len()
+            ));
+            return -1;
+        }
+        // SAFETY: caller guarantees 'out' has at least 'len' elements, which is
+        // at least the length of the source
+        unsafe { ptr::copy_nonoverlapping(xs.as_ptr(), out, xs.len()) };
+        xs.len() as i64
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build an FCS 3.0 file with two 8-bit measurements.
+    fn fcs_3_0(data: &[u8]) -> Vec<u8> {
+        let text_begin = 58;
+        let mut data_begin = text_begin;
+        loop {
+            let data_end = data_begin + data.len() - 1;
+            let text = format!(
+                "/$BEGINANALYSIS/0/$ENDANALYSIS/0/$BEGINSTEXT/0/$ENDSTEXT/0\
+                 /$BEGINDATA/{data_begin}/$ENDDATA/{data_end}/$BYTEORD/1\
+                 /$CYT/FACS/$DATATYPE/I/$MODE/L/$NEXTDATA/0/$PAR/2/$TOT/{}\
+                 /$P1B/8/$P1E/0,0/$P1N/FSC/$P1R/256\
+                 /$P2B/8/$P2E/0,0/$P2N/SSC/$P2R/256/",
+                data.len() / 2
+            );
+            let next_begin = text_begin + text.len();
+            if next_begin == data_begin {
+                let header = format!(
+                    "FCS3.0    {:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+                    text_begin,
+                    next_begin - 1,
+                    data_begin,
+                    data_end,
+                    0,
+                    0
+                );
+                let mut buf = header.into_bytes();
+                buf.extend(text.as_bytes());
+                buf.extend(data);
+                return buf;
+            }
+            data_begin = next_begin;
+        }
+    }
+
+    fn last_error() -> String {
+        let e = fireflow_last_error();
+        assert!(!e.is_null());
+        unsafe { CStr::from_ptr(e) }.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_open_copy_free() {
+        let p = std::env::temp_dir().join(format!("fireflow-ffi-{}.fcs", std::process::id()));
+        std::fs::write(&p, fcs_3_0(&[1, 2, 3, 4, 5, 6])).unwrap();
+        let path = CString::new(p.to_str().unwrap()).unwrap();
+        let file = unsafe { fireflow_open(path.as_ptr()) };
+        std::fs::remove_file(&p).unwrap();
+        assert!(!file.is_null(), "{}", last_error());
+
+        let mut summary = FireflowSummary {
+            version_major: 0,
+            version_minor: 0,
+            channels: 0,
+            events: 0,
+        };
+        assert_eq!(0, unsafe { fireflow_summary(file, &mut summary) });
+        assert_eq!(
+            (3, 0, 2, 3),
+            (
+                summary.version_major,
+                summary.version_minor,
+                summary.channels,
+                summary.events
+            )
+        );
+
+        let key = CString::new("$P2N").unwrap();
+        let v = unsafe { fireflow_get_keyword(file, key.as_ptr()) };
+        assert!(!v.is_null(), "{}", last_error());
+        assert_eq!("SSC", unsafe { CStr::from_ptr(v) }.to_str().unwrap());
+        unsafe { fireflow_free_string(v) };
+
+        let mut out = [0.0; 3];
+        let n = unsafe { fireflow_copy_channel_data_f64(file, 1, out.as_mut_ptr(), out.len()) };
+        assert_eq!(3, n);
+        assert_eq!([2.0, 4.0, 6.0], out);
+
+        let mut short = [0.0; 2];
+        let n = unsafe { fireflow_copy_channel_data_f64(file, 0, short.as_mut_ptr(), 2) };
+        assert_eq!(-1, n);
+        assert!(last_error().contains("length 2"));
+
+        let n = unsafe { fireflow_copy_channel_data_f64(file, 2, out.as_mut_ptr(), 3) };
+        assert_eq!(-1, n);
+        assert!(last_error().contains("channel 2"));
+
+        unsafe { fireflow_free(file) };
+    }
+
+    #[test]
+    fn test_open_missing() {
+        let path = CString::new("/nonexistent/fireflow.fcs").unwrap();
+        let file = unsafe { fireflow_open(path.as_ptr()) };
+        assert!(file.is_null());
+        assert!(!last_error().is_empty());
+        assert!(unsafe { fireflow_open(ptr::null()) }.is_null());
+        assert_eq!("path is NULL", last_error());
+    }
+
+    #[test]
+    fn test_catch_panic() {
+        let x = catch(-1, || panic!("boom"));
+        assert_eq!(-1, x);
+        assert_eq!("internal error: boom", last_error());
+    }
+}
@@ -10,12 +10,15 @@ use fireflow_core::text::keywords::*;
 use fireflow_core::text::named_vec::Element;
 use fireflow_core::text::optionalkw::*;
 use fireflow_core::text::ranged_float::*;
+use fireflow_core::text::registry::KeywordRegistry;
 use fireflow_core::text::scale::*;
+use fireflow_core::text::vendor::VendorProfile;
 use fireflow_core::validated::dataframe::*;
 use fireflow_core::validated::datepattern::DatePattern;
 use fireflow_core::validated::nonstandard::*;
 use fireflow_core::validated::other_width::*;
 use fireflow_core::validated::pattern::*;
+use fireflow_core::validated::repair::{Anonymizer, KeywordRepairs};
 use fireflow_core::validated::shortname::*;
 
 use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
@@ -84,7 +87,58 @@ fn pyreflow(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(py_fcs_read_header, m)?)?;
     m.add_function(wrap_pyfunction!(py_fcs_read_raw_text, m)?)?;
     m.add_function(wrap_pyfunction!(py_fcs_read_std_text, m)?)?;
-    m.add_function(wrap_pyfunction!(py_fcs_read_std_dataset, m)?)
+    m.add_function(wrap_pyfunction!(py_fcs_read_std_dataset, m)?)?;
+    m.add_function(wrap_pyfunction!(py_read_fcs, m)?)
+}
+
+/// Read an FCS file using default settings.
+///
+/// Return a dict with "version", "keywords" (all TEXT keywords including
+/// pseudostandard and nonstandard), and "data" (one numpy array per
+/// measurement keyed by $PnN, with the same type as stored in DATA).
+#[pyfunction]
+#[pyo3(name = "read_fcs")]
+fn py_read_fcs(py: Python<'_>, p: path::PathBuf) -> PyResult<Bound<'_, PyDict>> {
+    let conf = DataReadConfig::default();
+    let out: StdDatasetOutput =
+        fcs_read_std_dataset(&p, &conf).map_or_else(|e| Err(handle_failure(e)), handle_warnings)?;
+    let core = &out.dataset.standardized.core;
+
+    let keywords = PyDict::new(py);
+    for (k, v) in core.raw_keywords(None, None) {
+        keywords.set_item(k, v)?;
+    }
+    for (k, v) in out
+        .dataset
+        .pseudostandard
+        .iter()
+        .chain(out.dataset.unused_standard_keywords.iter())
+    {
+        keywords.set_item(k.to_string(), v)?;
+    }
+
+    let data = PyDict::new(py);
+    for (n, c) in core
+        .shortnames()
+        .into_iter()
+        .zip(core.as_data().iter_columns())
+    {
+        let xs = match c {
+            AnyFCSColumn::U08(xs) => xs.0.to_pyarray(py).into_any(),
+            AnyFCSColumn::U16(xs) => xs.0.to_pyarray(py).into_any(),
+            AnyFCSColumn::U32(xs) => xs.0.to_pyarray(py).into_any(),
+            AnyFCSColumn::U64(xs) => xs.0.to_pyarray(py).into_any(),
+            AnyFCSColumn::F32(xs) => xs.0.to_pyarray(py).into_any(),
+            AnyFCSColumn::F64(xs) => xs.0.to_pyarray(py).into_any(),
+        };
+        data.set_item(n.to_string(), xs)?;
+    }
+
+    let res = PyDict::new(py);
+    res.set_item("version", core.version().to_string())?;
+    res.set_item("keywords", keywords)?;
+    res.set_item("data", data)?;
+    Ok(res)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -151,21 +205,34 @@ fn py_fcs_read_header(
 
         supp_text_correction=(0,0),
         use_literal_delims=false,
+        fallback_literal_delims=false,
         allow_non_ascii_delim=false,
         ignore_stext=false,
         allow_duplicated_stext=false,
         allow_missing_final_delim=false,
         allow_nonunique=false,
+        nonunique_last_wins=false,
         allow_odd=false,
         allow_delim_at_boundary=false,
         allow_empty=false,
+        keep_empty_values=false,
         allow_non_utf8=false,
         allow_non_ascii_keywords=false,
         allow_missing_stext=false,
         allow_stext_own_delim=false,
+        stext_takes_precedence=false,
         allow_missing_nextdata=false,
         trim_value_whitespace=false,
-        date_pattern=None
+        date_pattern=None,
+        skip_nonstandard=false,
+        keep_raw_text=false,
+        keep_others=false,
+        collect_metrics=false,
+        lossy_values=false,
+        text_encoding="utf8".to_string(),
+        rename_keys=vec![],
+        rewrite_values=vec![],
+        insert_keys=vec![]
     )
 )]
 fn py_fcs_read_raw_text(
@@ -185,21 +252,34 @@ fn py_fcs_read_raw_text(
 
     supp_text_correction: (i32, i32),
     use_literal_delims: bool,
+    fallback_literal_delims: bool,
     allow_non_ascii_delim: bool,
     ignore_stext: bool,
     allow_duplicated_stext: bool,
     allow_missing_final_delim: bool,
     allow_nonunique: bool,
+    nonunique_last_wins: bool,
     allow_odd: bool,
     allow_delim_at_boundary: bool,
     allow_empty: bool,
+    keep_empty_values: bool,
     allow_non_utf8: bool,
     allow_non_ascii_keywords: bool,
     allow_missing_stext: bool,
     allow_stext_own_delim: bool,
+    stext_takes_precedence: bool,
     allow_missing_nextdata: bool,
     trim_value_whitespace: bool,
     date_pattern: Option<String>,
+    skip_nonstandard: bool,
+    keep_raw_text: bool,
+    keep_others: bool,
+    collect_metrics: bool,
+    lossy_values: bool,
+    text_encoding: String,
+    rename_keys: Vec<(String, String)>,
+    rewrite_values: Vec<(String, Option<String>, String)>,
+    insert_keys: Vec<(String, String)>,
 ) -> PyResult<(PyVersion, Bound<'_, PyDict>, Bound<'_, PyDict>, PyParseData)> {
     let header = header_config(
         version_override,
@@ -217,21 +297,34 @@ fn py_fcs_read_raw_text(
         header,
         supp_text_correction,
         use_literal_delims,
+        fallback_literal_delims,
         allow_non_ascii_delim,
         ignore_stext,
         allow_duplicated_stext,
         allow_missing_final_delim,
         allow_nonunique,
+        nonunique_last_wins,
         allow_odd,
         allow_delim_at_boundary,
         allow_empty,
+        keep_empty_values,
         allow_non_utf8,
         allow_non_ascii_keywords,
         allow_missing_stext,
         allow_stext_own_delim,
+        stext_takes_precedence,
         allow_missing_nextdata,
         trim_value_whitespace,
         date_pattern,
+        skip_nonstandard,
+        keep_raw_text,
+        keep_others,
+        collect_metrics,
+        lossy_values,
+        text_encoding,
+        rename_keys,
+        rewrite_values,
+        insert_keys,
     )?;
 
     let raw: RawTEXTOutput =
@@ -270,26 +363,40 @@ fn py_fcs_read_raw_text(
 
         supp_text_correction=(0,0),
         use_literal_delims=false,
+        fallback_literal_delims=false,
         allow_non_ascii_delim=false,
         ignore_stext=false,
         allow_duplicated_stext=false,
         allow_missing_final_delim=false,
         allow_nonunique=false,
+        nonunique_last_wins=false,
         allow_odd=false,
         allow_delim_at_boundary=false,
         allow_empty=false,
+        keep_empty_values=false,
         allow_non_utf8=false,
         allow_non_ascii_keywords=false,
         allow_missing_stext=false,
         allow_stext_own_delim=false,
+        stext_takes_precedence=false,
         allow_missing_nextdata=false,
         trim_value_whitespace=false,
         date_pattern=None,
+        skip_nonstandard=false,
+        keep_raw_text=false,
+        keep_others=false,
+        collect_metrics=false,
+        lossy_values=false,
+        text_encoding="utf8".to_string(),
+        rename_keys=vec![],
+        rewrite_values=vec![],
+        insert_keys=vec![],
 
         disallow_deprecated=false,
         time_ensure=false,
         allow_pseudostandard=false,
         fix_log_scale_offsets=false,
+        infer_par=false,
         shortname_prefix=None,
         nonstandard_measurement_pattern=None,
         time_pattern=None,
@@ -312,26 +419,40 @@ fn py_fcs_read_std_text(
 
     supp_text_correction: (i32, i32),
     use_literal_delims: bool,
+    fallback_literal_delims: bool,
     allow_non_ascii_delim: bool,
     ignore_stext: bool,
     allow_duplicated_stext: bool,
     allow_missing_final_delim: bool,
     allow_nonunique: bool,
+    nonunique_last_wins: bool,
     allow_odd: bool,
     allow_delim_at_boundary: bool,
     allow_empty: bool,
+    keep_empty_values: bool,
     allow_non_utf8: bool,
     allow_non_ascii_keywords: bool,
     allow_missing_stext: bool,
     allow_stext_own_delim: bool,
+    stext_takes_precedence: bool,
     allow_missing_nextdata: bool,
     trim_value_whitespace: bool,
     date_pattern: Option<String>,
+    skip_nonstandard: bool,
+    keep_raw_text: bool,
+    keep_others: bool,
+    collect_metrics: bool,
+    lossy_values: bool,
+    text_encoding: String,
+    rename_keys: Vec<(String, String)>,
+    rewrite_values: Vec<(String, Option<String>, String)>,
+    insert_keys: Vec<(String, String)>,
 
     disallow_deprecated: bool,
     time_ensure: bool,
     allow_pseudostandard: bool,
     fix_log_scale_offsets: bool,
+    infer_par: bool,
     shortname_prefix: Option<String>,
     nonstandard_measurement_pattern: Option<String>,
     time_pattern: Option<String>,
@@ -352,21 +473,34 @@ fn py_fcs_read_std_text(
         header,
         supp_text_correction,
         use_literal_delims,
+        fallback_literal_delims,
         allow_non_ascii_delim,
         ignore_stext,
         allow_duplicated_stext,
         allow_missing_final_delim,
         allow_nonunique,
+        nonunique_last_wins,
         allow_odd,
         allow_delim_at_boundary,
         allow_empty,
+        keep_empty_values,
         allow_non_utf8,
         allow_non_ascii_keywords,
         allow_missing_stext,
         allow_stext_own_delim,
+        stext_takes_precedence,
         allow_missing_nextdata,
         trim_value_whitespace,
         date_pattern,
+        skip_nonstandard,
+        keep_raw_text,
+        keep_others,
+        collect_metrics,
+        lossy_values,
+        text_encoding,
+        rename_keys,
+        rewrite_values,
+        insert_keys,
     )?;
 
     let conf = std_config(
@@ -375,6 +509,7 @@ fn py_fcs_read_std_text(
         time_ensure,
         allow_pseudostandard,
         fix_log_scale_offsets,
+        infer_par,
         shortname_prefix,
         nonstandard_measurement_pattern,
         time_pattern,
@@ -398,6 +533,7 @@ fn py_fcs_read_std_text(
         out.parse.into(),
         out.pseudostandard
             .into_iter()
+            .chain(out.unused_standard_keywords)
             .map(|(k, v)| (k.to_string(), v.clone()))
             .into_py_dict(py)?,
     ))
@@ -422,38 +558,64 @@ fn py_fcs_read_std_text(
 
         supp_text_correction=(0,0),
         use_literal_delims=false,
+        fallback_literal_delims=false,
         allow_non_ascii_delim=false,
         ignore_stext=false,
         allow_duplicated_stext=false,
         allow_missing_final_delim=false,
         allow_nonunique=false,
+        nonunique_last_wins=false,
         allow_odd=false,
         allow_delim_at_boundary=false,
         allow_empty=false,
+        keep_empty_values=false,
         allow_non_utf8=false,
         allow_non_ascii_keywords=false,
         allow_missing_stext=false,
         allow_stext_own_delim=false,
+        stext_takes_precedence=false,
         allow_missing_nextdata=false,
         trim_value_whitespace=false,
         date_pattern=None,
+        skip_nonstandard=false,
+        keep_raw_text=false,
+        keep_others=false,
+        collect_metrics=false,
+        lossy_values=false,
+        text_encoding="utf8".to_string(),
+        rename_keys=vec![],
+        rewrite_values=vec![],
+        insert_keys=vec![],
 
         disallow_deprecated=false,
         time_ensure=false,
         allow_pseudostandard=false,
         fix_log_scale_offsets=false,
+        infer_par=false,
         shortname_prefix=None,
         nonstandard_measurement_pattern=None,
         time_pattern=None,
 
         allow_uneven_event_width=false,
+        fix_uneven_event_width=false,
         allow_tot_mismatch=false,
         allow_header_text_offset_mismatch=false,
         allow_missing_required_offsets=false,
+        guess_byteord=false,
+        warn_overrange=false,
+        lenient_ascii=false,
+        check_crc=false,
+        allow_crc_mismatch=false,
+        check_data_hash=false,
+        allow_data_hash_mismatch=false,
+        allow_non_list_mode=false,
         text_data_correction=(0,0),
         text_analysis_correction=(0,0),
         disallow_bitmask_truncation=false,
-        warnings_are_errors=false
+        pad_integer_widths=false,
+        warnings_are_errors=false,
+        measurements=None,
+        event_range=None
     )
 )]
 fn py_fcs_read_std_dataset(
@@ -473,38 +635,64 @@ fn py_fcs_read_std_dataset(
 
     supp_text_correction: (i32, i32),
     use_literal_delims: bool,
+    fallback_literal_delims: bool,
     allow_non_ascii_delim: bool,
     ignore_stext: bool,
     allow_duplicated_stext: bool,
     allow_missing_final_delim: bool,
     allow_nonunique: bool,
+    nonunique_last_wins: bool,
     allow_odd: bool,
     allow_delim_at_boundary: bool,
     allow_empty: bool,
+    keep_empty_values: bool,
     allow_non_utf8: bool,
     allow_non_ascii_keywords: bool,
     allow_missing_stext: bool,
     allow_stext_own_delim: bool,
+    stext_takes_precedence: bool,
     allow_missing_nextdata: bool,
     trim_value_whitespace: bool,
     date_pattern: Option<String>,
+    skip_nonstandard: bool,
+    keep_raw_text: bool,
+    keep_others: bool,
+    collect_metrics: bool,
+    lossy_values: bool,
+    text_encoding: String,
+    rename_keys: Vec<(String, String)>,
+    rewrite_values: Vec<(String, Option<String>, String)>,
+    insert_keys: Vec<(String, String)>,
 
     disallow_deprecated: bool,
     time_ensure: bool,
     allow_pseudostandard: bool,
     fix_log_scale_offsets: bool,
+    infer_par: bool,
     shortname_prefix: Option<String>,
     nonstandard_measurement_pattern: Option<String>,
     time_pattern: Option<String>,
 
     allow_uneven_event_width: bool,
+    fix_uneven_event_width: bool,
     allow_tot_mismatch: bool,
     allow_header_text_offset_mismatch: bool,
     allow_missing_required_offsets: bool,
+    guess_byteord: bool,
+    warn_overrange: bool,
+    lenient_ascii: bool,
+    check_crc: bool,
+    allow_crc_mismatch: bool,
+    check_data_hash: bool,
+    allow_data_hash_mismatch: bool,
+    allow_non_list_mode: bool,
     text_data_correction: (i32, i32),
     text_analysis_correction: (i32, i32),
     disallow_bitmask_truncation: bool,
+    pad_integer_widths: bool,
     warnings_are_errors: bool,
+    measurements: Option<Vec<String>>,
+    event_range: Option<(usize, usize)>,
 ) -> PyResult<(Bound<'_, PyAny>, PyParseData, Bound<'_, PyDict>)> {
     let header = header_config(
         version_override,
@@ -522,21 +710,34 @@ fn py_fcs_read_std_dataset(
         header,
         supp_text_correction,
         use_literal_delims,
+        fallback_literal_delims,
         allow_non_ascii_delim,
         ignore_stext,
         allow_duplicated_stext,
         allow_missing_final_delim,
         allow_nonunique,
+        nonunique_last_wins,
         allow_odd,
         allow_delim_at_boundary,
         allow_empty,
+        keep_empty_values,
         allow_non_utf8,
         allow_non_ascii_keywords,
         allow_missing_stext,
         allow_stext_own_delim,
+        stext_takes_precedence,
         allow_missing_nextdata,
         trim_value_whitespace,
         date_pattern,
+        skip_nonstandard,
+        keep_raw_text,
+        keep_others,
+        collect_metrics,
+        lossy_values,
+        text_encoding,
+        rename_keys,
+        rewrite_values,
+        insert_keys,
     )?;
 
     let standard = std_config(
@@ -545,6 +746,7 @@ fn py_fcs_read_std_dataset(
         time_ensure,
         allow_pseudostandard,
         fix_log_scale_offsets,
+        infer_par,
         shortname_prefix,
         nonstandard_measurement_pattern,
         time_pattern,
@@ -553,14 +755,26 @@ fn py_fcs_read_std_dataset(
     let conf = data_config(
         standard,
         allow_uneven_event_width,
+        fix_uneven_event_width,
         allow_tot_mismatch,
         allow_header_text_offset_mismatch,
         allow_missing_required_offsets,
+        guess_byteord,
+        warn_overrange,
+        lenient_ascii,
+        check_crc,
+        allow_crc_mismatch,
+        check_data_hash,
+        allow_data_hash_mismatch,
+        allow_non_list_mode,
         text_data_correction,
         text_analysis_correction,
         disallow_bitmask_truncation,
+        pad_integer_widths,
         warnings_are_errors,
-    );
+        measurements,
+        event_range,
+    )?;
 
     let out: StdDatasetOutput =
         fcs_read_std_dataset(&p, &conf).map_or_else(|e| Err(handle_failure(e)), handle_warnings)?;
@@ -581,6 +795,7 @@ fn py_fcs_read_std_dataset(
         out.dataset
             .pseudostandard
             .into_iter()
+            .chain(out.dataset.unused_standard_keywords)
             .map(|(k, v)| (k.to_string(), v.clone()))
             .into_py_dict(py)?,
     ))
@@ -626,41 +841,70 @@ fn raw_config(
     header: HeaderConfig,
     supp_text_correction: (i32, i32),
     use_literal_delims: bool,
+    fallback_literal_delims: bool,
     allow_non_ascii_delim: bool,
     ignore_stext: bool,
     allow_duplicated_stext: bool,
     allow_missing_final_delim: bool,
     allow_nonunique: bool,
+    nonunique_last_wins: bool,
     allow_odd: bool,
     allow_delim_at_boundary: bool,
     allow_empty: bool,
+    keep_empty_values: bool,
     allow_non_utf8: bool,
     allow_non_ascii_keywords: bool,
     allow_missing_stext: bool,
     allow_stext_own_delim: bool,
+    stext_takes_precedence: bool,
     allow_missing_nextdata: bool,
     trim_value_whitespace: bool,
     date_pattern: Option<String>,
+    skip_nonstandard: bool,
+    keep_raw_text: bool,
+    keep_others: bool,
+    collect_metrics: bool,
+    lossy_values: bool,
+    text_encoding: String,
+    rename_keys: Vec<(String, String)>,
+    rewrite_values: Vec<(String, Option<String>, String)>,
+    insert_keys: Vec<(String, String)>,
 ) -> PyResult<RawTextReadConfig> {
     let out = RawTextReadConfig {
         header,
         stext_correction: OffsetCorrection::from(supp_text_correction),
         use_literal_delims,
+        fallback_literal_delims,
         ignore_stext,
         allow_duplicated_stext,
         allow_non_ascii_delim,
         allow_missing_final_delim,
         allow_nonunique,
+        nonunique_last_wins,
         allow_odd,
         allow_delim_at_boundary,
         allow_empty,
+        keep_empty_values,
         allow_non_utf8,
         allow_non_ascii_keywords,
         allow_missing_stext,
         allow_stext_own_delim,
+        stext_takes_precedence,
         allow_missing_nextdata,
         trim_value_whitespace,
         date_pattern: date_pattern.map(str_to_date_pat).transpose()?,
+        skip_nonstandard,
+        keep_raw_text,
+        keep_others,
+        collect_metrics,
+        lossy_values,
+        text_encoding: str_to_text_encoding(text_encoding)?,
+        keyword_repairs: KeywordRepairs {
+            rename: rename_keys,
+            rewrite: rewrite_values,
+            insert: insert_keys,
+        },
+        anonymize: Anonymizer::default(),
     };
     Ok(out)
 }
@@ -672,6 +916,7 @@ fn std_config(
     time_ensure: bool,
     allow_pseudostandard: bool,
     fix_log_scale_offsets: bool,
+    infer_par: bool,
     shortname_prefix: Option<String>,
     nonstandard_measurement_pattern: Option<String>,
     time_pattern: Option<String>,
@@ -688,13 +933,22 @@ fn std_config(
         time: TimeConfig {
             pattern: tp,
             allow_missing: time_ensure,
+            datetime_tolerance: None,
             // allow_nonlinear_scale: time_ensure_linear,
             // allow_nontime_keywords: time_ensure_nogain,
         },
+        lab_timezone: None,
+        datetime_timezone: None,
         allow_pseudostandard,
+        pseudostandard_types: HashMap::new(),
+        keyword_registry: KeywordRegistry::default(),
+        vendor: VendorProfile::default(),
         fix_log_scale_offsets,
+        infer_par,
+        force_linear_scale: vec![],
+        allowed_features: vec![],
         disallow_deprecated,
-        nonstandard_measurement_pattern: nsmp,
+        nonstandard_measurement_patterns: nsmp.into_iter().collect(),
     };
     Ok(out)
 }
@@ -703,29 +957,65 @@ fn std_config(
 fn data_config(
     standard: StdTextReadConfig,
     allow_uneven_event_width: bool,
+    fix_uneven_event_width: bool,
     allow_tot_mismatch: bool,
     allow_header_text_offset_mismatch: bool,
     allow_missing_required_offsets: bool,
+    guess_byteord: bool,
+    warn_overrange: bool,
+    lenient_ascii: bool,
+    check_crc: bool,
+    allow_crc_mismatch: bool,
+    check_data_hash: bool,
+    allow_data_hash_mismatch: bool,
+    allow_non_list_mode: bool,
     text_data_correction: (i32, i32),
     text_analysis_correction: (i32, i32),
     disallow_bitmask_truncation: bool,
+    pad_integer_widths: bool,
     warnings_are_errors: bool,
-) -> DataReadConfig {
-    DataReadConfig {
+    measurements: Option<Vec<String>>,
+    event_range: Option<(usize, usize)>,
+) -> PyResult<DataReadConfig> {
+    let measurements = measurements
+        .map(|ns| {
+            ns.into_iter()
+                .map(str_to_shortname)
+                .collect::<PyResult<_>>()
+        })
+        .transpose()?;
+    let conf = DataReadConfig {
         standard,
         shared: SharedConfig {
             disallow_bitmask_truncation,
+            pad_integer_widths,
             warnings_are_errors,
+            mixed_ascii_widths: MixedAsciiWidths::default(),
         },
         reader: ReaderConfig {
             allow_uneven_event_width,
+            fix_uneven_event_width,
             allow_tot_mismatch,
             allow_header_text_offset_mismatch,
             allow_missing_required_offsets,
+            guess_byteord,
+            warn_overrange,
+            lenient_ascii,
+            check_crc,
+            allow_crc_mismatch,
+            check_data_hash,
+            allow_data_hash_mismatch,
+            allow_non_list_mode,
             data: OffsetCorrection::from(text_data_correction),
             analysis: OffsetCorrection::from(text_analysis_correction),
+            selection: ReadSelection {
+                measurements,
+                event_range: event_range.map(|(a, b)| a..b),
+                subsample: None,
+            },
         },
-    }
+    };
+    Ok(conf)
 }
 
 macro_rules! py_wrap {
@@ -996,6 +1286,60 @@ impl PyParseData {
     fn byte_pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
         self.0.byte_pairs.clone()
     }
+
+    #[getter]
+    fn nonstandard_skipped(&self) -> usize {
+        self.0.nonstandard_skipped
+    }
+
+    #[getter]
+    fn contaminated_values(&self) -> Vec<String> {
+        self.0.contaminated_values.clone()
+    }
+
+    #[getter]
+    fn raw_text(&self) -> Option<Vec<u8>> {
+        self.0.raw_text.clone()
+    }
+
+    #[getter]
+    fn raw_supp_text(&self) -> Option<Vec<u8>> {
+        self.0.raw_supp_text.clone()
+    }
+
+    #[getter]
+    fn others(&self) -> Option<Vec<Vec<u8>>> {
+        self.0
+            .others
+            .as_ref()
+            .map(|os| os.0.iter().map(|o| o.0.clone()).collect())
+    }
+
+    #[getter]
+    fn keyword_offsets(&self) -> HashMap<String, u64> {
+        self.0.keyword_offsets.clone()
+    }
+
+    #[getter]
+    fn keyword_repairs(&self) -> Vec<String> {
+        self.0
+            .keyword_repairs
+            .iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
+    #[getter]
+    fn metrics(&self) -> Option<(u64, f64, f64, Option<f64>, Option<usize>)> {
+        self.0.metrics.as_ref().map(|m| {
+            (
+                m.bytes_read,
+                m.header_time.as_secs_f64(),
+                m.text_time.as_secs_f64(),
+                m.data_time.map(|t| t.as_secs_f64()),
+                m.events,
+            )
+        })
+    }
 }
 
 // core* objects
@@ -1575,6 +1919,37 @@ impl PyCoreTEXT3_2 {
     }
 }
 
+// $PnO for all measurements (all versions)
+macro_rules! meas_get_set_powers {
+    ($pytype:ident) => {
+        #[pymethods]
+        impl $pytype {
+            #[getter]
+            fn get_powers(&self) -> Vec<(usize, Vec<f32>)> {
+                self.0
+                    .powers()
+                    .into_iter()
+                    .map(|(i, x)| {
+                        (
+                            i.into(),
+                            x.map(|y| y.0.iter().map(|p| (*p).into()).collect())
+                                .unwrap_or_default(),
+                        )
+                    })
+                    .collect()
+            }
+
+            #[setter]
+            fn set_powers(&mut self, xs: Vec<Vec<f32>>) -> PyResult<()> {
+                let ps = xs.into_iter().map(to_power).collect::<PyResult<_>>()?;
+                self.0
+                    .set_powers(ps)
+                    .map_err(|e| PyreflowException::new_err(e.to_string()))
+            }
+        }
+    };
+}
+
 // Get/set methods for all versions
 macro_rules! common_methods {
     ($pytype:ident, $($rest:ident),*) => {
@@ -1586,7 +1961,7 @@ macro_rules! common_methods {
     ($pytype:ident) => {
         // common measurement keywords
         meas_get_set!(filters,           set_filters,           String,        $pytype);
-        meas_get_set!(powers,            set_powers,            u32,           $pytype);
+        meas_get_set_powers!($pytype);
         meas_get_set!(detector_types,    set_detector_types,    String,        $pytype);
         meas_get_set!(percents_emitted,  set_percents_emitted,  String,        $pytype);
 
@@ -2026,6 +2401,25 @@ macro_rules! common_meas_get_set {
                     Ok(self.0.rename_temporal(n).map(|n| n.to_string()))
                 }
 
+                fn rename_channel(&mut self, old: String, new: String) -> PyResult<()> {
+                    let o = str_to_shortname(old)?;
+                    let n = str_to_shortname(new)?;
+                    self.0
+                        .rename_channel(&o, n)
+                        .map(|_| ())
+                        .map_err(|e| PyreflowException::new_err(e.to_string()))
+                }
+
+                fn reorder_channels(&mut self, order: Vec<String>) -> PyResult<()> {
+                    let ns = order
+                        .into_iter()
+                        .map(str_to_shortname)
+                        .collect::<PyResult<Vec<_>>>()?;
+                    self.0
+                        .reorder_channels(&ns[..])
+                        .map_err(|e| PyreflowException::new_err(e.to_string()))
+                }
+
                 fn replace_temporal_at<'py>(
                     &mut self,
                     i: usize,
@@ -2182,23 +2576,36 @@ macro_rules! coredata_meas_get_set {
 
                 #[getter]
                 fn data(&self) -> PyDataFrame {
-                    let ns = self.0.all_shortnames();
-                    let columns = self
-                        .0
-                        .data()
-                        .iter_columns()
-                        .zip(ns)
-                        .map(|(c, n)| {
-                            // ASSUME this will not fail because the we know the types and
-                            // we don't have a validity array
-                            Series::from_arrow(n.as_ref().into(), c.as_array())
-                                .unwrap()
-                                .into()
+                    fcs_to_dataframe(self.0.data(), self.0.all_shortnames())
+                }
+
+                #[pyo3(signature = (raw = vec![]))]
+                fn scaled_data(&self, raw: Vec<String>) -> PyResult<PyDataFrame> {
+                    let rs = raw
+                        .into_iter()
+                        .map(str_to_shortname)
+                        .collect::<PyResult<Vec<_>>>()?;
+                    let df = self.0.scaled_data(&rs);
+                    Ok(fcs_to_dataframe(&df, self.0.all_shortnames()))
+                }
+
+                fn compensated_data(&self) -> PyResult<Option<PyDataFrame>> {
+                    self.0
+                        .compensated_data()
+                        .map(|res| {
+                            res.map(|df| fcs_to_dataframe(&df, self.0.all_shortnames()))
+                                .map_err(|e| PyreflowException::new_err(e.to_string()))
                         })
-                        .collect();
-                    // ASSUME this will not fail because all columns should have unique
-                    // names and the same length
-                    PyDataFrame(DataFrame::new(columns).unwrap())
+                        .transpose()
+                }
+
+                #[pyo3(signature = (name = None, order = "any"))]
+                fn time_column(&self, name: Option<String>, order: &str) -> PyResult<Vec<f64>> {
+                    let n = name.map(str_to_shortname).transpose()?;
+                    let o = str_to_time_order(order)?;
+                    self.0
+                        .time_column(n.as_ref(), o)
+                        .map_err(|e| PyreflowException::new_err(e.to_string()))
                 }
 
                 #[getter]
@@ -2900,6 +3307,32 @@ macro_rules! spillover_methods {
                 fn unset_spillover(&mut self) {
                     self.0.unset_spillover()
                 }
+
+                fn insert_spillover_measurement(&mut self, name: String) -> PyResult<bool> {
+                    let n = str_to_shortname(name)?;
+                    self.0
+                        .insert_spillover_measurement(n)
+                        .map_err(|e| PyreflowException::new_err(e.to_string()))
+                }
+
+                fn remove_spillover_measurement(&mut self, name: String) -> PyResult<bool> {
+                    let n = str_to_shortname(name)?;
+                    self.0
+                        .remove_spillover_measurement(&n)
+                        .map_err(|e| PyreflowException::new_err(e.to_string()))
+                }
+
+                fn set_spillover_value(&mut self, from: String, to: String, x: f32) -> PyResult<bool> {
+                    let f = str_to_shortname(from)?;
+                    let t = str_to_shortname(to)?;
+                    self.0
+                        .set_spillover_value(&f, &t, x)
+                        .map_err(|e| PyreflowException::new_err(e.to_string()))
+                }
+
+                fn set_spillover_precision(&mut self, precision: Option<usize>) {
+                    self.0.set_spillover_precision(precision)
+                }
             }
         )*
     };
@@ -3434,7 +3867,25 @@ shared_meas_get_set!(
 
 macro_rules! optical_common {
     ($($pytype:ident),*) => {
-        get_set_copied!($($pytype,)* [], get_power, set_power, power, u32);
+        $(
+            #[pymethods]
+            impl $pytype {
+                #[getter]
+                fn get_power(&self) -> Vec<f32> {
+                    self.0
+                        .power
+                        .as_ref_opt()
+                        .map(|x| x.0.iter().map(|p| (*p).into()).collect())
+                        .unwrap_or_default()
+                }
+
+                #[setter]
+                fn set_power(&mut self, xs: Vec<f32>) -> PyResult<()> {
+                    self.0.power = to_power(xs)?.into();
+                    Ok(())
+                }
+            }
+        )*
 
         get_set_str!($($pytype,)* [], get_filter,    set_filter,    filter);
         get_set_str!($($pytype,)* [], get_detector_type,    set_detector_type,    detector_type);
@@ -3641,7 +4092,7 @@ get_set_cloned!(
 );
 
 // $PnTYPE (3.2)
-get_set_copied!(
+get_set_cloned!(
     PyOptical3_2,
     [specific],
     get_feature,
@@ -3713,6 +4164,14 @@ fn to_non_neg_float(x: f32) -> PyResult<NonNegFloat> {
     NonNegFloat::try_from(x).map_err(|e| PyreflowException::new_err(e.to_string()))
 }
 
+fn to_power(xs: Vec<f32>) -> PyResult<Option<Power>> {
+    let ps = xs
+        .into_iter()
+        .map(to_non_neg_float)
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(NonEmpty::from_vec(ps).map(Power))
+}
+
 fn str_to_shortname(s: String) -> PyResult<Shortname> {
     s.parse::<Shortname>()
         .map_err(|e| PyreflowException::new_err(e.to_string()))
@@ -3743,6 +4202,22 @@ fn str_to_date_pat(s: String) -> PyResult<DatePattern> {
         .map_err(|e| PyreflowException::new_err(e.to_string()))
 }
 
+fn str_to_text_encoding(s: String) -> PyResult<TextEncoding> {
+    s.parse::<TextEncoding>()
+        .map_err(|e| PyreflowException::new_err(e.to_string()))
+}
+
+fn str_to_time_order(s: &str) -> PyResult<TimeOrder> {
+    match s {
+        "any" => Ok(TimeOrder::Any),
+        "nondecreasing" => Ok(TimeOrder::NonDecreasing),
+        "increasing" => Ok(TimeOrder::Increasing),
+        _ => Err(PyreflowException::new_err(format!(
+            "order must be 'any', 'nondecreasing', or 'increasing', got '{s}'"
+        ))),
+    }
+}
+
 fn vec_to_byteord(xs: Vec<u8>) -> PyResult<ByteOrd> {
     ByteOrd::try_from(xs).map_err(|e| PyreflowException::new_err(e.to_string()))
 }
@@ -3791,10 +4266,34 @@ fn series_to_fcs(ser: Series) -> Result<AnyFCSColumn, String> {
         DataType::Float64 => {
             column_to_buf!(ser, f64);
         }
+        DataType::Boolean => {
+            let ca = ser.bool().unwrap();
+            if ca.null_count() > 0 {
+                return Err(format!("column {} has null values", ser.name()));
+            }
+            Ok(BoolColumn(ca.into_no_null_iter().collect()).to_fcs_column())
+        }
         t => Err(format!("invalid datatype: {t}")),
     }
 }
 
+fn fcs_to_dataframe(df: &FCSDataFrame, ns: Vec<Shortname>) -> PyDataFrame {
+    let columns = df
+        .iter_columns()
+        .zip(ns)
+        .map(|(c, n)| {
+            // ASSUME this will not fail because the we know the types and
+            // we don't have a validity array
+            Series::from_arrow(n.as_ref().into(), c.as_array())
+                .unwrap()
+                .into()
+        })
+        .collect();
+    // ASSUME this will not fail because all columns should have unique
+    // names and the same length
+    PyDataFrame(DataFrame::new(columns).unwrap())
+}
+
 fn dataframe_to_fcs(mut df: DataFrame) -> Result<Vec<AnyFCSColumn>, String> {
     // make sure data is contiguous
     df.rechunk_mut();